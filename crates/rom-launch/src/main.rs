@@ -0,0 +1,101 @@
+use clap::Parser;
+use rom_loader_core::{interrupt, prompts, rom_launcher, systems};
+use rom_loader_core::events::EventOptions;
+use rom_loader_core::launch::{self, LaunchOverrides};
+use rom_loader_core::metadata::MetadataDb;
+use rom_loader_core::prompts::Answers;
+use rom_loader_core::rom_scanner;
+use rom_loader_core::settings::Settings;
+use rom_loader_core::state_store::StateStore;
+use std::io;
+use std::path::PathBuf;
+
+/// Launches a single ROM file or game directory and exits once the emulator does, e.g. from
+/// a frontend, a desktop shortcut or a script.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// ROM file or game directory to launch.
+    rom: PathBuf,
+
+    /// Path to the JSON configuration file for emulators.
+    #[arg(short, long, value_name = "FILE", default_value = "emulators.json", env = "ROM_LOADER_CONFIG")]
+    config_file: String,
+
+    /// Emulator to use instead of the suggested one (by name, case-insensitive).
+    #[arg(long = "with", value_name = "EMULATOR")]
+    with: Option<String>,
+
+    /// Stop emulators left running by an earlier rom-loader that crashed, without asking.
+    #[arg(long, env = "ROM_LOADER_REAP_ORPHANS")]
+    reap_orphans: bool,
+
+    /// Answer "yes" to every question (stopping leftover emulators, launching big images, ...)
+    /// instead of asking.
+    #[arg(short, long, conflicts_with = "no_input")]
+    yes: bool,
+
+    /// Never wait for input: every question takes its default answer.
+    #[arg(long, env = "ROM_LOADER_NO_INPUT")]
+    no_input: bool,
+
+    /// Show the emulator's output as it runs, each line prefixed with [emu].
+    #[arg(short, long, env = "ROM_LOADER_VERBOSE")]
+    verbose: bool,
+
+    #[command(flatten)]
+    events: EventOptions,
+
+    #[command(flatten)]
+    overrides: LaunchOverrides,
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    if let Err(e) = interrupt::install() {
+        eprintln!("⚠️ Ctrl-C won't stop things gracefully: {}", e);
+    }
+    let config_path = PathBuf::from(&args.config_file);
+    let settings = Settings::load(&config_path).unwrap_or_else(|e| {
+        eprintln!("⚠️ Ignoring the configuration's settings: {}", e);
+        Settings::default()
+    });
+    systems::disable_extensions(&settings.disabled_extensions);
+    prompts::set_answers(match (args.yes, args.no_input) {
+        (true, _) => Answers::Yes,
+        (false, true) => Answers::Defaults,
+        (false, false) => Answers::Ask,
+    });
+    rom_launcher::set_verbose(args.verbose);
+    if let Err(e) = args.events.open() {
+        eprintln!("⚠️ No events will be written: {}", e);
+    }
+
+    let Some(game) = rom_scanner::identify(&args.rom) else {
+        eprintln!("❌ {} is not a supported ROM or game directory.", args.rom.display());
+        std::process::exit(1);
+    };
+    let Some(emulator_config) = launch::load_emulator_config(&config_path) else { std::process::exit(1) };
+    let state = StateStore::load(&StateStore::path_for_config(&config_path)).unwrap_or_default();
+    let metadata = MetadataDb::load(&MetadataDb::path_for_config(&config_path)).unwrap_or_default();
+
+    let emulator = match args.with.as_deref() {
+        Some(name) => match emulator_config.find_emulator(name) {
+            Some(emulator) => emulator,
+            None => {
+                eprintln!("❌ No emulator named '{}' in the configuration.", name);
+                std::process::exit(1);
+            }
+        },
+        None => match launch::suggested_emulator(&game, &emulator_config, &emulator_config.extension_map(), &state) {
+            Some(emulator) => emulator,
+            None => {
+                launch::report_missing_emulator(&game);
+                std::process::exit(1);
+            }
+        },
+    };
+    launch::check_orphans(&config_path, args.reap_orphans);
+    launch::launch_game(emulator, &game, &config_path, &metadata, &args.overrides);
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use std::fs::File;
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::metadata::GameMetadata;
+use crate::rom_scanner::Game;
+
+/// Archive formats MAME loads sets from.
+const SET_ARCHIVE_EXTENSIONS: &[&str] = &["zip", "7z"];
+
+/// Returns the parent set an arcade clone needs but that is not next to it.
+///
+/// In split and merged sets a clone only holds the ROMs that differ from its parent, so MAME
+/// can't start it without the parent archive. A clone zip that contains as many files as the
+/// set has ROMs comes from a non-merged set and works on its own.
+pub fn missing_parent<'a>(game: &Game, metadata: &'a GameMetadata) -> Option<&'a str> {
+    let parent = metadata.clone_of.as_deref()?;
+    let dir = game.path.parent()?;
+    if SET_ARCHIVE_EXTENSIONS.iter().any(|ext| dir.join(format!("{}.{}", parent, ext)).is_file()) {
+        return None;
+    }
+    if let (Some(expected), Some(actual)) = (metadata.rom_count, zip_file_count(&game.path)) {
+        if actual >= expected {
+            return None;
+        }
+    }
+    Some(parent)
+}
+
+/// Whether a game is a clone of another set.
+pub fn is_clone(metadata: &GameMetadata) -> bool {
+    metadata.clone_of.is_some()
+}
+
+/// Counts the files in a zip archive, or `None` for other formats and unreadable archives.
+fn zip_file_count(path: &Path) -> Option<usize> {
+    if !path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip")) {
+        return None;
+    }
+    let archive = ZipArchive::new(File::open(path).ok()?).ok()?;
+    Some(archive.file_names().filter(|name| !name.ends_with('/')).count())
+}
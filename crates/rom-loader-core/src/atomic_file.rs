@@ -0,0 +1,58 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// How many earlier versions [`write_with_backups`] keeps, as `<name>.bak.1` (the newest) to
+/// `<name>.bak.3`.
+pub const BACKUPS_KEPT: usize = 3;
+
+/// Writes a file so that a crash or power loss in the middle leaves either its old or its new
+/// contents, never a mix of both.
+///
+/// The contents go to a temporary file next to it, which is flushed to disk and then renamed
+/// over the file. A symbolic link is followed, so a configuration linked from a dotfiles
+/// repository stays a link.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    let written = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&temp_path, &path)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    // Makes the rename itself durable; directories can't be opened for this on Windows.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+/// Like [`write`], but first keeps the current contents as a backup, for files that are
+/// edited by hand or hold what can't be rebuilt (the configuration, learned preferences).
+///
+/// The last [`BACKUPS_KEPT`] versions are kept; older ones are removed.
+pub fn write_with_backups(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if path.is_file() {
+        for n in (1..BACKUPS_KEPT).rev() {
+            let older = backup_path(&path, n);
+            if older.exists() {
+                fs::rename(&older, backup_path(&path, n + 1))?;
+            }
+        }
+        fs::copy(&path, backup_path(&path, 1))?;
+    }
+    write(&path, contents)
+}
+
+/// Returns the path of a backup kept by [`write_with_backups`], 1 being the newest, e.g.
+/// `emulators.json.bak.1`.
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.bak.{}", file_name, n))
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::atomic_file;
+use crate::history::{self, HISTORY_FILE_NAME};
+use crate::macros::MACROS_FILE_NAME;
+use crate::metadata::METADATA_FILE_NAME;
+use crate::playlists::PLAYLISTS_FILE_NAME;
+use crate::library_db::{LibraryDb, LIBRARY_DB_FILE_NAME};
+use crate::state_store::STATE_FILE_NAME;
+use crate::verify::HASH_STORE_FILE_NAME;
+
+/// Name of the manifest stored inside every backup archive.
+const MANIFEST_NAME: &str = "rom_loader_backup.json";
+
+/// Describes the contents of a backup archive.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    /// Unix timestamp of when the backup was made.
+    created: u64,
+    /// Archive entry holding the emulator configuration, restored under the current config file name.
+    config_file: String,
+    /// Data files stored next to the configuration (history, state, metadata, ...).
+    files: Vec<String>,
+}
+
+/// Returns the data files that live next to the emulator configuration and belong in a backup.
+fn data_file_names() -> [&'static str; 7] {
+    [STATE_FILE_NAME, HISTORY_FILE_NAME, METADATA_FILE_NAME, HASH_STORE_FILE_NAME, MACROS_FILE_NAME, LIBRARY_DB_FILE_NAME, PLAYLISTS_FILE_NAME]
+}
+
+/// Bundles the emulator configuration and everything stored next to it into a zip archive.
+///
+/// # Returns
+/// The files that were written to the archive.
+pub fn create(config_path: &Path, archive_path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !config_path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Emulator configuration file not found: {}", config_path.display()),
+        ));
+    }
+    let config_file = config_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let data_files: Vec<PathBuf> = data_file_names()
+        .iter()
+        .map(|name| config_path.with_file_name(name))
+        .filter(|path| path.is_file())
+        .collect();
+
+    let manifest = BackupManifest {
+        created: history::now_timestamp(),
+        config_file: config_file.clone(),
+        files: data_files.iter().map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string()).collect(),
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to serialize backup manifest: {}", e),
+        ))?;
+
+    let mut zip = ZipWriter::new(File::create(archive_path)?);
+    let options = SimpleFileOptions::default();
+    zip.start_file(MANIFEST_NAME, options)?;
+    zip.write_all(manifest_json.as_bytes())?;
+
+    let mut written = vec![config_path.to_path_buf()];
+    written.extend(data_files);
+    for (path, entry_name) in written.iter().zip(std::iter::once(&config_file).chain(&manifest.files)) {
+        let contents = if entry_name == LIBRARY_DB_FILE_NAME { database_snapshot(path)? } else { fs::read(path)? };
+        zip.start_file(entry_name.as_str(), options)?;
+        zip.write_all(&contents)?;
+    }
+    zip.finish()?;
+    Ok(written)
+}
+
+/// Reads a consistent copy of the library database, made through SQLite since the file alone
+/// lacks the changes still in its write-ahead log.
+fn database_snapshot(path: &Path) -> io::Result<Vec<u8>> {
+    let snapshot = std::env::temp_dir().join(format!("rom_loader_backup_{}.db", std::process::id()));
+    let _ = fs::remove_file(&snapshot);
+    let result = LibraryDb::open(path).and_then(|db| db.snapshot_to(&snapshot)).and_then(|_| fs::read(&snapshot));
+    let _ = fs::remove_file(&snapshot);
+    result
+}
+
+/// Restores a backup made with [`create`] next to the given emulator configuration file.
+///
+/// Existing files are only replaced when `overwrite` is set, so a restore can't silently
+/// throw away a newer setup.
+///
+/// # Returns
+/// The files that were restored.
+pub fn restore(config_path: &Path, archive_path: &Path, overwrite: bool) -> io::Result<Vec<PathBuf>> {
+    let mut zip = ZipArchive::new(File::open(archive_path)?)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to read backup {}: {}", archive_path.display(), e),
+        ))?;
+    let manifest: BackupManifest = {
+        let mut contents = String::new();
+        zip.by_name(MANIFEST_NAME)
+            .map_err(|_| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a ROM Loader backup (no {})", archive_path.display(), MANIFEST_NAME),
+            ))?
+            .read_to_string(&mut contents)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse backup manifest: {}", e),
+            ))?
+    };
+
+    // Only plain file names are accepted, so a crafted archive can't write outside the config directory.
+    let mut targets = vec![(manifest.config_file.clone(), config_path.to_path_buf())];
+    for name in &manifest.files {
+        if Path::new(name).file_name().is_none_or(|n| n != name.as_str()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Refusing to restore suspicious entry '{}'", name),
+            ));
+        }
+        targets.push((name.clone(), config_path.with_file_name(name)));
+    }
+
+    let existing: Vec<String> = targets
+        .iter()
+        .filter(|(_, target)| target.exists())
+        .map(|(_, target)| target.display().to_string())
+        .collect();
+    if !existing.is_empty() && !overwrite {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("Restoring would overwrite {}; pass --force to replace them", existing.join(", ")),
+        ));
+    }
+
+    // Read everything first so a damaged archive doesn't leave a half-restored setup behind.
+    let mut contents = Vec::new();
+    for (entry_name, target) in targets {
+        let mut data = Vec::new();
+        zip.by_name(&entry_name)?.read_to_end(&mut data)?;
+        contents.push((target, data));
+    }
+    let mut restored = Vec::new();
+    for (target, data) in contents {
+        if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        atomic_file::write(&target, data)?;
+        restored.push(target);
+    }
+    Ok(restored)
+}
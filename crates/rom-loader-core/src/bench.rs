@@ -0,0 +1,154 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::library_db::LibraryDb;
+use crate::metadata::MetadataDb;
+use crate::rom_scanner::{RomScanner, ScanEntry};
+use crate::scan_snapshot::ScanSnapshot;
+use crate::titles::TitleCleaner;
+use crate::{hashing, history, interrupt, netfs, systems};
+
+/// Name of the throwaway database `bench scan` writes its index to, in the cache directory.
+const SCRATCH_DB_FILE_NAME: &str = "bench_library.db";
+
+/// Where the time of one scan went.
+#[derive(Debug, Clone, Default)]
+pub struct ScanTimings {
+    pub games: usize,
+    /// Files and directories that could not be read.
+    pub problems: usize,
+    /// Listing directories and recognizing games.
+    pub walk: Duration,
+    /// Time until the first game was found; long on shares that are slow to list directories.
+    pub first_game: Option<Duration>,
+    /// Reading the size and modification time of each game, and hashing the new and changed
+    /// ones, as `scan` does for its snapshot.
+    pub file_info: Duration,
+    /// Writing the snapshot and the title index to a database in the cache directory.
+    pub index: Duration,
+}
+
+impl ScanTimings {
+    pub fn total(&self) -> Duration {
+        self.walk + self.file_info + self.index
+    }
+}
+
+/// Scans a library the way `scan` does and measures each step, without touching the library
+/// database: files already hashed by the last scan (`previous`) are not hashed again, and the
+/// index is written to a throwaway database in `scratch_dir`.
+pub fn bench_scan(roms_dir: &Path, previous: Option<&ScanSnapshot>, scratch_dir: &Path) -> io::Result<ScanTimings> {
+    let supported_extensions = systems::supported_extensions();
+    let mut timings = ScanTimings::default();
+    let mut snapshot = ScanSnapshot::new(roms_dir, history::now_timestamp());
+    let mut problems = Vec::new();
+    let start = Instant::now();
+    for entry in RomScanner::new(roms_dir, &supported_extensions).quiet(true).entries()? {
+        match entry? {
+            ScanEntry::Game(game) => {
+                timings.first_game.get_or_insert_with(|| start.elapsed());
+                let file_info_start = Instant::now();
+                snapshot.add(&game, previous, &mut problems);
+                timings.file_info += file_info_start.elapsed();
+                timings.games += 1;
+            }
+            ScanEntry::Problem(problem) => problems.push(problem),
+        }
+    }
+    timings.walk = start.elapsed() - timings.file_info;
+    timings.problems = problems.len();
+
+    fs::create_dir_all(scratch_dir)?;
+    let scratch_path = scratch_dir.join(SCRATCH_DB_FILE_NAME);
+    let index_start = Instant::now();
+    let indexed = LibraryDb::open(&scratch_path).and_then(|mut db| {
+        db.save_snapshot(&snapshot)?;
+        db.index_titles(snapshot.games.keys().map(PathBuf::as_path), &MetadataDb::default(), &TitleCleaner::new(None))
+    });
+    timings.index = index_start.elapsed();
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = fs::remove_file(format!("{}{}", scratch_path.display(), suffix));
+    }
+    indexed?;
+    Ok(timings)
+}
+
+/// How fast files were hashed with a number of threads.
+#[derive(Debug, Clone)]
+pub struct HashRun {
+    pub threads: usize,
+    pub files: usize,
+    pub bytes: u64,
+    /// Files that could not be read.
+    pub failed: usize,
+    pub elapsed: Duration,
+    /// Whether some files were already hashed by an earlier run, so they may have come from
+    /// the operating system's cache rather than the disk.
+    pub reused: bool,
+}
+
+impl HashRun {
+    /// Throughput in bytes per second.
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Thread counts worth comparing: powers of two up to the number of CPUs, and the number of
+/// CPUs itself (e.g. 1, 2, 4, 6 on six cores).
+pub fn thread_counts() -> Vec<usize> {
+    let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut counts: Vec<usize> = std::iter::successors(Some(1), |&n| Some(n * 2)).take_while(|&n| n < cpus).collect();
+    counts.push(cpus);
+    counts
+}
+
+/// Hashes library files with each of the given thread counts, as `verify` does.
+///
+/// Each run hashes different files, about `bytes_per_run` of them, so a run doesn't read what
+/// the previous one left in the operating system's cache; only when the library is too small
+/// are files hashed again (see [`HashRun::reused`]).
+pub fn bench_hash(files: &[PathBuf], thread_counts: &[usize], bytes_per_run: u64) -> io::Result<Vec<HashRun>> {
+    let sizes: Vec<u64> = files.iter().map(|path| fs::metadata(path).map_or(0, |m| m.len())).collect();
+    let _busy = interrupt::Busy::start();
+    let mut next_file = 0;
+    let mut hashed = 0;
+    let mut runs = Vec::new();
+    for &threads in thread_counts {
+        let mut batch = Vec::new();
+        let mut bytes = 0;
+        while bytes < bytes_per_run && batch.len() < files.len() {
+            batch.push(&files[next_file]);
+            bytes += sizes[next_file];
+            next_file = (next_file + 1) % files.len();
+        }
+        let reused = hashed + batch.len() > files.len();
+        hashed += batch.len();
+
+        let next = AtomicUsize::new(0);
+        let failed = AtomicUsize::new(0);
+        let start = Instant::now();
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    if interrupt::requested() {
+                        break;
+                    }
+                    let Some(path) = batch.get(next.fetch_add(1, Ordering::Relaxed)) else { break };
+                    if netfs::retry(|| hashing::crc32_file(path)).is_err() {
+                        failed.fetch_add(1, Ordering::Relaxed);
+                    }
+                });
+            }
+        });
+        if interrupt::requested() {
+            return Err(interrupt::error());
+        }
+        runs.push(HashRun { threads, files: batch.len(), bytes, failed: failed.into_inner(), elapsed: start.elapsed(), reused });
+    }
+    Ok(runs)
+}
@@ -0,0 +1,119 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::Value;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+use crate::history::{self, LaunchHistory};
+
+/// Number of launch history entries included in a bug report.
+const HISTORY_ENTRIES: usize = 50;
+/// Replacement for redacted values.
+const REDACTED: &str = "<redacted>";
+/// Configuration keys whose values are always redacted.
+const SECRET_KEYS: &[&str] = &["username", "password", "token", "api_key"];
+
+/// Writes a gzipped tarball with what is needed to look into a problem: `version.txt` (the
+/// given version information plus the platform), the configuration and the most recent launch
+/// history entries.
+///
+/// Nothing leaves the computer: the user attaches the archive to an issue themselves. The
+/// configuration and history are sanitized first, see [`sanitize`].
+///
+/// # Returns
+/// The names of the files in the archive.
+pub fn create(config_path: &Path, archive_path: &Path, version_info: &str) -> io::Result<Vec<String>> {
+    let mut files = vec![(
+        "version.txt".to_string(),
+        format!("{}\nplatform: {} {}\n", version_info.trim_end(), env::consts::OS, env::consts::ARCH),
+    )];
+
+    if config_path.is_file() {
+        let config_name = config_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let contents = match serde_json::from_str::<Value>(&fs::read_to_string(config_path)?) {
+            Ok(mut config) => {
+                sanitize(&mut config);
+                to_json(&config)?
+            }
+            // The raw file could contain anything, so only the parse error goes in.
+            Err(e) => format!("The configuration is not valid JSON: {}\n", e),
+        };
+        files.push((config_name, contents));
+    }
+
+    let entries = LaunchHistory::for_config(config_path).load()?;
+    if !entries.is_empty() {
+        let mut lines = String::new();
+        for entry in &entries[entries.len().saturating_sub(HISTORY_ENTRIES)..] {
+            let mut entry = serde_json::to_value(entry).map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize history entry: {}", e),
+            ))?;
+            sanitize(&mut entry);
+            lines.push_str(&entry.to_string());
+            lines.push('\n');
+        }
+        files.push((history::HISTORY_FILE_NAME.to_string(), lines));
+    }
+
+    let mut tar = tar::Builder::new(GzEncoder::new(File::create(archive_path)?, Compression::default()));
+    for (name, contents) in &files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(history::now_timestamp());
+        header.set_cksum();
+        tar.append_data(&mut header, name, contents.as_bytes())?;
+    }
+    tar.into_inner()?.finish()?;
+    Ok(files.into_iter().map(|(name, _)| name).collect())
+}
+
+/// Removes personal details from a configuration or history value.
+///
+/// Credentials are replaced entirely, absolute paths keep only their file name (so the ROM or
+/// emulator is still recognizable), and the home directory inside other text becomes `~`.
+pub fn sanitize(value: &mut Value) {
+    let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok().filter(|home| home.len() > 1);
+    sanitize_value(value, home.as_deref());
+}
+
+fn sanitize_value(value: &mut Value, home: Option<&str>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_KEYS.contains(&key.to_lowercase().as_str()) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    sanitize_value(value, home);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|value| sanitize_value(value, home)),
+        Value::String(text) => *text = sanitize_text(text, home),
+        _ => {}
+    }
+}
+
+fn sanitize_text(text: &str, home: Option<&str>) -> String {
+    let path = Path::new(text);
+    if path.is_absolute() || text.starts_with('~') {
+        return match path.file_name() {
+            Some(name) => format!("{}/{}", REDACTED, name.to_string_lossy()),
+            None => REDACTED.to_string(),
+        };
+    }
+    match home {
+        Some(home) => text.replace(home, "~"),
+        None => text.to_string(),
+    }
+}
+
+fn to_json(value: &Value) -> io::Result<String> {
+    serde_json::to_string_pretty(value).map_err(|e| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Failed to serialize configuration: {}", e),
+    ))
+}
@@ -0,0 +1,145 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+use crate::settings::Settings;
+
+const MB: u64 = 1024 * 1024;
+
+/// Subdirectory of the cache directory holding preloaded images.
+pub const PRELOAD_DIR_NAME: &str = "preload";
+/// Subdirectory of the cache directory holding ROMs unpacked from zip files.
+pub const UNZIPPED_DIR_NAME: &str = "unzipped";
+/// Subdirectory of the cache directory holding SNES ROMs without their copier header.
+pub const UNHEADERED_DIR_NAME: &str = "unheadered";
+/// Subdirectory of the cache directory a download is kept in until it has been checked.
+pub const DOWNLOADS_DIR_NAME: &str = "downloads";
+
+/// The subdirectories rom-loader writes; anything else in the cache directory is left alone,
+/// since `cache_dir` may be a folder other programs use too.
+const SUBDIR_NAMES: &[&str] = &[PRELOAD_DIR_NAME, UNZIPPED_DIR_NAME, UNHEADERED_DIR_NAME, DOWNLOADS_DIR_NAME];
+
+/// The cache directory, where derived data is kept: disc images preloaded onto local storage,
+/// ROMs unpacked from zip files, and anything else rom-loader can make again.
+///
+/// Each file or folder in one of rom-loader's subdirectories of the cache (e.g. the folder
+/// `preload/<key>` holding one preloaded image) is an item; when the cache has a size limit, the items used least
+/// recently are removed first.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+    max_bytes: Option<u64>,
+}
+
+/// A file or folder in the cache.
+#[derive(Debug, Clone)]
+pub struct CacheItem {
+    pub path: PathBuf,
+    pub size: u64,
+    /// When the item was written or last reused, as a Unix timestamp.
+    pub last_used: u64,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, max_bytes: Option<u64>) -> Self {
+        Cache { dir, max_bytes }
+    }
+
+    /// The cache of a configuration: `cache_dir`, limited to `cache_max_mb`.
+    pub fn for_config(config_path: &Path, settings: &Settings) -> Self {
+        Cache::new(settings.cache_dir_for(config_path), settings.cache_max_mb.map(|mb| mb * MB))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The size limit in bytes, if any.
+    pub fn max_bytes(&self) -> Option<u64> {
+        self.max_bytes
+    }
+
+    /// Lists the items in rom-loader's subdirectories of the cache, least recently used first.
+    pub fn items(&self) -> io::Result<Vec<CacheItem>> {
+        let mut items = Vec::new();
+        for subdir in SUBDIR_NAMES {
+            let dir = self.dir.join(subdir);
+            if !dir.is_dir() {
+                continue;
+            }
+            for item in fs::read_dir(&dir)? {
+                items.push(cache_item(item?.path()));
+            }
+        }
+        items.sort_by_key(|item| item.last_used);
+        Ok(items)
+    }
+
+    /// Makes room for `needed` bytes in a subdirectory of the cache before it is written to.
+    ///
+    /// Without a size limit only the newest item of each subdirectory is kept, so the
+    /// subdirectory is emptied. With one, the least recently used items of the whole cache are
+    /// removed until `needed` more bytes fit; if they can't, everything is removed.
+    pub fn make_room(&self, subdir: &str, needed: u64) -> io::Result<()> {
+        if !SUBDIR_NAMES.contains(&subdir) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is not a cache subdirectory", subdir)));
+        }
+        let Some(max_bytes) = self.max_bytes else {
+            let dir = self.dir.join(subdir);
+            if dir.is_dir() {
+                fs::remove_dir_all(&dir)?;
+            }
+            return Ok(());
+        };
+        self.prune(max_bytes.saturating_sub(needed), false).map(|_| ())
+    }
+
+    /// Removes the least recently used items until the cache is no bigger than `max_bytes`;
+    /// with `dry_run` they are only listed.
+    ///
+    /// # Returns
+    /// The removed items, least recently used first.
+    pub fn prune(&self, max_bytes: u64, dry_run: bool) -> io::Result<Vec<CacheItem>> {
+        let items = self.items()?;
+        let mut total: u64 = items.iter().map(|item| item.size).sum();
+        let mut removed = Vec::new();
+        for item in items {
+            if total <= max_bytes {
+                break;
+            }
+            if !dry_run {
+                if item.path.is_dir() {
+                    fs::remove_dir_all(&item.path)?;
+                } else {
+                    fs::remove_file(&item.path)?;
+                }
+            }
+            total -= item.size;
+            removed.push(item);
+        }
+        Ok(removed)
+    }
+}
+
+/// Marks a cached file as just used, so pruning keeps it longer than the others.
+pub fn touch(path: &Path) -> io::Result<()> {
+    File::options().write(true).open(path)?.set_modified(SystemTime::now())
+}
+
+/// Measures a cache item; folders are as big as all their files, and as recently used as the
+/// newest of them.
+fn cache_item(path: PathBuf) -> CacheItem {
+    let mut size = 0;
+    let mut last_used = 0;
+    for entry in WalkDir::new(&path).into_iter().filter_map(|e| e.ok()) {
+        let Ok(metadata) = entry.metadata() else { continue };
+        if metadata.is_file() {
+            size += metadata.len();
+        }
+        let modified = metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_secs());
+        last_used = last_used.max(modified);
+    }
+    CacheItem { path, size, last_used }
+}
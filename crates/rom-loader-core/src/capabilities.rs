@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::emulator_config::Emulator;
+use crate::rom_launcher::LaunchProfile;
+
+/// How long an emulator may take to print its version or help before it is killed; some
+/// emulators ignore `--version` and open their window instead.
+const OUTPUT_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Fullscreen flags looked for in the help of emulators without a launch profile, most common first.
+const FULLSCREEN_FLAGS: &[&str] = &["--fullscreen", "-fullscreen", "--full-screen", "-fs"];
+
+/// What an installed emulator supports, as found by `doctor`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Version the emulator reported, e.g. "1.19.1".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Whether the emulator opens zipped ROMs itself; zipped ROMs are unpacked before
+    /// launching emulators that don't.
+    #[serde(default)]
+    pub zip: bool,
+    /// Flag that starts the emulator in fullscreen, for emulators rom-loader has no launch
+    /// profile for, e.g. "--fullscreen".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullscreen_flag: Option<String>,
+}
+
+impl Capabilities {
+    /// Finds out what an emulator supports by running it with its version and help flags.
+    ///
+    /// MAME and RetroArch open zipped ROMs themselves; other emulators are taken to if their
+    /// help mentions zip files.
+    pub fn detect(emulator: &Emulator) -> io::Result<Self> {
+        let executable = emulator.executable();
+        let profile = LaunchProfile::detect(&emulator.name);
+        let (version_arg, help_arg) = match profile {
+            LaunchProfile::Mame => ("-version", "-showusage"),
+            _ => ("--version", "--help"),
+        };
+        let version = find_version(&command_output(&executable, version_arg)?);
+        let help = command_output(&executable, help_arg)?;
+        let zip = matches!(profile, LaunchProfile::Mame | LaunchProfile::RetroArch) || help.to_lowercase().contains("zip");
+        let fullscreen_flag = match profile {
+            LaunchProfile::Generic | LaunchProfile::Rpcs3 => {
+                let words: Vec<&str> = help.split(|c: char| c.is_whitespace() || ",[]|=".contains(c)).collect();
+                FULLSCREEN_FLAGS.iter().find(|flag| words.contains(flag)).map(|flag| flag.to_string())
+            }
+            // The launch profile already knows the flag.
+            _ => None,
+        };
+        Ok(Capabilities { version, zip, fullscreen_flag })
+    }
+}
+
+/// Checks an emulator's detected version against its `min_version`.
+///
+/// # Returns
+/// `Some(true)` if the version is new enough, `Some(false)` if it is older, or `None` if there
+/// is no `min_version` or the version hasn't been detected.
+pub fn meets_min_version(emulator: &Emulator) -> Option<bool> {
+    let min_version = emulator.min_version.as_deref()?;
+    let version = emulator.capabilities.as_ref()?.version.as_deref()?;
+    Some(compare_versions(version, min_version) != Ordering::Less)
+}
+
+/// Runs a program with one argument and returns what it printed on stdout and stderr.
+///
+/// The program is killed if it is still running after a few seconds.
+pub fn command_output(program: &Path, arg: &str) -> io::Result<String> {
+    // Output goes to a file rather than a pipe, so an emulator can't block on a full pipe.
+    let output_path = std::env::temp_dir().join(format!("rom_loader_output_{}.log", std::process::id()));
+    let output = File::create(&output_path)?;
+    let mut child = Command::new(program)
+        .arg(arg)
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(output.try_clone()?))
+        .stderr(Stdio::from(output))
+        .spawn()?;
+    let deadline = Instant::now() + OUTPUT_TIMEOUT;
+    while child.try_wait()?.is_none() {
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let text = fs::read_to_string(&output_path).unwrap_or_default();
+    let _ = fs::remove_file(&output_path);
+    Ok(text)
+}
+
+/// Finds the first dotted version number in a text, e.g. "1.19.1" in
+/// "RetroArch: Frontend for libretro -- v1.19.1 -- ...".
+pub fn find_version(text: &str) -> Option<String> {
+    text.split(|c: char| !c.is_ascii_digit() && c != '.')
+        .map(|part| part.trim_matches('.'))
+        .find(|part| part.contains('.') && !part.contains(".."))
+        .map(str::to_string)
+}
+
+/// Compares dotted version numbers numerically, treating missing parts as zero.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parse = |version: &str| -> Vec<u64> { version.split('.').map(|part| part.parse().unwrap_or(0)).collect() };
+    let (a, b) = (parse(a), parse(b));
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
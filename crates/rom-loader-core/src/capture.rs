@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+/// Default address of the obs-websocket server built into OBS 28 and later.
+const DEFAULT_OBS_ADDRESS: &str = "localhost:4455";
+
+/// How play sessions with an emulator are captured.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CaptureSettings {
+    /// Start and stop recording (or streaming) in a running OBS through obs-websocket.
+    Obs {
+        /// Host and port of the obs-websocket server.
+        #[serde(default = "default_obs_address")]
+        address: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+        /// Start the stream instead of a recording.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        stream: bool,
+    },
+    /// Record the screen with ffmpeg.
+    Ffmpeg {
+        /// Directory recordings are written to, named after the game and the start time.
+        output_dir: PathBuf,
+        /// ffmpeg input arguments; by default the whole desktop is grabbed.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        input_args: Vec<String>,
+        /// Encoding arguments placed before the output file, e.g. ["-c:v", "libx264"].
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        output_args: Vec<String>,
+    },
+}
+
+fn default_obs_address() -> String {
+    DEFAULT_OBS_ADDRESS.to_string()
+}
+
+/// A capture started for a play session, stopped once the emulator exits.
+#[derive(Debug)]
+pub enum CaptureSession {
+    Obs { address: String, password: Option<String>, stream: bool },
+    Ffmpeg { child: Child, output: PathBuf },
+}
+
+impl CaptureSession {
+    /// Starts capturing a session of `game_path`.
+    pub fn start(settings: &CaptureSettings, game_path: &Path) -> io::Result<Self> {
+        match settings {
+            CaptureSettings::Obs { address, password, stream } => {
+                obs_request(address, password.as_deref(), if *stream { "StartStream" } else { "StartRecord" })?;
+                Ok(CaptureSession::Obs { address: address.clone(), password: password.clone(), stream: *stream })
+            }
+            CaptureSettings::Ffmpeg { output_dir, input_args, output_args } => {
+                fs::create_dir_all(output_dir)?;
+                let output = output_dir.join(format!(
+                    "{} {}.mkv",
+                    game_path.file_stem().unwrap_or_default().to_string_lossy(),
+                    chrono::Local::now().format("%Y-%m-%d %H-%M-%S")
+                ));
+                let mut command = Command::new("ffmpeg");
+                command.args(["-hide_banner", "-loglevel", "error", "-y"]);
+                if input_args.is_empty() {
+                    command.args(default_ffmpeg_input());
+                } else {
+                    command.args(input_args);
+                }
+                command.args(output_args).arg(&output).stdin(Stdio::piped()).stdout(Stdio::null());
+                let child = command.spawn()
+                    .map_err(|e| io::Error::new(e.kind(), format!("Failed to start ffmpeg: {}", e)))?;
+                Ok(CaptureSession::Ffmpeg { child, output })
+            }
+        }
+    }
+
+    /// Stops the capture.
+    ///
+    /// # Returns
+    /// The recording written by ffmpeg; OBS saves recordings to its own output folder.
+    pub fn stop(self) -> io::Result<Option<PathBuf>> {
+        match self {
+            CaptureSession::Obs { address, password, stream } => {
+                obs_request(&address, password.as_deref(), if stream { "StopStream" } else { "StopRecord" })?;
+                Ok(None)
+            }
+            CaptureSession::Ffmpeg { mut child, output } => {
+                // 'q' makes ffmpeg finish the file properly; killing it would leave it unplayable.
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(b"q")?;
+                }
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(io::Error::other(format!("ffmpeg exited with {}", status)));
+                }
+                Ok(Some(output))
+            }
+        }
+    }
+}
+
+/// ffmpeg arguments that grab the whole desktop on the current platform.
+fn default_ffmpeg_input() -> Vec<String> {
+    if cfg!(windows) {
+        vec!["-f".into(), "gdigrab".into(), "-framerate".into(), "60".into(), "-i".into(), "desktop".into()]
+    } else if cfg!(target_os = "macos") {
+        // avfoundation lists cameras first; "1" is usually the main screen.
+        vec!["-f".into(), "avfoundation".into(), "-framerate".into(), "60".into(), "-i".into(), "1:none".into()]
+    } else {
+        let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+        vec!["-f".into(), "x11grab".into(), "-framerate".into(), "60".into(), "-i".into(), display]
+    }
+}
+
+/// Sends a single request to OBS.
+#[cfg(feature = "obs")]
+fn obs_request(address: &str, password: Option<&str>, request_type: &str) -> io::Result<()> {
+    crate::obs_websocket::request(address, password, request_type)
+}
+
+#[cfg(not(feature = "obs"))]
+fn obs_request(_address: &str, _password: Option<&str>, _request_type: &str) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "this build of rom-loader can't talk to OBS (it needs the obs feature); capture with ffmpeg instead",
+    ))
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::rom_scanner::Game;
+use crate::titles::TitleCleaner;
+
+/// Directory of the downloaded cheat database, next to the emulator configuration.
+pub const CHEATS_DIR_NAME: &str = "cheats";
+
+/// Extensions of cheat files: RetroArch's .cht and the .cheats some standalone emulators use.
+const CHEAT_EXTENSIONS: &[&str] = &["cht", "cheats"];
+
+/// The libretro cheat collection, one .cht per game in a folder per system.
+#[cfg(feature = "install")]
+const LIBRETRO_CHEATS_URL: &str = "https://buildbot.libretro.com/assets/frontend/cheats.zip";
+
+/// Returns the cheat database directory that belongs to the given emulator configuration file.
+pub fn dir_for_config(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(CHEATS_DIR_NAME)
+}
+
+/// Finds the cheat file for a game.
+///
+/// A .cht or .cheats file next to the ROM with its name wins; otherwise the cheat database is
+/// searched for a file named like the ROM, then for one whose cleaned-up title matches the
+/// ROM's (so "Super Mario World (USA).cht" is found for "super_mario_world.sfc").
+pub fn find(game: &Game, config_path: &Path) -> Option<PathBuf> {
+    let stem = game.path.file_stem()?.to_string_lossy().to_lowercase();
+    let is_cheat_file = |path: &Path| path.extension().is_some_and(|e| CHEAT_EXTENSIONS.iter().any(|ext| e.eq_ignore_ascii_case(ext)));
+    let same_stem = |path: &Path| path.file_stem().is_some_and(|s| s.to_string_lossy().to_lowercase() == stem);
+
+    let dir = game.path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let sibling = fs::read_dir(dir).ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|p| is_cheat_file(p) && same_stem(p));
+    if sibling.is_some() {
+        return sibling;
+    }
+
+    let database: Vec<PathBuf> = WalkDir::new(dir_for_config(config_path))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_cheat_file(e.path()))
+        .map(|e| e.into_path())
+        .collect();
+    if let Some(exact) = database.iter().find(|p| same_stem(p)) {
+        return Some(exact.clone());
+    }
+    let titles = TitleCleaner::new(None);
+    let title = titles.clean(&game.path.file_name()?.to_string_lossy()).to_lowercase();
+    database
+        .into_iter()
+        .find(|p| titles.clean(&p.file_name().unwrap_or_default().to_string_lossy()).to_lowercase() == title)
+}
+
+/// Returns where a cheat file goes in an emulator's cheat directory: named after the ROM,
+/// since emulators look a game's cheats up by its file name.
+pub fn target_path(cheat_dir: &Path, game: &Game, cheat_file: &Path) -> PathBuf {
+    let stem = game.path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = cheat_file.extension().unwrap_or_default().to_string_lossy();
+    cheat_dir.join(format!("{}.{}", stem, extension))
+}
+
+/// Downloads the libretro cheat collection into the cheat database directory, replacing the
+/// previous download.
+///
+/// # Returns
+/// The number of cheat files in the database.
+#[cfg(feature = "install")]
+pub fn update_database(config_path: &Path) -> std::io::Result<usize> {
+    let dir = dir_for_config(config_path);
+    let staging = config_path.with_file_name(format!(".{}.download", CHEATS_DIR_NAME));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    let archive = staging.join("cheats.zip");
+    let unpacked = staging.join(CHEATS_DIR_NAME);
+    let result = crate::emulator_install::download_file(LIBRETRO_CHEATS_URL, &archive)
+        .and_then(|()| crate::emulator_install::unpack(&archive, &unpacked))
+        .and_then(|()| {
+            if dir.exists() {
+                fs::remove_dir_all(&dir)?;
+            }
+            fs::rename(&unpacked, &dir)
+        });
+    let _ = fs::remove_dir_all(&staging);
+    result?;
+    Ok(WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()).count())
+}
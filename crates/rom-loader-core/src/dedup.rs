@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use crate::hashing;
+
+/// Files with identical contents on the same filesystem.
+#[derive(Debug)]
+pub struct DuplicateGroup {
+    /// The file the duplicates get linked to.
+    pub original: PathBuf,
+    pub duplicates: Vec<PathBuf>,
+    /// Size of each file in bytes.
+    pub size: u64,
+}
+
+impl DuplicateGroup {
+    /// Bytes freed by linking the duplicates to the original.
+    pub fn reclaimable(&self) -> u64 {
+        self.size * self.duplicates.len() as u64
+    }
+}
+
+/// Finds files with identical contents that could share their storage.
+///
+/// Candidates are grouped by filesystem and size, then by CRC32, and finally compared byte for
+/// byte so a hash collision can never link two different ROMs. Files that are already hard
+/// links of each other count once. Empty files, and files that can't be read, are skipped.
+pub fn find_duplicates(files: &[PathBuf]) -> io::Result<Vec<DuplicateGroup>> {
+    let mut by_size: BTreeMap<(u64, u64), Vec<&PathBuf>> = BTreeMap::new();
+    let mut seen_inodes = HashSet::new();
+    for path in files {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("  ⚠️ Skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if metadata.len() == 0 {
+            continue;
+        }
+        let (device, inode) = file_identity(&metadata);
+        if inode.is_some() && !seen_inodes.insert((device, inode)) {
+            continue;
+        }
+        by_size.entry((device, metadata.len())).or_default().push(path);
+    }
+
+    let mut groups = Vec::new();
+    for ((_, size), paths) in by_size {
+        if paths.len() < 2 {
+            continue;
+        }
+        let mut by_crc: BTreeMap<String, Vec<&PathBuf>> = BTreeMap::new();
+        for path in paths {
+            match hashing::crc32_file(path) {
+                Ok(crc) => by_crc.entry(crc).or_default().push(path),
+                Err(e) => eprintln!("  ⚠️ Skipping {}: {}", path.display(), e),
+            }
+        }
+        for candidates in by_crc.into_values() {
+            let mut remaining = candidates;
+            while remaining.len() > 1 {
+                let original = remaining.remove(0);
+                // Otherwise every comparison would fail and the others would be skipped instead.
+                if let Err(e) = File::open(original) {
+                    eprintln!("  ⚠️ Skipping {}: {}", original.display(), e);
+                    continue;
+                }
+                let mut duplicates = Vec::new();
+                let mut different = Vec::new();
+                for path in remaining {
+                    match same_contents(original, path) {
+                        Ok(true) => duplicates.push(path.clone()),
+                        Ok(false) => different.push(path),
+                        // E.g. unreadable, or shrunk since it was hashed.
+                        Err(e) => eprintln!("  ⚠️ Skipping {}: {}", path.display(), e),
+                    }
+                }
+                if !duplicates.is_empty() {
+                    groups.push(DuplicateGroup { original: original.clone(), duplicates, size });
+                }
+                remaining = different;
+            }
+        }
+    }
+    Ok(groups)
+}
+
+/// Replaces `duplicate` with a hard link to `original`.
+///
+/// The link is created under a temporary name and renamed over the duplicate, so the
+/// duplicate is never missing if linking fails halfway.
+pub fn link_duplicate(original: &Path, duplicate: &Path) -> io::Result<()> {
+    let mut temp_name = duplicate.as_os_str().to_owned();
+    temp_name.push(".rom_loader_link");
+    let temp_path = PathBuf::from(temp_name);
+    fs::hard_link(original, &temp_path)?;
+    fs::rename(&temp_path, duplicate).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
+}
+
+/// Returns the device and, where the platform exposes it, the inode of a file.
+#[cfg(unix)]
+fn file_identity(metadata: &fs::Metadata) -> (u64, Option<u64>) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), Some(metadata.ino()))
+}
+
+/// Returns the device and, where the platform exposes it, the inode of a file.
+///
+/// Without this information all files are assumed to be on one filesystem; linking across
+/// volumes then fails and is reported for the affected file.
+#[cfg(not(unix))]
+fn file_identity(_metadata: &fs::Metadata) -> (u64, Option<u64>) {
+    (0, None)
+}
+
+/// Compares two files byte for byte.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut file_a = File::open(a)?;
+    let mut file_b = File::open(b)?;
+    let mut buffer_a = vec![0u8; 64 * 1024];
+    let mut buffer_b = vec![0u8; 64 * 1024];
+    loop {
+        let read = file_a.read(&mut buffer_a)?;
+        if read == 0 {
+            return Ok(file_b.read(&mut buffer_b)? == 0);
+        }
+        file_b.read_exact(&mut buffer_b[..read])?;
+        if buffer_a[..read] != buffer_b[..read] {
+            return Ok(false);
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::capabilities::Capabilities;
+use crate::capture::CaptureSettings;
+use crate::quirks::Quirks;
+use crate::gpu::GpuPreference;
+use crate::rom_scanner::Game;
+use crate::{atomic_file, first_run, priority, prompts, systems};
+use crate::sandbox::Sandbox;
+use crate::settings::Settings;
+
+/// Represents a single emulator configuration.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Emulator {
+    pub name: String,
+    pub path: PathBuf, // The executable, or a bare command name such as "retroarch" looked up on the PATH
+    pub extensions: Vec<String>,
+    #[serde(default)]
+    pub core_path: Option<PathBuf>, // For RetroArch cores (optional, will be null for MAME-only setup)
+    #[serde(default)]
+    pub system_name: Option<String>, // For MAME console system short names (e.g., "genesis", "nes")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub controller_profiles: Vec<ControllerProfile>, // Controller configs deployed before launch
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub append_configs: Vec<AppendConfig>, // RetroArch --appendconfig files (shaders, bezels)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub display: Vec<DisplayPreset>, // Fullscreen/monitor/resolution per system or game
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub performance: Vec<PerformancePreset>, // Speed or latency settings per system or game (speedhacks, runahead)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<Sandbox>, // Run the emulator under firejail or bubblewrap
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<GpuPreference>, // "discrete" or "integrated", expanded to the platform's env vars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_device: Option<String>, // Output device/sink name, e.g. "alsa_output.hdmi-stereo"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio_driver: Option<String>, // SDL audio driver, e.g. "pulseaudio" or "alsa"
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>, // Extra environment variables, e.g. DXVK_HUD or DRI_PRIME
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wrapper: Vec<String>, // Command to start the emulator through, e.g. ["prime-run"]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capture: Option<CaptureSettings>, // Record or stream play sessions with OBS or ffmpeg
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screenshot_dir: Option<PathBuf>, // Where the emulator saves screenshots, collected after each session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cheat_dir: Option<PathBuf>, // Where the emulator loads a game's cheats from by ROM name; for RetroArch the core's folder, e.g. cheats/Snes9x
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nice: Option<i32>, // Process niceness, -20 (highest priority) to 19 (lowest)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cpu_affinity: Vec<usize>, // CPUs the emulator may run on, e.g. [2, 3]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<String>, // Oldest version that works with this entry, e.g. "1.15"; checked before launching
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<Capabilities>, // Version, zip support and fullscreen flag found by 'doctor'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quirks: Option<Quirks>, // Replaces the built-in quirks of the emulator, e.g. {"absolute_path": true}; {} turns them off
+    #[serde(skip)]
+    pub base_dir: Option<PathBuf>, // Where relative paths start from; set to the emulators root when the configuration is loaded
+}
+
+impl Emulator {
+    /// Whether `path` is a bare command name, without a directory.
+    pub fn is_command_name(&self) -> bool {
+        self.path.components().count() == 1 && !self.path.has_root()
+    }
+
+    /// Returns the emulator executable to run.
+    ///
+    /// A `path` that is a bare command name ("retroarch", "mgba-qt") is looked up on the
+    /// `PATH` like a shell would, so configurations can be shared between machines that install
+    /// emulators in different places; one that isn't on the `PATH` is taken from the emulators
+    /// root (see [`Settings::emulators_root`]), or the current directory. Other relative paths
+    /// start from the emulators root, so a portable drive works wherever it is mounted.
+    pub fn executable(&self) -> PathBuf {
+        if self.is_command_name() {
+            if let Some(found) = first_run::find_executable(&self.path.to_string_lossy()) {
+                return found;
+            }
+            let local = self.resolve(&self.path);
+            // Running a bare name would search the PATH again instead of the directory.
+            if local.is_file() {
+                return std::path::absolute(&local).unwrap_or(local);
+            }
+            return self.path.clone();
+        }
+        self.resolve(&self.path)
+    }
+
+    /// Returns the RetroArch core to load, a relative `core_path` starting from the emulators root.
+    pub fn core(&self) -> Option<PathBuf> {
+        self.core_path.as_deref().map(|core| self.resolve(core))
+    }
+
+    /// Resolves a relative path against the emulators root the configuration was loaded with.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.base_dir {
+            Some(base) if path.is_relative() => base.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
+/// A prepared controller configuration (RetroArch remap file, MAME cfg, ...) that is put in
+/// place before a launch and removed again when the emulator exits.
+///
+/// Without `game` or `system` the profile applies to every launch with its emulator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerProfile {
+    /// The prepared controller config file.
+    pub source: PathBuf,
+    /// Where the emulator expects to find it.
+    pub target: PathBuf,
+    /// Only deploy for this game, matched against the ROM file name or stem (case-insensitive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    /// Only deploy for games of this system id (e.g. "snes").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// Symlink the file into place instead of copying it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub link: bool,
+}
+
+/// A RetroArch config file appended with `--appendconfig`, typically selecting a shader
+/// preset or an overlay/bezel for a system or a single game.
+///
+/// All matching entries are applied, the most specific one last so that it wins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppendConfig {
+    /// The RetroArch config file to append.
+    pub path: PathBuf,
+    /// Only append for this game, matched against the ROM file name or stem (case-insensitive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    /// Only append for games of this system id (e.g. "snes").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+}
+
+/// Fullscreen, monitor and resolution settings for an emulator, e.g. to send a system's games
+/// to a CRT on the second output.
+///
+/// All matching presets are combined; fields set by a more specific preset win.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayPreset {
+    /// Only apply to this game, matched against the ROM file name or stem (case-insensitive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    /// Only apply to games of this system id (e.g. "snes").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fullscreen: Option<bool>,
+    /// Zero-based monitor index (0 is the primary display).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<u32>,
+    /// Fullscreen resolution as "WIDTHxHEIGHT", e.g. "640x480".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+}
+
+/// Emulator settings for games that need more speed or less input lag, e.g. a PCSX2
+/// speedhack profile for a demanding game or RetroArch runahead for fighting games.
+///
+/// Unlike display presets, presets aren't combined: only the most specific matching one is
+/// used for a launch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformancePreset {
+    /// Shown when the preset is used, e.g. "runahead".
+    pub name: String,
+    /// Only use for this game, matched against the ROM file name or stem (case-insensitive).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<String>,
+    /// Only use for games of this system id (e.g. "ps2").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// Prepared config file swapped in over `target` for the launch, e.g. a PCSX2 settings
+    /// ini with speedhacks enabled; the original is put back when the emulator exits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PathBuf>,
+    /// The config file the emulator reads, replaced by `source`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<PathBuf>,
+    /// RetroArch settings for the launch, e.g. `{"run_ahead_enabled": "true",
+    /// "run_ahead_frames": "1"}`, appended after the entry's other append configs.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub retroarch: BTreeMap<String, String>,
+}
+
+/// Represents the overall emulator configuration, containing a list of emulators.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EmulatorConfig {
+    /// The global `settings` section; empty for configurations that are just the emulator array.
+    #[serde(default)]
+    pub settings: Settings,
+    /// The `systems` section: system id to the name of the emulator entry that runs its games,
+    /// e.g. `{ "snes": "RetroArch (Snes9x)", "psx": "DuckStation" }`. Wins over the extension
+    /// lists, so entries can share extensions (".bin", ".iso") without colliding.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub systems: BTreeMap<String, String>,
+    #[serde(rename = "emulators")] // Map JSON root array to a field named "emulators" for clarity
+    pub emulators: Vec<Emulator>,
+}
+
+/// The two accepted layouts of the configuration file.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigFile {
+    /// The original layout: just the array of emulators.
+    Emulators(Vec<Emulator>),
+    /// An object with the emulators and a global settings section.
+    Sections {
+        // Boxed, as the settings are much larger than the other variant.
+        #[serde(default)]
+        settings: Box<Settings>,
+        #[serde(default)]
+        systems: BTreeMap<String, String>,
+        emulators: Vec<Emulator>,
+    },
+}
+
+impl EmulatorConfig {
+    /// Loads emulator configurations from a specified JSON file.
+    ///
+    /// The JSON file is either an array of emulator objects or an object with an `emulators`
+    /// array and `settings` and `systems` sections. A `systems` section naming an unknown
+    /// system or emulator is an error.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the JSON configuration file.
+    ///
+    /// # Returns
+    /// A `Result` containing an `EmulatorConfig` if successful, or an `io::Error` on failure.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Emulator configuration file not found: {}", path.display()),
+            ));
+        }
+
+        let contents = fs::read_to_string(path)?;
+        let config: ConfigFile = serde_json::from_str(&contents)
+            .map_err(|e| {
+                // Saves keep the previous versions, which a broken hand edit can be undone with.
+                let backup = atomic_file::backup_path(path, 1);
+                let hint = if backup.is_file() { format!(" (the previous version is in {})", backup.display()) } else { String::new() };
+                io::Error::new(io::ErrorKind::InvalidData, format!("Failed to parse emulator config JSON: {}{}", e, hint))
+            })?;
+
+        let mut config = match config {
+            ConfigFile::Emulators(emulators) => EmulatorConfig { emulators, ..Default::default() },
+            ConfigFile::Sections { settings, systems, emulators } => EmulatorConfig { emulators, systems, settings: *settings },
+        };
+        for (system, name) in &config.systems {
+            config.check_system_mapping(system, name)?;
+        }
+        let root = config.settings.emulators_root_for(path);
+        for emulator in &mut config.emulators {
+            emulator.base_dir = Some(root.clone());
+        }
+        Ok(config)
+    }
+
+    /// Saves the current emulator configurations to a JSON file.
+    /// Used by the `config` subcommands to edit the configuration.
+    ///
+    /// Without settings or systems the file keeps the plain array layout. The file is replaced
+    /// atomically and its previous versions are kept as `.bak.1` to `.bak.3`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        atomic_file::write_with_backups(path, self.to_json()?)
+    }
+
+    /// Returns the JSON that [`save`](Self::save) writes.
+    pub fn to_json(&self) -> io::Result<String> {
+        let contents = if self.settings == Settings::default() && self.systems.is_empty() {
+            serde_json::to_string_pretty(&self.emulators)
+        } else {
+            serde_json::to_string_pretty(self)
+        };
+        contents
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize emulator config to JSON: {}", e),
+            ))
+    }
+
+    /// Shows what saving would change in the file as a unified diff, then asks before saving,
+    /// since the file may be maintained by hand (`--yes` saves without asking). With
+    /// `dry_run` only the diff is shown.
+    ///
+    /// # Returns
+    /// Whether the file was saved; it isn't when nothing changed, on a dry run, or when the
+    /// user said no.
+    pub fn save_with_preview(&self, path: &Path, dry_run: bool) -> io::Result<bool> {
+        let old = match fs::read_to_string(path) {
+            Ok(old) => old,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let new = self.to_json()?;
+        if old.trim_end() == new.trim_end() {
+            println!("🟰 {} is unchanged.", path.display());
+            return Ok(false);
+        }
+        let name = path.display().to_string();
+        let diff = similar::TextDiff::from_lines(&old, &new);
+        print!("{}", diff.unified_diff().context_radius(3).header(&format!("{} (now)", name), &format!("{} (after saving)", name)));
+        if dry_run {
+            println!("🔍 Dry run, {} was not changed.", path.display());
+            return Ok(false);
+        }
+        if !prompts::confirm(&format!("Save these changes to {}?", path.display()), true) {
+            println!("🚫 Nothing saved.");
+            return Ok(false);
+        }
+        self.save(path)?;
+        Ok(true)
+    }
+
+    /// Creates a mapping from file extension to the preferred emulator.
+    ///
+    /// This allows quick lookup of which emulator to use for a given ROM extension.
+    /// The first emulator listed for an extension wins.
+    pub fn extension_map(&self) -> HashMap<String, &Emulator> {
+        let mut extension_to_emulator: HashMap<String, &Emulator> = HashMap::new();
+        for emulator in &self.emulators {
+            for ext in &emulator.extensions {
+                extension_to_emulator.entry(ext.to_lowercase()).or_insert(emulator);
+            }
+        }
+        extension_to_emulator
+    }
+
+    /// Returns the emulator the `systems` section picks for a game's system, if any.
+    pub fn system_emulator(&self, game: &Game) -> Option<&Emulator> {
+        let system = game.system()?;
+        self.systems
+            .iter()
+            .find(|(id, _)| id.eq_ignore_ascii_case(system.id))
+            .and_then(|(_, name)| self.find_emulator(name))
+    }
+
+    /// Maps a system to the emulator entry that runs its games, or removes its mapping when
+    /// `name` is empty.
+    pub fn set_system_emulator(&mut self, system: &str, name: &str) -> io::Result<()> {
+        let system = system.to_lowercase();
+        if name.is_empty() {
+            self.systems.remove(&system);
+            return Ok(());
+        }
+        self.check_system_mapping(&system, name)?;
+        self.systems.insert(system, name.to_string());
+        Ok(())
+    }
+
+    /// Checks that a `systems` entry names a known system and a configured emulator.
+    fn check_system_mapping(&self, system: &str, name: &str) -> io::Result<()> {
+        if systems::find_by_id(&system.to_lowercase()).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unknown system '{}' in the systems section", system),
+            ));
+        }
+        if self.find_emulator(name).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("The systems section maps {} to '{}', which is not a configured emulator", system, name),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Finds an emulator by name (case-insensitive).
+    pub fn find_emulator(&self, name: &str) -> Option<&Emulator> {
+        self.emulators.iter().find(|e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Finds an emulator by name (case-insensitive) for editing.
+    pub fn find_emulator_mut(&mut self, name: &str) -> Option<&mut Emulator> {
+        self.emulators.iter_mut().find(|e| e.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Adds a new emulator entry, rejecting duplicate names.
+    pub fn add_emulator(&mut self, emulator: Emulator) -> io::Result<()> {
+        if self.emulators.iter().any(|e| e.name.eq_ignore_ascii_case(&emulator.name)) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("An emulator named '{}' already exists", emulator.name),
+            ));
+        }
+        if emulator.extensions.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Emulator '{}' needs at least one extension", emulator.name),
+            ));
+        }
+        self.emulators.push(emulator);
+        Ok(())
+    }
+
+    /// Removes an emulator entry by name (case-insensitive) and returns it, along with the
+    /// `systems` mappings pointing at it.
+    pub fn remove_emulator(&mut self, name: &str) -> io::Result<Emulator> {
+        let index = self.emulators.iter().position(|e| e.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No emulator named '{}' in the configuration", name),
+            ))?;
+        self.systems.retain(|_, mapped| !mapped.eq_ignore_ascii_case(name));
+        Ok(self.emulators.remove(index))
+    }
+
+    /// Sets a single field of an emulator entry from its string form.
+    ///
+    /// Supported fields are `name`, `path`, `extensions` (comma separated), `core_path`,
+    /// `system_name`, `sandbox`, `gpu`, `audio_device`, `audio_driver`, `screenshot_dir`, `cheat_dir`, `nice`, `cpu_affinity` and `min_version`. Optional fields are cleared with an empty value or `null`.
+    pub fn set_field(&mut self, name: &str, field: &str, value: &str) -> io::Result<()> {
+        if field == "name" && !value.eq_ignore_ascii_case(name)
+            && self.emulators.iter().any(|e| e.name.eq_ignore_ascii_case(value))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("An emulator named '{}' already exists", value),
+            ));
+        }
+        let emulator = self.find_emulator_mut(name)
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No emulator named '{}' in the configuration", name),
+            ))?;
+        let optional = |value: &str| {
+            if value.is_empty() || value.eq_ignore_ascii_case("null") { None } else { Some(value.to_string()) }
+        };
+
+        match field {
+            "name" => {
+                let old_name = std::mem::replace(&mut emulator.name, value.to_string());
+                for mapped in self.systems.values_mut().filter(|mapped| mapped.eq_ignore_ascii_case(&old_name)) {
+                    *mapped = value.to_string();
+                }
+            }
+            "path" => emulator.path = PathBuf::from(value),
+            "extensions" => {
+                let extensions: Vec<String> = value
+                    .split(',')
+                    .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                    .filter(|ext| !ext.is_empty())
+                    .collect();
+                if extensions.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "At least one extension is required",
+                    ));
+                }
+                emulator.extensions = extensions;
+            }
+            "core_path" => emulator.core_path = optional(value).map(PathBuf::from),
+            "system_name" => emulator.system_name = optional(value),
+            "sandbox" => {
+                emulator.sandbox = optional(value)
+                    .map(|v| v.parse())
+                    .transpose()
+                    .map_err(|e: String| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            }
+            "audio_device" => emulator.audio_device = optional(value),
+            "audio_driver" => emulator.audio_driver = optional(value),
+            "screenshot_dir" => emulator.screenshot_dir = optional(value).map(PathBuf::from),
+            "cheat_dir" => emulator.cheat_dir = optional(value).map(PathBuf::from),
+            "gpu" => {
+                emulator.gpu = optional(value)
+                    .map(|v| v.parse())
+                    .transpose()
+                    .map_err(|e: String| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            }
+            "nice" => {
+                emulator.nice = optional(value)
+                    .map(|v| v.parse::<i32>().ok().filter(|n| priority::NICE_RANGE.contains(n)).ok_or(format!("Invalid nice value '{}' (expected -20 to 19)", v)))
+                    .transpose()
+                    .map_err(|e: String| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            }
+            "cpu_affinity" => {
+                emulator.cpu_affinity = priority::parse_affinity(value)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            }
+            "min_version" => emulator.min_version = optional(value),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Unknown field '{}' (expected name, path, extensions, core_path, system_name, sandbox, gpu, audio_device, audio_driver, screenshot_dir, cheat_dir, nice, cpu_affinity or min_version)", field),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
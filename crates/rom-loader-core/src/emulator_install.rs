@@ -0,0 +1,266 @@
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Name of the directory next to the emulator configuration that holds installed emulators.
+pub const EMULATORS_DIR_NAME: &str = "emulators";
+
+/// RetroArch release installed from the libretro buildbot.
+const RETROARCH_VERSION: &str = "1.19.1";
+
+/// User agent sent with downloads; the GitHub API rejects requests without one.
+pub(crate) const USER_AGENT: &str = concat!("rom-loader/", env!("CARGO_PKG_VERSION"));
+
+/// Where an emulator's official builds are published.
+#[derive(Debug)]
+enum Source {
+    /// The libretro buildbot's stable builds.
+    RetroArchBuildbot,
+    /// The latest release of a GitHub repository; the asset for a platform is the one whose
+    /// (lower-case) name contains every listed part.
+    GitHubRelease { repo: &'static str, assets: &'static [(&'static str, &'static [&'static str])] },
+}
+
+/// An emulator `install-emulator` knows how to get.
+#[derive(Debug)]
+pub struct InstallableEmulator {
+    /// Name used on the command line, e.g. "mgba".
+    pub id: &'static str,
+    /// Name of the configuration entry.
+    pub name: &'static str,
+    /// ROM extensions the configuration entry handles; empty for RetroArch, whose entries come
+    /// from the installed cores.
+    pub extensions: &'static [&'static str],
+    /// Official download page, for platforms without a build that can be installed here.
+    pub download_page: &'static str,
+    /// Executable names (lower-case) to look for in the unpacked build; AppImages always match.
+    executables: &'static [&'static str],
+    source: Source,
+}
+
+/// Emulators that can be installed.
+pub const INSTALLABLE_EMULATORS: &[InstallableEmulator] = &[
+    InstallableEmulator {
+        id: "retroarch",
+        name: "RetroArch",
+        extensions: &[],
+        download_page: "https://www.retroarch.com/?page=platforms",
+        executables: &["retroarch.exe", "retroarch"],
+        source: Source::RetroArchBuildbot,
+    },
+    InstallableEmulator {
+        id: "mgba",
+        name: "mGBA",
+        extensions: &["gba", "gb"],
+        download_page: "https://mgba.io/downloads.html",
+        executables: &["mgba.exe", "mgba-qt", "mgba"],
+        source: Source::GitHubRelease {
+            repo: "mgba-emu/mgba",
+            assets: &[("linux", &["appimage", "x64"]), ("windows", &["win64", ".7z"])],
+        },
+    },
+    InstallableEmulator {
+        id: "snes9x",
+        name: "Snes9x",
+        extensions: &["sfc", "smc", "snes"],
+        download_page: "https://www.snes9x.com/downloads.php",
+        executables: &["snes9x-x64.exe", "snes9x.exe", "snes9x-gtk", "snes9x"],
+        source: Source::GitHubRelease {
+            repo: "snes9xgit/snes9x",
+            assets: &[("linux", &["x86_64", ".appimage"]), ("windows", &["win32-x64", ".zip"])],
+        },
+    },
+];
+
+/// Finds an installable emulator by id or name (case-insensitive).
+pub fn find(name: &str) -> Option<&'static InstallableEmulator> {
+    INSTALLABLE_EMULATORS
+        .iter()
+        .find(|e| e.id.eq_ignore_ascii_case(name) || e.name.eq_ignore_ascii_case(name))
+}
+
+/// Returns the directory an emulator is installed into, e.g. `emulators/mgba` next to the configuration.
+pub fn install_dir(config_path: &Path, emulator: &InstallableEmulator) -> PathBuf {
+    config_path.with_file_name(EMULATORS_DIR_NAME).join(emulator.id)
+}
+
+/// Builds are only published for 64-bit x86 Linux and Windows by every emulator here.
+pub(crate) fn platform() -> Option<(&'static str, &'static str)> {
+    match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => Some(("linux", "x86_64")),
+        ("windows", "x86_64") => Some(("windows", "x86_64")),
+        _ => None,
+    }
+}
+
+impl InstallableEmulator {
+    /// Returns the download URL of the official build for this platform.
+    ///
+    /// # Returns
+    /// `None` if there is no build for this platform that can be installed automatically.
+    pub fn download_url(&self) -> io::Result<Option<String>> {
+        let Some((os, arch)) = platform() else { return Ok(None) };
+        match &self.source {
+            Source::RetroArchBuildbot => Ok(Some(format!(
+                "https://buildbot.libretro.com/stable/{}/{}/{}/RetroArch.7z",
+                RETROARCH_VERSION, os, arch
+            ))),
+            Source::GitHubRelease { repo, assets } => {
+                let Some((_, parts)) = assets.iter().find(|(asset_os, _)| *asset_os == os) else { return Ok(None) };
+                latest_release_asset(repo, parts)
+            }
+        }
+    }
+
+    /// Downloads the build from `url` and unpacks it into `dir`, replacing an earlier install
+    /// once the new one is complete.
+    ///
+    /// # Returns
+    /// The path of the emulator's executable.
+    pub fn install(&self, url: &str, dir: &Path) -> io::Result<PathBuf> {
+        let staging = dir.with_extension("partial");
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+        let result = self.download_into(url, &staging);
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&staging);
+        }
+        result?;
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        fs::rename(&staging, dir)?;
+        self.find_executable(dir)
+    }
+
+    fn download_into(&self, url: &str, dir: &Path) -> io::Result<()> {
+        let file_name = url.rsplit('/').next().unwrap_or("download");
+        let download = dir.join(file_name);
+        download_file(url, &download)?;
+        if !file_name.to_lowercase().ends_with(".appimage") {
+            unpack(&download, dir)?;
+            fs::remove_file(&download)?;
+        }
+        self.find_executable(dir).map(|_| ())
+    }
+
+    fn find_executable(&self, dir: &Path) -> io::Result<PathBuf> {
+        let executable = WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .find(|path| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+                name.ends_with(".appimage") || self.executables.contains(&name.as_str())
+            })
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No {} executable found in the downloaded build", self.name),
+            ))?;
+        make_executable(&executable)?;
+        Ok(executable)
+    }
+}
+
+/// Downloads a RetroArch core from the libretro buildbot into the `cores` folder of an installed
+/// RetroArch, e.g. `snes9x` for `snes9x_libretro`.
+///
+/// # Returns
+/// The path of the core.
+pub fn install_retroarch_core(retroarch_dir: &Path, core: &str) -> io::Result<PathBuf> {
+    let Some((os, arch)) = platform() else {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("No RetroArch cores are published for {}/{}", env::consts::OS, env::consts::ARCH),
+        ));
+    };
+    let file_name = format!("{}_libretro.{}", core.trim_end_matches("_libretro"), env::consts::DLL_EXTENSION);
+    let url = format!("https://buildbot.libretro.com/nightly/{}/{}/latest/{}.zip", os, arch, file_name);
+    let cores_dir = retroarch_dir.join("cores");
+    fs::create_dir_all(&cores_dir)?;
+    let download = cores_dir.join(format!("{}.zip", file_name));
+    download_file(&url, &download)?;
+    unpack(&download, &cores_dir)?;
+    fs::remove_file(&download)?;
+    let core_path = cores_dir.join(&file_name);
+    if !core_path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} was not in the downloaded archive", file_name),
+        ));
+    }
+    Ok(core_path)
+}
+
+/// Fetches the latest release of a GitHub repository, as returned by the releases API.
+pub(crate) fn latest_release(repo: &str) -> io::Result<serde_json::Value> {
+    let url = format!("https://api.github.com/repos/{}/releases/latest", repo);
+    let body = fetch_text(&url)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not look up the latest release of {}: {}", repo, e)))?;
+    serde_json::from_str(&body)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Unexpected release data: {}", e)))
+}
+
+/// Downloads a (small) text document.
+pub(crate) fn fetch_text(url: &str) -> io::Result<String> {
+    ureq::get(url)
+        .header("User-Agent", USER_AGENT)
+        .call()
+        .map_err(io::Error::other)?
+        .body_mut()
+        .read_to_string()
+        .map_err(io::Error::other)
+}
+
+/// Looks up the download URL of the latest release asset whose name contains all `parts`.
+fn latest_release_asset(repo: &str, parts: &[&str]) -> io::Result<Option<String>> {
+    let release = latest_release(repo)?;
+    let assets = release["assets"].as_array().cloned().unwrap_or_default();
+    Ok(assets.iter().find_map(|asset| {
+        let name = asset["name"].as_str()?.to_lowercase();
+        if !parts.iter().all(|part| name.contains(part)) {
+            return None;
+        }
+        asset["browser_download_url"].as_str().map(str::to_string)
+    }))
+}
+
+pub(crate) fn download_file(url: &str, target: &Path) -> io::Result<()> {
+    let mut response = ureq::get(url)
+        .header("User-Agent", USER_AGENT)
+        .call()
+        .map_err(|e| io::Error::other(format!("Download of {} failed: {}", url, e)))?;
+    let mut file = File::create(target)?;
+    io::copy(&mut response.body_mut().as_reader(), &mut file)?;
+    Ok(())
+}
+
+/// Unpacks a zip or 7z archive into `dir`.
+pub(crate) fn unpack(archive: &Path, dir: &Path) -> io::Result<()> {
+    let is_7z = archive.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("7z"));
+    if is_7z {
+        sevenz_rust2::decompress_file(archive, dir).map_err(io::Error::other)
+    } else {
+        zip::ZipArchive::new(File::open(archive)?)
+            .and_then(|mut zip| zip.extract(dir))
+            .map_err(io::Error::other)
+    }
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
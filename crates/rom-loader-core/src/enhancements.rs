@@ -0,0 +1,118 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::rom_scanner::{Game, GameKind};
+use crate::systems;
+
+/// Extensions of soft patches RetroArch applies when they sit next to a ROM with its name.
+const SOFT_PATCH_EXTENSIONS: &[&str] = &["ips", "bps", "ups"];
+
+/// RetroArch SNES cores (by file name part) that don't play MSU-1 audio.
+const CORES_WITHOUT_MSU1: &[&str] = &["snes9x2002", "snes9x2005", "snes9x2010"];
+
+/// Files that belong to a SNES ROM and are found next to it by name: an MSU-1 pack
+/// (`Game.msu`, `Game-1.pcm`, `Game-2.pcm`, ... and its `Game.bml` or `Game.xml` manifest)
+/// and soft patches (`Game.bps`).
+///
+/// Emulators look these up next to the ROM they are given, so they must stay next to it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Companions {
+    /// The MSU-1 data file.
+    pub msu_data: Option<PathBuf>,
+    /// MSU-1 audio tracks, in track order.
+    pub msu_tracks: Vec<PathBuf>,
+    /// Manifest describing the cartridge to bsnes-family emulators.
+    pub manifest: Option<PathBuf>,
+    pub patches: Vec<PathBuf>,
+}
+
+impl Companions {
+    /// Finds the companion files of a single-file SNES game.
+    ///
+    /// # Returns
+    /// `None` for other games and for ROMs without companions.
+    pub fn find(game: &Game) -> Option<Companions> {
+        if game.kind != GameKind::File || game.system().is_none_or(|s| s.id != "snes") {
+            return None;
+        }
+        let stem = game.path.file_stem()?.to_string_lossy().to_lowercase();
+        let dir = game.path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let mut companions = Companions::default();
+        let mut tracks: Vec<(u32, PathBuf)> = Vec::new();
+        for path in fs::read_dir(dir).ok()?.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let (Some(file_stem), Some(extension)) = (path.file_stem(), path.extension()) else { continue };
+            let file_stem = file_stem.to_string_lossy().to_lowercase();
+            let extension = extension.to_string_lossy().to_lowercase();
+            if file_stem == stem {
+                match extension.as_str() {
+                    "msu" => companions.msu_data = Some(path),
+                    "bml" | "xml" => companions.manifest = Some(path),
+                    ext if SOFT_PATCH_EXTENSIONS.contains(&ext) => companions.patches.push(path),
+                    _ => {}
+                }
+            } else if extension == "pcm" {
+                // Game-12.pcm is track 12.
+                let track = file_stem.strip_prefix(&stem).and_then(|rest| rest.strip_prefix('-')).and_then(|n| n.parse().ok());
+                if let Some(track) = track {
+                    tracks.push((track, path));
+                }
+            }
+        }
+        tracks.sort();
+        companions.msu_tracks = tracks.into_iter().map(|(_, path)| path).collect();
+        companions.patches.sort();
+        (companions != Companions::default()).then_some(companions)
+    }
+
+    /// Whether the ROM comes with an MSU-1 pack.
+    pub fn has_msu1(&self) -> bool {
+        self.msu_data.is_some() || !self.msu_tracks.is_empty()
+    }
+
+    /// Every companion file.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.msu_data.iter()
+            .chain(&self.msu_tracks)
+            .chain(&self.manifest)
+            .chain(&self.patches)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Finds an MSU-1 data file next to a SNES ROM that has another name than the ROM, which
+/// emulators won't pick up (e.g. `alttp_msu.msu` next to `Zelda (USA).sfc`).
+///
+/// # Returns
+/// `None` if the ROM has its own pack, or the folder has no single other pack.
+pub fn misnamed_msu1_pack(game: &Game) -> Option<PathBuf> {
+    if game.kind != GameKind::File || game.system().is_none_or(|s| s.id != "snes") {
+        return None;
+    }
+    if Companions::find(game).is_some_and(|c| c.has_msu1()) {
+        return None;
+    }
+    let dir = game.path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let files: Vec<PathBuf> = fs::read_dir(dir).ok()?.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    let has_extension = |path: &Path, extensions: &[&str]| {
+        path.extension().is_some_and(|e| extensions.iter().any(|ext| e.eq_ignore_ascii_case(ext)))
+    };
+    let rom_extensions = systems::find_by_id("snes").map_or(&[][..], |s| s.extensions);
+    // A pack named like another ROM in the folder belongs to that ROM.
+    let packs: Vec<PathBuf> = files
+        .iter()
+        .filter(|p| has_extension(p, &["msu"]))
+        .filter(|pack| !files.iter().any(|f| has_extension(f, rom_extensions) && f.file_stem() == pack.file_stem()))
+        .cloned()
+        .collect();
+    match packs.as_slice() {
+        [pack] => Some(pack.clone()),
+        _ => None,
+    }
+}
+
+/// Whether a RetroArch core (by its path) is known not to support MSU-1.
+pub fn core_lacks_msu1(core_path: &Path) -> bool {
+    let core = core_path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    CORES_WITHOUT_MSU1.iter().any(|name| core.contains(name))
+}
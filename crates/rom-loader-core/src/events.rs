@@ -0,0 +1,78 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// How events are written for other programs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EventFormat {
+    /// One JSON object per line.
+    Ndjson,
+}
+
+/// Where events for other programs go, shared by the command-line tools.
+#[derive(clap::Args, Debug, Default)]
+pub struct EventOptions {
+    /// Write events (scan_started, rom_found, launch_started, emulator_exited) to --events-to,
+    /// for other programs such as stream overlays.
+    #[arg(long, value_name = "FORMAT", global = true, env = "ROM_LOADER_EVENTS", requires = "events_to")]
+    pub events: Option<EventFormat>,
+
+    /// File or FIFO to write --events to; a FIFO waits for a reader.
+    #[arg(long, value_name = "PATH", global = true, env = "ROM_LOADER_EVENTS_TO", requires = "events")]
+    pub events_to: Option<PathBuf>,
+}
+
+impl EventOptions {
+    /// Starts writing events if they were asked for.
+    pub fn open(&self) -> io::Result<()> {
+        match (self.events, &self.events_to) {
+            (Some(format), Some(path)) => open(format, path),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Something that happened, for wrappers such as stream overlays or home dashboards to react to.
+///
+/// Each is written as one object with the event's name in `event`, an RFC 3339 `time` and its
+/// fields, e.g. `{"event":"rom_found","path":"roms/nes/Alter Ego.nes","system":"nes","time":...}`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    ScanStarted { roms_dir: &'a Path },
+    RomFound { path: &'a Path, system: Option<&'a str> },
+    LaunchStarted { rom: &'a Path, emulator: &'a str, pid: u32 },
+    /// `code` is `None` when the emulator was ended by a signal.
+    EmulatorExited { rom: &'a Path, emulator: &'a str, code: Option<i32>, success: bool, seconds: u64 },
+}
+
+static SINK: OnceLock<Mutex<Box<dyn Write + Send>>> = OnceLock::new();
+
+/// Starts writing events to `path`. Only the first call has an effect; without a call, [`emit`]
+/// does nothing.
+///
+/// Events never go to stdout, where they would be mixed with the usual output. `path` may be
+/// a FIFO (`mkfifo`), in which case this waits until a reader opens it. Otherwise events are
+/// appended to the file, which is created if needed.
+pub fn open(format: EventFormat, path: &Path) -> io::Result<()> {
+    let EventFormat::Ndjson = format;
+    let sink = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not open {} for events: {}", path.display(), e)))?;
+    let _ = SINK.set(Mutex::new(Box::new(sink)));
+    Ok(())
+}
+
+/// Writes an event if events were asked for. A reader that went away is not an error.
+pub fn emit(event: Event) {
+    let Some(sink) = SINK.get() else { return };
+    let Ok(Value::Object(mut line)) = serde_json::to_value(&event) else { return };
+    line.insert("time".to_string(), Value::String(chrono::Local::now().to_rfc3339()));
+    let Ok(mut sink) = sink.lock() else { return };
+    let _ = writeln!(sink, "{}", Value::Object(line)).and_then(|_| sink.flush());
+}
@@ -0,0 +1,163 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::atomic_file;
+use crate::emulator_config::Emulator;
+use crate::settings::Settings;
+use crate::systems::SYSTEMS;
+
+/// Standalone emulators looked for on the `PATH`: executable names, entry name and extensions.
+const STANDALONE_EMULATORS: &[(&[&str], &str, &[&str])] = &[
+    (&["mame"], "MAME", &["zip", "7z"]),
+    (&["dosbox", "dosbox-x", "dosbox-staging"], "DOSBox", &["dos"]),
+    (&["scummvm"], "ScummVM", &["scummvm"]),
+    (&["mgba-qt", "mgba"], "mGBA", &["gba", "gb"]),
+    (&["snes9x-gtk", "snes9x"], "Snes9x", &["sfc", "smc", "snes"]),
+    (&["rpcs3"], "RPCS3", &["ps3"]),
+    (&["cemu", "Cemu"], "Cemu", &["wud", "wux", "wua", "wiiu"]),
+    // Both play music rips through Game Music Emu (mpv when its ffmpeg is built with libgme).
+    (&["audacious", "mpv"], "Music player", &["nsf", "nsfe", "spc", "gbs", "vgm", "vgz", "gym", "hes", "kss", "ay", "sap"]),
+];
+
+/// Explanation written at the top of a starter configuration, since JSON has no comments.
+const STARTER_COMMENT: &[&str] = &[
+    "Starter configuration written by rom-loader. Edit it freely or use 'rom-loader config'.",
+    "Each emulator entry needs a name, the executable path and the ROM extensions it handles;",
+    "the first entry listing an extension is suggested for it. RetroArch entries also take a",
+    "core_path, e.g. { \"name\": \"RetroArch\", \"path\": \"/usr/bin/retroarch\",",
+    "\"extensions\": [\"sfc\"], \"core_path\": \"/usr/lib/libretro/snes9x_libretro.so\" }.",
+    "A \"systems\" section next to \"emulators\" picks an entry per system whatever the",
+    "extension, e.g. \"systems\": { \"psx\": \"DuckStation\" } ('rom-loader config set-system').",
+    "Relative paths start from this file's folder, or from emulators_root (itself relative to",
+    "this file) when set, so a portable drive works wherever it is mounted.",
+    "Settings: roms_dirs (used when --roms-dir is not given), cache_dir, cache_max_mb (the",
+    "least recently used preloaded images and unpacked ROMs go first), emulators_root, bios_dirs, region_priority,",
+    "language_priority (e.g. [\"en\", \"es\"], picks between language variants of a game),",
+    "disabled_extensions (e.g. [\"bin\"] when all .bin files are BIOS dumps), hash_threads",
+    "(files hashed at once; 'rom-loader bench hash' shows the fastest count), homebrew_sources",
+    "(URLs or files of homebrew feeds for 'rom-loader discover homebrew'), ui",
+    "(ui.list sets the columns, title, date_format and max_width of the game list;",
+    "ui.time_limit_minutes closes a game after that long, for cabinets at events),",
+    "large_images ({ \"confirm_above_mb\": 2048, \"preload\": true } asks before launching big",
+    "disc images and copies them into the cache first), gpio ({ \"next\": 17, \"previous\": 27,",
+    "\"launch\": 22, \"exit\": 23 } maps buttons on a Raspberry Pi's GPIO pins to the browser,",
+    "in builds with the gpio feature).",
+];
+
+/// Layout of a starter configuration, with the comment first.
+#[derive(Serialize)]
+struct StarterConfig<'a> {
+    #[serde(rename = "_comment")]
+    comment: &'a [&'a str],
+    settings: Settings,
+    emulators: &'a [Emulator],
+}
+
+/// Looks for emulators installed on this computer.
+///
+/// RetroArch gets one entry per installed core that the system registry suggests, handling
+/// every system that core is suggested for; standalone emulators are found on the `PATH`.
+pub fn detect_emulators() -> Vec<Emulator> {
+    let mut emulators = Vec::new();
+    if let Some(retroarch) = find_executable("retroarch") {
+        let core_dirs = retroarch_core_dirs(&retroarch);
+        for (core, extensions) in default_core_extensions() {
+            let file_name = format!("{}.{}", core, env::consts::DLL_EXTENSION);
+            let Some(core_path) = core_dirs.iter().map(|dir| dir.join(&file_name)).find(|p| p.is_file()) else { continue };
+            emulators.push(Emulator {
+                name: format!("RetroArch ({})", core.trim_end_matches("_libretro")),
+                path: retroarch.clone(),
+                extensions,
+                core_path: Some(core_path),
+                ..Default::default()
+            });
+        }
+    }
+    for (executables, name, extensions) in STANDALONE_EMULATORS {
+        if let Some(path) = executables.iter().find_map(|exe| find_executable(exe)) {
+            emulators.push(Emulator {
+                name: name.to_string(),
+                path,
+                extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+                ..Default::default()
+            });
+        }
+    }
+    emulators
+}
+
+/// Groups the extensions of every system by the RetroArch core suggested for it, e.g.
+/// `snes9x_libretro` → `["sfc", "smc"]`.
+pub fn default_core_extensions() -> BTreeMap<&'static str, Vec<String>> {
+    let mut cores: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for system in SYSTEMS {
+        let Some(core) = system.default_core else { continue };
+        let extensions = cores.entry(core).or_default();
+        if system.extensions.is_empty() {
+            // Directory-based games are matched by their system id.
+            extensions.push(system.id.to_string());
+        }
+        extensions.extend(system.extensions.iter().map(|ext| ext.to_string()));
+    }
+    cores
+}
+
+/// Writes a starter configuration with the given emulators and an explanatory comment.
+///
+/// Fails if the file already exists, so a hand-maintained configuration is never replaced.
+pub fn write_starter_config(path: &Path, emulators: &[Emulator]) -> io::Result<()> {
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    let starter = StarterConfig { comment: STARTER_COMMENT, settings: Settings::default(), emulators };
+    let contents = serde_json::to_string_pretty(&starter)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to serialize starter config to JSON: {}", e),
+        ))?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    atomic_file::write(path, contents)
+}
+
+/// Finds an executable on the `PATH`, like `which` (or `where` on Windows, where ".exe" is
+/// added to names without an extension).
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    let file_name = Path::new(name);
+    env::split_paths(&path).find_map(|dir| {
+        let mut candidate = dir.join(file_name);
+        if candidate.extension().is_none() {
+            candidate.set_extension(env::consts::EXE_EXTENSION);
+        }
+        candidate.is_file().then_some(candidate)
+    })
+}
+
+/// Directories RetroArch cores are commonly installed in.
+fn retroarch_core_dirs(retroarch: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    // Portable installs (and Windows) keep the cores next to the executable.
+    if let Some(dir) = retroarch.parent() {
+        dirs.push(dir.join("cores"));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        dirs.push(home.join(".config/retroarch/cores"));
+        dirs.push(home.join(".var/app/org.libretro.RetroArch/config/retroarch/cores"));
+        dirs.push(home.join("Library/Application Support/RetroArch/cores"));
+    }
+    if let Some(app_data) = env::var_os("APPDATA") {
+        dirs.push(PathBuf::from(app_data).join("RetroArch/cores"));
+    }
+    dirs.extend(["/usr/lib/libretro", "/usr/lib/x86_64-linux-gnu/libretro", "/usr/lib/aarch64-linux-gnu/libretro", "/usr/local/lib/libretro"].map(PathBuf::from));
+    dirs
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::hashing;
+use crate::rom_scanner::Game;
+
+/// Characters of a game ID: lowercase letters and digits, leaving out the easily confused
+/// i, l, o, 0 and 1. The letters come first.
+const ID_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// Number of letters at the start of [`ID_ALPHABET`].
+const ID_LETTERS: u32 = 23;
+
+/// Length of a full game ID; enough characters to hold the whole 64-bit path hash.
+const ID_LENGTH: usize = 14;
+
+/// The fewest characters of an ID that are shown and accepted.
+pub const MIN_SHORT_ID_LENGTH: usize = 4;
+
+/// Returns a game's full ID, derived from its path inside the ROMs directory.
+///
+/// The ID stays the same across rescans and when other games are added or removed, unlike
+/// its number in the list; it only changes when the game is renamed or moved. It always starts
+/// with a letter, so it can't be mistaken for a list number.
+pub fn game_id(roms_dir: &Path, game: &Game) -> String {
+    let relative = game.path.strip_prefix(roms_dir).unwrap_or(&game.path);
+    // Joined with '/' so a library shared between Windows and Linux has the same IDs.
+    let key: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+    let mut hash = hashing::fnv1a_64(key.join("/").as_bytes());
+    let mut id = String::with_capacity(ID_LENGTH);
+    id.push(ID_ALPHABET[(hash % ID_LETTERS as u64) as usize] as char);
+    hash /= ID_LETTERS as u64;
+    for _ in 1..ID_LENGTH {
+        id.push(ID_ALPHABET[(hash % ID_ALPHABET.len() as u64) as usize] as char);
+        hash /= ID_ALPHABET.len() as u64;
+    }
+    id
+}
+
+/// The IDs of the games of a library, in list order.
+pub struct GameIds {
+    ids: Vec<String>,
+    /// Length of the shortest prefix of each ID no other ID starts with (at least
+    /// [`MIN_SHORT_ID_LENGTH`]).
+    short_lengths: Vec<usize>,
+}
+
+impl GameIds {
+    pub fn new(roms_dir: &Path, games: &[Game]) -> Self {
+        let ids: Vec<String> = games.iter().map(|game| game_id(roms_dir, game)).collect();
+        let mut short_lengths = vec![MIN_SHORT_ID_LENGTH; ids.len()];
+        // In sorted order, the ID sharing the longest prefix with an ID is one of its neighbours.
+        let mut order: Vec<usize> = (0..ids.len()).collect();
+        order.sort_by(|&a, &b| ids[a].cmp(&ids[b]));
+        for pair in order.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let common = ids[a].bytes().zip(ids[b].bytes()).take_while(|(x, y)| x == y).count();
+            let needed = (common + 1).min(ID_LENGTH);
+            short_lengths[a] = short_lengths[a].max(needed);
+            short_lengths[b] = short_lengths[b].max(needed);
+        }
+        GameIds { ids, short_lengths }
+    }
+
+    /// The shortest form of the ID of the game at `index` that tells it apart from the other
+    /// games of the library.
+    pub fn short(&self, index: usize) -> &str {
+        &self.ids[index][..self.short_lengths[index]]
+    }
+
+    /// Finds the game whose ID starts with `prefix`.
+    ///
+    /// # Returns
+    /// The game's index, or a message saying no game or several games have such an ID.
+    pub fn find(&self, prefix: &str) -> Result<usize, String> {
+        let mut matches = self.ids.iter().enumerate().filter(|(_, id)| id.starts_with(prefix)).map(|(i, _)| i);
+        match (matches.next(), matches.next()) {
+            (Some(index), None) => Ok(index),
+            (None, _) => Err(format!("No game has the ID {}.", prefix)),
+            (Some(_), Some(_)) => Err(format!("Several games have an ID starting with {}; give more of it.", prefix)),
+        }
+    }
+}
+
+/// A game as named on the command line: by its number in the list or by its ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameRef {
+    /// 1-based number in the game list.
+    Number(usize),
+    /// An ID or the start of one, at least [`MIN_SHORT_ID_LENGTH`] characters long.
+    Id(String),
+}
+
+impl GameRef {
+    /// Finds the game this refers to among the games of a library.
+    ///
+    /// # Returns
+    /// The game's index, or a message saying why there is no such game.
+    pub fn resolve(&self, roms_dir: &Path, games: &[Game]) -> Result<usize, String> {
+        match self {
+            GameRef::Number(number) => number
+                .checked_sub(1)
+                .filter(|&i| i < games.len())
+                .ok_or_else(|| format!("There is no ROM number {} (found {} ROMs).", number, games.len())),
+            GameRef::Id(prefix) => GameIds::new(roms_dir, games).find(prefix),
+        }
+    }
+}
+
+impl FromStr for GameRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+            return s.parse().map(GameRef::Number).map_err(|_| format!("'{}' is too large for a ROM number", s));
+        }
+        let id = s.to_ascii_lowercase();
+        let valid = id.len() >= MIN_SHORT_ID_LENGTH
+            && id.len() <= ID_LENGTH
+            && id.bytes().all(|c| ID_ALPHABET.contains(&c))
+            && id.as_bytes()[0].is_ascii_alphabetic();
+        if !valid {
+            return Err(format!("'{}' is neither a ROM number nor a game ID (as shown by 'info')", s));
+        }
+        Ok(GameRef::Id(id))
+    }
+}
+
+impl fmt::Display for GameRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameRef::Number(number) => write!(f, "{}", number),
+            GameRef::Id(id) => write!(f, "{}", id),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use rppal::gpio::{Gpio, InputPin, Trigger};
+use std::io;
+use std::time::Duration;
+
+use crate::interrupt;
+use crate::settings::GpioPins;
+
+/// Presses closer together than this on one button count once, as switches bounce.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+
+/// What a cabinet button does in the interactive browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonAction {
+    Next,
+    Previous,
+    Launch,
+    Exit,
+}
+
+/// Cabinet buttons being watched; they stop being watched when this is dropped.
+pub struct Buttons {
+    _pins: Vec<InputPin>,
+}
+
+impl Buttons {
+    /// Watches the configured buttons and calls `on_press` from a background thread for each
+    /// press.
+    ///
+    /// Buttons are expected to connect their pin to ground, so the pins are pulled up and a
+    /// press is a falling edge. Presses while an emulator runs are ignored, as the buttons
+    /// may double as game controls.
+    pub fn watch<F>(pins: &GpioPins, on_press: F) -> io::Result<Self>
+    where
+        F: Fn(ButtonAction) + Clone + Send + 'static,
+    {
+        let gpio = Gpio::new()
+            .map_err(|e| io::Error::other(format!("Could not open the GPIO header: {}", e)))?;
+        let buttons = [
+            (pins.next, ButtonAction::Next),
+            (pins.previous, ButtonAction::Previous),
+            (pins.launch, ButtonAction::Launch),
+            (pins.exit, ButtonAction::Exit),
+        ];
+        let mut watched = Vec::new();
+        for (pin, action) in buttons {
+            let Some(pin) = pin else { continue };
+            let on_press = on_press.clone();
+            let mut input = gpio
+                .get(pin)
+                .map_err(|e| io::Error::other(format!("Could not open GPIO pin {}: {}", pin, e)))?
+                .into_input_pullup();
+            input
+                .set_async_interrupt(Trigger::FallingEdge, Some(DEBOUNCE), move |_| {
+                    if !interrupt::child_running() {
+                        on_press(action);
+                    }
+                })
+                .map_err(|e| io::Error::other(format!("Could not watch GPIO pin {}: {}", pin, e)))?;
+            watched.push(input);
+        }
+        Ok(Buttons { _pins: watched })
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Which GPU an emulator should render on in hybrid graphics (laptop) setups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuPreference {
+    /// The dedicated, more powerful GPU (PRIME render offload).
+    Discrete,
+    /// The integrated, power-saving GPU.
+    Integrated,
+}
+
+impl FromStr for GpuPreference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "discrete" | "dedicated" => Ok(GpuPreference::Discrete),
+            "integrated" => Ok(GpuPreference::Integrated),
+            other => Err(format!("Unknown GPU preference '{}' (expected discrete or integrated)", other)),
+        }
+    }
+}
+
+impl GpuPreference {
+    /// Expands the preference to the environment variables that select the GPU on this platform.
+    ///
+    /// On Linux the NVIDIA proprietary driver uses its own render offload variables (the same
+    /// ones `prime-run` sets); Mesa drivers use `DRI_PRIME`. Other platforms pick the GPU
+    /// through system settings, so nothing is returned there.
+    pub fn env(self) -> Vec<(String, String)> {
+        if !cfg!(target_os = "linux") {
+            eprintln!("⚠️ Warning: the 'gpu' setting only has an effect on Linux; choose the GPU in the system graphics settings instead.");
+            return Vec::new();
+        }
+        let vars: &[(&str, &str)] = match self {
+            GpuPreference::Discrete if Path::new("/proc/driver/nvidia").exists() => &[
+                ("__NV_PRIME_RENDER_OFFLOAD", "1"),
+                ("__GLX_VENDOR_LIBRARY_NAME", "nvidia"),
+                ("__VK_LAYER_NV_optimus", "NVIDIA_only"),
+            ],
+            GpuPreference::Discrete => &[("DRI_PRIME", "1")],
+            GpuPreference::Integrated => &[("DRI_PRIME", "0")],
+        };
+        vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+}
@@ -0,0 +1,105 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Size of the buffer used when reading files to hash them.
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Computes the CRC32 of a file, as used by No-Intro and libretro databases.
+///
+/// # Returns
+///
+/// The checksum as an 8 digit upper-case hex string (e.g. "B19ED489").
+pub fn crc32_file(path: &Path) -> io::Result<String> {
+    crc32_reader(&mut File::open(path)?)
+}
+
+/// 64-bit FNV-1a, which unlike std's hashers is the same in every build and on every platform;
+/// for keys derived from paths, not for checking file contents.
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+/// Archive formats whose members can be hashed instead of the archive itself.
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "7z"];
+
+/// A file inside an archive and its CRC32.
+#[derive(Debug, Clone)]
+pub struct ArchiveMember {
+    pub name: String,
+    pub crc32: String,
+}
+
+/// Whether a file is an archive whose members can be hashed with [`archive_crc32s`].
+pub fn is_archive(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ARCHIVE_EXTENSIONS.iter().any(|a| ext.eq_ignore_ascii_case(a)))
+}
+
+/// Computes the CRC32 of every file inside a zip or 7z archive, sorted by name.
+///
+/// Members are decompressed and hashed rather than trusting the CRC recorded in the archive,
+/// so a damaged archive is reported instead of looking intact.
+pub fn archive_crc32s(path: &Path) -> io::Result<Vec<ArchiveMember>> {
+    let is_7z = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("7z"));
+    let mut members = if is_7z { sevenz_crc32s(path)? } else { zip_crc32s(path)? };
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(members)
+}
+
+/// Computes the CRC32 that identifies a ROM.
+///
+/// With `inner`, archives are hashed by their contents, as No-Intro and MAME dats expect: the
+/// CRC32 of the single ROM inside, or the CRC32s of all members joined with `+` (sorted by name)
+/// for multi-file archives. Everything else, and empty archives, are hashed as a whole.
+pub fn content_crc32(path: &Path, inner: bool) -> io::Result<String> {
+    if !inner || !is_archive(path) {
+        return crc32_file(path);
+    }
+    let members = archive_crc32s(path)?;
+    if members.is_empty() {
+        return crc32_file(path);
+    }
+    Ok(members.iter().map(|m| m.crc32.as_str()).collect::<Vec<_>>().join("+"))
+}
+
+fn zip_crc32s(path: &Path) -> io::Result<Vec<ArchiveMember>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?).map_err(io::Error::other)?;
+    let mut members = Vec::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().to_string();
+        members.push(ArchiveMember { name, crc32: crc32_reader(&mut entry)? });
+    }
+    Ok(members)
+}
+
+fn sevenz_crc32s(path: &Path) -> io::Result<Vec<ArchiveMember>> {
+    let mut archive = sevenz_rust2::ArchiveReader::open(path, sevenz_rust2::Password::empty())
+        .map_err(io::Error::other)?;
+    let mut members = Vec::new();
+    archive
+        .for_each_entries(|entry, reader| {
+            if !entry.is_directory && entry.has_stream {
+                members.push(ArchiveMember { name: entry.name.clone(), crc32: crc32_reader(reader)? });
+            }
+            Ok(true)
+        })
+        .map_err(io::Error::other)?;
+    Ok(members)
+}
+
+fn crc32_reader(reader: &mut dyn Read) -> io::Result<String> {
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:08X}", hasher.finalize()))
+}
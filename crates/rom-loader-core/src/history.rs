@@ -0,0 +1,111 @@
+use chrono::{Local, TimeZone};
+use std::fmt::Write as _;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// File name of the launch history, stored next to the emulator configuration.
+pub const HISTORY_FILE_NAME: &str = "launch_history.jsonl";
+
+/// A single recorded emulator launch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unix timestamp (seconds) of when the launch started.
+    pub timestamp: u64,
+    pub rom: PathBuf,
+    pub emulator: String,
+    pub success: bool,
+    #[serde(default)]
+    pub exit_code: Option<i32>, // None when the process was killed by a signal or never started
+    #[serde(default)]
+    pub error: Option<String>, // Set when the emulator could not be started at all
+    pub duration_secs: u64,
+}
+
+/// Append-only launch history, stored as one JSON object per line.
+pub struct LaunchHistory {
+    path: PathBuf,
+}
+
+impl LaunchHistory {
+    /// Creates a history backed by the given file.
+    pub fn new(path: PathBuf) -> Self {
+        LaunchHistory { path }
+    }
+
+    /// Creates the history that lives next to the given emulator configuration file.
+    pub fn for_config(config_path: &Path) -> Self {
+        Self::new(config_path.with_file_name(HISTORY_FILE_NAME))
+    }
+
+    /// Appends an entry to the history file, creating it if needed.
+    pub fn append(&self, entry: &HistoryEntry) -> io::Result<()> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize history entry: {}", e),
+            ))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", line)
+    }
+
+    /// Loads all entries, oldest first.
+    ///
+    /// A missing history file is treated as an empty history, and malformed lines
+    /// (e.g. from an interrupted write) are skipped with a warning.
+    pub fn load(&self) -> io::Result<Vec<HistoryEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&self.path)?;
+        let mut entries = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => eprintln!("⚠️ Skipping malformed history line {} in {}: {}", i + 1, self.path.display(), e),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Returns the current time as a Unix timestamp in seconds.
+pub fn now_timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Formats a Unix timestamp in local time for display.
+pub fn format_timestamp(timestamp: u64) -> String {
+    match Local.timestamp_opt(timestamp as i64, 0).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// Formats a Unix timestamp in local time with a chrono format string such as "%d.%m.%Y",
+/// falling back to [`format_timestamp`] if the format is invalid.
+pub fn format_timestamp_with(timestamp: u64, format: &str) -> String {
+    let Some(time) = Local.timestamp_opt(timestamp as i64, 0).single() else { return timestamp.to_string() };
+    let mut formatted = String::new();
+    // Invalid format strings only show up as an error while formatting.
+    match write!(formatted, "{}", time.format(format)) {
+        Ok(()) => formatted,
+        Err(_) => format_timestamp(timestamp),
+    }
+}
+
+/// Formats a duration in seconds as a short human readable string (e.g. "1h 05m", "3m 12s").
+pub fn format_duration(secs: u64) -> String {
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
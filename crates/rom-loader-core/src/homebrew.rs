@@ -0,0 +1,183 @@
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::cache::{Cache, DOWNLOADS_DIR_NAME};
+use crate::emulator_install::{download_file, fetch_text, unpack};
+use crate::metadata::GameMetadata;
+use crate::{hashing, systems};
+
+/// Folder of the library that `discover homebrew` downloads into, with a folder per system.
+pub const HOMEBREW_DIR_NAME: &str = "homebrew";
+
+/// A free game listed in a homebrew feed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HomebrewEntry {
+    pub title: String,
+    /// System id, e.g. "nes".
+    pub system: String,
+    /// Direct download of the ROM, or of a zip or 7z file holding it.
+    pub url: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub year: Option<String>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Terms the game is shared under, e.g. "CC BY-NC 4.0" or "freeware".
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+/// A feed is a JSON array of entries, or an object with a `games` array (and anything else,
+/// such as a name, alongside).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Feed {
+    List(Vec<HomebrewEntry>),
+    Named { games: Vec<HomebrewEntry> },
+}
+
+/// Reads a homebrew feed from a URL or a local file, e.g. a list kept from itch.io or
+/// romhacking.net pages:
+///
+/// ```json
+/// { "name": "NES picks", "games": [ { "title": "Alter Ego", "system": "nes",
+///   "url": "https://example.org/alter_ego.zip", "author": "Shiru", "year": "2011" } ] }
+/// ```
+pub fn load_feed(source: &str) -> io::Result<Vec<HomebrewEntry>> {
+    let text = if source.starts_with("http://") || source.starts_with("https://") {
+        fetch_text(source)?
+    } else {
+        fs::read_to_string(source)?
+    };
+    let feed: Feed = serde_json::from_str(&text)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Not a homebrew feed: {}", e)))?;
+    Ok(match feed {
+        Feed::List(games) | Feed::Named { games } => games,
+    })
+}
+
+/// Returns the folder a feed entry is downloaded into: `homebrew/<system>` in the library.
+pub fn target_dir(roms_dir: &Path, entry: &HomebrewEntry) -> PathBuf {
+    roms_dir.join(HOMEBREW_DIR_NAME).join(entry.system.to_lowercase())
+}
+
+/// Returns the name of the file a URL points to, without any query string and with escapes
+/// such as `%20` decoded.
+pub(crate) fn download_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let name = path.rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("download");
+    let mut bytes = Vec::new();
+    let mut rest = name.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail.get(..2).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    // Separators and dot names in escapes would lead out of the download folder.
+    let name = String::from_utf8_lossy(&bytes).replace(['/', '\\'], "_");
+    if name.trim_matches('.').is_empty() {
+        return "download".to_string();
+    }
+    name
+}
+
+/// Downloads a feed entry into the library.
+///
+/// Zip and 7z downloads are unpacked and their ROMs for the entry's system kept, since the
+/// library only looks into archives for arcade sets. The download is unpacked in the cache,
+/// so only finished ROMs ever show up in the library; ROMs already there are never replaced.
+///
+/// # Returns
+/// The downloaded ROMs.
+pub fn download(entry: &HomebrewEntry, roms_dir: &Path, cache: &Cache) -> io::Result<Vec<PathBuf>> {
+    let system = systems::find_by_id(&entry.system.to_lowercase()).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown system '{}'", entry.system))
+    })?;
+    let dir = target_dir(roms_dir, entry);
+    let staging = cache.dir().join(DOWNLOADS_DIR_NAME).join(format!("homebrew-{}", std::process::id()));
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    let result = download_into(entry, system, &dir, &staging);
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+fn download_into(entry: &HomebrewEntry, system: &systems::System, dir: &Path, staging: &Path) -> io::Result<Vec<PathBuf>> {
+    let file_name = download_name(&entry.url);
+    let download = staging.join(&file_name);
+    download_file(&entry.url, &download)?;
+    let is_rom = |path: &Path| {
+        path.extension().is_some_and(|ext| system.extensions.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+    };
+    let found: Vec<PathBuf> = if hashing::is_archive(&download) && system.id != "arcade" {
+        let unpacked = staging.join("unpacked");
+        unpack(&download, &unpacked)?;
+        WalkDir::new(&unpacked)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && is_rom(e.path()))
+            .map(|e| e.into_path())
+            .collect()
+    } else if is_rom(&download) {
+        vec![download]
+    } else {
+        Vec::new()
+    };
+    if found.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} holds no {} ROM", file_name, system.name),
+        ));
+    }
+    let moves: Vec<(PathBuf, PathBuf)> = found
+        .into_iter()
+        .map(|path| {
+            let target = dir.join(path.file_name().unwrap_or_default());
+            (path, target)
+        })
+        .collect();
+    if let Some((_, target)) = moves.iter().find(|(_, target)| target.exists()) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} is already in the library", target.display())));
+    }
+    fs::create_dir_all(dir)?;
+    let mut roms = Vec::new();
+    for (path, target) in moves {
+        // The cache may be on another drive than the library.
+        if fs::rename(&path, &target).is_err() {
+            fs::copy(&path, &target)?;
+        }
+        roms.push(target);
+    }
+    Ok(roms)
+}
+
+/// Returns the metadata recorded for a downloaded ROM, which marks it as homebrew.
+pub fn metadata_for(entry: &HomebrewEntry, rom: &Path) -> GameMetadata {
+    GameMetadata {
+        title: entry.title.clone(),
+        rom_name: rom.file_name().map(|name| name.to_string_lossy().into_owned()),
+        crc: hashing::crc32_file(rom).ok(),
+        description: entry.description.clone(),
+        year: entry.year.clone(),
+        genre: entry.genre.clone(),
+        developer: entry.author.clone(),
+        homebrew: true,
+        ..GameMetadata::default()
+    }
+}
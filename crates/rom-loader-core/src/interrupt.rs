@@ -0,0 +1,134 @@
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+/// Set by Ctrl-C while long-running work is in progress, which checks it and stops early.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Number of [`Busy`] guards alive.
+static BUSY: AtomicUsize = AtomicUsize::new(0);
+
+/// Process id of the running emulator, 0 if none.
+static CHILD: AtomicU32 = AtomicU32::new(0);
+
+/// The terminal settings at startup (`stty -g`), restored on the way out.
+static TERMINAL: OnceLock<Option<String>> = OnceLock::new();
+
+/// Exit status of a program stopped by Ctrl-C, as shells report it.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Installs the Ctrl-C (and termination signal) handler.
+///
+/// While work marked with [`Busy`] runs (a scan, a verification, an emulator session), the
+/// first Ctrl-C only asks it to stop, so state files are still written and launch hooks
+/// still run; the emulator gets the Ctrl-C from the terminal itself. A second Ctrl-C, or one
+/// while idle at a prompt, kills the running emulator, restores the terminal and exits.
+pub fn install() -> io::Result<()> {
+    TERMINAL.get_or_init(save_terminal);
+    ctrlc::set_handler(|| {
+        if BUSY.load(Ordering::SeqCst) > 0 && !INTERRUPTED.swap(true, Ordering::SeqCst) {
+            eprintln!("\n⏹️ Stopping... (press Ctrl-C again to quit right away)");
+            return;
+        }
+        kill_child();
+        restore_terminal();
+        eprintln!("\n👋 Interrupted.");
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    })
+    .map_err(io::Error::other)
+}
+
+/// Whether Ctrl-C asked the current work to stop.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// The error long-running work returns when it stops for Ctrl-C.
+pub fn error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "Interrupted by Ctrl-C")
+}
+
+/// Marks work that the first Ctrl-C should stop gracefully rather than exit the program.
+///
+/// The interrupt request is cleared when the outermost guard is created, so a Ctrl-C that
+/// stopped one scan doesn't stop the next.
+pub struct Busy(());
+
+impl Busy {
+    pub fn start() -> Self {
+        if BUSY.fetch_add(1, Ordering::SeqCst) == 0 {
+            INTERRUPTED.store(false, Ordering::SeqCst);
+        }
+        Busy(())
+    }
+}
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        BUSY.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Remembers the running emulator, so a forced quit doesn't leave it behind; 0 clears it.
+pub fn set_child(pid: u32) {
+    CHILD.store(pid, Ordering::SeqCst);
+}
+
+/// Whether an emulator is running.
+pub fn child_running() -> bool {
+    CHILD.load(Ordering::SeqCst) != 0
+}
+
+fn kill_child() {
+    let pid = CHILD.swap(0, Ordering::SeqCst);
+    if pid != 0 {
+        kill(pid);
+    }
+}
+
+/// Kills a process (and on Windows its child processes) without waiting for it to exit cleanly.
+pub(crate) fn kill(pid: u32) {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("taskkill");
+        command.args(["/F", "/T", "/PID", &pid.to_string()]);
+        command
+    } else {
+        let mut command = Command::new("kill");
+        command.args(["-KILL", &pid.to_string()]);
+        command
+    };
+    let _ = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
+
+/// Asks a process to quit, as closing its window would, so it can save before exiting.
+#[cfg(feature = "launch")]
+pub(crate) fn terminate(pid: u32) {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("taskkill");
+        command.args(["/T", "/PID", &pid.to_string()]);
+        command
+    } else {
+        let mut command = Command::new("kill");
+        command.args(["-TERM", &pid.to_string()]);
+        command
+    };
+    let _ = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
+
+/// Reads the terminal settings, so an emulator or interrupted prompt that leaves the terminal
+/// in raw mode or without echo can be undone.
+fn save_terminal() -> Option<String> {
+    if cfg!(windows) || !io::stdin().is_terminal() {
+        return None;
+    }
+    let output = Command::new("stty").arg("-g").stdin(Stdio::inherit()).stderr(Stdio::null()).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Puts the terminal settings back as they were at startup.
+pub fn restore_terminal() {
+    if let Some(Some(settings)) = TERMINAL.get() {
+        let _ = Command::new("stty").arg(settings).stdin(Stdio::inherit()).stderr(Stdio::null()).status();
+    }
+}
@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{atomic_file, history};
+
+/// File name of the undo journal, stored next to the emulator configuration.
+pub const JOURNAL_FILE_NAME: &str = "undo_journal.json";
+
+/// Directory next to the configuration holding copies of files that batches replaced.
+pub const UNDO_DIR_NAME: &str = "undo";
+
+/// Number of batches kept; older ones can no longer be undone and their copies are removed.
+const MAX_BATCHES: usize = 20;
+
+/// One change to the library, recorded so it can be reverted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    /// A file's contents were replaced; `backup` holds the previous contents.
+    Replaced { path: PathBuf, backup: PathBuf },
+    /// A new file was written.
+    Created { path: PathBuf },
+    /// A file or directory was moved or renamed.
+    Moved { from: PathBuf, to: PathBuf },
+    /// A file or directory was moved to the system trash.
+    Trashed { path: PathBuf },
+}
+
+impl Operation {
+    /// Makes every path absolute, so undo works from any working directory (and matches the
+    /// absolute paths the trash records).
+    fn absolute(self) -> io::Result<Self> {
+        Ok(match self {
+            Operation::Replaced { path, backup } => Operation::Replaced { path: std::path::absolute(path)?, backup: std::path::absolute(backup)? },
+            Operation::Created { path } => Operation::Created { path: std::path::absolute(path)? },
+            Operation::Moved { from, to } => Operation::Moved { from: std::path::absolute(from)?, to: std::path::absolute(to)? },
+            Operation::Trashed { path } => Operation::Trashed { path: std::path::absolute(path)? },
+        })
+    }
+
+    /// Reverts the operation.
+    fn undo(&self) -> io::Result<()> {
+        match self {
+            Operation::Replaced { path, backup } => {
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                let staging = path.with_file_name(format!(".{}.restoring", file_name));
+                fs::copy(backup, &staging)?;
+                fs::rename(&staging, path).inspect_err(|_| {
+                    let _ = fs::remove_file(&staging);
+                })
+            }
+            Operation::Created { path } => match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+            Operation::Moved { from, to } => {
+                if from.exists() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} exists again", from.display()),
+                    ));
+                }
+                if let Some(parent) = from.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(to, from)
+            }
+            Operation::Trashed { path } => restore_from_trash(path),
+        }
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Replaced { path, .. } => write!(f, "restore {}", path.display()),
+            Operation::Created { path } => write!(f, "remove {}", path.display()),
+            Operation::Moved { from, to } => write!(f, "move {} back to {}", to.display(), from.display()),
+            Operation::Trashed { path } => write!(f, "restore {} from the trash", path.display()),
+        }
+    }
+}
+
+/// The changes made by one command, e.g. one `strip-headers` or `delete` run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Batch {
+    /// Unix timestamp of the command.
+    pub timestamp: u64,
+    /// The command, as shown by `undo`.
+    pub command: String,
+    pub operations: Vec<Operation>,
+}
+
+impl Batch {
+    /// Directory holding the copies this batch made of replaced files.
+    pub fn backup_dir(&self, config_path: &Path) -> PathBuf {
+        config_path.with_file_name(UNDO_DIR_NAME).join(self.timestamp.to_string())
+    }
+}
+
+/// The recent batches of file changes, most recent last.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    #[serde(default)]
+    pub batches: Vec<Batch>,
+}
+
+impl Journal {
+    /// Returns the journal path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(JOURNAL_FILE_NAME)
+    }
+
+    /// Loads the journal, returning an empty journal if nothing was recorded yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(Journal::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse undo journal {}: {}", path.display(), e),
+            ))
+    }
+
+    /// Saves the journal as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize undo journal to JSON: {}", e),
+            ))?;
+        atomic_file::write(path, contents)
+    }
+}
+
+/// Records the changes of one command into the journal as they happen.
+///
+/// The journal is saved after every operation, so a batch interrupted halfway (a crash, a full
+/// disk) can still be undone up to where it got. Batches without operations are not saved.
+pub struct BatchRecorder {
+    config_path: PathBuf,
+    journal: Journal,
+}
+
+impl BatchRecorder {
+    /// Starts a batch for `command` in the journal next to `config_path`.
+    pub fn begin(config_path: &Path, command: &str) -> io::Result<Self> {
+        let mut journal = Journal::load(&Journal::path_for_config(config_path))?;
+        let mut timestamp = history::now_timestamp();
+        // Two batches in the same second would share a backup directory.
+        if let Some(last) = journal.batches.last() {
+            timestamp = timestamp.max(last.timestamp + 1);
+        }
+        journal.batches.push(Batch { timestamp, command: command.to_string(), operations: Vec::new() });
+        Ok(BatchRecorder { config_path: config_path.to_path_buf(), journal })
+    }
+
+    fn batch(&mut self) -> &mut Batch {
+        self.journal.batches.last_mut().expect("a batch is started in begin")
+    }
+
+    /// Records an operation that was just carried out and saves the journal.
+    pub fn record(&mut self, operation: Operation) -> io::Result<()> {
+        let operation = operation.absolute()?;
+        self.batch().operations.push(operation);
+        if self.journal.batches.len() > MAX_BATCHES {
+            let dropped: Vec<Batch> = self.journal.batches.drain(..self.journal.batches.len() - MAX_BATCHES).collect();
+            for batch in dropped {
+                let _ = fs::remove_dir_all(batch.backup_dir(&self.config_path));
+            }
+        }
+        self.journal.save(&Journal::path_for_config(&self.config_path))
+    }
+
+    /// Copies a file into the batch's backup directory before it is changed, so undo can put
+    /// the old contents back.
+    ///
+    /// # Returns
+    /// The operation to record once the file has been changed.
+    pub fn back_up(&mut self, path: &Path) -> io::Result<Operation> {
+        let config_path = self.config_path.clone();
+        let dir = self.batch().backup_dir(&config_path);
+        fs::create_dir_all(&dir)?;
+        // Number the copies so files with the same name in different folders don't collide.
+        let backup = dir.join(format!("{}-{}", self.batch().operations.len(), path.file_name().unwrap_or_default().to_string_lossy()));
+        fs::copy(path, &backup)?;
+        Ok(Operation::Replaced { path: path.to_path_buf(), backup })
+    }
+}
+
+/// What undoing a batch did.
+#[derive(Debug)]
+pub struct UndoReport {
+    pub batch: Batch,
+    /// Every operation, most recent first, with the outcome of reverting it.
+    pub results: Vec<(Operation, io::Result<()>)>,
+}
+
+/// Reverts the most recent batch in the journal next to `config_path`, last operation first.
+///
+/// Operations that could not be reverted stay in the journal, so `undo` can be retried once
+/// the problem is fixed; a fully reverted batch is removed together with its backups.
+///
+/// # Returns
+/// `None` if there is nothing to undo.
+pub fn undo_last(config_path: &Path) -> io::Result<Option<UndoReport>> {
+    let journal_path = Journal::path_for_config(config_path);
+    let mut journal = Journal::load(&journal_path)?;
+    let Some(batch) = journal.batches.pop() else { return Ok(None) };
+    let results: Vec<(Operation, io::Result<()>)> = batch.operations
+        .iter()
+        .rev()
+        .map(|operation| (operation.clone(), operation.undo()))
+        .collect();
+    let failed: Vec<Operation> = results.iter().rev().filter(|(_, r)| r.is_err()).map(|(op, _)| op.clone()).collect();
+    if failed.is_empty() {
+        let _ = fs::remove_dir_all(batch.backup_dir(config_path));
+    } else {
+        journal.batches.push(Batch { operations: failed, ..batch.clone() });
+    }
+    journal.save(&journal_path)?;
+    Ok(Some(UndoReport { batch, results }))
+}
+
+#[cfg(any(windows, all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))))]
+fn restore_from_trash(path: &Path) -> io::Result<()> {
+    let items = trash::os_limited::list().map_err(io::Error::other)?;
+    // The most recently trashed item, in case the same path was deleted more than once.
+    let item = items
+        .into_iter()
+        .filter(|item| item.original_path() == path)
+        .max_by_key(|item| item.time_deleted)
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} is no longer in the trash", path.display()),
+        ))?;
+    trash::os_limited::restore_all([item]).map_err(io::Error::other)
+}
+
+#[cfg(not(any(windows, all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android")))))]
+fn restore_from_trash(path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Restoring from the trash is not supported here; put {} back from the trash by hand", path.display()),
+    ))
+}
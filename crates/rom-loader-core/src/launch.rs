@@ -0,0 +1,505 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::cache::Cache;
+use crate::emulator_config::{Emulator, EmulatorConfig};
+use crate::history::{self, HistoryEntry, LaunchHistory};
+use crate::launch_hooks::LaunchHooks;
+use crate::macros::CommandInput;
+use crate::metadata::MetadataDb;
+use crate::rom_format::{self, N64ByteOrder};
+use crate::rom_launcher::{self, LaunchOptions};
+use crate::rom_scanner::{Game, GameKind};
+use crate::sessions::{self, SessionFile};
+use crate::settings::Settings;
+use crate::state_store::StateStore;
+use crate::systems::{self, System};
+use crate::{arcade, first_run, interrupt, preflight, preload, prompts, quirks, screenshots};
+
+/// Settings for a single launch, on top of the emulator's configuration.
+#[derive(clap::Args, Debug, Default)]
+pub struct LaunchOverrides {
+    /// Resume from this save state slot (RetroArch, MAME).
+    #[arg(long, value_name = "SLOT")]
+    pub load_state: Option<u32>,
+
+    /// Start in fullscreen, overriding display presets.
+    #[arg(long)]
+    pub start_fullscreen: bool,
+
+    /// Record the session to this file (RetroArch, MAME).
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Put the game's cheat file into the emulator's cheat_dir for this launch.
+    #[arg(long)]
+    pub cheats: bool,
+
+    /// Arguments appended to the emulator command, given after `--` (e.g. `launch 3 -- --verbose`).
+    #[arg(last = true, value_name = "EMULATOR_ARGS")]
+    pub emulator_args: Vec<String>,
+}
+
+impl LaunchOverrides {
+    /// Layers the overrides on top of the launch options built from the configuration.
+    pub fn apply(&self, options: &mut LaunchOptions) {
+        if self.start_fullscreen {
+            options.display.fullscreen = Some(true);
+        }
+        if self.load_state.is_some() {
+            options.load_state = self.load_state;
+        }
+        if self.record.is_some() {
+            options.record = self.record.clone();
+        }
+        options.extra_args.extend(self.emulator_args.iter().cloned());
+    }
+}
+
+
+/// Loads the emulator configuration, reporting errors the same way everywhere.
+///
+/// When there is no configuration yet and someone is at the terminal (or `--yes` or
+/// `--no-input` answers for them), offers to write a starter configuration with the emulators
+/// found on this computer instead.
+pub fn load_emulator_config(config_path: &Path) -> Option<EmulatorConfig> {
+    match EmulatorConfig::load(config_path) {
+        Ok(config) => {
+            println!("✅ Loaded emulator configuration from: {}", config_path.display());
+            Some(config)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound && (io::stdin().is_terminal() || !prompts::interactive()) => {
+            eprintln!("📭 No emulator configuration found at {}.", config_path.display());
+            if !offer_starter_config(config_path) {
+                return None;
+            }
+            EmulatorConfig::load(config_path)
+                .inspect_err(|e| eprintln!("❌ Error loading emulator configuration from {}: {}", config_path.display(), e))
+                .ok()
+        }
+        Err(e) => {
+            eprintln!("❌ Error loading emulator configuration from {}: {}", config_path.display(), e);
+            eprintln!("Please ensure 'emulators.json' exists and is correctly formatted.");
+            if e.kind() == io::ErrorKind::NotFound {
+                eprintln!("💡 'rom-loader config init' writes a starter configuration.");
+            }
+            None
+        }
+    }
+}
+
+/// Asks whether to write a starter configuration and writes it.
+///
+/// # Returns
+/// `true` if the configuration was written.
+fn offer_starter_config(config_path: &Path) -> bool {
+    let emulators = first_run::detect_emulators();
+    match emulators.len() {
+        0 => println!("🔍 No emulators were found on this computer; the starter configuration will be empty."),
+        count => {
+            let names: Vec<&str> = emulators.iter().map(|e| e.name.as_str()).collect();
+            println!("🔍 Found {} emulators: {}", count, names.join(", "));
+        }
+    }
+    if !prompts::confirm(&format!("Create a starter configuration at {}?", config_path.display()), true) {
+        return false;
+    }
+    match first_run::write_starter_config(config_path, &emulators) {
+        Ok(()) => {
+            println!("📝 Wrote a starter configuration to {}; edit it or use 'rom-loader config' to add emulators.", config_path.display());
+            true
+        }
+        Err(e) => {
+            eprintln!("❌ Could not write the starter configuration: {}", e);
+            false
+        }
+    }
+}
+
+/// Picks the emulator to suggest for a game.
+///
+/// A preference learned from repeated overrides wins, then the emulator the configuration's
+/// `systems` section maps the game's system to, then the static extension mapping. For a
+/// file whose system was detected from its contents (a `.bin` disc track or cartridge), the
+/// first emulator for its extension that also handles that system is preferred over the first
+/// one listing the extension.
+pub fn suggested_emulator<'a>(
+    game: &Game,
+    config: &'a EmulatorConfig,
+    extension_to_emulator: &HashMap<String, &'a Emulator>,
+    state: &StateStore,
+) -> Option<&'a Emulator> {
+    let emulators = &config.emulators;
+    if let Some(name) = state.preferred_emulator(&game.path, game.system().map(|s| s.id)) {
+        if let Some(emulator) = emulators.iter().find(|e| e.name == name) {
+            return Some(emulator);
+        }
+    }
+    if let Some(emulator) = config.system_emulator(game) {
+        return Some(emulator);
+    }
+    if let (Some(system), Some(rom_extension)) = (game.detected_system.and_then(systems::find_by_id), game.get_extension()) {
+        let fits = emulators.iter().find(|e| {
+            e.extensions.iter().any(|ext| ext.eq_ignore_ascii_case(rom_extension))
+                && e.extensions.iter().any(|ext| handles_system(ext, system))
+        });
+        if fits.is_some() {
+            return fits;
+        }
+    }
+    game.get_extension()
+        .and_then(|ext| extension_to_emulator.get(&ext.to_lowercase()).copied())
+}
+
+/// Whether an emulator extension entry points at a system: one of the system's own
+/// extensions, its id, or a cue sheet for the disc-based systems.
+fn handles_system(extension: &str, system: &System) -> bool {
+    let extension = extension.to_lowercase();
+    extension == system.id
+        || system.extensions.iter().any(|ext| *ext != "bin" && *ext == extension)
+        || (matches!(system.id, "psx" | "segacd") && extension == "cue")
+}
+
+/// Returns the emulators that could launch a game, the suggested one first.
+///
+/// The suggestion is followed by the emulator the `systems` section maps the game's system to,
+/// every other emulator configured for the game's extension and, with `include_all`, by the
+/// remaining emulators.
+pub fn emulator_candidates<'a>(
+    game: &Game,
+    config: &'a EmulatorConfig,
+    suggested: Option<&'a Emulator>,
+    include_all: bool,
+) -> Vec<&'a Emulator> {
+    let rom_extension = game.get_extension().unwrap_or("");
+    let mut candidates: Vec<&Emulator> = suggested.into_iter().chain(config.system_emulator(game)).collect();
+    candidates.dedup_by(|a, b| a.name == b.name);
+    let capable = config.emulators.iter().filter(|e| e.extensions.iter().any(|ext| ext.eq_ignore_ascii_case(rom_extension)));
+    let others = config.emulators.iter().filter(|_| include_all);
+    for emulator in capable.chain(others) {
+        if !candidates.iter().any(|c| c.name == emulator.name) {
+            candidates.push(emulator);
+        }
+    }
+    candidates
+}
+
+/// Asks the user which of several capable emulators to use. Pressing Enter accepts the first one, which is the suggestion if there is one.
+///
+/// With `--yes` or `--no-input` the first one is taken without asking.
+pub fn prompt_emulator_choice<'a>(
+    candidates: &[&'a Emulator],
+    suggested: Option<&Emulator>,
+    command_input: &mut CommandInput,
+) -> io::Result<&'a Emulator> {
+    println!("🎮 Several emulators can run this game:");
+    for (i, emulator) in candidates.iter().enumerate() {
+        let marker = if suggested.is_some_and(|s| s.name == emulator.name) { " (suggested)" } else { "" };
+        println!("  {}. {}{}", i + 1, emulator.name, marker);
+    }
+    if !prompts::interactive() {
+        println!("Choose an emulator [Enter = 1]: 1");
+        return Ok(candidates[0]);
+    }
+    loop {
+        print!("Choose an emulator [Enter = 1]: ");
+        io::stdout().flush()?;
+
+        let input = command_input.read_line()?;
+        if input.is_empty() {
+            return Ok(candidates[0]);
+        }
+        match input.parse::<usize>() {
+            Ok(num) if num > 0 && num <= candidates.len() => return Ok(candidates[num - 1]),
+            _ => println!("🚫 Invalid choice. Enter a number between 1 and {}.", candidates.len()),
+        }
+    }
+}
+
+/// Where an emulator the user picked from several is remembered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RememberChoice {
+    /// Not at all; the choice is for this launch only.
+    No,
+    /// For every game of the game's system, in the configuration's `systems` section.
+    System,
+    /// For this game, in the state store.
+    Game,
+}
+
+/// Asks whether an emulator picked from several should be used for the game's whole system,
+/// for just this game, or only this time. Pressing Enter keeps it for this time only.
+///
+/// Without a terminal to ask on (`--yes`, `--no-input`) nothing is remembered.
+pub fn prompt_remember_choice(emulator: &Emulator, game: &Game, command_input: &mut CommandInput) -> io::Result<RememberChoice> {
+    if !prompts::interactive() {
+        return Ok(RememberChoice::No);
+    }
+    let system = game.system();
+    loop {
+        match system {
+            Some(system) => print!("🧠 Remember {} for [s] all {} games, [g] this game, or [Enter] not at all? ", emulator.name, system.name),
+            None => print!("🧠 Remember {} for [g] this game, or [Enter] not at all? ", emulator.name),
+        }
+        io::stdout().flush()?;
+        match command_input.read_line()?.to_lowercase().as_str() {
+            "" | "n" | "no" => return Ok(RememberChoice::No),
+            "s" if system.is_some() => return Ok(RememberChoice::System),
+            "g" => return Ok(RememberChoice::Game),
+            _ => println!("🚫 Answer {}g or press Enter.", if system.is_some() { "s, " } else { "" }),
+        }
+    }
+}
+
+/// Launches a game with the given emulator.
+///
+/// Launch hooks run around the emulator process, the launch is recorded in the history and
+/// screenshots taken during the session are collected into the game's asset folder.
+/// Arcade clones whose parent set is missing are flagged first, as MAME will fail to load them,
+/// and the launch is checked with [`preflight::check`], stopping if it can't work.
+/// `overrides` adjust this launch only (save state, recording, extra arguments, ...).
+pub fn launch_game(emulator: &Emulator, game: &Game, config_path: &Path, metadata: &MetadataDb, overrides: &LaunchOverrides) {
+    println!("Launching {} with {}...",
+        game.path.file_name().unwrap_or_default().to_string_lossy(),
+        emulator.name
+    );
+    if let Some(parent) = metadata.lookup(game).and_then(|m| arcade::missing_parent(game, m)) {
+        eprintln!("⚠️ Warning: this is a clone, but the parent set '{}' is not next to it; with split or merged sets MAME won't find all ROMs.",
+                  parent
+        );
+    }
+    if game.system().is_some_and(|s| s.id == "n64") && rom_format::requires_big_endian_n64(&emulator.name, &emulator.path) {
+        if let Ok(Some(order)) = rom_format::n64_byte_order(&game.path) {
+            if order != N64ByteOrder::Z64 {
+                eprintln!("⚠️ Warning: {} only loads big-endian ROMs, but this one is {}; 'convert --to z64' fixes it.",
+                          emulator.name,
+                          order.extension()
+                );
+            }
+        }
+    }
+    let settings = Settings::load(config_path).unwrap_or_default();
+    let problems = preflight::check(emulator, game, game.launch_path(), &settings.bios_dirs);
+    if !problems.is_empty() {
+        let fatal = problems.iter().any(|p| p.fatal);
+        eprintln!("{} {} with {}:",
+            if fatal { "❌ Can't launch" } else { "⚠️ Problems launching" },
+            game.path.file_name().unwrap_or_default().to_string_lossy(),
+            emulator.name
+        );
+        for problem in &problems {
+            eprintln!("   • {}", problem);
+        }
+        if fatal {
+            return;
+        }
+    }
+    let Some(launch_path) = prepare_large_image(game, config_path, &settings) else {
+        println!("🚫 Launch cancelled.");
+        return;
+    };
+    // 'doctor' found the emulator can't open zipped ROMs itself.
+    let is_zip = launch_path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+    let launch_path = if is_zip && emulator.capabilities.as_ref().is_some_and(|c| !c.zip) {
+        match preload::unzip(&launch_path, &Cache::for_config(config_path, &settings)) {
+            Ok(unzipped) => unzipped,
+            Err(e) => {
+                eprintln!("❌ Could not unpack {} for {}: {}", launch_path.display(), emulator.name, e);
+                return;
+            }
+        }
+    } else {
+        launch_path
+    };
+    let launch_path = if quirks::for_emulator(emulator).strip_copier_header && rom_format::has_copier_header(&launch_path).unwrap_or(false) {
+        match preload::without_copier_header(&launch_path, &Cache::for_config(config_path, &settings)) {
+            Ok(unheadered) => {
+                println!("📦 {} rejects ROMs with a copier header; launching a copy without it.", emulator.name);
+                unheadered
+            }
+            Err(e) => {
+                eprintln!("⚠️ Could not copy the ROM without its copier header, launching it as it is: {}", e);
+                launch_path
+            }
+        }
+    } else {
+        launch_path
+    };
+    let system_name = effective_system_name(emulator, game);
+    let mut hooks = LaunchHooks::before_launch(emulator, game);
+    overrides.apply(hooks.options_mut());
+    hooks.options_mut().session_file = Some(SessionFile::path_for_config(config_path));
+    if overrides.cheats {
+        hooks.deploy_cheats(emulator, game, config_path);
+    }
+    // Ctrl-C reaches the emulator through the terminal; rom-loader stays to clean up after it.
+    let _busy = interrupt::Busy::start();
+    let started_at = history::now_timestamp();
+    let timer = Instant::now();
+    // Pass emulator name, core path, AND system name for specific handling
+    let result = rom_launcher::launch_rom(
+        &emulator.executable(),
+        &launch_path,
+        &emulator.name,
+        emulator.core().as_ref(),
+        system_name.as_ref(),
+        hooks.options(),
+    );
+    hooks.after_exit();
+    if interrupt::requested() {
+        interrupt::restore_terminal();
+    }
+    if let Some(screenshot_dir) = &emulator.screenshot_dir {
+        let target_dir = screenshots::screenshots_dir(config_path, game);
+        match screenshots::harvest(screenshot_dir, &target_dir, started_at) {
+            Ok(harvested) if !harvested.is_empty() => {
+                println!("📸 Collected {} screenshots into {}", harvested.len(), target_dir.display());
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("⚠️ Could not collect screenshots: {}", e),
+        }
+    }
+    let mut entry = HistoryEntry {
+        timestamp: started_at,
+        rom: game.path.clone(),
+        emulator: emulator.name.clone(),
+        success: false,
+        exit_code: None,
+        error: None,
+        duration_secs: timer.elapsed().as_secs(),
+    };
+    match result {
+        Ok(status) => {
+            entry.success = status.success();
+            entry.exit_code = status.code();
+            if status.success() {
+                println!("✅ Launch command sent.");
+            } else {
+                eprintln!("❌ Emulator exited with {}", status);
+            }
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to launch emulator: {}", e);
+            entry.error = Some(e.to_string());
+        }
+    }
+    if let Err(e) = LaunchHistory::for_config(config_path).append(&entry) {
+        eprintln!("⚠️ Could not record launch history: {}", e);
+    }
+}
+
+/// Looks for emulators left running by an earlier rom-loader that crashed or was killed, so
+/// a new launch doesn't end up stacked on top of an old fullscreen window.
+///
+/// With `reap` (or `--yes`) they are stopped right away; otherwise the user is asked when
+/// stdin is a terminal, and warned when it isn't.
+pub fn check_orphans(config_path: &Path, reap: bool) {
+    let path = SessionFile::path_for_config(config_path);
+    let orphans = match sessions::find_orphans(&path) {
+        Ok(orphans) if !orphans.is_empty() => orphans,
+        Ok(_) => return,
+        Err(e) => {
+            eprintln!("⚠️ Could not check for emulators left running: {}", e);
+            return;
+        }
+    };
+    println!("👻 Emulators left running by an earlier session:");
+    for orphan in &orphans {
+        println!("  - {} (process {}) running {}", orphan.emulator, orphan.pid, orphan.game.display());
+    }
+    let mut reap = reap;
+    if !reap {
+        if !io::stdin().is_terminal() && prompts::automatic_answer(false) != Some(true) {
+            eprintln!("⚠️ Stop them by hand, or start with --reap-orphans to stop them automatically.");
+            return;
+        }
+        reap = prompts::confirm("Stop them?", false);
+    }
+    if reap {
+        for orphan in &orphans {
+            match sessions::reap(&path, orphan) {
+                Ok(()) => println!("🧹 Stopped {} (process {}).", orphan.emulator, orphan.pid),
+                Err(e) => eprintln!("⚠️ Could not update the session file: {}", e),
+            }
+        }
+    }
+}
+
+/// Warns before launching a disc image larger than `large_images.confirm_above_mb` and, with
+/// `large_images.preload`, copies it into the cache directory so the emulator reads it locally.
+///
+/// Someone at the terminal is asked first; otherwise (or with `--yes` or `--no-input`) the
+/// launch goes ahead as configured.
+///
+/// # Returns
+/// The path to launch, or `None` if the launch was cancelled.
+fn prepare_large_image(game: &Game, config_path: &Path, settings: &Settings) -> Option<PathBuf> {
+    let launch_path = game.launch_path().to_path_buf();
+    let Some(limit_mb) = settings.large_images.confirm_above_mb else { return Some(launch_path) };
+    if game.kind != GameKind::File {
+        return Some(launch_path);
+    }
+    let size = fs::metadata(&launch_path).map_or(0, |m| m.len());
+    if size <= limit_mb * 1024 * 1024 {
+        return Some(launch_path);
+    }
+
+    println!("⚠️ {} is {}; read from slow media, the emulator may freeze while it loads.",
+        game.path.file_name().unwrap_or_default().to_string_lossy(),
+        preload::format_size(size)
+    );
+    let mut copy = settings.large_images.preload;
+    if io::stdin().is_terminal() && prompts::interactive() {
+        if copy {
+            print!("Copy it to the local cache first? [Y/n = launch directly, c = cancel]: ");
+        } else {
+            print!("Launch it anyway? [Y/n, p = copy it to the local cache first]: ");
+        }
+        let mut answer = String::new();
+        if io::stdout().flush().and_then(|_| io::stdin().read_line(&mut answer)).is_err() {
+            return None;
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "n" | "no" if !copy => return None,
+            "c" | "cancel" => return None,
+            "n" | "no" => copy = false,
+            "p" => copy = true,
+            _ => {}
+        }
+    }
+    if !copy {
+        return Some(launch_path);
+    }
+    match preload::preload(&launch_path, &Cache::for_config(config_path, settings)) {
+        Ok(local) => Some(local),
+        Err(e) => {
+            eprintln!("⚠️ Could not copy the image to the cache, launching it from its library: {}", e);
+            Some(launch_path)
+        }
+    }
+}
+
+/// Returns the MAME system name to launch a game with.
+///
+/// MAME entries without an explicit system name fall back to the registry's short name.
+pub fn effective_system_name(emulator: &Emulator, game: &Game) -> Option<String> {
+    emulator.system_name.clone().or_else(|| {
+        game.system()
+            .and_then(|s| s.mame_system)
+            .map(|s| s.to_string())
+    })
+}
+
+/// Explains that no emulator is configured for a game, suggesting a RetroArch core when one is known.
+pub fn report_missing_emulator(game: &Game) {
+    eprintln!("❌ No configured emulator found for '{}' files.", game.get_extension().unwrap_or(""));
+    eprintln!("Please add an entry to your 'emulators.json' for this ROM type.");
+    if let Some(system) = game.system() {
+        if let Some(core) = system.default_core {
+            eprintln!("💡 {} ROMs run well with the RetroArch core '{}'.", system.name, core);
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::capture::CaptureSession;
+use crate::cheats;
+use crate::emulator_config::{ControllerProfile, DisplayPreset, Emulator, PerformancePreset};
+use crate::rom_launcher::{self, LaunchOptions, LaunchProfile};
+use crate::rom_scanner::Game;
+use crate::{quirks, sandbox};
+
+/// Suffix of the backup made when a deployment replaces an existing file.
+const BACKUP_SUFFIX: &str = "rom_loader.bak";
+
+/// A file put in place for a single launch, remembering what it replaced.
+#[derive(Debug)]
+pub struct FileDeployment {
+    target: PathBuf,
+    backup: Option<PathBuf>,
+}
+
+impl FileDeployment {
+    /// Copies (or symlinks) `source` to `target`, backing up any existing file at `target`.
+    ///
+    /// If a backup from an earlier, interrupted session is still around, the file at `target`
+    /// is one of our own deployments and the backup holds the user's original, so the backup
+    /// is kept rather than overwritten.
+    ///
+    /// `earlier` are the deployments already made for this launch. If one of them replaced
+    /// `target`, the backup is that deployment's to restore, so this one doesn't claim it.
+    pub fn deploy(source: &Path, target: &Path, link: bool, earlier: &[FileDeployment]) -> io::Result<Self> {
+        if !source.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Prepared file not found: {}", source.display()),
+            ));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let backup_path = backup_path(target);
+        let backup = if earlier.iter().any(|deployment| deployment.target == target) {
+            fs::remove_file(target).or_else(ignore_not_found)?;
+            None
+        } else if backup_path.exists() {
+            fs::remove_file(target).or_else(ignore_not_found)?;
+            Some(backup_path)
+        } else if target.symlink_metadata().is_ok() {
+            fs::rename(target, &backup_path)?;
+            Some(backup_path)
+        } else {
+            None
+        };
+
+        if link {
+            symlink_file(source, target)?;
+        } else {
+            fs::copy(source, target)?;
+        }
+        Ok(FileDeployment { target: target.to_path_buf(), backup })
+    }
+
+    /// Removes the deployed file and puts back whatever it replaced.
+    pub fn restore(self) -> io::Result<()> {
+        fs::remove_file(&self.target).or_else(ignore_not_found)?;
+        if let Some(backup) = self.backup {
+            fs::rename(backup, &self.target)?;
+        }
+        Ok(())
+    }
+}
+
+/// Work done around a single emulator launch that has to be undone once the emulator exits.
+#[derive(Debug, Default)]
+pub struct LaunchHooks {
+    deployments: Vec<FileDeployment>,
+    options: LaunchOptions,
+    capture: Option<CaptureSession>,
+}
+
+impl LaunchHooks {
+    /// Runs the pre-launch hooks for a game: deploying matching controller profiles and the
+    /// performance preset, collecting the RetroArch config files to append, the display preset, and the
+    /// emulator's audio, environment, wrapper, priority and sandbox settings, then starting the
+    /// session capture.
+    ///
+    /// Failures are reported and skipped so that a missing remap file never blocks a launch.
+    pub fn before_launch(emulator: &Emulator, game: &Game) -> Self {
+        let mut hooks = LaunchHooks::default();
+
+        // Least specific first: RetroArch lets later --appendconfig files override earlier ones.
+        let mut append_configs: Vec<(u8, &PathBuf)> = emulator.append_configs
+            .iter()
+            .filter_map(|c| specificity(c.game.as_deref(), c.system.as_deref(), game).map(|rank| (rank, &c.path)))
+            .collect();
+        append_configs.sort_by_key(|(rank, _)| *rank);
+        for (_, path) in append_configs {
+            if !path.is_file() {
+                eprintln!("⚠️ Append config not found: {}", path.display());
+                continue;
+            }
+            hooks.options.append_configs.push(path.clone());
+        }
+
+        // Least specific first, so fields from presets naming the system or game win.
+        let mut display_presets: Vec<(u8, &DisplayPreset)> = emulator.display
+            .iter()
+            .filter_map(|p| specificity(p.game.as_deref(), p.system.as_deref(), game).map(|rank| (rank, p)))
+            .collect();
+        display_presets.sort_by_key(|(rank, _)| *rank);
+        for (_, preset) in display_presets {
+            let display = &mut hooks.options.display;
+            display.fullscreen = preset.fullscreen.or(display.fullscreen);
+            display.monitor = preset.monitor.or(display.monitor);
+            if let Some(resolution) = &preset.resolution {
+                match parse_resolution(resolution) {
+                    Some(size) => display.resolution = Some(size),
+                    None => eprintln!("⚠️ Ignoring invalid resolution '{}' (expected e.g. 640x480)", resolution),
+                }
+            }
+        }
+
+        // Explicit env entries come last so they can override what the GPU preference sets.
+        if let Some(gpu) = emulator.gpu {
+            hooks.options.env.extend(gpu.env());
+        }
+        hooks.options.env.extend(audio_env(emulator));
+        hooks.options.audio_device = emulator.audio_device.clone();
+        hooks.options.env.extend(emulator.env.iter().map(|(k, v)| (k.clone(), v.clone())));
+        hooks.options.wrapper = emulator.wrapper.clone();
+        hooks.options.nice = emulator.nice;
+        hooks.options.cpu_affinity = emulator.cpu_affinity.clone();
+        hooks.options.fullscreen_flag = emulator.capabilities.as_ref().and_then(|c| c.fullscreen_flag.clone());
+        let quirks = quirks::for_emulator(emulator);
+        if let Some(warning) = &quirks.warning {
+            eprintln!("⚠️ {}: {}", emulator.name, warning);
+        }
+        hooks.options.quirk_args = quirks.args;
+        hooks.options.absolute_rom_path = quirks.absolute_path;
+
+        if let Some(sandbox) = emulator.sandbox {
+            hooks.options.sandbox = Some(sandbox);
+            hooks.options.read_only_dirs.push(sandbox::game_files_dir(&game.path));
+        }
+
+        for profile in matching_controller_profiles(emulator, game) {
+            match FileDeployment::deploy(&profile.source, &profile.target, profile.link, &hooks.deployments) {
+                Ok(deployment) => {
+                    println!("🎮 Controller profile deployed: {}", profile.target.display());
+                    hooks.deployments.push(deployment);
+                }
+                Err(e) => eprintln!("⚠️ Could not deploy controller profile {}: {}", profile.source.display(), e),
+            }
+        }
+
+        if let Some(preset) = matching_performance_preset(emulator, game) {
+            println!("⚡ Performance preset: {}", preset.name);
+            hooks.apply_performance_preset(emulator, preset);
+        }
+
+        // Started last so the recording doesn't begin long before the emulator does.
+        if let Some(capture) = &emulator.capture {
+            match CaptureSession::start(capture, &game.path) {
+                Ok(session) => {
+                    println!("🔴 Capture started.");
+                    hooks.capture = Some(session);
+                }
+                Err(e) => eprintln!("⚠️ Could not start capture: {}", e),
+            }
+        }
+        hooks
+    }
+
+    /// Swaps in a performance preset's config file and appends its RetroArch settings.
+    fn apply_performance_preset(&mut self, emulator: &Emulator, preset: &PerformancePreset) {
+        match (&preset.source, &preset.target) {
+            (Some(source), Some(target)) => match FileDeployment::deploy(source, target, false, &self.deployments) {
+                Ok(deployment) => self.deployments.push(deployment),
+                Err(e) => eprintln!("⚠️ Could not swap in {} for preset '{}': {}", source.display(), preset.name, e),
+            },
+            (None, None) => {}
+            _ => eprintln!("⚠️ Preset '{}' needs both a source and a target config file; not swapping files.", preset.name),
+        }
+        if preset.retroarch.is_empty() {
+            return;
+        }
+        if LaunchProfile::detect(&emulator.name) != LaunchProfile::RetroArch {
+            eprintln!("⚠️ Preset '{}' has RetroArch settings, but {} isn't RetroArch; ignoring them.", preset.name, emulator.name);
+            return;
+        }
+        let settings: String = preset.retroarch.iter().map(|(key, value)| format!("{} = \"{}\"\n", key, value)).collect();
+        match rom_launcher::write_temp_config("rom_loader_performance.cfg", &settings) {
+            Ok(path) => self.options.append_configs.push(path),
+            Err(e) => eprintln!("⚠️ Could not write the RetroArch settings of preset '{}': {}", preset.name, e),
+        }
+    }
+
+    /// Puts the game's cheat file (see [`cheats::find`]) into the emulator's `cheat_dir` for
+    /// this launch; it is removed again after the emulator exits.
+    pub fn deploy_cheats(&mut self, emulator: &Emulator, game: &Game, config_path: &Path) {
+        let Some(cheat_dir) = &emulator.cheat_dir else {
+            eprintln!("⚠️ No cheats: {} has no cheat_dir; set it with 'config set \"{}\" cheat_dir <dir>'.", emulator.name, emulator.name);
+            return;
+        };
+        let Some(source) = cheats::find(game, config_path) else {
+            eprintln!("⚠️ No cheat file found for {}; put a .cht next to it or run 'cheats update'.",
+                game.path.file_name().unwrap_or_default().to_string_lossy());
+            return;
+        };
+        let target = cheats::target_path(cheat_dir, game, &source);
+        match FileDeployment::deploy(&source, &target, false, &self.deployments) {
+            Ok(deployment) => {
+                println!("🃏 Cheats deployed: {}", source.display());
+                self.deployments.push(deployment);
+                self.options.cheats = true;
+            }
+            Err(e) => eprintln!("⚠️ Could not deploy cheats {}: {}", source.display(), e),
+        }
+    }
+
+    /// Launch options contributed by the hooks.
+    pub fn options(&self) -> &LaunchOptions {
+        &self.options
+    }
+
+    /// Launch options for adding per-launch settings on top of the hooks' own.
+    pub fn options_mut(&mut self) -> &mut LaunchOptions {
+        &mut self.options
+    }
+
+    /// Runs the post-exit hooks, restoring everything `before_launch` changed and removing the
+    /// config files generated for the launch.
+    pub fn after_exit(self) {
+        if let Some(capture) = self.capture {
+            match capture.stop() {
+                Ok(Some(recording)) => println!("🎞️ Recording saved to: {}", recording.display()),
+                Ok(None) => println!("⏹️ Capture stopped."),
+                Err(e) => eprintln!("⚠️ Could not stop capture: {}", e),
+            }
+        }
+        // Restore in reverse order in case two deployments touched the same file.
+        for deployment in self.deployments.into_iter().rev() {
+            let target = deployment.target.clone();
+            if let Err(e) = deployment.restore() {
+                eprintln!("⚠️ Could not restore {}: {}", target.display(), e);
+            }
+        }
+        rom_launcher::remove_temp_configs();
+    }
+}
+
+/// Ranks how specifically a per-launch setting targets a game, or `None` if it doesn't apply.
+///
+/// A setting naming the game beats one naming the system, which beats one without filters.
+fn specificity(game_filter: Option<&str>, system_filter: Option<&str>, game: &Game) -> Option<u8> {
+    if let Some(wanted) = game_filter {
+        let file_name = game.path.file_name().unwrap_or_default().to_string_lossy();
+        let file_stem = game.path.file_stem().unwrap_or_default().to_string_lossy();
+        if !wanted.eq_ignore_ascii_case(&file_name) && !wanted.eq_ignore_ascii_case(&file_stem) {
+            return None;
+        }
+    }
+    if let Some(wanted) = system_filter {
+        if !game.system().is_some_and(|s| s.id.eq_ignore_ascii_case(wanted)) {
+            return None;
+        }
+    }
+    Some(match (game_filter, system_filter) {
+        (Some(_), _) => 2,
+        (None, Some(_)) => 1,
+        (None, None) => 0,
+    })
+}
+
+/// Selects the controller profiles that apply to a game, keeping the most specific one per target.
+fn matching_controller_profiles<'a>(emulator: &'a Emulator, game: &Game) -> Vec<&'a ControllerProfile> {
+    let mut selected: Vec<(&ControllerProfile, u8)> = Vec::new();
+    for profile in &emulator.controller_profiles {
+        let Some(rank) = specificity(profile.game.as_deref(), profile.system.as_deref(), game) else { continue };
+        match selected.iter_mut().find(|(p, _)| p.target == profile.target) {
+            Some(existing) if existing.1 < rank => *existing = (profile, rank),
+            Some(_) => {}
+            None => selected.push((profile, rank)),
+        }
+    }
+    selected.into_iter().map(|(profile, _)| profile).collect()
+}
+
+/// Selects the performance preset for a game: the most specific matching one, the first
+/// listed among equally specific ones.
+fn matching_performance_preset<'a>(emulator: &'a Emulator, game: &Game) -> Option<&'a PerformancePreset> {
+    let mut best: Option<(&PerformancePreset, u8)> = None;
+    for preset in &emulator.performance {
+        let Some(rank) = specificity(preset.game.as_deref(), preset.system.as_deref(), game) else { continue };
+        if best.is_none_or(|(_, best_rank)| rank > best_rank) {
+            best = Some((preset, rank));
+        }
+    }
+    best.map(|(preset, _)| preset)
+}
+
+/// Expands an emulator's audio settings to environment variables.
+///
+/// The device is routed through `PULSE_SINK`, which PulseAudio and PipeWire honour for any
+/// program; RetroArch additionally gets it as its own `audio_device` setting. The driver is
+/// passed to SDL-based emulators (MAME, DOSBox, ScummVM, ...) as `SDL_AUDIODRIVER`.
+fn audio_env(emulator: &Emulator) -> Vec<(String, String)> {
+    let mut env = Vec::new();
+    if let Some(device) = &emulator.audio_device {
+        if cfg!(all(unix, not(target_os = "macos"))) {
+            env.push(("PULSE_SINK".to_string(), device.clone()));
+        } else if LaunchProfile::detect(&emulator.name) != LaunchProfile::RetroArch {
+            eprintln!("⚠️ Warning: audio_device only works with RetroArch on this platform; choose the output in {} instead.", emulator.name);
+        }
+    }
+    if let Some(driver) = &emulator.audio_driver {
+        env.push(("SDL_AUDIODRIVER".to_string(), driver.clone()));
+    }
+    env
+}
+
+/// Parses a resolution such as "640x480" into (width, height).
+fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+    let (width, height) = resolution.trim().to_lowercase().split_once('x')
+        .map(|(w, h)| (w.trim().parse().ok(), h.trim().parse().ok()))?;
+    Some((width?, height?))
+}
+
+fn backup_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(BACKUP_SUFFIX);
+    target.with_file_name(name)
+}
+
+fn ignore_not_found(e: io::Error) -> io::Result<()> {
+    if e.kind() == io::ErrorKind::NotFound { Ok(()) } else { Err(e) }
+}
+
+#[cfg(unix)]
+fn symlink_file(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::canonicalize(source)?, target)
+}
+
+#[cfg(windows)]
+fn symlink_file(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(fs::canonicalize(source)?, target)
+}
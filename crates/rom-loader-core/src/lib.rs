@@ -0,0 +1,89 @@
+//! Shared core of the ROM Loader binaries: the game library (scanning, metadata, hashes,
+//! history) and, with the `launch` feature, everything needed to start emulators.
+//!
+//! `rom-scan` builds without `launch`, so the index and verification tools can run on servers
+//! that have no emulators installed.
+
+pub mod arcade;
+pub mod atomic_file;
+pub mod backup;
+pub mod bench;
+pub mod cache;
+pub mod bug_report;
+pub mod dedup;
+pub mod enhancements;
+pub mod events;
+pub mod game_ids;
+pub mod hashing;
+pub mod history;
+pub mod interrupt;
+pub mod journal;
+pub mod library;
+pub mod library_bundle;
+pub mod library_db;
+pub mod macros;
+pub mod manuals;
+pub mod metadata;
+pub mod netfs;
+pub mod playlists;
+pub mod prompts;
+pub mod rom_format;
+pub mod rom_scanner;
+pub mod scan_snapshot;
+pub mod screenshots;
+pub mod search;
+pub mod settings;
+pub mod state_store;
+pub mod sync;
+pub mod systems;
+pub mod titles;
+pub mod torrentzip;
+pub mod variants;
+pub mod verify;
+
+#[cfg(feature = "launch")]
+pub mod capabilities;
+#[cfg(feature = "launch")]
+pub mod capture;
+#[cfg(feature = "launch")]
+pub mod cheats;
+#[cfg(feature = "launch")]
+pub mod emulator_config;
+#[cfg(feature = "install")]
+pub mod emulator_install;
+#[cfg(feature = "launch")]
+pub mod first_run;
+#[cfg(feature = "gpio")]
+pub mod gpio;
+#[cfg(feature = "launch")]
+pub mod gpu;
+#[cfg(feature = "install")]
+pub mod homebrew;
+#[cfg(feature = "launch")]
+pub mod launch;
+#[cfg(feature = "launch")]
+pub mod launch_hooks;
+#[cfg(feature = "obs")]
+pub mod obs_websocket;
+#[cfg(feature = "launch")]
+pub mod preflight;
+#[cfg(feature = "launch")]
+pub mod preload;
+#[cfg(feature = "launch")]
+pub mod priority;
+#[cfg(feature = "launch")]
+pub mod quirks;
+#[cfg(feature = "launch")]
+pub mod rom_launcher;
+#[cfg(feature = "launch")]
+pub mod sandbox;
+#[cfg(feature = "launch")]
+pub mod session_actions;
+#[cfg(feature = "launch")]
+pub mod sessions;
+#[cfg(feature = "launch")]
+pub mod test_launch;
+#[cfg(feature = "install")]
+pub mod update_check;
+#[cfg(feature = "install")]
+pub mod url_import;
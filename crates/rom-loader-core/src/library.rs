@@ -0,0 +1,1107 @@
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::enhancements::Companions;
+use crate::rom_format::N64ByteOrder;
+use crate::rom_scanner::{Game, GameKind, RomScanner, ScanEntry, ScanFilter, ScanSummary};
+use crate::library_db::LibraryDb;
+use crate::metadata::MetadataDb;
+use crate::scan_snapshot::ScanSnapshot;
+use crate::settings::Settings;
+use crate::state_store::StateStore;
+use crate::titles::TitleCleaner;
+use crate::verify::{self, HashStore, VerifyOptions, VerifyStatus, VerifySummary};
+use crate::sync::{self, Remote, SyncDirection};
+use crate::journal::{self, BatchRecorder, Journal, Operation};
+#[cfg(feature = "install")]
+use crate::cache::Cache;
+#[cfg(feature = "install")]
+use crate::url_import::{self, ExpectedHash};
+use crate::{bench, dedup, history, interrupt, library_bundle, netfs, rom_format, systems, torrentzip};
+
+/// Directory next to the configuration where `strip-headers` keeps the original ROMs.
+pub const HEADER_BACKUPS_DIR_NAME: &str = "header_backups";
+
+/// Library maintenance subcommands, shared by `rom-loader` and `rom-scan`.
+#[derive(clap::Subcommand, Debug)]
+pub enum LibraryCommand {
+    /// Scans the library, summarizes the games found per system and shows what was added,
+    /// removed, renamed or changed since the last scan.
+    Scan {
+        /// Print the summary as JSON on stdout, for scripts and cron jobs.
+        #[arg(long)]
+        json: bool,
+
+        /// Only rescan these systems (comma separated ids, e.g. "snes,nes"); the rest of the
+        /// library is kept as it was at the last scan.
+        #[arg(long = "system", value_name = "ID", value_delimiter = ',')]
+        systems: Vec<String>,
+
+        /// Only rescan files with these extensions (comma separated, e.g. "gba"); combined with
+        /// --system, games matching either are rescanned.
+        #[arg(long = "ext", value_name = "EXT", value_delimiter = ',')]
+        extensions: Vec<String>,
+
+        /// Exit with status 1 if files or directories could not be read (permission denied,
+        /// broken symbolic links, I/O errors), instead of only listing them.
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Hashes library files and compares them with their stored hashes to detect bit rot.
+    ///
+    /// Exits with status 1 if files are corrupted, unreadable or missing.
+    Verify {
+        /// Re-hash files even if their size and modification time did not change.
+        #[arg(long)]
+        deep: bool,
+
+        /// With --deep, only re-hash files last verified longer ago than this (e.g. 30d, 12h, 2w).
+        #[arg(long, value_name = "AGE", requires = "deep", value_parser = verify::parse_age)]
+        since: Option<u64>,
+
+        /// Hash the ROMs inside zip and 7z archives instead of the archives, as No-Intro and MAME dats do.
+        #[arg(long)]
+        inner: bool,
+
+        /// Print the summary as JSON on stdout instead of a report, for scripts and cron jobs.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Checks that zipped games are TorrentZip-normalized, so their hashes match romset conventions.
+    Torrentzip {
+        /// Re-zip archives that are not normalized.
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Hard-links identical ROM files on the same filesystem together to reclaim space. Files
+    /// inside game directories (saves, settings) are left alone.
+    OptimizeStorage {
+        /// Only report the duplicates and how much space linking them would save.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Removes the 512-byte copier headers of SNES ROMs, which break hash matching and some
+    /// emulators; the originals are kept in `header_backups` next to the configuration.
+    StripHeaders {
+        /// Only list the headered ROMs.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Converts the library's N64 ROMs to one byte order, renaming them to the matching
+    /// extension; byte-swapped dumps break hash matching and some emulators.
+    Convert {
+        /// Byte order to convert to.
+        #[arg(long, value_enum)]
+        to: N64ByteOrder,
+
+        /// Only list the ROMs that would be converted.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Reverts the last batch of file changes (`strip-headers`, `convert`, `delete`).
+    Undo {
+        /// Only list the batches that can be undone, most recent first.
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Writes a library bundle (hashes, metadata, hidden games and art) that another
+    /// rom-loader instance can import instead of hashing and scraping everything again.
+    Export {
+        /// Bundle to write (default: rom_loader_library_<date>.zip).
+        archive: Option<PathBuf>,
+
+        /// Store paths relative to the ROMs directory, so the bundle can be imported onto a
+        /// library at another path.
+        #[arg(long)]
+        portable: bool,
+    },
+
+    /// Imports a library bundle made with `export`; portable bundles are re-rooted onto this
+    /// ROMs directory. With --from-url, downloads a ROM into the library instead.
+    ///
+    /// Exits with status 1 if a download failed or did not match --hash.
+    Import {
+        /// Bundle made with `export`.
+        #[arg(required_unless_present = "from_url", conflicts_with = "from_url")]
+        archive: Option<PathBuf>,
+
+        /// Downloads a ROM you are entitled to from this URL into its system's folder of the
+        /// library, and adds it to the library index and the hashes `verify` checks.
+        #[arg(long, value_name = "URL")]
+        from_url: Option<String>,
+
+        /// Checksum the download must match, in hex: CRC32, SHA-1 or SHA-256 (told apart by
+        /// length), e.g. from a No-Intro dat. Nothing is imported when it doesn't match.
+        #[arg(long, value_name = "HEX", requires = "from_url")]
+        hash: Option<String>,
+
+        /// System the ROM is for, e.g. "megadrive", when its extension doesn't tell.
+        #[arg(long, requires = "from_url")]
+        system: Option<String>,
+    },
+
+    /// Copies the games one library is missing between this library and another (e.g. a
+    /// handheld's SD card or a desktop over SSH), matching games by hash.
+    ///
+    /// Exits with status 1 if games could not be copied.
+    Sync {
+        /// The other ROMs directory: a path, or [user@]host:path over SSH.
+        remote: String,
+
+        /// Portable bundle exported from the other library (`export --portable`); needed for
+        /// SSH remotes, while local directories are scanned directly.
+        #[arg(long, value_name = "BUNDLE")]
+        index: Option<PathBuf>,
+
+        /// Which way to copy games.
+        #[arg(long, value_enum, default_value_t = SyncDirection::Both)]
+        direction: SyncDirection,
+
+        /// Only list the games that would be copied.
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Searches the titles, descriptions and file names indexed by the last `scan`, best
+    /// matches first; every word must match the start of a word.
+    Search {
+        /// Words to look for, e.g. "zelda link".
+        #[arg(required = true)]
+        words: Vec<String>,
+
+        /// Maximum number of results.
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Measures how fast the library is scanned or hashed, to tune `hash_threads` and pick
+    /// where the cache lives (e.g. a local SSD rather than the NAS holding the ROMs).
+    ///
+    /// Nothing in the library database or the hash store is changed.
+    Bench {
+        /// What to measure: `scan` times the directory walk, the file info and the index
+        /// written to the cache directory; `hash` compares hashing speeds with 1 thread up to
+        /// one per CPU.
+        #[arg(value_enum)]
+        target: BenchTarget,
+
+        /// With `hash`, megabytes of ROMs hashed for each thread count.
+        #[arg(long, value_name = "MB", default_value_t = 256)]
+        sample_mb: u64,
+    },
+}
+
+/// What `bench` measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BenchTarget {
+    Scan,
+    Hash,
+}
+
+impl LibraryCommand {
+    /// Runs the command on the library in `roms_dir`; state files are kept next to `config_path`.
+    ///
+    /// Scans and verifications exit with status 1 when they find problems.
+    pub fn run(self, config_path: &Path, roms_dir: &str) -> io::Result<()> {
+        match self {
+            LibraryCommand::Scan { json, systems, extensions, strict } => {
+                let filter = match ScanFilter::new(&systems, &extensions) {
+                    Ok(filter) => filter,
+                    Err(e) => {
+                        eprintln!("❌ {}; see the system ids in the list's System column.", e);
+                        return exit_on_failure(false);
+                    }
+                };
+                let ok = scan_summary(config_path, roms_dir, &filter, json, strict)?;
+                exit_on_failure(ok)
+            }
+            LibraryCommand::Verify { deep, since, inner, json } => {
+                let threads = Settings::load(config_path).unwrap_or_default().hash_threads;
+                let options = VerifyOptions { deep, min_age_secs: since, inner, now: history::now_timestamp(), threads };
+                let ok = verify_library(config_path, roms_dir, &options, json)?;
+                exit_on_failure(ok)
+            }
+            LibraryCommand::Torrentzip { fix } => check_torrentzip(roms_dir, fix),
+            LibraryCommand::OptimizeStorage { dry_run } => optimize_storage(roms_dir, dry_run),
+            LibraryCommand::StripHeaders { dry_run } => strip_headers(config_path, roms_dir, dry_run),
+            LibraryCommand::Convert { to, dry_run } => convert_n64_roms(config_path, roms_dir, to, dry_run),
+            LibraryCommand::Undo { list } => undo(config_path, list),
+            LibraryCommand::Export { archive, portable } => {
+                let archive = archive.unwrap_or_else(|| {
+                    PathBuf::from(format!("rom_loader_library_{}.zip", chrono::Local::now().format("%Y-%m-%d")))
+                });
+                export_library(config_path, roms_dir, &archive, portable)
+            }
+            LibraryCommand::Import { from_url: Some(url), hash, system, .. } => {
+                let ok = import_from_url(config_path, roms_dir, &url, hash.as_deref(), system.as_deref())?;
+                exit_on_failure(ok)
+            }
+            LibraryCommand::Import { archive, .. } => {
+                import_library(config_path, roms_dir, &archive.expect("clap requires a bundle without --from-url"))
+            }
+            LibraryCommand::Sync { remote, index, direction, dry_run } => {
+                let ok = sync_library(config_path, roms_dir, &Remote::parse(&remote), index.as_deref(), direction, dry_run)?;
+                exit_on_failure(ok)
+            }
+            LibraryCommand::Search { words, limit } => search_library(config_path, &words.join(" "), limit),
+            LibraryCommand::Bench { target: BenchTarget::Scan, .. } => bench_scan(config_path, roms_dir),
+            LibraryCommand::Bench { target: BenchTarget::Hash, sample_mb } => bench_hash(roms_dir, sample_mb),
+        }
+    }
+}
+
+/// Exits with status 1 when a batch command found problems, so scripts and cron jobs notice.
+pub fn exit_on_failure(ok: bool) -> io::Result<()> {
+    if !ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Prints a summary as pretty-printed JSON on stdout.
+pub fn print_json<T: serde::Serialize>(summary: &T) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(summary)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to serialize summary to JSON: {}", e),
+        ))?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Scans the ROMs directory; `quiet` turns off the scan log.
+pub fn scan_roms(roms_dir_path: &Path, quiet: bool) -> io::Result<Vec<Game>> {
+    // Supported ROM extensions come from the built-in system registry.
+    let supported_extensions = systems::supported_extensions();
+    RomScanner::new(roms_dir_path, &supported_extensions).quiet(quiet).scan_roms()
+}
+
+/// Reports a ROMs directory on storage that went offline (an unmounted or unreachable network
+/// share), which looks like an empty or missing library otherwise.
+///
+/// # Returns
+/// Whether the directory is offline.
+pub fn report_offline(roms_dir_path: &Path) -> bool {
+    match netfs::offline_reason(roms_dir_path) {
+        Some(reason) => {
+            eprintln!("📴 ROMs source offline: {} can't be read because {}.", roms_dir_path.display(), reason);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Scans the ROMs directory, reporting an empty, unreadable or offline library.
+pub fn scan_library(roms_dir_path: &Path) -> Option<Vec<Game>> {
+    match scan_roms(roms_dir_path, false) {
+        Ok(r) => {
+            if r.is_empty() {
+                if !report_offline(roms_dir_path) {
+                    println!("⚠️ No supported ROMs found in {}.", roms_dir_path.display());
+                }
+                return None;
+            }
+            println!("📚 Found {} ROMs in {}:", r.len(), roms_dir_path.display());
+            Some(r)
+        }
+        Err(e) => {
+            if !report_offline(roms_dir_path) {
+                eprintln!("❌ Error scanning ROMs in {}: {}", roms_dir_path.display(), e);
+            }
+            None
+        }
+    }
+}
+
+/// Scans the library, prints how many games were found per system and what changed since the
+/// previous scan, then stores a new scan snapshot.
+///
+/// With a non-empty `filter` only the matching games are scanned and summarized; the other
+/// games of the previous scan are carried over unchanged. Files and directories that could
+/// not be read are listed as problems.
+///
+/// # Returns
+/// `false` if the library could not be scanned, or with `strict` if there were problems.
+pub fn scan_summary(config_path: &Path, roms_dir: &str, filter: &ScanFilter, json: bool, strict: bool) -> io::Result<bool> {
+    let roms_dir_path = Path::new(roms_dir);
+    let mut db = match LibraryDb::open(&LibraryDb::path_for_config(config_path)) {
+        Ok(db) => Some(db),
+        Err(e) => {
+            eprintln!("⚠️ Could not open the library database, changes since the last scan are not shown: {}", e);
+            None
+        }
+    };
+    let previous = db.as_ref().and_then(|db| db.load_snapshot().unwrap_or_else(|e| {
+        eprintln!("⚠️ Ignoring the previous scan snapshot: {}", e);
+        None
+    }));
+    // A snapshot of another library would make everything look added and removed.
+    let previous = previous.filter(|p| p.roms_dir == roms_dir_path);
+
+    // Games go into the snapshot and the summary as they are found, not collected first.
+    let supported_extensions = systems::supported_extensions();
+    let mut snapshot = ScanSnapshot::new(roms_dir_path, history::now_timestamp());
+    let mut summary = ScanSummary::new(roms_dir_path);
+    let mut problems = Vec::new();
+    let scanned = RomScanner::new(roms_dir_path, &supported_extensions).filter(filter).quiet(json).entries().and_then(|entries| {
+        for entry in entries {
+            match entry? {
+                ScanEntry::Game(game) => {
+                    snapshot.add(&game, previous.as_ref(), &mut problems);
+                    summary.add(&game);
+                }
+                ScanEntry::Problem(problem) => problems.push(problem),
+            }
+        }
+        Ok(())
+    });
+    match scanned {
+        // An unmounted share would make the whole library look removed.
+        Ok(()) if summary.total == 0 && report_offline(roms_dir_path) => return Ok(false),
+        Ok(()) => {}
+        Err(e) => {
+            if !report_offline(roms_dir_path) {
+                eprintln!("❌ Error scanning ROMs in {}: {}", roms_dir_path.display(), e);
+            }
+            return Ok(false);
+        }
+    }
+    if let Some(previous) = &previous {
+        // Games that could not be read, or are in directories that could not be, aren't known to be gone.
+        snapshot.keep_unscanned(previous, |path| filter.covers(path) && !problems.iter().any(|p| path.starts_with(&p.path)));
+    }
+    summary.diff = previous.as_ref().map(|p| p.diff(&snapshot));
+    summary.problems = problems;
+    let ok = !strict || summary.problems.is_empty();
+    if let Some(db) = &mut db {
+        let metadata = MetadataDb::load(&MetadataDb::path_for_config(config_path)).unwrap_or_default();
+        let settings = Settings::load(config_path).unwrap_or_default();
+        let titles = TitleCleaner::new(settings.ui.list.title_rules.as_deref());
+        if let Err(e) = db.save_snapshot(&snapshot).and_then(|_| db.index_titles(snapshot.games.keys().map(PathBuf::as_path), &metadata, &titles)) {
+            eprintln!("⚠️ Could not save the scan to the library database: {}", e);
+        }
+    }
+
+    if json {
+        print_json(&summary)?;
+        return Ok(ok);
+    }
+    if filter.is_empty() {
+        println!("📚 Found {} games in {}:", summary.total, roms_dir_path.display());
+    } else {
+        let only: Vec<String> = filter.systems.iter().cloned().chain(filter.extensions.iter().map(|ext| format!(".{}", ext))).collect();
+        println!("📚 Found {} games in {} (only {}):", summary.total, roms_dir_path.display(), only.join(", "));
+    }
+    for (system_id, count) in &summary.systems {
+        let name = systems::find_by_id(system_id).map_or("Unknown system", |s| s.name);
+        println!("  {:>5}  {} ({})", count, name, system_id);
+    }
+    match (&summary.diff, &previous) {
+        (Some(diff), Some(previous)) if diff.is_empty() => {
+            println!("🟰 No changes since the last scan ({}).", history::format_timestamp(previous.created));
+        }
+        (Some(diff), Some(previous)) => {
+            println!("\n--- Changes since {} ---", history::format_timestamp(previous.created));
+            for path in &diff.added {
+                println!("  ➕ Added:   {}", path.display());
+            }
+            for path in &diff.removed {
+                println!("  ➖ Removed: {}", path.display());
+            }
+            for rename in &diff.renamed {
+                println!("  🔀 Renamed: {} -> {}", rename.from.display(), rename.to.display());
+            }
+            for path in &diff.changed {
+                println!("  ✏️ Changed: {}", path.display());
+            }
+            println!("  ({} added, {} removed, {} renamed, {} changed)",
+                diff.added.len(),
+                diff.removed.len(),
+                diff.renamed.len(),
+                diff.changed.len()
+            );
+        }
+        _ => println!("📸 Saved a first scan snapshot; the next scan will show what changed."),
+    }
+    if !summary.problems.is_empty() {
+        println!("\n--- Could not scan ---");
+        for problem in &summary.problems {
+            println!("  ⚠️ {}: {}", problem.path.display(), problem.error);
+        }
+        println!("  ({} problems{})", summary.problems.len(), if strict { "" } else { "; --strict fails the scan on them" });
+    }
+    Ok(ok)
+}
+
+/// Checks every library file against its stored hash and reports silently corrupted files.
+///
+/// New and intentionally modified files get their hash recorded, so the first run builds the
+/// baseline. A corrupted file keeps its known-good hash so it is reported again until fixed.
+/// With `json`, only the summary is printed on stdout.
+///
+/// # Returns
+/// `false` if files are corrupted, unreadable or missing, or the check could not run.
+pub fn verify_library(config_path: &Path, roms_dir: &str, options: &VerifyOptions, json: bool) -> io::Result<bool> {
+    let store_path = HashStore::path_for_config(config_path);
+    let mut store = match HashStore::load(&store_path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(false);
+        }
+    };
+    // The JSON summary is the only thing printed on stdout, so the scan is done quietly.
+    let roms = if json {
+        match scan_roms(Path::new(roms_dir), true) {
+            Ok(roms) => roms,
+            Err(e) => {
+                eprintln!("❌ Error scanning ROMs in {}: {}", roms_dir, e);
+                return Ok(false);
+            }
+        }
+    } else {
+        let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(false) };
+        roms
+    };
+
+    // Folder-based games are verified file by file.
+    let files = library_files(&roms);
+
+    let mut summary = VerifySummary { checked: files.len(), ..VerifySummary::default() };
+    let results = verify::verify_files(&files, &mut store, options);
+    for (file, result) in files.iter().zip(results) {
+        let label = match result {
+            Ok(VerifyStatus::New) => "new",
+            Ok(VerifyStatus::Unchanged) => "unchanged",
+            Ok(VerifyStatus::Verified) => "verified",
+            Ok(VerifyStatus::Skipped) => "recently verified",
+            Ok(VerifyStatus::Modified) => {
+                summary.modified.push(file.clone());
+                "modified"
+            }
+            Ok(VerifyStatus::Corrupted { expected, actual }) => {
+                summary.corrupted.push(verify::CorruptedFile { path: file.clone(), expected, actual });
+                "corrupted"
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => "not checked (interrupted)",
+            Err(e) => {
+                summary.unreadable.push(verify::UnreadableFile { path: file.clone(), error: e.to_string() });
+                "unreadable"
+            }
+        };
+        *summary.counts.entry(label).or_insert(0) += 1;
+        if rom_format::has_copier_header(file).unwrap_or(false) {
+            summary.headered.push(file.clone());
+        }
+    }
+
+    // Files that were hashed before but are no longer in the library (or the disk is gone).
+    let roms_root = Path::new(roms_dir);
+    let present: HashSet<&PathBuf> = files.iter().collect();
+    summary.missing = store.files.keys()
+        .filter(|path| path.starts_with(roms_root) && !present.contains(path))
+        .cloned()
+        .collect();
+    if !summary.missing.is_empty() {
+        summary.counts.insert("missing", summary.missing.len());
+    }
+
+    store.save(&store_path)?;
+    if json {
+        print_json(&summary)?;
+        return Ok(!summary.has_failures());
+    }
+
+    for file in &summary.modified {
+        println!("  ✏️ Modified since last check, hash updated: {}", file.display());
+    }
+    for file in &summary.corrupted {
+        println!("  💥 CORRUPTED: {} (CRC32 {} expected, found {})", file.path.display(), file.expected, file.actual);
+    }
+    for file in &summary.unreadable {
+        println!("  ⚠️ Could not read {}: {}", file.path.display(), file.error);
+    }
+    for path in &summary.missing {
+        println!("  ❓ Missing: {}", path.display());
+    }
+    for path in &summary.headered {
+        println!("  🧾 SNES copier header: {}", path.display());
+    }
+    let counts: Vec<String> = summary.counts.iter().map(|(label, count)| format!("{} {}", count, label)).collect();
+    println!("🔎 Checked {} files: {}.", files.len(), counts.join(", "));
+    if !summary.corrupted.is_empty() {
+        println!("💥 {} files are corrupted; restore them from a backup or a fresh dump.", summary.corrupted.len());
+    } else if options.deep {
+        println!("✅ No corruption found.");
+    }
+    if !summary.headered.is_empty() {
+        println!("💡 {} ROMs have copier headers and won't match dat hashes; 'strip-headers' removes them.", summary.headered.len());
+    }
+    Ok(!summary.has_failures())
+}
+
+/// Writes a library bundle and reports what went into it.
+pub fn export_library(config_path: &Path, roms_dir: &str, archive: &Path, portable: bool) -> io::Result<()> {
+    match library_bundle::export(config_path, Path::new(roms_dir), archive, portable) {
+        Ok((bundle, assets)) => {
+            let hashed = bundle.games.iter().filter(|g| g.crc32.is_some()).count();
+            let described = bundle.games.iter().filter(|g| g.metadata.is_some()).count();
+            println!("📦 Exported {} games ({} hashed, {} with metadata) and {} asset files to {}{}.",
+                bundle.games.len(),
+                hashed,
+                described,
+                assets,
+                archive.display(),
+                if portable { " (portable)" } else { "" }
+            );
+        }
+        Err(e) => eprintln!("❌ Could not export the library: {}", e),
+    }
+    Ok(())
+}
+
+/// Imports a library bundle and reports what was taken over.
+pub fn import_library(config_path: &Path, roms_dir: &str, archive: &Path) -> io::Result<()> {
+    match library_bundle::import(config_path, Path::new(roms_dir), archive) {
+        Ok(summary) => {
+            println!("📥 Imported {}: {} of {} games found in {}.", archive.display(), summary.found, summary.games, roms_dir);
+            println!("  {} hashes, {} metadata entries, {} hidden games and {} asset files taken over.",
+                summary.hashes,
+                summary.metadata,
+                summary.hidden,
+                summary.assets
+            );
+        }
+        Err(e) => eprintln!("❌ Could not import the library bundle: {}", e),
+    }
+    Ok(())
+}
+
+/// Downloads a ROM into the library, records its hash for `verify` and adds it to the library
+/// index if the library has been scanned.
+///
+/// # Returns
+/// Whether the ROM was imported.
+#[cfg(feature = "install")]
+pub fn import_from_url(config_path: &Path, roms_dir: &str, url: &str, hash: Option<&str>, system: Option<&str>) -> io::Result<bool> {
+    let expected = match hash.map(str::parse::<ExpectedHash>).transpose() {
+        Ok(expected) => expected,
+        Err(e) => {
+            eprintln!("❌ --hash: {}", e);
+            return Ok(false);
+        }
+    };
+    if expected.is_none() {
+        println!("⚠️ Without --hash the download can't be checked for corruption or tampering.");
+    }
+    let roms_dir_path = Path::new(roms_dir);
+    let settings = Settings::load(config_path).unwrap_or_default();
+    println!("⬇️ Downloading {}...", url);
+    let imported = match url_import::import(url, expected.as_ref(), system, roms_dir_path, &Cache::for_config(config_path, &settings)) {
+        Ok(imported) => imported,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(false);
+        }
+    };
+    if let Some(expected) = &expected {
+        println!("✅ {} matches.", expected.name());
+    }
+    let path = imported.game.path.clone();
+    println!("📥 Imported as a {} game: {}", imported.system.name, path.display());
+
+    let hashes_path = HashStore::path_for_config(config_path);
+    let mut hashes = HashStore::load(&hashes_path)?;
+    let options = VerifyOptions { deep: false, min_age_secs: None, inner: false, now: history::now_timestamp(), threads: None };
+    if let Some(Err(e)) = verify::verify_files(std::slice::from_ref(&path), &mut hashes, &options).pop() {
+        eprintln!("⚠️ Could not record the hash of {}: {}", path.display(), e);
+    }
+    hashes.save(&hashes_path)?;
+
+    let db_path = LibraryDb::path_for_config(config_path);
+    // Opening the database would create it, and a library never scanned has no snapshot.
+    let mut db = if db_path.exists() { Some(LibraryDb::open(&db_path)?) } else { None };
+    let snapshot = match &db {
+        Some(db) => db.load_snapshot()?.filter(|s| s.roms_dir == roms_dir_path),
+        None => None,
+    };
+    match (&mut db, snapshot) {
+        (Some(db), Some(mut snapshot)) => {
+            let mut problems = Vec::new();
+            snapshot.add(&imported.game, None, &mut problems);
+            let metadata = MetadataDb::load(&MetadataDb::path_for_config(config_path)).unwrap_or_default();
+            let titles = TitleCleaner::new(settings.ui.list.title_rules.as_deref());
+            db.save_snapshot(&snapshot)?;
+            db.index_titles([path.as_path()], &metadata, &titles)?;
+            println!("📚 Added to the library index.");
+        }
+        _ => println!("💡 Run 'scan' to add it to the library index."),
+    }
+    Ok(true)
+}
+
+/// Stands in for [`import_from_url`] in builds that can't download, such as `rom-scan`.
+#[cfg(not(feature = "install"))]
+pub fn import_from_url(_config_path: &Path, _roms_dir: &str, _url: &str, _hash: Option<&str>, _system: Option<&str>) -> io::Result<bool> {
+    eprintln!("❌ This build of rom-loader can't download files (it needs the install feature).");
+    Ok(false)
+}
+
+/// Compares this library with another one and copies the games each is missing.
+///
+/// # Returns
+/// `false` if the libraries could not be compared or games could not be copied.
+pub fn sync_library(
+    config_path: &Path,
+    roms_dir: &str,
+    remote: &Remote,
+    index: Option<&Path>,
+    direction: SyncDirection,
+    dry_run: bool,
+) -> io::Result<bool> {
+    let roms_dir = Path::new(roms_dir);
+    let remote_index = match (index, remote) {
+        (Some(index), _) => library_bundle::read_bundle(index),
+        (None, Remote::Local(dir)) => library_bundle::build_index(config_path, dir, true).map(|(bundle, _)| bundle),
+        (None, Remote::Ssh { .. }) => {
+            eprintln!("❌ Run 'export --portable' on {} and pass the bundle with --index to sync over SSH.", remote);
+            return Ok(false);
+        }
+    };
+    let indexes = remote_index.and_then(|remote_index| {
+        let (local_index, _) = library_bundle::build_index(config_path, roms_dir, true)?;
+        Ok((local_index, remote_index))
+    });
+    let (local_index, remote_index) = match indexes {
+        Ok(indexes) => indexes,
+        Err(e) => {
+            eprintln!("❌ Could not compare the libraries: {}", e);
+            return Ok(false);
+        }
+    };
+    if !remote_index.portable {
+        eprintln!("❌ The bundle for {} was not exported with --portable, so its paths can't be matched.", remote);
+        return Ok(false);
+    }
+
+    let plan = sync::plan(&local_index, &remote_index, direction);
+    if plan.push.is_empty() && plan.pull.is_empty() {
+        println!("🟰 {} and {} have the same games.", roms_dir.display(), remote);
+        return Ok(true);
+    }
+    let mut failed = 0;
+    let mut copied = [0, 0];
+    let _busy = interrupt::Busy::start();
+    for (games, push) in [(&plan.push, true), (&plan.pull, false)] {
+        for game in games {
+            if interrupt::requested() {
+                println!("⏹️ Sync stopped; run it again to copy the rest.");
+                return Ok(false);
+            }
+            let (arrow, target) = if push { ("⬆️", remote.to_string()) } else { ("⬇️", roms_dir.display().to_string()) };
+            if dry_run {
+                println!("  {} Would copy {} to {}", arrow, game.display(), target);
+                continue;
+            }
+            match sync::copy_game(roms_dir, remote, game, push) {
+                Ok(()) => {
+                    copied[usize::from(!push)] += 1;
+                    println!("  {} Copied {} to {}", arrow, game.display(), target);
+                }
+                Err(e) => {
+                    failed += 1;
+                    eprintln!("  ❌ Could not copy {}: {}", game.display(), e);
+                }
+            }
+        }
+    }
+    if dry_run {
+        println!("🔄 Would copy {} games to {} and {} games from it.", plan.push.len(), remote, plan.pull.len());
+    } else {
+        println!("🔄 Copied {} games to {} and {} games from it.", copied[0], remote, copied[1]);
+    }
+    if failed > 0 {
+        println!("❌ {} games could not be copied.", failed);
+    }
+    Ok(failed == 0)
+}
+
+/// Returns every file of the library: single-file games with their companion files (MSU-1
+/// packs, soft patches) and the files inside game directories.
+pub fn library_files(roms: &[Game]) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    for game in roms {
+        match game.kind {
+            GameKind::File => {
+                files.push(game.path.clone());
+                files.extend(Companions::find(game).map(|c| c.files()).unwrap_or_default());
+            }
+            GameKind::Directory { .. } => files.extend(
+                walkdir::WalkDir::new(&game.path)
+                    .into_iter()
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                    .map(|e| e.into_path()),
+            ),
+        }
+    }
+    files
+}
+
+/// Strips the copier headers of the library's SNES ROMs, or only lists them with `dry_run`.
+///
+/// Originals are kept in [`HEADER_BACKUPS_DIR_NAME`] next to the configuration.
+pub fn strip_headers(config_path: &Path, roms_dir: &str, dry_run: bool) -> io::Result<()> {
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let backup_dir = config_path.with_file_name(HEADER_BACKUPS_DIR_NAME);
+    let mut journal = BatchRecorder::begin(config_path, "strip-headers")?;
+    let mut headered = 0;
+    let mut stripped = 0;
+    let _busy = interrupt::Busy::start();
+    for game in roms.iter().filter(|g| g.kind == GameKind::File) {
+        if interrupt::requested() {
+            println!("⏹️ Stopped; the ROMs stripped so far can be restored with 'undo'.");
+            break;
+        }
+        match rom_format::has_copier_header(&game.path) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                println!("  ⚠️ Could not check {}: {}", game.path.display(), e);
+                continue;
+            }
+        }
+        headered += 1;
+        if dry_run {
+            println!("  🧾 Copier header: {}", game.path.display());
+            continue;
+        }
+        match rom_format::strip_copier_header(&game.path, &backup_dir) {
+            Ok(backup) => {
+                println!("  ✂️ Stripped: {} (original kept as {})", game.path.display(), backup.display());
+                journal.record(Operation::Replaced { path: game.path.clone(), backup })?;
+                stripped += 1;
+            }
+            Err(e) => println!("  ❌ Could not strip {}: {}", game.path.display(), e),
+        }
+    }
+    if headered == 0 {
+        println!("✅ No headered SNES ROMs found.");
+    } else if dry_run {
+        println!("💡 {} ROMs have copier headers; run without --dry-run to strip them.", headered);
+    } else {
+        println!("✂️ Stripped {} of {} headered ROMs.", stripped, headered);
+    }
+    Ok(())
+}
+
+/// Converts the library's N64 ROMs to the byte order `to`, or only lists them with `dry_run`.
+pub fn convert_n64_roms(config_path: &Path, roms_dir: &str, to: N64ByteOrder, dry_run: bool) -> io::Result<()> {
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let mut journal = BatchRecorder::begin(config_path, &format!("convert --to {}", to.extension()))?;
+    let state_path = StateStore::path_for_config(config_path);
+    let hashes_path = HashStore::path_for_config(config_path);
+    let mut state = StateStore::load(&state_path)?;
+    let mut hashes = HashStore::load(&hashes_path)?;
+    let mut found = 0;
+    let mut converted = 0;
+    let _busy = interrupt::Busy::start();
+    for game in roms.iter().filter(|g| g.kind == GameKind::File && g.system().is_some_and(|s| s.id == "n64")) {
+        if interrupt::requested() {
+            println!("⏹️ Stopped; the ROMs converted so far can be restored with 'undo'.");
+            break;
+        }
+        let order = match rom_format::n64_byte_order(&game.path) {
+            Ok(Some(order)) => order,
+            Ok(None) => {
+                println!("  ⚠️ Not an N64 ROM image: {}", game.path.display());
+                continue;
+            }
+            Err(e) => {
+                println!("  ⚠️ Could not check {}: {}", game.path.display(), e);
+                continue;
+            }
+        };
+        // Also fixes ROMs in the right order with the wrong extension.
+        if order == to && game.path.extension().is_some_and(|e| e.eq_ignore_ascii_case(to.extension())) {
+            continue;
+        }
+        found += 1;
+        if dry_run {
+            println!("  🔀 {} ({})", game.path.display(), order.extension());
+            continue;
+        }
+        let original = journal.back_up(&game.path)?;
+        match rom_format::convert_n64(&game.path, to) {
+            Ok(target) => {
+                println!("  🔀 Converted: {} -> {}", game.path.display(), target.display());
+                journal.record(original)?;
+                // The stored hash is of the old byte order; `verify` would report the new one as corrupted.
+                hashes.forget(&game.path);
+                if target != game.path {
+                    state.rename(&game.path, &target);
+                    journal.record(Operation::Created { path: target })?;
+                }
+                converted += 1;
+            }
+            Err(e) => {
+                println!("  ❌ Could not convert {}: {}", game.path.display(), e);
+                if let Operation::Replaced { backup, .. } = original {
+                    let _ = std::fs::remove_file(backup);
+                }
+            }
+        }
+    }
+    if converted > 0 {
+        state.save(&state_path)?;
+        hashes.save(&hashes_path)?;
+    }
+    if found == 0 {
+        println!("✅ All N64 ROMs are already {}.", to.extension());
+    } else if dry_run {
+        println!("💡 {} ROMs would be converted to {}; run without --dry-run to convert them.", found, to.extension());
+    } else {
+        println!("🔀 Converted {} of {} ROMs to {}.", converted, found, to.extension());
+    }
+    Ok(())
+}
+
+/// Prints the games of the library database's full-text index that match `text`.
+pub fn search_library(config_path: &Path, text: &str, limit: usize) -> io::Result<()> {
+    let db = LibraryDb::open(&LibraryDb::path_for_config(config_path))?;
+    if db.load_snapshot()?.is_none() {
+        println!("📭 The library has not been indexed yet; run 'scan' first.");
+        return Ok(());
+    }
+    let hits = db.search(text, limit)?;
+    if hits.is_empty() {
+        println!("🔍 No games match '{}'.", text);
+        return Ok(());
+    }
+    println!("🔍 Games matching '{}':", text);
+    for hit in hits {
+        println!("  {} — {}", hit.title, hit.path.display());
+    }
+    Ok(())
+}
+
+/// Formats a measured time for `bench`, e.g. "1.25 s".
+fn format_secs(duration: Duration) -> String {
+    format!("{:.2} s", duration.as_secs_f64())
+}
+
+/// Scans the library twice and prints where the time went.
+///
+/// The second scan usually finds the directory listings in the operating system's cache, so a
+/// big difference between the two means the storage, not rom-loader, is what's slow.
+pub fn bench_scan(config_path: &Path, roms_dir: &str) -> io::Result<()> {
+    let roms_dir_path = Path::new(roms_dir);
+    let db_path = LibraryDb::path_for_config(config_path);
+    // Opening the database would create it, and a library never scanned has no snapshot.
+    let previous = if db_path.exists() { LibraryDb::open(&db_path)?.load_snapshot()? } else { None };
+    let previous = previous.filter(|p| p.roms_dir == roms_dir_path);
+    if previous.is_none() {
+        println!("ℹ️ The library hasn't been scanned yet, so the first scan hashes every file as a first 'scan' would.");
+    }
+    let cache_dir = Settings::load(config_path).unwrap_or_default().cache_dir_for(config_path);
+    println!("⏱️ Scanning {} twice, writing the index to {}...", roms_dir_path.display(), cache_dir.display());
+    let mut totals = Vec::new();
+    for pass in ["First", "Second"] {
+        let timings = match bench::bench_scan(roms_dir_path, previous.as_ref(), &cache_dir) {
+            Ok(timings) => timings,
+            Err(e) => {
+                if !report_offline(roms_dir_path) {
+                    eprintln!("❌ Error scanning ROMs in {}: {}", roms_dir_path.display(), e);
+                }
+                return Ok(());
+            }
+        };
+        println!("  {} scan: {} games in {}", pass, timings.games, format_secs(timings.total()));
+        println!(
+            "    walk {} (first game after {}), file info {}, index {}",
+            format_secs(timings.walk),
+            timings.first_game.map_or_else(|| "-".to_string(), format_secs),
+            format_secs(timings.file_info),
+            format_secs(timings.index),
+        );
+        if timings.problems > 0 {
+            println!("    {} files or directories could not be read; 'scan' lists them.", timings.problems);
+        }
+        totals.push(timings.total());
+    }
+    if totals[0] > totals[1] * 3 {
+        println!("💡 The first scan was much slower: the ROMs' storage is slow to list (typical of network shares), not rom-loader.");
+    }
+    Ok(())
+}
+
+/// Hashes library files with 1 thread up to one per CPU and prints the speed of each, to
+/// pick `hash_threads`.
+pub fn bench_hash(roms_dir: &str, sample_mb: u64) -> io::Result<()> {
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let files = library_files(&roms);
+    let thread_counts = bench::thread_counts();
+    let counts: Vec<String> = thread_counts.iter().map(usize::to_string).collect();
+    println!("⏱️ Hashing about {} MB for each thread count ({})...", sample_mb, counts.join(", "));
+    let runs = bench::bench_hash(&files, &thread_counts, sample_mb * 1024 * 1024)?;
+    let thread_label = |threads: usize| if threads == 1 { "1 thread".to_string() } else { format!("{} threads", threads) };
+    for run in &runs {
+        println!(
+            "  {:>11}: {} files, {:.1} MB in {} — {:.1} MB/s{}",
+            thread_label(run.threads),
+            run.files,
+            run.bytes as f64 / (1024.0 * 1024.0),
+            format_secs(run.elapsed),
+            run.bytes_per_sec() / (1024.0 * 1024.0),
+            if run.failed > 0 { format!(" ({} unreadable)", run.failed) } else { String::new() },
+        );
+    }
+    if runs.iter().any(|run| run.reused) {
+        println!("⚠️ The library is smaller than {} MB per run, so later runs re-read files that may come from the OS cache and look faster.", sample_mb);
+    }
+    if let Some(fastest) = runs.iter().max_by(|a, b| a.bytes_per_sec().total_cmp(&b.bytes_per_sec())) {
+        println!(
+            "💡 Fastest with {}; set \"hash_threads\": {} in the settings to use it for verify, export and sync.",
+            thread_label(fastest.threads),
+            fastest.threads,
+        );
+    }
+    Ok(())
+}
+
+/// Reverts the last batch of file changes recorded in the undo journal, or lists the batches
+/// with `list`.
+pub fn undo(config_path: &Path, list: bool) -> io::Result<()> {
+    if list {
+        let journal = Journal::load(&Journal::path_for_config(config_path))?;
+        if journal.batches.is_empty() {
+            println!("📭 Nothing to undo.");
+        }
+        for batch in journal.batches.iter().rev() {
+            println!("  {}  {} ({} changes)", history::format_timestamp(batch.timestamp), batch.command, batch.operations.len());
+        }
+        return Ok(());
+    }
+    let Some(report) = journal::undo_last(config_path)? else {
+        println!("📭 Nothing to undo.");
+        return Ok(());
+    };
+    println!("↩️ Undoing '{}' from {}:", report.batch.command, history::format_timestamp(report.batch.timestamp));
+    let mut failed = 0;
+    for (operation, result) in &report.results {
+        match result {
+            Ok(()) => println!("  ✅ {}", operation),
+            Err(e) => {
+                println!("  ❌ Could not {}: {}", operation, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        println!("⚠️ {} changes could not be reverted; they stay in the journal, so 'undo' can be retried.", failed);
+    } else {
+        println!("↩️ Reverted {} changes.", report.results.len());
+    }
+    Ok(())
+}
+
+/// Reports zipped games that are not TorrentZip-normalized and optionally re-zips them.
+pub fn check_torrentzip(roms_dir: &str, fix: bool) -> io::Result<()> {
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let zips: Vec<&Game> = roms.iter()
+        .filter(|g| g.path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip")))
+        .collect();
+    if zips.is_empty() {
+        println!("📭 No zipped games found.");
+        return Ok(());
+    }
+
+    let mut not_normalized = 0;
+    let mut fixed = 0;
+    for game in &zips {
+        match torrentzip::is_torrentzipped(&game.path) {
+            Ok(true) => {}
+            Ok(false) => {
+                not_normalized += 1;
+                if fix {
+                    match torrentzip::rezip(&game.path) {
+                        Ok(()) => {
+                            println!("  🔧 Re-zipped: {}", game.path.display());
+                            fixed += 1;
+                        }
+                        Err(e) if e.kind() == io::ErrorKind::Unsupported && !cfg!(feature = "zlib") => {
+                            eprintln!("❌ Could not re-zip the archives: {}", e);
+                            return Ok(());
+                        }
+                        Err(e) => println!("  ❌ Could not re-zip {}: {}", game.path.display(), e),
+                    }
+                } else {
+                    println!("  📦 Not TorrentZipped: {}", game.path.display());
+                }
+            }
+            Err(e) => println!("  ⚠️ Could not check {}: {}", game.path.display(), e),
+        }
+    }
+
+    println!("🗜️ {} of {} zips are TorrentZipped.", zips.len() - not_normalized + fixed, zips.len());
+    if not_normalized > fixed && !fix {
+        println!("💡 Run with --fix to re-zip them deterministically.");
+    }
+    Ok(())
+}
+
+/// Hard-links identical library files together to reclaim space, or only reports them with `dry_run`.
+///
+/// Only single-file ROMs and their companion files are linked: game directories hold saves
+/// and settings that are written in place, so a linked file would change in every game at once.
+pub fn optimize_storage(roms_dir: &str, dry_run: bool) -> io::Result<()> {
+    let Some(mut roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    roms.retain(|game| game.kind == GameKind::File);
+    let files = library_files(&roms);
+    println!("🔍 Looking for identical files among {} files...", files.len());
+    let groups = match dedup::find_duplicates(&files) {
+        Ok(groups) => groups,
+        Err(e) => {
+            eprintln!("❌ Could not compare library files: {}", e);
+            return Ok(());
+        }
+    };
+    if groups.is_empty() {
+        println!("✅ No duplicate files found.");
+        return Ok(());
+    }
+
+    let mut reclaimed = 0;
+    for group in &groups {
+        println!("\n  📄 {} ({} bytes)", group.original.display(), group.size);
+        for duplicate in &group.duplicates {
+            if dry_run {
+                println!("     = {}", duplicate.display());
+                continue;
+            }
+            match dedup::link_duplicate(&group.original, duplicate) {
+                Ok(()) => {
+                    println!("     🔗 {}", duplicate.display());
+                    reclaimed += group.size;
+                }
+                Err(e) => println!("     ⚠️ Could not link {}: {}", duplicate.display(), e),
+            }
+        }
+    }
+    let reclaimable: u64 = groups.iter().map(|g| g.reclaimable()).sum();
+    if dry_run {
+        println!("\n💡 {} bytes could be reclaimed; run without --dry-run to hard-link the duplicates.", reclaimable);
+    } else {
+        println!("\n💾 Reclaimed {} bytes.", reclaimed);
+    }
+    Ok(())
+}
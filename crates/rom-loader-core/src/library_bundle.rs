@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::enhancements::Companions;
+use crate::library::{library_files, scan_roms};
+use crate::metadata::{GameMetadata, MetadataDb};
+use crate::rom_scanner::{Game, GameKind};
+use crate::screenshots::{self, ASSETS_DIR_NAME};
+use crate::settings::Settings;
+use crate::state_store::StateStore;
+use crate::verify::{HashStore, StoredHash, VerifyOptions};
+use crate::{atomic_file, history, manuals, verify};
+
+/// Name of the index stored inside every library bundle.
+pub const BUNDLE_INDEX_NAME: &str = "library.json";
+
+/// The index of a library bundle: every game with its hash, metadata and hidden flag.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryBundle {
+    /// Unix timestamp of the export.
+    pub created: u64,
+    /// Whether game paths are relative to the ROMs directory, so the bundle can be imported
+    /// onto another machine's library.
+    pub portable: bool,
+    /// The library that was exported.
+    pub roms_dir: PathBuf,
+    pub games: Vec<BundledGame>,
+}
+
+/// One game of a library bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundledGame {
+    /// Path of the game; relative to the ROMs directory in portable bundles.
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// Size in bytes; the total size for game directories.
+    pub size: u64,
+    /// CRC32 of the file; not taken for game directories.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<GameMetadata>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    /// Files that belong with the game, such as an MSU-1 pack; relative like `path`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub companions: Vec<PathBuf>,
+    /// Manuals and maps, for frontends that show them. Those next to the game are relative
+    /// like `path`; those in the game's assets are relative to the bundle's assets folder.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub manuals: Vec<PathBuf>,
+}
+
+impl LibraryBundle {
+    /// Returns where a bundled game lives in the library at `roms_dir`.
+    ///
+    /// Portable bundles are re-rooted onto `roms_dir`; other bundles keep their absolute paths.
+    pub fn local_path(&self, game: &BundledGame, roms_dir: &Path) -> PathBuf {
+        if self.portable { roms_dir.join(&game.path) } else { game.path.clone() }
+    }
+}
+
+/// What an import added to this machine's library data.
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    /// Games in the bundle.
+    pub games: usize,
+    /// Bundled games found in the local library.
+    pub found: usize,
+    /// Hashes added to the hash store, so the files don't have to be hashed again.
+    pub hashes: usize,
+    pub metadata: usize,
+    pub hidden: usize,
+    /// Asset files (art, screenshots) extracted.
+    pub assets: usize,
+}
+
+/// Builds the index of the library in `roms_dir`.
+///
+/// Files not hashed yet are hashed first and recorded in the hash store next to `config_path`.
+/// With `portable`, game paths are relative to `roms_dir`.
+///
+/// # Returns
+/// The index and the scanned games, in the same order.
+pub fn build_index(config_path: &Path, roms_dir: &Path, portable: bool) -> io::Result<(LibraryBundle, Vec<Game>)> {
+    let roms = scan_roms(roms_dir, true)?;
+    let store_path = HashStore::path_for_config(config_path);
+    let mut store = HashStore::load(&store_path)?;
+    let files: Vec<PathBuf> = roms.iter().filter(|g| g.kind == GameKind::File).map(|g| g.path.clone()).collect();
+    let threads = Settings::load(config_path).unwrap_or_default().hash_threads;
+    let options = VerifyOptions { deep: false, min_age_secs: None, inner: false, now: history::now_timestamp(), threads };
+    for (file, result) in files.iter().zip(verify::verify_files(&files, &mut store, &options)) {
+        if let Err(e) = result {
+            eprintln!("⚠️ Could not hash {}: {}", file.display(), e);
+        }
+    }
+    store.save(&store_path)?;
+
+    let metadata = MetadataDb::load(&MetadataDb::path_for_config(config_path))?;
+    let state = StateStore::load(&StateStore::path_for_config(config_path))?;
+    let mut bundle = LibraryBundle { created: options.now, portable, roms_dir: roms_dir.to_path_buf(), games: Vec::new() };
+    let assets_root = config_path.with_file_name(ASSETS_DIR_NAME);
+    for game in &roms {
+        let path = if portable {
+            game.path.strip_prefix(roms_dir).unwrap_or(&game.path).to_path_buf()
+        } else {
+            game.path.clone()
+        };
+        let (size, crc32) = match game.kind {
+            GameKind::File => match store.files.get(&game.path).filter(|h| !h.inner) {
+                Some(hash) => (hash.size, Some(hash.crc32.clone())),
+                None => (fs::metadata(&game.path).map_or(0, |m| m.len()), None),
+            },
+            GameKind::Directory { .. } => {
+                let size = library_files(std::slice::from_ref(game))
+                    .iter()
+                    .filter_map(|file| fs::metadata(file).ok())
+                    .map(|m| m.len())
+                    .sum();
+                (size, None)
+            }
+        };
+        let companions = Companions::find(game)
+            .map(|c| c.files())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|file| if portable { file.strip_prefix(roms_dir).map(Path::to_path_buf).unwrap_or(file) } else { file })
+            .collect();
+        let manuals = manuals::find(config_path, game)
+            .into_iter()
+            .map(|file| match file.strip_prefix(&assets_root) {
+                Ok(asset) => Path::new(ASSETS_DIR_NAME).join(asset),
+                Err(_) if portable => file.strip_prefix(roms_dir).map(Path::to_path_buf).unwrap_or(file),
+                Err(_) => file,
+            })
+            .collect();
+        bundle.games.push(BundledGame {
+            path,
+            system: game.system().map(|s| s.id.to_string()),
+            size,
+            crc32,
+            metadata: metadata.lookup(game).cloned(),
+            hidden: state.is_hidden(&game.path),
+            companions,
+            manuals,
+        });
+    }
+    Ok((bundle, roms))
+}
+
+/// Writes the library as a bundle another rom-loader instance can import: a zip archive with
+/// the game index ([`BUNDLE_INDEX_NAME`], see [`build_index`]) and the games' assets.
+///
+/// # Returns
+/// The index that was written and the number of asset files included.
+pub fn export(config_path: &Path, roms_dir: &Path, archive_path: &Path, portable: bool) -> io::Result<(LibraryBundle, usize)> {
+    let (bundle, roms) = build_index(config_path, roms_dir, portable)?;
+    let index = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to serialize library bundle: {}", e),
+        ))?;
+    let mut zip = ZipWriter::new(File::create(archive_path)?);
+    let options = SimpleFileOptions::default();
+    zip.start_file(BUNDLE_INDEX_NAME, options)?;
+    zip.write_all(index.as_bytes())?;
+
+    let assets_root = config_path.with_file_name(ASSETS_DIR_NAME);
+    let mut assets = 0;
+    for game in &roms {
+        let dir = screenshots::game_assets_dir(config_path, game);
+        for file in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+            let Ok(relative) = file.path().strip_prefix(&assets_root) else { continue };
+            // Zip entries always use forward slashes.
+            let name: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+            zip.start_file(format!("{}/{}", ASSETS_DIR_NAME, name.join("/")), options)?;
+            zip.write_all(&fs::read(file.path())?)?;
+            assets += 1;
+        }
+    }
+    zip.finish()?;
+    Ok((bundle, assets))
+}
+
+/// Reads the index of a library bundle made with [`export`].
+pub fn read_bundle(archive_path: &Path) -> io::Result<LibraryBundle> {
+    let mut zip = open_bundle(archive_path)?;
+    read_index(&mut zip, archive_path)
+}
+
+/// Imports a library bundle into the data next to `config_path`, re-rooting portable bundles
+/// onto `roms_dir`.
+///
+/// Hashes are only taken over for local files of the same size that have no stored hash yet,
+/// metadata is merged into the metadata database, hidden games are hidden here too, and assets
+/// are extracted unless the file already exists.
+pub fn import(config_path: &Path, roms_dir: &Path, archive_path: &Path) -> io::Result<ImportSummary> {
+    let mut zip = open_bundle(archive_path)?;
+    let bundle = read_index(&mut zip, archive_path)?;
+
+    let store_path = HashStore::path_for_config(config_path);
+    let metadata_path = MetadataDb::path_for_config(config_path);
+    let state_path = StateStore::path_for_config(config_path);
+    let mut store = HashStore::load(&store_path)?;
+    let mut metadata = MetadataDb::load(&metadata_path)?;
+    let mut state = StateStore::load(&state_path)?;
+    let mut summary = ImportSummary { games: bundle.games.len(), ..ImportSummary::default() };
+    let now = history::now_timestamp();
+
+    for game in &bundle.games {
+        if let Some(entry) = &game.metadata {
+            metadata.insert(entry.clone());
+            summary.metadata += 1;
+        }
+        let path = bundle.local_path(game, roms_dir);
+        let Ok(file) = fs::metadata(&path) else { continue };
+        summary.found += 1;
+        if game.hidden && state.set_hidden(&path, true) {
+            summary.hidden += 1;
+        }
+        let Some(crc32) = &game.crc32 else { continue };
+        if file.is_file() && file.len() == game.size && !store.files.contains_key(&path) {
+            let modified = file.modified()?.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            store.files.insert(path, StoredHash { size: game.size, modified, crc32: crc32.clone(), verified: now, inner: false });
+            summary.hashes += 1;
+        }
+    }
+
+    let assets_root = config_path.with_file_name(ASSETS_DIR_NAME);
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let Some(relative) = entry.enclosed_name() else { continue };
+        let Ok(relative) = relative.strip_prefix(ASSETS_DIR_NAME) else { continue };
+        // Only plain relative paths, so a crafted bundle can't write outside the assets folder.
+        if entry.is_dir() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            continue;
+        }
+        let target = assets_root.join(relative);
+        if target.exists() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        atomic_file::write(&target, data)?;
+        summary.assets += 1;
+    }
+
+    store.save(&store_path)?;
+    metadata.save(&metadata_path)?;
+    state.save(&state_path)?;
+    Ok(summary)
+}
+
+fn open_bundle(archive_path: &Path) -> io::Result<ZipArchive<File>> {
+    ZipArchive::new(File::open(archive_path)?)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to read library bundle {}: {}", archive_path.display(), e),
+        ))
+}
+
+fn read_index(zip: &mut ZipArchive<File>, archive_path: &Path) -> io::Result<LibraryBundle> {
+    let mut contents = String::new();
+    zip.by_name(BUNDLE_INDEX_NAME)
+        .map_err(|_| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a library bundle (no {})", archive_path.display(), BUNDLE_INDEX_NAME),
+        ))?
+        .read_to_string(&mut contents)?;
+    serde_json::from_str(&contents)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to parse library bundle index: {}", e),
+        ))
+}
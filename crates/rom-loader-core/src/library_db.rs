@@ -0,0 +1,234 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::metadata::MetadataDb;
+use crate::scan_snapshot::{ScanSnapshot, SnapshotEntry, SNAPSHOT_FILE_NAME};
+use crate::titles::TitleCleaner;
+
+/// File name of the library database, stored next to the emulator configuration.
+pub const LIBRARY_DB_FILE_NAME: &str = "library.db";
+
+/// Schema version, stored as SQLite's `user_version`.
+const SCHEMA_VERSION: i32 = 1;
+
+/// How long to wait for another process (e.g. a scan in another terminal) to finish writing.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS scans (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        roms_dir TEXT NOT NULL,
+        created INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS games (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE,
+        size INTEGER NOT NULL,
+        modified INTEGER NOT NULL,
+        crc32 TEXT,
+        title TEXT NOT NULL DEFAULT '',
+        description TEXT NOT NULL DEFAULT ''
+    );
+    CREATE VIRTUAL TABLE IF NOT EXISTS games_fts USING fts5(
+        title, description, file_name, tokenize = 'unicode61 remove_diacritics 2'
+    );
+";
+
+/// A game found by a full-text search.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub title: String,
+}
+
+/// The library cache: the games of the last scan with their sizes, modification times and
+/// hashes, and a full-text index over their titles, descriptions and file names.
+///
+/// The database is opened in WAL mode, so a listing or search can read it while a scan in
+/// another process updates it.
+pub struct LibraryDb {
+    conn: Connection,
+    path: PathBuf,
+}
+
+fn db_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(format!("Library database error: {}", e))
+}
+
+impl LibraryDb {
+    /// Returns the library database path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(LIBRARY_DB_FILE_NAME)
+    }
+
+    /// Opens the library database, creating it if needed.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let conn = Connection::open(path).map_err(db_error)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(db_error)?;
+        conn.pragma_update(None, "journal_mode", "WAL").map_err(db_error)?;
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).map_err(db_error)?;
+        if version > SCHEMA_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} was written by a newer rom-loader (schema version {})", path.display(), version),
+            ));
+        }
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION).map_err(db_error)?;
+        Ok(LibraryDb { conn, path: path.to_path_buf() })
+    }
+
+    /// Writes a consistent copy of the database to `path`, including the changes still in the
+    /// write-ahead log, which copying the file would miss. `path` must not exist yet.
+    pub fn snapshot_to(&self, path: &Path) -> io::Result<()> {
+        self.conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()]).map_err(db_error)?;
+        Ok(())
+    }
+
+    /// Loads the games of the last scan, or `None` if the library was never scanned.
+    ///
+    /// A `scan_snapshot.json` left by earlier versions next to the database is used instead
+    /// when the database has no scan yet; it is removed once a scan is saved.
+    pub fn load_snapshot(&self) -> io::Result<Option<ScanSnapshot>> {
+        let scan: Option<(String, u64)> = self.conn
+            .query_row("SELECT roms_dir, created FROM scans WHERE id = 1", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()
+            .map_err(db_error)?;
+        let Some((roms_dir, created)) = scan else {
+            return ScanSnapshot::load(&self.path.with_file_name(SNAPSHOT_FILE_NAME));
+        };
+        let mut statement = self.conn.prepare("SELECT path, size, modified, crc32 FROM games").map_err(db_error)?;
+        let games = statement
+            .query_map([], |row| {
+                let path: String = row.get(0)?;
+                Ok((PathBuf::from(path), SnapshotEntry { size: row.get(1)?, modified: row.get(2)?, crc32: row.get(3)? }))
+            })
+            .map_err(db_error)?
+            .collect::<Result<BTreeMap<_, _>, _>>()
+            .map_err(db_error)?;
+        Ok(Some(ScanSnapshot { created, roms_dir: PathBuf::from(roms_dir), games }))
+    }
+
+    /// Saves a scan, only writing the games that were added, removed or changed.
+    pub fn save_snapshot(&mut self, snapshot: &ScanSnapshot) -> io::Result<()> {
+        let tx = self.conn.transaction().map_err(db_error)?;
+        {
+            let mut existing: BTreeMap<String, (i64, SnapshotEntry)> = BTreeMap::new();
+            let mut statement = tx.prepare("SELECT id, path, size, modified, crc32 FROM games").map_err(db_error)?;
+            let rows = statement
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(1)?, (row.get(0)?, SnapshotEntry { size: row.get(2)?, modified: row.get(3)?, crc32: row.get(4)? })))
+                })
+                .map_err(db_error)?;
+            for row in rows {
+                let (path, entry) = row.map_err(db_error)?;
+                existing.insert(path, entry);
+            }
+
+            for (path, (id, _)) in &existing {
+                if !snapshot.games.contains_key(Path::new(path)) {
+                    tx.execute("DELETE FROM games WHERE id = ?1", params![id]).map_err(db_error)?;
+                    tx.execute("DELETE FROM games_fts WHERE rowid = ?1", params![id]).map_err(db_error)?;
+                }
+            }
+            for (path, entry) in &snapshot.games {
+                let key = path.to_string_lossy();
+                match existing.get(key.as_ref()) {
+                    Some((_, old)) if old.size == entry.size && old.modified == entry.modified && old.crc32 == entry.crc32 => {}
+                    Some((id, _)) => {
+                        tx.execute(
+                            "UPDATE games SET size = ?1, modified = ?2, crc32 = ?3 WHERE id = ?4",
+                            params![entry.size, entry.modified, entry.crc32, id],
+                        ).map_err(db_error)?;
+                    }
+                    None => {
+                        tx.execute(
+                            "INSERT INTO games (path, size, modified, crc32) VALUES (?1, ?2, ?3, ?4)",
+                            params![key, entry.size, entry.modified, entry.crc32],
+                        ).map_err(db_error)?;
+                    }
+                }
+            }
+            tx.execute(
+                "INSERT OR REPLACE INTO scans (id, roms_dir, created) VALUES (1, ?1, ?2)",
+                params![snapshot.roms_dir.to_string_lossy(), snapshot.created],
+            ).map_err(db_error)?;
+        }
+        tx.commit().map_err(db_error)?;
+
+        let legacy = self.path.with_file_name(SNAPSHOT_FILE_NAME);
+        if legacy.exists() {
+            fs::remove_file(legacy)?;
+        }
+        Ok(())
+    }
+
+    /// Updates the titles and descriptions in the search index: the metadata title where the
+    /// game is known, otherwise its file name cleaned up by the title rules.
+    ///
+    /// Only games whose text changed are re-indexed. Games are taken one at a time, so the
+    /// paths can come straight from a scan snapshot.
+    pub fn index_titles<'p>(&mut self, games: impl IntoIterator<Item = &'p Path>, metadata: &MetadataDb, titles: &TitleCleaner) -> io::Result<()> {
+        let tx = self.conn.transaction().map_err(db_error)?;
+        {
+            let mut select = tx.prepare("SELECT id, title, description FROM games WHERE path = ?1").map_err(db_error)?;
+            for path in games {
+                let key = path.to_string_lossy();
+                let Some((id, old_title, old_description)) = select
+                    .query_row(params![key], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+                    .optional()
+                    .map_err(db_error)?
+                else {
+                    continue;
+                };
+                let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+                let known = metadata.lookup_path(path);
+                let title = known.map_or_else(|| titles.clean(&file_name), |m| m.title.clone());
+                let description = known.and_then(|m| m.description.clone()).unwrap_or_default();
+                if title == old_title && description == old_description {
+                    continue;
+                }
+                tx.execute("UPDATE games SET title = ?1, description = ?2 WHERE id = ?3", params![title, description, id])
+                    .map_err(db_error)?;
+                tx.execute("DELETE FROM games_fts WHERE rowid = ?1", params![id]).map_err(db_error)?;
+                tx.execute(
+                    "INSERT INTO games_fts (rowid, title, description, file_name) VALUES (?1, ?2, ?3, ?4)",
+                    params![id, title, description, file_name],
+                ).map_err(db_error)?;
+            }
+        }
+        tx.commit().map_err(db_error)
+    }
+
+    /// Searches titles, descriptions and file names, best matches first.
+    ///
+    /// Every word must match, as a prefix: "zel lin" finds "The Legend of Zelda: A Link to the Past".
+    pub fn search(&self, text: &str, limit: usize) -> io::Result<Vec<SearchHit>> {
+        // Quote each word so FTS5 operators and punctuation in titles are taken literally.
+        let query: Vec<String> = text
+            .split_whitespace()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut statement = self.conn
+            .prepare(
+                "SELECT g.path, g.title FROM games_fts JOIN games g ON g.id = games_fts.rowid
+                 WHERE games_fts MATCH ?1 ORDER BY bm25(games_fts) LIMIT ?2",
+            )
+            .map_err(db_error)?;
+        let hits = statement
+            .query_map(params![query.join(" "), limit as i64], |row| {
+                Ok(SearchHit { path: PathBuf::from(row.get::<_, String>(0)?), title: row.get(1)? })
+            })
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error)?;
+        Ok(hits)
+    }
+}
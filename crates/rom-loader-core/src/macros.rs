@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::atomic_file;
+
+/// File name of the saved macros, kept next to the emulator configuration.
+pub const MACROS_FILE_NAME: &str = "macros.json";
+
+/// Named sequences of interactive commands, e.g. `kiosk` = `["l --genre rpg", "3"]`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MacroStore {
+    #[serde(default)]
+    pub macros: BTreeMap<String, Vec<String>>,
+}
+
+impl MacroStore {
+    /// Returns the macros path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(MACROS_FILE_NAME)
+    }
+
+    /// Loads the saved macros, returning an empty store if none were recorded yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(MacroStore::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse macros {}: {}", path.display(), e),
+            ))
+    }
+
+    /// Saves the macros as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize macros to JSON: {}", e),
+            ))?;
+        atomic_file::write(path, contents)
+    }
+}
+
+/// Splits a macro written on one line, with commands separated by `;`.
+pub fn parse_commands(line: &str) -> Vec<String> {
+    line.split(';')
+        .map(|command| command.trim().to_string())
+        .filter(|command| !command.is_empty())
+        .collect()
+}
+
+/// Where the interactive browser reads its commands from: replayed macro commands first, then
+/// stdin and any other source set up with [`CommandInput::listen`].
+///
+/// While recording, every line typed (including answers to prompts such as the emulator choice)
+/// is kept for the macro, except the `record` commands that control the recording. Replayed
+/// commands are not recorded again: a `macro <name>` line is kept instead of its expansion.
+#[derive(Debug, Default)]
+pub struct CommandInput {
+    pending: VecDeque<String>,
+    recording: Option<(String, Vec<String>)>,
+    /// Lines from stdin and other sources, once there is more than stdin.
+    lines: Option<(Receiver<String>, Sender<String>)>,
+}
+
+impl CommandInput {
+    /// Queues commands to be replayed before reading from stdin again.
+    pub fn replay(&mut self, commands: &[String]) {
+        // Commands queued by a macro that is being replayed still run first, in order.
+        for command in commands.iter().rev() {
+            self.pending.push_front(command.clone());
+        }
+    }
+
+    /// Returns a sender for commands coming from somewhere other than stdin, such as cabinet
+    /// buttons; they are read in turn with the lines typed.
+    pub fn listen(&mut self) -> Sender<String> {
+        let (_, sender) = self.lines.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            let typed = sender.clone();
+            // Stdin can only be read blocking, so it gets a thread feeding the same channel.
+            thread::spawn(move || {
+                let mut input = String::new();
+                while io::stdin().read_line(&mut input).is_ok_and(|read| read > 0) {
+                    if typed.send(input.trim().to_string()).is_err() {
+                        break;
+                    }
+                    input.clear();
+                }
+            });
+            (receiver, sender)
+        });
+        sender.clone()
+    }
+
+    /// Reads the next command line, trimmed. Replayed commands are echoed so the session reads like a transcript.
+    pub fn read_line(&mut self) -> io::Result<String> {
+        if let Some(command) = self.pending.pop_front() {
+            println!("{}", command);
+            return Ok(command);
+        }
+        let line = match &self.lines {
+            // The channel's own sender is kept, so this only fails if a sending thread panicked.
+            Some((receiver, _)) => receiver.recv().unwrap_or_default(),
+            None => {
+                let mut input = String::new();
+                io::stdin().read_line(&mut input)?;
+                input.trim().to_string()
+            }
+        };
+        if let Some((_, commands)) = &mut self.recording {
+            if line != "record" && !line.starts_with("record ") {
+                commands.push(line.clone());
+            }
+        }
+        Ok(line)
+    }
+
+    /// Starts recording the following lines into a macro called `name`.
+    pub fn start_recording(&mut self, name: &str) {
+        self.recording = Some((name.to_string(), Vec::new()));
+    }
+
+    /// Whether a macro is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Stops recording and returns the macro's name and commands.
+    pub fn stop_recording(&mut self) -> Option<(String, Vec<String>)> {
+        self.recording.take()
+    }
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use walkdir::WalkDir;
+
+use crate::rom_scanner::{Game, GameKind};
+use crate::screenshots;
+
+/// Extensions of manuals, maps and other printed extras.
+const MANUAL_EXTENSIONS: &[&str] = &["pdf", "cbz", "cbr", "djvu", "epub"];
+
+/// Returns the folder in a game's assets where manuals can be kept, e.g.
+/// `assets/snes/Super Mario World/manuals`.
+pub fn manuals_dir(config_path: &Path, game: &Game) -> PathBuf {
+    screenshots::game_assets_dir(config_path, game).join("manuals")
+}
+
+/// Finds a game's manuals and other extras, sorted by path.
+///
+/// These are the files in its `manuals` assets folder, files next to the ROM whose name starts
+/// with the ROM's (`Game.pdf`, `Game (Map).pdf`), and for game directories the documents
+/// inside them.
+pub fn find(config_path: &Path, game: &Game) -> Vec<PathBuf> {
+    let is_manual = |path: &Path| path.extension().is_some_and(|e| MANUAL_EXTENSIONS.iter().any(|ext| e.eq_ignore_ascii_case(ext)));
+    let mut manuals: Vec<PathBuf> = WalkDir::new(manuals_dir(config_path, game))
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && is_manual(e.path()))
+        .map(|e| e.into_path())
+        .collect();
+    match game.kind {
+        GameKind::File => {
+            let stem = game.path.file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+            let dir = game.path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            if let Ok(entries) = fs::read_dir(dir) {
+                manuals.extend(
+                    entries
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .filter(|p| is_manual(p) && p.file_stem().is_some_and(|s| s.to_string_lossy().to_lowercase().starts_with(&stem))),
+                );
+            }
+        }
+        GameKind::Directory { .. } => manuals.extend(
+            WalkDir::new(&game.path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file() && is_manual(e.path()))
+                .map(|e| e.into_path()),
+        ),
+    }
+    manuals.sort();
+    manuals
+}
+
+/// Opens a file in the system's default viewer.
+///
+/// `xdg-open`, `open` and `start` hand the file over and return, so this doesn't wait for the
+/// viewer to close.
+pub fn open_in_viewer(path: &Path) -> io::Result<()> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        // The empty title keeps `start` from taking a quoted path as the window title.
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    let status = command
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not start the document viewer: {}", e)))?;
+    if !status.success() {
+        return Err(io::Error::other(format!("The document viewer could not open {} ({})", path.display(), status)));
+    }
+    Ok(())
+}
@@ -0,0 +1,577 @@
+use crate::atomic_file;
+use crate::rom_scanner::Game;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// File name of the imported metadata database, stored next to the emulator configuration.
+pub const METADATA_FILE_NAME: &str = "metadata.json";
+
+/// Magic bytes at the start of a libretro .rdb file.
+const RDB_MAGIC: &[u8; 8] = b"RARCHDB\0";
+
+/// A ROM file listed for a game in a dat: (file name, CRC32).
+type DatRom = (Option<String>, Option<String>);
+
+/// Descriptive information about a single game, as found in an offline database.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameMetadata {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rom_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc: Option<String>, // Upper-case hex CRC32 of the ROM image
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub developer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub players: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<String>, // Age rating, e.g. "E - Everyone"
+    /// Short set name when it differs from the title, e.g. "pacman" for arcade games.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_name: Option<String>,
+    /// Parent set of an arcade clone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clone_of: Option<String>,
+    /// Number of ROM files the set consists of, including those shared with its parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rom_count: Option<usize>,
+    /// Free homebrew downloaded by `discover homebrew`; found with the `is:homebrew` search.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub homebrew: bool,
+}
+
+impl GameMetadata {
+    /// Copies every field set in `other` over this entry, keeping fields `other` leaves empty.
+    ///
+    /// libretro-database splits metadata into one file per field (genre, release year, ...)
+    /// keyed by CRC, so entries for the same game are assembled from several imports.
+    fn merge(&mut self, other: GameMetadata) {
+        if !other.title.is_empty() {
+            self.title = other.title;
+        }
+        let fields = [
+            (&mut self.rom_name, other.rom_name),
+            (&mut self.crc, other.crc),
+            (&mut self.description, other.description),
+            (&mut self.year, other.year),
+            (&mut self.genre, other.genre),
+            (&mut self.developer, other.developer),
+            (&mut self.publisher, other.publisher),
+            (&mut self.players, other.players),
+            (&mut self.region, other.region),
+            (&mut self.rating, other.rating),
+            (&mut self.set_name, other.set_name),
+            (&mut self.clone_of, other.clone_of),
+        ];
+        for (field, value) in fields {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+        if other.rom_count.is_some() {
+            self.rom_count = other.rom_count;
+        }
+        self.homebrew |= other.homebrew;
+    }
+
+    /// Completes a game read from a dat file from its set name, comment and ROM list.
+    ///
+    /// The title is the description (the full name for MAME sets), falling back to the set
+    /// name and then to the comment the per-field metadat files carry. ROM name and CRC are
+    /// only kept for single-file games: arcade sets share ROMs between parent and clones.
+    fn finish_dat_entry(mut self, name: Option<String>, comment: Option<String>, roms: Vec<DatRom>) -> Self {
+        self.title = self.description.clone().or_else(|| name.clone()).or(comment).unwrap_or_default();
+        self.set_name = name.filter(|n| *n != self.title);
+        if let [(rom_name, crc)] = roms.as_slice() {
+            self.rom_name = rom_name.clone();
+            self.crc = crc.as_ref().map(|c| c.to_uppercase());
+        }
+        if !roms.is_empty() {
+            self.rom_count = Some(roms.len());
+        }
+        self
+    }
+
+    /// Returns a short "(year, genre)" style summary of the known details, if any.
+    pub fn summary(&self) -> Option<String> {
+        let details: Vec<&str> = [&self.year, &self.genre]
+            .into_iter()
+            .filter_map(|field| field.as_deref())
+            .collect();
+        if details.is_empty() {
+            None
+        } else {
+            Some(details.join(", "))
+        }
+    }
+
+    /// Returns the release year as a number, ignoring databases that store full dates.
+    pub fn release_year(&self) -> Option<u32> {
+        self.year.as_deref()?.get(..4)?.parse().ok()
+    }
+
+    /// Returns the maximum number of simultaneous players (e.g. 4 for "1-4").
+    pub fn max_players(&self) -> Option<u32> {
+        self.players
+            .as_deref()?
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|part| part.parse().ok())
+            .max()
+    }
+}
+
+/// An inclusive range of release years, written as "1994", "1994..1998", "1994.." or "..1998".
+#[derive(Debug, Clone, Copy)]
+pub struct YearRange {
+    pub start: Option<u32>,
+    pub end: Option<u32>,
+}
+
+impl YearRange {
+    /// Whether the given year falls inside the range.
+    pub fn contains(&self, year: u32) -> bool {
+        self.start.is_none_or(|start| year >= start) && self.end.is_none_or(|end| year <= end)
+    }
+}
+
+impl FromStr for YearRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_year = |part: &str| -> Result<Option<u32>, String> {
+            let part = part.trim();
+            if part.is_empty() {
+                Ok(None)
+            } else {
+                part.parse().map(Some).map_err(|_| format!("'{}' is not a year", part))
+            }
+        };
+        match s.split_once("..") {
+            Some((start, end)) => Ok(YearRange { start: parse_year(start)?, end: parse_year(end)? }),
+            None => {
+                let year = parse_year(s)?.ok_or("expected a year or a range like 1994..1998")?;
+                Ok(YearRange { start: Some(year), end: Some(year) })
+            }
+        }
+    }
+}
+
+/// Offline game metadata imported from database files, used without any network access.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetadataDb {
+    #[serde(default)]
+    pub entries: Vec<GameMetadata>,
+    /// Entry indices by CRC, rebuilt after loading.
+    #[serde(skip)]
+    by_crc: HashMap<String, usize>,
+    /// Entry indices by lower-case ROM file name and title, rebuilt after loading.
+    #[serde(skip)]
+    by_name: HashMap<String, usize>,
+}
+
+impl MetadataDb {
+    /// Returns the metadata database path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(METADATA_FILE_NAME)
+    }
+
+    /// Loads the metadata database, returning an empty one if nothing was imported yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(MetadataDb::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        let mut db: MetadataDb = serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse metadata database {}: {}", path.display(), e),
+            ))?;
+        db.rebuild_index();
+        Ok(db)
+    }
+
+    /// Saves the metadata database as JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string(self)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize metadata database to JSON: {}", e),
+            ))?;
+        atomic_file::write(path, contents)
+    }
+
+    /// Imports a libretro-database file, merging it into the existing entries.
+    ///
+    /// clrmamepro-style `.dat` files (including libretro's per-field `metadat` files),
+    /// Logiqx XML dats and MAME `-listxml` output, and compiled `.rdb` files are supported;
+    /// the format is detected from the file contents.
+    ///
+    /// # Returns
+    ///
+    /// The number of games read from the file.
+    pub fn import_file(&mut self, path: &Path) -> io::Result<usize> {
+        let bytes = fs::read(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("Failed to read {}: {}", path.display(), e)))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let games = if bytes.starts_with(RDB_MAGIC) {
+            parse_rdb(&bytes)
+        } else if text.trim_start().starts_with('<') {
+            parse_xml_dat(&text)
+        } else {
+            parse_dat(&text)
+        }
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to import {}: {}", path.display(), e),
+        ))?;
+
+        let count = games.len();
+        for game in games {
+            self.insert(game);
+        }
+        Ok(count)
+    }
+
+    /// Finds the metadata for a scanned game by its file (or directory) name.
+    pub fn lookup(&self, game: &Game) -> Option<&GameMetadata> {
+        self.lookup_path(&game.path)
+    }
+
+    /// Finds the metadata for the game at a path, by its file name or stem.
+    pub fn lookup_path(&self, path: &Path) -> Option<&GameMetadata> {
+        self.index_of(path).map(|i| &self.entries[i])
+    }
+
+    fn index_of(&self, path: &Path) -> Option<usize> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        let stem = path.file_stem()?.to_string_lossy().to_lowercase();
+        self.by_name.get(&file_name).or_else(|| self.by_name.get(&stem)).copied()
+    }
+
+    /// Drops the entries that no game of the library matches by name, for when a big import
+    /// (such as a whole MAME list) backs a small library on a low-memory board.
+    ///
+    /// Lookups by CRC then only find the kept entries, so archives whose members match an
+    /// entry only by CRC lose their metadata.
+    pub fn retain_games(&mut self, games: &[Game]) {
+        let used: HashSet<usize> = games.iter().filter_map(|game| self.index_of(&game.path)).collect();
+        let mut index = 0;
+        self.entries.retain(|_| {
+            index += 1;
+            used.contains(&(index - 1))
+        });
+        self.entries.shrink_to_fit();
+        self.by_crc = HashMap::new();
+        self.by_name = HashMap::new();
+        self.rebuild_index();
+    }
+
+    /// Finds the metadata for a ROM image by its CRC32 (hex, any case).
+    pub fn lookup_crc(&self, crc: &str) -> Option<&GameMetadata> {
+        self.by_crc.get(&crc.to_uppercase()).map(|&i| &self.entries[i])
+    }
+
+    /// Adds an entry, merging it into an existing entry for the same CRC or ROM name.
+    pub fn insert(&mut self, game: GameMetadata) {
+        let existing = game.crc.as_ref().and_then(|crc| self.by_crc.get(crc).copied())
+            .or_else(|| {
+                game.rom_name.as_ref().or(game.set_name.as_ref())
+                    .and_then(|name| self.by_name.get(&name.to_lowercase()).copied())
+            });
+        let index = match existing {
+            Some(i) => {
+                self.entries[i].merge(game);
+                i
+            }
+            None => {
+                // Field-only entries (e.g. a genre with just a CRC) still get a title later.
+                self.entries.push(game);
+                self.entries.len() - 1
+            }
+        };
+        self.index_entry(index);
+    }
+
+    fn rebuild_index(&mut self) {
+        self.by_crc.clear();
+        self.by_name.clear();
+        for i in 0..self.entries.len() {
+            self.index_entry(i);
+        }
+    }
+
+    fn index_entry(&mut self, index: usize) {
+        let entry = &self.entries[index];
+        if let Some(crc) = &entry.crc {
+            self.by_crc.insert(crc.clone(), index);
+        }
+        if let Some(rom_name) = &entry.rom_name {
+            self.by_name.insert(rom_name.to_lowercase(), index);
+        }
+        if let Some(set_name) = &entry.set_name {
+            self.by_name.insert(set_name.to_lowercase(), index);
+        }
+        if !entry.title.is_empty() {
+            self.by_name.entry(entry.title.to_lowercase()).or_insert(index);
+        }
+    }
+}
+
+/// A value in a clrmamepro .dat file: either a plain value or a nested `( ... )` block.
+enum DatValue {
+    Text(String),
+    Block(Vec<(String, DatValue)>),
+}
+
+/// Parses a clrmamepro-style .dat file into game metadata.
+fn parse_dat(contents: &str) -> Result<Vec<GameMetadata>, String> {
+    let tokens = tokenize_dat(contents)?;
+    let mut position = 0;
+    let top_level = parse_dat_block(&tokens, &mut position, false)?;
+
+    let mut games = Vec::new();
+    for (key, value) in top_level {
+        let (DatValue::Block(fields), "game" | "machine") = (value, key.as_str()) else { continue };
+        let mut game = GameMetadata::default();
+        let mut name = None;
+        let mut comment = None;
+        let mut roms = Vec::new();
+        for (field, value) in fields {
+            match (field.as_str(), value) {
+                ("name", DatValue::Text(v)) => name = Some(v),
+                ("comment", DatValue::Text(v)) => comment = Some(v),
+                ("cloneof", DatValue::Text(v)) => game.clone_of = Some(v),
+                ("description", DatValue::Text(v)) => game.description = Some(v),
+                ("releaseyear" | "year", DatValue::Text(v)) => game.year = Some(v),
+                ("genre", DatValue::Text(v)) => game.genre = Some(v),
+                ("developer", DatValue::Text(v)) => game.developer = Some(v),
+                ("publisher" | "manufacturer", DatValue::Text(v)) => game.publisher = Some(v),
+                ("users", DatValue::Text(v)) => game.players = Some(v),
+                ("region", DatValue::Text(v)) => game.region = Some(v),
+                ("esrb_rating", DatValue::Text(v)) => game.rating = Some(v),
+                ("rom", DatValue::Block(rom)) => {
+                    let (mut rom_name, mut crc) = (None, None);
+                    for (rom_field, rom_value) in rom {
+                        match (rom_field.as_str(), rom_value) {
+                            ("name", DatValue::Text(v)) => rom_name = Some(v),
+                            ("crc", DatValue::Text(v)) => crc = Some(v),
+                            _ => {}
+                        }
+                    }
+                    roms.push((rom_name, crc));
+                }
+                _ => {}
+            }
+        }
+        games.push(game.finish_dat_entry(name, comment, roms));
+    }
+    Ok(games)
+}
+
+/// Parses a Logiqx XML dat or MAME `-listxml` output into game metadata.
+fn parse_xml_dat(contents: &str) -> Result<Vec<GameMetadata>, String> {
+    use quick_xml::events::Event;
+
+    let mut reader = quick_xml::Reader::from_str(contents);
+    let mut games = Vec::new();
+    // The game being read, with its set name and ROM list, and the child element whose text is collected.
+    let mut current: Option<(GameMetadata, Option<String>, Vec<DatRom>)> = None;
+    let mut field: Option<String> = None;
+    let mut text = String::new();
+
+    loop {
+        let event = reader.read_event()
+            .map_err(|e| format!("XML error at byte {}: {}", reader.error_position(), e))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let tag = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let mut attributes = HashMap::new();
+                for attribute in e.attributes().flatten() {
+                    let key = String::from_utf8_lossy(attribute.key.as_ref()).to_string();
+                    let value = attribute.unescape_value().map_err(|e| e.to_string())?.to_string();
+                    attributes.insert(key, value);
+                }
+                match (tag.as_str(), current.as_mut()) {
+                    ("game" | "machine", None) => {
+                        let game = GameMetadata { clone_of: attributes.remove("cloneof"), ..Default::default() };
+                        current = Some((game, attributes.remove("name"), Vec::new()));
+                        if matches!(event, Event::Empty(_)) {
+                            let (game, name, roms) = current.take().unwrap_or_default();
+                            games.push(game.finish_dat_entry(name, None, roms));
+                        }
+                    }
+                    ("rom", Some((_, _, roms))) => roms.push((attributes.remove("name"), attributes.remove("crc"))),
+                    (_, Some(_)) if matches!(event, Event::Start(_)) => {
+                        field = Some(tag);
+                        text.clear();
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(e) if field.is_some() => text.push_str(&e.decode().map_err(|e| e.to_string())?),
+            Event::GeneralRef(e) if field.is_some() => {
+                let resolved = match e.resolve_char_ref().map_err(|e| e.to_string())? {
+                    Some(c) => c,
+                    None => match &*e {
+                        b"amp" => '&',
+                        b"lt" => '<',
+                        b"gt" => '>',
+                        b"quot" => '"',
+                        b"apos" => '\'',
+                        _ => continue,
+                    },
+                };
+                text.push(resolved);
+            }
+            Event::End(e) => {
+                let tag = e.name();
+                if matches!(tag.as_ref(), b"game" | b"machine") {
+                    if let Some((game, name, roms)) = current.take() {
+                        games.push(game.finish_dat_entry(name, None, roms));
+                    }
+                } else if let (Some(name), Some((game, _, _))) = (field.take(), current.as_mut()) {
+                    let value = Some(text.trim().to_string()).filter(|v| !v.is_empty());
+                    match name.as_str() {
+                        "description" => game.description = value,
+                        "year" => game.year = value,
+                        "manufacturer" | "publisher" => game.publisher = value,
+                        "developer" => game.developer = value,
+                        "genre" => game.genre = value,
+                        _ => {}
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(games)
+}
+
+fn tokenize_dat(contents: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' || c == ')' {
+            tokens.push(c.to_string());
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => text.push(c),
+                    None => return Err("unterminated string".to_string()),
+                }
+            }
+            // Quoted values are stored with a leading quote so "(" is not read as a block.
+            tokens.push(format!("\"{}", text));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '(' || c == ')' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(word);
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_dat_block(tokens: &[String], position: &mut usize, nested: bool) -> Result<Vec<(String, DatValue)>, String> {
+    let mut fields = Vec::new();
+    while *position < tokens.len() {
+        let key = &tokens[*position];
+        *position += 1;
+        if key == ")" {
+            if nested {
+                return Ok(fields);
+            }
+            return Err("unexpected ')'".to_string());
+        }
+        let Some(value) = tokens.get(*position) else {
+            return Err(format!("missing value for '{}'", key));
+        };
+        *position += 1;
+        let value = if value == "(" {
+            DatValue::Block(parse_dat_block(tokens, position, true)?)
+        } else {
+            DatValue::Text(value.trim_start_matches('"').to_string())
+        };
+        fields.push((key.trim_start_matches('"').to_string(), value));
+    }
+    if nested {
+        return Err("missing ')'".to_string());
+    }
+    Ok(fields)
+}
+
+/// Parses a compiled libretro .rdb file: a header followed by one MessagePack map per game.
+fn parse_rdb(bytes: &[u8]) -> Result<Vec<GameMetadata>, String> {
+    let mut cursor = Cursor::new(bytes);
+    // Skip the magic and the offset of the trailing metadata record.
+    let mut header = [0u8; 16];
+    cursor.read_exact(&mut header).map_err(|e| e.to_string())?;
+
+    let mut games = Vec::new();
+    loop {
+        let value = rmpv::decode::read_value(&mut cursor).map_err(|e| e.to_string())?;
+        let rmpv::Value::Map(fields) = value else { break }; // A nil value ends the record list
+        let mut game = GameMetadata::default();
+        for (key, value) in fields {
+            let Some(key) = key.as_str() else { continue };
+            let text = rdb_text(&value);
+            match key {
+                "name" => game.title = text.unwrap_or_default(),
+                "description" => game.description = text,
+                "rom_name" => game.rom_name = text,
+                "releaseyear" => game.year = text,
+                "genre" => game.genre = text,
+                "developer" => game.developer = text,
+                "publisher" => game.publisher = text,
+                "users" => game.players = text,
+                "region" => game.region = text,
+                "esrb_rating" => game.rating = text,
+                "crc" => {
+                    game.crc = value.as_slice()
+                        .map(|b| b.iter().map(|byte| format!("{:02X}", byte)).collect())
+                        .or_else(|| text.map(|t| t.to_uppercase()));
+                }
+                _ => {}
+            }
+        }
+        if game.title.is_empty() {
+            game.title = game.description.clone().unwrap_or_default();
+        }
+        games.push(game);
+    }
+    Ok(games)
+}
+
+fn rdb_text(value: &rmpv::Value) -> Option<String> {
+    match value {
+        rmpv::Value::String(s) => s.as_str().map(str::to_string),
+        rmpv::Value::Integer(i) => Some(i.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+/// How many times an operation is tried before its error is reported.
+const ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry; doubled for each further one (0.25 s, 0.5 s, 1 s).
+const FIRST_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Filesystem types of network shares in `/etc/fstab` and `/proc/mounts`.
+const NETWORK_FS_TYPES: &[&str] = &["cifs", "smb3", "smbfs", "nfs", "nfs4", "fuse.sshfs", "fuse.rclone", "9p", "davfs"];
+
+/// Windows errors of network paths: ERROR_BAD_NETPATH, ERROR_UNEXP_NET_ERR,
+/// ERROR_NETNAME_DELETED and ERROR_SEM_TIMEOUT.
+#[cfg(windows)]
+const WINDOWS_NETWORK_ERRORS: &[i32] = &[53, 59, 64, 121];
+
+/// Whether an error means the server behind a path can't be reached: a stale NFS handle, a
+/// dropped SMB session, a network that is down.
+pub fn is_offline_error(e: &io::Error) -> bool {
+    #[cfg(windows)]
+    if e.raw_os_error().is_some_and(|code| WINDOWS_NETWORK_ERRORS.contains(&code)) {
+        return true;
+    }
+    matches!(
+        e.kind(),
+        ErrorKind::StaleNetworkFileHandle
+            | ErrorKind::HostUnreachable
+            | ErrorKind::NetworkUnreachable
+            | ErrorKind::NetworkDown
+            | ErrorKind::NotConnected
+            | ErrorKind::TimedOut
+    )
+}
+
+/// Whether an error may go away if the operation is tried again, as network filesystems
+/// report hiccups (a timeout, a reconnecting share, a busy server).
+pub fn is_transient(e: &io::Error) -> bool {
+    // EIO is what soft NFS mounts and FUSE filesystems return when a request times out.
+    let io_error = cfg!(unix) && e.raw_os_error() == Some(5);
+    io_error
+        || is_offline_error(e)
+        || matches!(
+            e.kind(),
+            ErrorKind::Interrupted
+                | ErrorKind::WouldBlock
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::ResourceBusy
+        )
+}
+
+/// Runs a filesystem operation, trying it again with a growing delay while it fails with a
+/// transient error.
+pub fn retry<T>(mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = FIRST_RETRY_DELAY;
+    for _ in 1..ATTEMPTS {
+        match operation() {
+            Err(e) if is_transient(&e) => {
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+    operation()
+}
+
+/// Checks whether a ROMs directory that can't be scanned, or has no games, is on storage that
+/// went offline rather than really empty.
+///
+/// # Returns
+/// What is offline, e.g. "the network share //nas/roms is not mounted at /mnt/nas", or `None`
+/// if the directory looks reachable.
+pub fn offline_reason(roms_dir: &Path) -> Option<String> {
+    if let Err(e) = fs::metadata(roms_dir) {
+        if is_offline_error(&e) {
+            return Some(format!("its server can't be reached ({})", e));
+        }
+    }
+    let absolute = std::path::absolute(roms_dir).unwrap_or_else(|_| roms_dir.to_path_buf());
+    if let Some((share, mount_point)) = unmounted_network_share(&absolute) {
+        return Some(format!("the network share {} is not mounted at {}", share, mount_point.display()));
+    }
+    // Volumes that aren't mounted have no folder under /Volumes.
+    if cfg!(target_os = "macos") {
+        let mut components = absolute.components().skip(1);
+        if components.next().is_some_and(|c| c.as_os_str() == "Volumes") {
+            if let Some(volume) = components.next() {
+                if !Path::new("/Volumes").join(volume).exists() {
+                    return Some(format!("the volume {} is not mounted", volume.as_os_str().to_string_lossy()));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Finds the network share in `/etc/fstab` that should hold `path` but isn't in the mount table.
+fn unmounted_network_share(path: &Path) -> Option<(String, PathBuf)> {
+    let fstab = fs::read_to_string("/etc/fstab").ok()?;
+    let mounted = fs::read_to_string("/proc/mounts").ok()?;
+    let mount_points: Vec<&str> = mounted.lines().filter_map(|line| line.split_whitespace().nth(1)).collect();
+    fstab
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [share, mount_point, fs_type, ..] if NETWORK_FS_TYPES.contains(fs_type) => Some((*share, *mount_point)),
+                _ => None,
+            }
+        })
+        // The innermost share containing the path.
+        .filter(|(_, mount_point)| path.starts_with(mount_point))
+        .max_by_key(|(_, mount_point)| mount_point.len())
+        .filter(|(_, mount_point)| !mount_points.contains(mount_point))
+        .map(|(share, mount_point)| (share.to_string(), PathBuf::from(mount_point)))
+}
@@ -0,0 +1,85 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::net::TcpStream;
+use tungstenite::{Message, WebSocket};
+
+/// obs-websocket opcodes (protocol v5).
+const OBS_OP_HELLO: u64 = 0;
+const OBS_OP_IDENTIFY: u64 = 1;
+const OBS_OP_IDENTIFIED: u64 = 2;
+const OBS_OP_REQUEST: u64 = 6;
+const OBS_OP_REQUEST_RESPONSE: u64 = 7;
+
+/// Connects to obs-websocket (protocol v5), identifies and sends a single request.
+pub fn request(address: &str, password: Option<&str>, request_type: &str) -> io::Result<()> {
+    let stream = TcpStream::connect(address)
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not connect to OBS at {}: {}", address, e)))?;
+    let (mut socket, _) = tungstenite::client(format!("ws://{}", address), stream)
+        .map_err(|e| io::Error::other(format!("obs-websocket handshake with {} failed: {}", address, e)))?;
+
+    let hello = read_obs_message(&mut socket, OBS_OP_HELLO)?;
+    let mut identify = json!({ "rpcVersion": 1 });
+    if let Some(auth) = hello.get("authentication") {
+        let Some(password) = password else {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "OBS requires a password; set \"password\" in the capture settings",
+            ));
+        };
+        let salt = auth["salt"].as_str().unwrap_or_default();
+        let challenge = auth["challenge"].as_str().unwrap_or_default();
+        identify["authentication"] = json!(obs_authentication(password, salt, challenge));
+    }
+    send_obs_message(&mut socket, OBS_OP_IDENTIFY, identify)?;
+    read_obs_message(&mut socket, OBS_OP_IDENTIFIED)?;
+
+    send_obs_message(&mut socket, OBS_OP_REQUEST, json!({ "requestType": request_type, "requestId": "rom_loader" }))?;
+    let response = read_obs_message(&mut socket, OBS_OP_REQUEST_RESPONSE)?;
+    let _ = socket.close(None);
+    if response["requestStatus"]["result"].as_bool() == Some(true) {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "OBS rejected {}: {}",
+            request_type,
+            response["requestStatus"]["comment"].as_str().unwrap_or("no reason given")
+        )))
+    }
+}
+
+/// Computes the obs-websocket authentication string:
+/// base64(sha256(base64(sha256(password + salt)) + challenge)).
+fn obs_authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    BASE64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+fn send_obs_message(socket: &mut WebSocket<TcpStream>, op: u64, data: Value) -> io::Result<()> {
+    socket.send(Message::text(json!({ "op": op, "d": data }).to_string()))
+        .map_err(|e| io::Error::other(format!("Failed to talk to OBS: {}", e)))
+}
+
+/// Reads messages until one with the given opcode arrives and returns its data.
+fn read_obs_message(socket: &mut WebSocket<TcpStream>, op: u64) -> io::Result<Value> {
+    loop {
+        let message = socket.read().map_err(|e| io::Error::other(format!("Failed to talk to OBS: {}", e)))?;
+        match message {
+            Message::Text(text) => {
+                let mut value: Value = serde_json::from_str(text.as_str())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Unexpected message from OBS: {}", e)))?;
+                if value["op"].as_u64() == Some(op) {
+                    return Ok(value["d"].take());
+                }
+            }
+            Message::Close(frame) => {
+                // OBS closes the connection with a reason, e.g. when authentication failed.
+                let reason = frame.map_or_else(String::new, |f| f.reason.to_string());
+                return Err(io::Error::other(format!("OBS closed the connection: {}", reason)));
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::atomic_file;
+
+/// File name of the saved playlists, kept next to the emulator configuration.
+pub const PLAYLISTS_FILE_NAME: &str = "playlists.json";
+
+/// Named lists of games, e.g. `party` = the four-player games for a game night.
+///
+/// Games are kept by their ID (see [`crate::game_ids`]), so a playlist survives rescans and
+/// other games being added or removed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PlaylistStore {
+    #[serde(default)]
+    pub playlists: BTreeMap<String, Vec<String>>,
+}
+
+impl PlaylistStore {
+    /// Returns the playlists path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(PLAYLISTS_FILE_NAME)
+    }
+
+    /// Loads the saved playlists, returning an empty store if none were made yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(PlaylistStore::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse playlists {}: {}", path.display(), e),
+            ))
+    }
+
+    /// Saves the playlists as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize playlists to JSON: {}", e),
+            ))?;
+        atomic_file::write(path, contents)
+    }
+}
+
+/// Puts items in a random order, different on every run.
+///
+/// Not suitable for anything where the randomness matters beyond variety.
+pub fn shuffle<T>(items: &mut [T]) {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+    // xorshift64*, seeded from the clock and the process; the seed must not be zero.
+    let mut state = (nanos ^ (u64::from(std::process::id()) << 32)) | 1;
+    let mut next = || {
+        state ^= state >> 12;
+        state ^= state << 25;
+        state ^= state >> 27;
+        state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    };
+    // Fisher-Yates.
+    for i in (1..items.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
@@ -0,0 +1,155 @@
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::capabilities;
+use crate::emulator_config::Emulator;
+use crate::enhancements::{self, Companions};
+use crate::rom_launcher::LaunchProfile;
+use crate::rom_scanner::Game;
+
+/// BIOS files systems need: system id, file names of which one must be present, and the
+/// cores (by file name part) that need them; an empty list means every emulator does.
+const REQUIRED_BIOS: &[(&str, &[&str], &[&str])] = &[
+    ("psx", &["scph5500.bin", "scph5501.bin", "scph5502.bin", "scph1001.bin", "scph7001.bin"], &["mednafen_psx", "swanstation", "duckstation"]),
+    ("lynx", &["lynxboot.img"], &[]),
+    ("a5200", &["5200.rom"], &[]),
+    ("coleco", &["colecovision.rom"], &[]),
+    ("intv", &["exec.bin"], &[]),
+    ("intv", &["grom.bin"], &[]),
+];
+
+/// Something that may keep a game from starting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub message: String,
+    /// Whether the launch can't work at all; missing BIOS files are only likely to break it,
+    /// as an emulator may find them somewhere rom-loader doesn't look.
+    pub fatal: bool,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Checks everything a launch needs before the emulator is started, so a missing piece is
+/// reported clearly instead of as a blank window: the ROM is readable, the emulator executable
+/// exists and may be run, the RetroArch core exists, the emulator is at least its `min_version`,
+/// the system's BIOS files are present and an MSU-1 pack next to a SNES ROM will be picked up.
+///
+/// BIOS files are looked for in `bios_dirs` and, for RetroArch, in its system directories.
+///
+/// # Returns
+/// Every problem found; empty if the launch looks fine.
+pub fn check(emulator: &Emulator, game: &Game, launch_path: &Path, bios_dirs: &[PathBuf]) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let mut fatal = |message: String| problems.push(Problem { message, fatal: true });
+
+    // Game directories launch through their marker file, which must be readable too.
+    if let Err(e) = File::open(launch_path).and_then(|mut file| file.read(&mut [0u8; 1])) {
+        fatal(format!("The game can't be read: {} ({})", launch_path.display(), e));
+    }
+
+    let executable = emulator.executable();
+    if !executable.is_file() {
+        let on_path = if emulator.is_command_name() { " (nor on the PATH)" } else { "" };
+        fatal(format!("The emulator executable was not found: {}{}", emulator.path.display(), on_path));
+    } else if !is_executable(&executable) {
+        fatal(format!("The emulator is not executable: {} (try 'chmod +x')", executable.display()));
+    }
+
+    if let Some(core) = emulator.core() {
+        if !core.is_file() {
+            fatal(format!("The RetroArch core was not found: {}", core.display()));
+        }
+    }
+
+    if let Some(min_version) = &emulator.min_version {
+        let version = emulator.capabilities.as_ref().and_then(|c| c.version.as_deref()).unwrap_or_default();
+        match capabilities::meets_min_version(emulator) {
+            Some(false) => fatal(format!("{} {} is older than the required {}; update it and run 'rom-loader doctor'",
+                emulator.name, version, min_version)),
+            Some(true) => {}
+            None => problems.push(Problem {
+                message: format!("The installed version is unknown, so min_version {} can't be checked; run 'rom-loader doctor'", min_version),
+                fatal: false,
+            }),
+        }
+    }
+
+    if Companions::find(game).is_some_and(|c| c.has_msu1()) && emulator.core_path.as_deref().is_some_and(enhancements::core_lacks_msu1) {
+        problems.push(Problem {
+            message: "This game has an MSU-1 pack, but the core doesn't play MSU-1 audio; use the snes9x or bsnes core".to_string(),
+            fatal: false,
+        });
+    }
+    if let Some(pack) = enhancements::misnamed_msu1_pack(game) {
+        let extension = game.path.extension().unwrap_or_default().to_string_lossy();
+        problems.push(Problem {
+            message: format!("The MSU-1 pack {} is named differently from the ROM, so the emulator won't find it; rename the ROM to {}.{}",
+                pack.file_name().unwrap_or_default().to_string_lossy(),
+                pack.file_stem().unwrap_or_default().to_string_lossy(),
+                extension
+            ),
+            fatal: false,
+        });
+    }
+
+    let mut dirs = bios_dirs.to_vec();
+    if LaunchProfile::detect(&emulator.name) == LaunchProfile::RetroArch {
+        dirs.extend(retroarch_system_dirs(&emulator.executable()));
+    }
+    if !dirs.is_empty() {
+        for message in missing_bios(emulator, game, &dirs) {
+            problems.push(Problem { message, fatal: false });
+        }
+    }
+    problems
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Lists the BIOS files the game's system needs with this emulator that are in none of `dirs`.
+fn missing_bios(emulator: &Emulator, game: &Game, dirs: &[PathBuf]) -> Vec<String> {
+    let Some(system) = game.system() else { return Vec::new() };
+    let core = emulator.core_path.as_ref().map(|c| c.to_string_lossy().to_lowercase()).unwrap_or_default();
+    REQUIRED_BIOS
+        .iter()
+        .filter(|(id, _, cores)| *id == system.id && (cores.is_empty() || cores.iter().any(|c| core.contains(c))))
+        .filter(|(_, files, _)| !files.iter().any(|file| dirs.iter().any(|dir| dir.join(file).is_file())))
+        .map(|(_, files, _)| {
+            let wanted = if files.len() == 1 { files[0].to_string() } else { format!("one of {}", files.join(", ")) };
+            format!("{} needs the BIOS file {}, which is not in {}", system.name, wanted,
+                dirs.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", "))
+        })
+        .collect()
+}
+
+/// Directories RetroArch commonly loads BIOS files from.
+fn retroarch_system_dirs(retroarch: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    // Portable installs (and Windows) keep the system directory next to the executable.
+    if let Some(dir) = retroarch.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        dirs.push(dir.join("system"));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        dirs.push(home.join(".config/retroarch/system"));
+        dirs.push(home.join(".var/app/org.libretro.RetroArch/config/retroarch/system"));
+        dirs.push(home.join("Library/Application Support/RetroArch/system"));
+    }
+    dirs.into_iter().filter(|dir| dir.is_dir()).collect()
+}
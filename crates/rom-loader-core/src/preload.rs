@@ -0,0 +1,168 @@
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::cache::{self, Cache, PRELOAD_DIR_NAME, UNHEADERED_DIR_NAME, UNZIPPED_DIR_NAME};
+use crate::hashing;
+use crate::rom_format::COPIER_HEADER_SIZE;
+
+/// Files are copied in chunks of this size, updating the progress bar after each.
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Width of the progress bar in characters.
+const BAR_WIDTH: usize = 30;
+
+/// Copies a disc image into the cache directory so the emulator reads it from local storage.
+///
+/// A copy left by an earlier launch of the same image is reused if it is complete and newer
+/// than the image.
+/// Older copies are removed first so preloading doesn't fill the disk over time: all of them,
+/// or with a cache size limit the least recently used cache items.
+///
+/// # Returns
+/// The path of the local copy.
+pub fn preload(image: &Path, cache: &Cache) -> io::Result<PathBuf> {
+    let file_name = image.file_name().unwrap_or_default();
+    let target = cached_path(cache, PRELOAD_DIR_NAME, image, file_name);
+    let source = fs::metadata(image)?;
+    if let Ok(cached) = fs::metadata(&target) {
+        if cached.len() == source.len() && cached.modified()? >= source.modified()? {
+            println!("📦 Using the cached copy of {}.", file_name.to_string_lossy());
+            cache::touch(&target)?;
+            return Ok(target);
+        }
+    }
+
+    cache.make_room(PRELOAD_DIR_NAME, source.len())?;
+    fs::create_dir_all(target.parent().unwrap_or(cache.dir()))?;
+    let partial = target.with_extension("partial");
+    copy_with_progress(image, &partial, source.len()).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })?;
+    fs::rename(&partial, &target)?;
+    Ok(target)
+}
+
+/// Unpacks the ROM in a zip file into the cache directory, for emulators that can't open
+/// zipped ROMs themselves.
+///
+/// The ROM is the largest file in the archive. Like preloaded images, it is kept in a folder of
+/// its own for the archive, older ROMs are removed to make room, and a copy left by an earlier
+/// launch of the same zip is reused if it is newer than the zip.
+///
+/// # Returns
+/// The path of the unpacked ROM.
+pub fn unzip(archive_path: &Path, cache: &Cache) -> io::Result<PathBuf> {
+    let mut archive = zip::ZipArchive::new(File::open(archive_path)?).map_err(io::Error::other)?;
+    let mut largest: Option<(usize, u64)> = None;
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i).map_err(io::Error::other)?;
+        if entry.is_file() && largest.is_none_or(|(_, size)| entry.size() > size) {
+            largest = Some((i, entry.size()));
+        }
+    }
+    let Some((index, size)) = largest else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is empty", archive_path.display())));
+    };
+    let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+    // Only the file name, so entry paths can't point outside the cache.
+    let file_name = entry.enclosed_name()
+        .and_then(|name| name.file_name().map(|n| n.to_os_string()))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid file name in {}", archive_path.display())))?;
+
+    let target = cached_path(cache, UNZIPPED_DIR_NAME, archive_path, &file_name);
+    if let Ok(cached) = fs::metadata(&target) {
+        if cached.len() == size && cached.modified()? >= fs::metadata(archive_path)?.modified()? {
+            cache::touch(&target)?;
+            return Ok(target);
+        }
+    }
+    cache.make_room(UNZIPPED_DIR_NAME, size)?;
+    fs::create_dir_all(target.parent().unwrap_or(cache.dir()))?;
+    println!("📦 Unpacking {}...", file_name.to_string_lossy());
+    let partial = target.with_extension("partial");
+    io::copy(&mut entry, &mut File::create(&partial)?).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })?;
+    fs::rename(&partial, &target)?;
+    Ok(target)
+}
+
+/// Copies a SNES ROM without its copier header into the cache directory, for emulators that
+/// reject headered dumps; the ROM in the library is left as it is.
+///
+/// Like unpacked ROMs, each copy gets its own folder, older copies are removed to make room,
+/// and a copy left by an earlier launch is reused if it is newer than the ROM.
+///
+/// # Returns
+/// The path of the copy.
+pub fn without_copier_header(rom: &Path, cache: &Cache) -> io::Result<PathBuf> {
+    let target = cached_path(cache, UNHEADERED_DIR_NAME, rom, rom.file_name().unwrap_or_default());
+    let source = fs::metadata(rom)?;
+    let size = source.len().saturating_sub(COPIER_HEADER_SIZE);
+    if let Ok(cached) = fs::metadata(&target) {
+        if cached.len() == size && cached.modified()? >= source.modified()? {
+            cache::touch(&target)?;
+            return Ok(target);
+        }
+    }
+    let data = fs::read(rom)?;
+    cache.make_room(UNHEADERED_DIR_NAME, size)?;
+    fs::create_dir_all(target.parent().unwrap_or(cache.dir()))?;
+    let partial = target.with_extension("partial");
+    fs::write(&partial, &data[COPIER_HEADER_SIZE as usize..]).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })?;
+    fs::rename(&partial, &target)?;
+    Ok(target)
+}
+
+/// Returns where the cached copy of `source` named `file_name` goes in a cache subdirectory.
+///
+/// Each source gets a folder named after a hash of its full path, so files with the same
+/// name from different folders (`ps1/Disc 1.chd`, `saturn/Disc 1.chd`) don't share a copy.
+fn cached_path(cache: &Cache, subdir: &str, source: &Path, file_name: &OsStr) -> PathBuf {
+    let source = fs::canonicalize(source).or_else(|_| std::path::absolute(source)).unwrap_or_else(|_| source.to_path_buf());
+    let key = format!("{:016x}", hashing::fnv1a_64(source.to_string_lossy().as_bytes()));
+    cache.dir().join(subdir).join(key).join(file_name)
+}
+
+fn copy_with_progress(from: &Path, to: &Path, total: u64) -> io::Result<()> {
+    let mut reader = File::open(from)?;
+    let mut writer = File::create(to)?;
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut copied = 0u64;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..read])?;
+        copied += read as u64;
+        let fraction = if total == 0 { 1.0 } else { copied as f64 / total as f64 };
+        let filled = (fraction * BAR_WIDTH as f64) as usize;
+        print!(
+            "\r📥 [{}{}] {:>3}% {} / {}",
+            "#".repeat(filled.min(BAR_WIDTH)),
+            " ".repeat(BAR_WIDTH - filled.min(BAR_WIDTH)),
+            (fraction * 100.0) as u32,
+            format_size(copied),
+            format_size(total)
+        );
+        io::stdout().flush()?;
+    }
+    println!();
+    writer.sync_all()
+}
+
+/// Formats a byte count for people, e.g. "3.4 GB" or "700 MB".
+pub fn format_size(bytes: u64) -> String {
+    const MB: f64 = 1024.0 * 1024.0;
+    let mb = bytes as f64 / MB;
+    if mb >= 1024.0 {
+        format!("{:.1} GB", mb / 1024.0)
+    } else {
+        format!("{:.0} MB", mb)
+    }
+}
@@ -0,0 +1,94 @@
+use std::io;
+use std::process::Command;
+
+use crate::rom_launcher;
+
+/// Lowest and highest niceness: -20 is the highest priority, 19 the lowest.
+pub const NICE_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+/// Parses a CPU list such as "2,3" or "0-3,6" into CPU indices.
+pub fn parse_affinity(s: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parse = |n: &str| n.trim().parse::<usize>().map_err(|_| format!("Invalid CPU '{}' in '{}' (expected e.g. 0-3,6)", n.trim(), s));
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("Invalid CPU range '{}' (the first CPU comes after the last)", part));
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// Wraps an emulator command so it runs with the given niceness and on the given CPUs.
+///
+/// On Linux this uses `nice` and `taskset`; macOS only supports `nice`. A negative niceness
+/// usually needs root: `nice` then warns and starts the emulator at normal priority. On
+/// Windows the niceness is mapped to a priority class and both are applied through `start`.
+///
+/// # Returns
+/// The command unchanged if neither setting is given.
+pub fn wrap(mut command: Command, nice: Option<i32>, cpu_affinity: &[usize]) -> io::Result<Command> {
+    if let Some(nice) = nice.filter(|n| !NICE_RANGE.contains(n)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid nice value {} (expected -20 to 19)", nice),
+        ));
+    }
+    if nice.is_none() && cpu_affinity.is_empty() {
+        return Ok(command);
+    }
+    if cfg!(windows) {
+        return Ok(wrap_windows(command, nice, cpu_affinity));
+    }
+
+    if let Some(nice) = nice {
+        let mut wrapper = Command::new("nice");
+        wrapper.arg("-n").arg(nice.to_string());
+        command = rom_launcher::wrap_command(wrapper, command);
+        println!("  (Niceness: {})", nice);
+    }
+    if !cpu_affinity.is_empty() {
+        if cfg!(target_os = "linux") {
+            let cpus: Vec<String> = cpu_affinity.iter().map(|c| c.to_string()).collect();
+            let mut wrapper = Command::new("taskset");
+            wrapper.arg("-c").arg(cpus.join(","));
+            command = rom_launcher::wrap_command(wrapper, command);
+            println!("  (CPU affinity: {})", cpus.join(","));
+        } else {
+            eprintln!("⚠️ Warning: the 'cpu_affinity' setting is not supported on this platform and is ignored.");
+        }
+    }
+    Ok(command)
+}
+
+/// Runs the command through `cmd /C start`, which sets the priority class and affinity mask.
+fn wrap_windows(command: Command, nice: Option<i32>, cpu_affinity: &[usize]) -> Command {
+    let mut wrapper = Command::new("cmd");
+    // The empty title keeps `start` from taking a quoted program path as the window title.
+    wrapper.args(["/C", "start", "", "/B", "/WAIT"]);
+    if let Some(nice) = nice {
+        let class = match nice {
+            ..=-15 => "/HIGH",
+            -14..=-1 => "/ABOVENORMAL",
+            0 => "/NORMAL",
+            1..=9 => "/BELOWNORMAL",
+            _ => "/LOW",
+        };
+        wrapper.arg(class);
+        println!("  (Priority class: {})", &class[1..]);
+    }
+    let mask = cpu_affinity.iter().filter(|&&c| c < 64).fold(0u64, |mask, &c| mask | 1 << c);
+    if mask != 0 {
+        wrapper.arg("/AFFINITY").arg(format!("{:X}", mask));
+        println!("  (CPU affinity mask: {:X})", mask);
+    }
+    rom_launcher::wrap_command(wrapper, command)
+}
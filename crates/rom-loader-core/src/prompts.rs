@@ -0,0 +1,65 @@
+use std::io::{self, Write};
+use std::sync::OnceLock;
+
+/// How questions to the user are answered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Answers {
+    /// Ask on the terminal.
+    #[default]
+    Ask,
+    /// Answer "yes" to every confirmation (`--yes`).
+    Yes,
+    /// Never read stdin; take each question's default answer (`--no-input`).
+    Defaults,
+}
+
+static ANSWERS: OnceLock<Answers> = OnceLock::new();
+
+/// Sets how questions are answered for the rest of the run. Only the first call has an effect.
+pub fn set_answers(answers: Answers) {
+    let _ = ANSWERS.set(answers);
+}
+
+/// How questions are answered.
+pub fn answers() -> Answers {
+    ANSWERS.get().copied().unwrap_or_default()
+}
+
+/// Whether questions are asked on the terminal, rather than answered by `--yes` or `--no-input`.
+pub fn interactive() -> bool {
+    answers() == Answers::Ask
+}
+
+/// Returns the answer to a yes/no question that isn't asked because of `--yes` or
+/// `--no-input`, or `None` if the user should be asked.
+pub fn automatic_answer(default: bool) -> Option<bool> {
+    match answers() {
+        Answers::Ask => None,
+        Answers::Yes => Some(true),
+        Answers::Defaults => Some(default),
+    }
+}
+
+/// Asks a yes/no question on the terminal, e.g. `confirm("Stop them?", false)`.
+///
+/// Enter (or anything other than yes or no) takes the default. With `--yes` or `--no-input`
+/// the question and its automatic answer are printed instead, so logs show what was decided.
+///
+/// # Returns
+/// The answer, or `false` if stdin could not be read.
+pub fn confirm(question: &str, default: bool) -> bool {
+    if let Some(answer) = automatic_answer(default) {
+        println!("{} {}", question, if answer { "yes" } else { "no" });
+        return answer;
+    }
+    print!("{} {}: ", question, if default { "[Y/n]" } else { "[y/N]" });
+    let mut answer = String::new();
+    if io::stdout().flush().and_then(|_| io::stdin().read_line(&mut answer)).is_err() {
+        return false;
+    }
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    }
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::emulator_config::Emulator;
+
+/// Oddities of an emulator that launches work around, e.g.
+/// `{ "absolute_path": true, "args": ["-nogui"] }`.
+///
+/// Known emulators get theirs from [`BUILTIN_QUIRKS`]; an entry's `quirks` field replaces
+/// them, so `{}` turns them off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Quirks {
+    /// Pass the ROM as an absolute path, for emulators that resolve relative paths against
+    /// their own folder.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub absolute_path: bool,
+    /// Launch SNES ROMs that have a copier header from a copy without it in the cache, for
+    /// emulators that reject or misdetect headered dumps.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strip_copier_header: bool,
+    /// Arguments the emulator needs before the ROM, e.g. to run without its main window.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Shown before each launch, for problems rom-loader can't work around.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+/// A built-in quirk entry; `emulator` is matched like launch profiles, against the lowercase
+/// entry name or the executable's name.
+pub struct BuiltinQuirks {
+    pub emulator: &'static str,
+    pub absolute_path: bool,
+    pub strip_copier_header: bool,
+    pub args: &'static [&'static str],
+    pub warning: Option<&'static str>,
+}
+
+/// Quirks of emulators rom-loader knows about.
+pub const BUILTIN_QUIRKS: &[BuiltinQuirks] = &[
+    // mGBA looks relative ROM paths up from its own folder when started from a launcher.
+    BuiltinQuirks { emulator: "mgba", absolute_path: true, strip_copier_header: false, args: &[], warning: None },
+    // Snes9x refuses some headered dumps and misdetects the memory map of others.
+    BuiltinQuirks { emulator: "snes9x", absolute_path: false, strip_copier_header: true, args: &[], warning: None },
+    // Without -nogui DuckStation opens its game list next to the game and stays open after it.
+    BuiltinQuirks { emulator: "duckstation", absolute_path: false, strip_copier_header: false, args: &["-nogui"], warning: None },
+];
+
+impl BuiltinQuirks {
+    fn to_quirks(&self) -> Quirks {
+        Quirks {
+            absolute_path: self.absolute_path,
+            strip_copier_header: self.strip_copier_header,
+            args: self.args.iter().map(|arg| arg.to_string()).collect(),
+            warning: self.warning.map(str::to_string),
+        }
+    }
+}
+
+/// Returns the quirks of an emulator entry: its own `quirks` field, or the built-in ones for
+/// the emulator, or none.
+pub fn for_emulator(emulator: &Emulator) -> Quirks {
+    if let Some(quirks) = &emulator.quirks {
+        return quirks.clone();
+    }
+    let name = emulator.name.to_lowercase();
+    let executable = emulator.path.file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+    BUILTIN_QUIRKS
+        .iter()
+        .find(|known| name.contains(known.emulator) || executable.contains(known.emulator))
+        .map(BuiltinQuirks::to_quirks)
+        .unwrap_or_default()
+}
@@ -0,0 +1,456 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Size of the header copier devices put in front of some ROM dumps.
+pub const COPIER_HEADER_SIZE: u64 = 512;
+
+/// Extensions of SNES ROMs, headered or not.
+const SNES_EXTENSIONS: &[&str] = &["sfc", "smc", "snes"];
+
+/// Sizes of Atari 2600 cartridges (2K, 4K and the bank-switched 8K, 12K, 16K, 32K and 64K).
+const ATARI_2600_SIZES: &[u64] = &[2048, 4096, 8192, 12288, 16384, 32768, 65536];
+
+/// Emulators that only load big-endian (`.z64`) N64 ROMs, matched against the emulator's name
+/// and executable.
+const BIG_ENDIAN_ONLY_EMULATORS: &[&str] = &["cen64"];
+
+/// The byte order of an N64 ROM, recognizable from its first four bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum N64ByteOrder {
+    /// Big-endian, the cartridge's native order and what dats hash.
+    Z64,
+    /// Byte-swapped (16-bit words swapped), as Doctor V64 dumps are.
+    V64,
+    /// Little-endian (32-bit words reversed).
+    N64,
+}
+
+impl N64ByteOrder {
+    /// The file extension conventionally used for this byte order.
+    pub fn extension(self) -> &'static str {
+        match self {
+            N64ByteOrder::Z64 => "z64",
+            N64ByteOrder::V64 => "v64",
+            N64ByteOrder::N64 => "n64",
+        }
+    }
+
+    fn from_magic(magic: [u8; 4]) -> Option<Self> {
+        match magic {
+            [0x80, 0x37, 0x12, 0x40] => Some(N64ByteOrder::Z64),
+            [0x37, 0x80, 0x40, 0x12] => Some(N64ByteOrder::V64),
+            [0x40, 0x12, 0x37, 0x80] => Some(N64ByteOrder::N64),
+            _ => None,
+        }
+    }
+
+    /// Converts between this order and big-endian; every conversion is its own inverse.
+    fn swap(self, data: &mut [u8]) {
+        match self {
+            N64ByteOrder::Z64 => {}
+            N64ByteOrder::V64 => data.chunks_exact_mut(2).for_each(|word| word.swap(0, 1)),
+            N64ByteOrder::N64 => data.chunks_exact_mut(4).for_each(|word| word.reverse()),
+        }
+    }
+}
+
+/// What a `.bin` file holds: disc images, Genesis and Atari 2600 cartridges and BIOS dumps all
+/// use the extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinContents {
+    /// A track of a disc image that a .cue sheet next to it refers to.
+    DiscTrack { system_id: &'static str },
+    /// A cartridge ROM.
+    Cartridge { system_id: &'static str },
+    /// A BIOS or firmware dump, which is not a game.
+    Bios,
+}
+
+impl BinContents {
+    /// The system the file belongs to, `None` for a BIOS.
+    pub fn system_id(self) -> Option<&'static str> {
+        match self {
+            BinContents::DiscTrack { system_id } | BinContents::Cartridge { system_id } => Some(system_id),
+            BinContents::Bios => None,
+        }
+    }
+}
+
+/// What a ROM's internal header says about the game.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RomHeader {
+    /// Internal title, if the format has one (NES ROMs don't).
+    pub title: Option<String>,
+    /// Region or video standard, e.g. "Japan", "USA" or "PAL".
+    pub region: Option<String>,
+    /// Product code, e.g. "AGBE" for GBA games.
+    pub code: Option<String>,
+}
+
+impl RomHeader {
+    /// Returns the title and region for display, e.g. "POKEMON RED (International)".
+    pub fn label(&self) -> Option<String> {
+        match (&self.title, &self.region) {
+            (Some(title), Some(region)) => Some(format!("{} ({})", title, region)),
+            (Some(title), None) => Some(title.clone()),
+            (None, Some(region)) => Some(format!("({})", region)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Reads the internal header of a Game Boy (Color), Game Boy Advance, NES or SNES ROM.
+///
+/// # Returns
+/// `None` for other formats and for files whose header doesn't check out.
+pub fn read_header(path: &Path) -> io::Result<Option<RomHeader>> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let mut file = File::open(path)?;
+    match extension.as_str() {
+        "gb" | "gbc" => game_boy_header(&mut file),
+        "gba" => gba_header(&mut file),
+        "nes" => nes_header(&mut file),
+        extension if SNES_EXTENSIONS.contains(&extension) => {
+            let size = file.metadata()?.len();
+            snes_header(&mut file, size)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Works out what a `.bin` file is.
+///
+/// A file a .cue sheet refers to is a disc track: Sega CD if the disc's first track starts
+/// with the Sega CD system area, PlayStation otherwise. Other files are told apart by their
+/// contents: a "SEGA" console name at 0x100 makes a Genesis ROM, a PlayStation BIOS or a file
+/// named like a BIOS (e.g. `bios_MD.bin`, `scph1001.bin`) is a BIOS, and a file of an Atari
+/// 2600 cartridge size is an Atari 2600 ROM. Anything else is taken for a Genesis ROM, the
+/// most common kind of .bin cartridge.
+pub fn classify_bin(path: &Path) -> io::Result<BinContents> {
+    if let Some(first_track) = cue_first_track(path)? {
+        let mut system_area = [0u8; 0x20];
+        let segacd = File::open(&first_track)
+            .and_then(|mut file| read_at(&mut file, 0, &mut system_area))
+            .unwrap_or(false)
+            // 2048-byte sectors start with the system area; raw 2352-byte sectors have a 16-byte sync and header first.
+            && (system_area.starts_with(b"SEGADISCSYSTEM") || system_area[0x10..].starts_with(b"SEGADISCSYSTEM"));
+        return Ok(BinContents::DiscTrack { system_id: if segacd { "segacd" } else { "psx" } });
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+    if stem.contains("bios") || stem.starts_with("scph") {
+        return Ok(BinContents::Bios);
+    }
+    let mut file = File::open(path)?;
+    let mut header = [0u8; 0x30];
+    if read_at(&mut file, 0x100, &mut header)? {
+        if header[..0x10].windows(4).any(|w| w == b"SEGA") {
+            return Ok(BinContents::Cartridge { system_id: "genesis" });
+        }
+        // PlayStation BIOS images carry the copyright string near the start.
+        if header.windows(4).any(|w| w == b"Sony") {
+            return Ok(BinContents::Bios);
+        }
+    }
+    if ATARI_2600_SIZES.contains(&file.metadata()?.len()) {
+        return Ok(BinContents::Cartridge { system_id: "a2600" });
+    }
+    Ok(BinContents::Cartridge { system_id: "genesis" })
+}
+
+/// Finds a .cue sheet next to a file that refers to it, and returns the first track of that
+/// sheet (which may be the file itself).
+fn cue_first_track(path: &Path) -> io::Result<Option<PathBuf>> {
+    let Some(dir) = path.parent() else { return Ok(None) };
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    for entry in fs::read_dir(if dir.as_os_str().is_empty() { Path::new(".") } else { dir })? {
+        let cue = entry?.path();
+        if !cue.extension().is_some_and(|e| e.eq_ignore_ascii_case("cue")) {
+            continue;
+        }
+        // Cue sheets are a few lines; anything big is not one.
+        if fs::metadata(&cue).map_or(true, |m| m.len() > 64 * 1024) {
+            continue;
+        }
+        let Ok(sheet) = fs::read_to_string(&cue) else { continue };
+        // FILE "Game (Track 1).bin" BINARY
+        let tracks: Vec<&str> = sheet
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("FILE "))
+            .map(|rest| match rest.strip_prefix('"') {
+                Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+                None => rest.split_whitespace().next().unwrap_or_default(),
+            })
+            .collect();
+        if tracks.iter().any(|track| track.to_lowercase() == file_name) {
+            return Ok(tracks.first().map(|track| dir.join(track)));
+        }
+    }
+    Ok(None)
+}
+
+/// Whether a file name says little about the game, e.g. "rom1.gba" or "game.sfc", so the
+/// internal title is worth showing.
+pub fn is_uninformative_name(path: &Path) -> bool {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+    let letters: String = stem.chars().filter(|c| c.is_alphabetic()).collect();
+    letters.len() < 3 || ["rom", "game", "untitled", "new", "file", "image", "dump", "copy", "unknown"].contains(&letters.as_str())
+}
+
+fn read_at(file: &mut File, offset: u64, buffer: &mut [u8]) -> io::Result<bool> {
+    file.seek(SeekFrom::Start(offset))?;
+    match file.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Turns a fixed-size, zero- or space-padded header field into text.
+fn header_text(bytes: &[u8]) -> Option<String> {
+    let text: String = bytes
+        .iter()
+        .take_while(|&&b| b != 0)
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { ' ' })
+        .collect();
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn game_boy_header(file: &mut File) -> io::Result<Option<RomHeader>> {
+    // 0x104: the start of the Nintendo logo every cartridge carries.
+    let mut header = [0u8; 0x50];
+    if !read_at(file, 0x100, &mut header)? || header[4..8] != [0xCE, 0xED, 0x66, 0x66] {
+        return Ok(None);
+    }
+    // Color games use the last bytes of the 16-byte title for a manufacturer code and flag.
+    let cgb = header[0x43] & 0x80 != 0;
+    let title = header_text(&header[0x34..if cgb { 0x43 } else { 0x44 }]);
+    let region = match header[0x4A] {
+        0 => Some("Japan".to_string()),
+        1 => Some("International".to_string()),
+        _ => None,
+    };
+    Ok(Some(RomHeader { title, region, code: None }))
+}
+
+fn gba_header(file: &mut File) -> io::Result<Option<RomHeader>> {
+    let mut header = [0u8; 0xC0];
+    // 0xB2 always holds 0x96.
+    if !read_at(file, 0, &mut header)? || header[0xB2] != 0x96 {
+        return Ok(None);
+    }
+    let code = header_text(&header[0xAC..0xB0]);
+    let region = code.as_deref().and_then(|code| code.chars().nth(3)).and_then(|c| {
+        Some(match c {
+            'J' => "Japan",
+            'E' => "USA",
+            'P' => "Europe",
+            'D' => "Germany",
+            'F' => "France",
+            'S' => "Spain",
+            'I' => "Italy",
+            'K' => "Korea",
+            'C' => "China",
+            _ => return None,
+        })
+    });
+    Ok(Some(RomHeader { title: header_text(&header[0xA0..0xAC]), region: region.map(str::to_string), code }))
+}
+
+fn nes_header(file: &mut File) -> io::Result<Option<RomHeader>> {
+    let mut header = [0u8; 16];
+    if !read_at(file, 0, &mut header)? || &header[..4] != b"NES\x1A" {
+        return Ok(None);
+    }
+    // iNES has no title; NES 2.0 (flags 7 bits 2-3 = 2) has a detailed timing byte.
+    let region = if header[7] & 0x0C == 0x08 {
+        ["NTSC", "PAL", "NTSC/PAL", "Dendy"][(header[12] & 0x03) as usize]
+    } else if header[9] & 0x01 != 0 {
+        "PAL"
+    } else {
+        "NTSC"
+    };
+    Ok(Some(RomHeader { title: None, region: Some(region.to_string()), code: None }))
+}
+
+fn snes_header(file: &mut File, size: u64) -> io::Result<Option<RomHeader>> {
+    let copier = if size % 1024 == COPIER_HEADER_SIZE { COPIER_HEADER_SIZE } else { 0 };
+    let Some(header) = find_snes_header(file, copier)? else { return Ok(None) };
+    let region = match header[0x19] {
+        0x00 => "Japan",
+        0x01 => "USA",
+        0x02 => "Europe",
+        0x03 => "Sweden",
+        0x04 => "Finland",
+        0x05 => "Denmark",
+        0x06 => "France",
+        0x07 => "Netherlands",
+        0x08 => "Spain",
+        0x09 => "Germany",
+        0x0A => "Italy",
+        0x0B => "China",
+        0x0D => "Korea",
+        0x0F => "Canada",
+        0x10 => "Brazil",
+        0x11 => "Australia",
+        _ => "Unknown",
+    };
+    Ok(Some(RomHeader {
+        title: header_text(&header[..0x15]),
+        region: Some(region.to_string()),
+        code: None,
+    }))
+}
+
+/// Returns the 32-byte SNES internal header of a ROM whose image starts at `start`.
+///
+/// The header sits at the end of the first LoROM bank or HiROM bank; the one whose checksum
+/// and checksum complement add up is the real one.
+fn find_snes_header(file: &mut File, start: u64) -> io::Result<Option<[u8; 0x20]>> {
+    for offset in [0x7FC0, 0xFFC0, 0x40FFC0] {
+        let mut header = [0u8; 0x20];
+        if !read_at(file, start + offset, &mut header)? {
+            continue;
+        }
+        let complement = u16::from_le_bytes([header[0x1C], header[0x1D]]);
+        let checksum = u16::from_le_bytes([header[0x1E], header[0x1F]]);
+        if complement ^ checksum == 0xFFFF {
+            return Ok(Some(header));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the enhancement chip a SNES ROM's header asks for, e.g. "SuperFX" or "SA-1".
+///
+/// Enhanced hacks of games that originally had no chip (SA-1 speed-ups, SuperFX overclocks)
+/// need an emulator or core that emulates it.
+///
+/// # Returns
+/// `None` for ROMs without a chip (or only the DSP most emulators handle) and for files
+/// without a valid SNES header.
+pub fn snes_coprocessor(path: &Path) -> io::Result<Option<&'static str>> {
+    let mut file = File::open(path)?;
+    let copier = if file.metadata()?.len() % 1024 == COPIER_HEADER_SIZE { COPIER_HEADER_SIZE } else { 0 };
+    let Some(header) = find_snes_header(&mut file, copier)? else { return Ok(None) };
+    // The ROM type byte: the low nibble says whether there is a chip, the high one which.
+    let rom_type = header[0x16];
+    if rom_type & 0x0F < 3 {
+        return Ok(None);
+    }
+    Ok(match rom_type >> 4 {
+        0x1 => Some("SuperFX"),
+        0x2 => Some("OBC1"),
+        0x3 => Some("SA-1"),
+        0x4 => Some("S-DD1"),
+        0x5 => Some("S-RTC"),
+        0xF => Some("SPC7110/Cx4/ST010"),
+        _ => None,
+    })
+}
+
+/// Whether a SNES ROM starts with a 512-byte copier header (SMC, SWC, FIG, ...).
+///
+/// Such dumps don't match dat hashes and confuse some emulators. A file counts as headered
+/// when its size is 512 bytes over a whole number of kilobytes and a valid internal header
+/// (checksum and complement adding up) sits 512 bytes further in than usual.
+pub fn has_copier_header(path: &Path) -> io::Result<bool> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !SNES_EXTENSIONS.contains(&extension.as_str()) {
+        return Ok(false);
+    }
+    let mut file = File::open(path)?;
+    if file.metadata()?.len() % 1024 != COPIER_HEADER_SIZE {
+        return Ok(false);
+    }
+    Ok(find_snes_header(&mut file, COPIER_HEADER_SIZE)?.is_some())
+}
+
+/// Removes the copier header of a SNES ROM, keeping the original in `backup_dir`.
+///
+/// The stripped image is written next to the ROM and renamed over it, so an interrupted
+/// strip never leaves a truncated file behind.
+///
+/// # Returns
+/// Where the original was backed up.
+pub fn strip_copier_header(path: &Path, backup_dir: &Path) -> io::Result<PathBuf> {
+    let data = fs::read(path)?;
+    if data.len() as u64 % 1024 != COPIER_HEADER_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} has no copier header", path.display()),
+        ));
+    }
+    fs::create_dir_all(backup_dir)?;
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let mut backup = backup_dir.join(&file_name);
+    let mut n = 1;
+    while backup.exists() {
+        backup = backup_dir.join(format!("{}.{}", file_name, n));
+        n += 1;
+    }
+    fs::copy(path, &backup)?;
+
+    let staging = path.with_file_name(format!(".{}.stripping", file_name));
+    fs::write(&staging, &data[COPIER_HEADER_SIZE as usize..])?;
+    fs::rename(&staging, path).inspect_err(|_| {
+        let _ = fs::remove_file(&staging);
+    })?;
+    Ok(backup)
+}
+
+/// Detects the byte order of an N64 ROM from its header.
+///
+/// # Returns
+/// `None` if the file doesn't start like an N64 ROM in any byte order.
+pub fn n64_byte_order(path: &Path) -> io::Result<Option<N64ByteOrder>> {
+    let mut magic = [0u8; 4];
+    if !read_at(&mut File::open(path)?, 0, &mut magic)? {
+        return Ok(None);
+    }
+    Ok(N64ByteOrder::from_magic(magic))
+}
+
+/// Rewrites an N64 ROM in another byte order and gives it the matching extension
+/// (e.g. `Game.v64` becomes `Game.z64`).
+///
+/// # Returns
+/// The path of the converted ROM.
+pub fn convert_n64(path: &Path, to: N64ByteOrder) -> io::Result<PathBuf> {
+    let mut data = fs::read(path)?;
+    let from = data.get(..4)
+        .and_then(|magic| N64ByteOrder::from_magic([magic[0], magic[1], magic[2], magic[3]]))
+        .ok_or_else(|| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not an N64 ROM", path.display()),
+        ))?;
+    let target = path.with_extension(to.extension());
+    if target != path && target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    from.swap(&mut data);
+    to.swap(&mut data);
+
+    let file_name = target.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let staging = target.with_file_name(format!(".{}.converting", file_name));
+    fs::write(&staging, &data)?;
+    fs::rename(&staging, &target).inspect_err(|_| {
+        let _ = fs::remove_file(&staging);
+    })?;
+    if target != path {
+        fs::remove_file(path)?;
+    }
+    Ok(target)
+}
+
+/// Whether an emulator is known to load only big-endian (`.z64`) N64 ROMs.
+pub fn requires_big_endian_n64(emulator_name: &str, emulator_path: &Path) -> bool {
+    let executable = emulator_path.file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+    let name = emulator_name.to_lowercase();
+    BIG_ENDIAN_ONLY_EMULATORS.iter().any(|known| name.contains(known) || executable == *known)
+}
@@ -0,0 +1,832 @@
+use std::fs;
+use std::net::UdpSocket;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::io::{self, BufRead, BufReader, Read};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::events::{self, Event};
+use crate::interrupt;
+use crate::priority;
+use crate::sandbox::Sandbox;
+use crate::sessions;
+
+/// Per-game DOSBox configuration file that marks a directory as a DOS game.
+const DOSBOX_CONF_MARKER: &str = "dosbox.conf";
+/// Extension of the ScummVM game id files.
+const SCUMMVM_ID_EXTENSION: &str = "scummvm";
+
+/// Executable extensions considered when guessing a DOS game's start program.
+const DOS_EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "com", "bat"];
+/// File stems that are almost never the program that starts the game.
+const DOS_NON_GAME_STEMS: &[&str] = &["setup", "install", "config", "setsound", "uninstal", "dos4gw"];
+
+/// Prefix of the emulator output lines streamed in verbose mode.
+const OUTPUT_PREFIX: &str = "[emu]";
+
+/// How long before the session time limit the players are warned.
+const TIME_LIMIT_WARNING: Duration = Duration::from_secs(60);
+/// How long an emulator asked to quit at the time limit gets before it is killed.
+const QUIT_GRACE: Duration = Duration::from_secs(5);
+/// How often an emulator under a time limit is polled for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// Port RetroArch listens on for network commands.
+const RETROARCH_COMMAND_PORT: u16 = 55355;
+
+static VERBOSE: OnceLock<bool> = OnceLock::new();
+
+/// Turns verbose mode on or off for the rest of the run: the emulator's output is streamed line
+/// by line with an `[emu]` prefix. Only the first call has an effect.
+pub fn set_verbose(verbose: bool) {
+    let _ = VERBOSE.set(verbose);
+}
+
+fn verbose() -> bool {
+    VERBOSE.get().copied().unwrap_or(false)
+}
+
+static TIME_LIMIT: OnceLock<Option<Duration>> = OnceLock::new();
+
+/// Sets how long a game may be played before the emulator is closed, for kiosks and events
+/// where players take turns; `None` for no limit. Only the first call has an effect.
+pub fn set_time_limit(limit: Option<Duration>) {
+    let _ = TIME_LIMIT.set(limit);
+}
+
+fn time_limit() -> Option<Duration> {
+    TIME_LIMIT.get().copied().flatten()
+}
+
+/// Window and monitor settings for a launch; unset fields keep the emulator's own settings.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DisplaySettings {
+    pub fullscreen: Option<bool>,
+    /// Zero-based index of the monitor to show the game on.
+    pub monitor: Option<u32>,
+    /// Fullscreen resolution as (width, height).
+    pub resolution: Option<(u32, u32)>,
+}
+
+impl DisplaySettings {
+    /// Whether no display setting is set.
+    pub fn is_empty(&self) -> bool {
+        *self == DisplaySettings::default()
+    }
+}
+
+/// Extra per-launch settings layered on top of an emulator's own arguments.
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    /// RetroArch config files passed with `--appendconfig` (shader presets, overlays/bezels, ...).
+    pub append_configs: Vec<PathBuf>,
+    /// Fullscreen, monitor and resolution settings, translated to the emulator's own flags.
+    pub display: DisplaySettings,
+    /// Fullscreen flag found by `doctor`, used for emulators without a launch profile.
+    pub fullscreen_flag: Option<String>,
+    /// Audio output device (sink) for emulators that take it as a setting rather than from the environment.
+    pub audio_device: Option<String>,
+    /// Extra environment variables for the emulator (GPU selection, DXVK settings, ...).
+    pub env: Vec<(String, String)>,
+    /// Command the emulator is started through, e.g. `["prime-run"]` or `["gamemoderun"]`.
+    pub wrapper: Vec<String>,
+    /// Sandbox to run the emulator in, if any.
+    pub sandbox: Option<Sandbox>,
+    /// Directories that stay read-only inside the sandbox.
+    pub read_only_dirs: Vec<PathBuf>,
+    /// Niceness of the emulator process, -20 (highest priority) to 19 (lowest).
+    pub nice: Option<i32>,
+    /// CPUs the emulator is pinned to; empty to run on any.
+    pub cpu_affinity: Vec<usize>,
+    /// Whether cheats were deployed for this launch and should be switched on.
+    pub cheats: bool,
+    /// Save state slot to resume from.
+    pub load_state: Option<u32>,
+    /// File to record the session to.
+    pub record: Option<PathBuf>,
+    /// Arguments appended to the emulator's command line as-is, for one-off experiments.
+    pub extra_args: Vec<String>,
+    /// Arguments put first on the command line, which the emulator's quirks call for.
+    pub quirk_args: Vec<String>,
+    /// Pass the ROM as an absolute path, for emulators that don't resolve relative ones.
+    pub absolute_rom_path: bool,
+    /// Session file the running emulator is recorded in, so one left behind by a crashed
+    /// rom-loader can be found on the next start.
+    pub session_file: Option<PathBuf>,
+}
+
+/// The argument conventions used to launch a particular family of emulators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LaunchProfile {
+    Mame,
+    RetroArch,
+    DosBox,
+    ScummVm,
+    Rpcs3,
+    Cemu,
+    Generic,
+}
+
+impl LaunchProfile {
+    /// Picks the profile matching an emulator's configured name.
+    ///
+    /// RetroArch is checked before DOSBox/ScummVM so that entries such as
+    /// "RetroArch (DOSBox Pure)" keep using the core-based command line.
+    pub fn detect(emulator_name: &str) -> Self {
+        let name = emulator_name.to_lowercase();
+        if name.contains("mame") {
+            LaunchProfile::Mame
+        } else if name.contains("retroarch") {
+            LaunchProfile::RetroArch
+        } else if name.contains("dosbox") {
+            LaunchProfile::DosBox
+        } else if name.contains("scummvm") {
+            LaunchProfile::ScummVm
+        } else if name.contains("rpcs3") {
+            LaunchProfile::Rpcs3
+        } else if name.contains("cemu") {
+            LaunchProfile::Cemu
+        } else {
+            LaunchProfile::Generic
+        }
+    }
+}
+
+/// Launches an emulator with a specified ROM file.
+///
+/// This function attempts to execute the emulator program, passing the ROM path as an argument.
+/// Special handling is included for MAME, RetroArch, DOSBox, ScummVM, RPCS3 and Cemu, which typically require specific arguments.
+///
+/// # Arguments
+/// * `emulator_path` - The path to the emulator executable.
+/// * `rom_path` - The path to the ROM file to be launched.
+/// * `emulator_name` - The name of the emulator, used to identify MAME or RetroArch.
+/// * `core_path` - An optional path to the RetroArch core, if applicable.
+/// * `system_name` - An optional MAME system short name (e.g., "genesis", "nes") for console ROMs.
+/// * `options` - Extra per-launch settings such as RetroArch append configs.
+///
+/// The emulator writes to the terminal directly; in verbose mode its output is instead streamed
+/// as it comes, each line prefixed with `[emu]`, so its errors are told apart from rom-loader's.
+///
+/// # Returns
+/// A `Result` containing the emulator's `ExitStatus` once it exits, or an `io::Error` if the
+/// command fails to execute. A non-zero exit is reported through the status, not as an error.
+pub fn launch_rom(
+    emulator_path: &Path,
+    rom_path: &Path,
+    emulator_name: &str,
+    core_path: Option<&PathBuf>,
+    system_name: Option<&String>,
+    options: &LaunchOptions,
+) -> io::Result<ExitStatus> {
+    let mut command = build_command(emulator_path, rom_path, emulator_name, core_path, system_name, options)?;
+    let verbose = verbose();
+    if verbose {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+
+    let mut child = command.spawn()?; // `spawn` starts the process and returns immediately.
+    let pid = child.id();
+    let started = Instant::now();
+    events::emit(Event::LaunchStarted { rom: rom_path, emulator: emulator_name, pid });
+    let streams = [
+        child.stdout.take().map(|out| stream_output(out, false)),
+        child.stderr.take().map(|err| stream_output(err, true)),
+    ];
+    // A forced quit (a second Ctrl-C) kills the emulator instead of leaving it running.
+    interrupt::set_child(pid);
+    if let Some(file) = &options.session_file {
+        let program = Path::new(command.get_program());
+        if let Err(e) = sessions::track(file, pid, &[program, emulator_path], emulator_name, rom_path) {
+            eprintln!("⚠️ Could not record the emulator session: {}", e);
+        }
+    }
+    let status = match time_limit() {
+        Some(limit) => wait_with_time_limit(&mut child, limit, LaunchProfile::detect(emulator_name) == LaunchProfile::RetroArch),
+        None => child.wait(), // `wait` blocks until the process finishes.
+    };
+    interrupt::set_child(0);
+    if let Some(file) = &options.session_file {
+        let _ = sessions::untrack(file, pid);
+    }
+    // The rest of the output, after the emulator exited, is printed before the exit status.
+    for stream in streams.into_iter().flatten() {
+        let _ = stream.join();
+    }
+    let status = status?;
+    events::emit(Event::EmulatorExited {
+        rom: rom_path,
+        emulator: emulator_name,
+        code: status.code(),
+        success: status.success(),
+        seconds: started.elapsed().as_secs(),
+    });
+
+    if !status.success() {
+        eprintln!("Emulator process exited with non-zero status: {:?}", status);
+    }
+
+    Ok(status)
+}
+
+/// Waits for the emulator like [`Child::wait`], but closes it once the session has lasted
+/// `limit`, warning the players a minute before.
+///
+/// RetroArch shows the warning on screen and is asked to quit through its network commands;
+/// other emulators are asked to quit like a window being closed. Either is killed if it is
+/// still running a few seconds later.
+fn wait_with_time_limit(child: &mut Child, limit: Duration, retroarch: bool) -> io::Result<ExitStatus> {
+    let start = Instant::now();
+    let warn_at = limit.saturating_sub(TIME_LIMIT_WARNING.min(limit / 2));
+    let mut warned = false;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        let elapsed = start.elapsed();
+        if elapsed >= limit {
+            break;
+        }
+        if !warned && elapsed >= warn_at {
+            warned = true;
+            let left = (limit - elapsed).as_secs_f64().round() as u64;
+            let message = if left >= 60 {
+                format!("{} min left in this session", left / 60)
+            } else {
+                format!("{} s left in this session", left)
+            };
+            println!("⏳ {}.", message);
+            if retroarch {
+                send_retroarch_command(&format!("SHOW_MSG {}", message));
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    println!("⌛ Time is up, closing the emulator.");
+    if retroarch {
+        send_retroarch_command("QUIT");
+    } else {
+        interrupt::terminate(child.id());
+    }
+    let deadline = Instant::now() + QUIT_GRACE;
+    while Instant::now() < deadline {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    let _ = child.kill();
+    child.wait()
+}
+
+/// Sends a network command to a RetroArch running on this computer, which listens for them
+/// when `network_cmd_enable` is set. Best effort: nothing tells whether it arrived.
+fn send_retroarch_command(command: &str) {
+    if let Ok(socket) = UdpSocket::bind(("127.0.0.1", 0)) {
+        let _ = socket.send_to(command.as_bytes(), ("127.0.0.1", RETROARCH_COMMAND_PORT));
+    }
+}
+
+/// Prints an emulator output stream line by line as it arrives, prefixed with `[emu]`, on a
+/// thread of its own; lines aren't collected, so long sessions don't fill memory.
+fn stream_output(stream: impl Read + Send + 'static, to_stderr: bool) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        // Byte lines, as emulators don't always print valid UTF-8.
+        while reader.read_until(b'\n', &mut line).is_ok_and(|read| read > 0) {
+            let text = String::from_utf8_lossy(&line);
+            let text = text.trim_end_matches(['\r', '\n']);
+            if to_stderr {
+                eprintln!("{} {}", OUTPUT_PREFIX, text);
+            } else {
+                println!("{} {}", OUTPUT_PREFIX, text);
+            }
+            line.clear();
+        }
+    })
+}
+
+/// Builds the emulator command line for a ROM without starting it.
+///
+/// Takes the same arguments as [`launch_rom`]; callers that need to control the child
+/// process themselves (e.g. test launches with a timeout) spawn the returned command.
+pub fn build_command(
+    emulator_path: &Path,
+    rom_path: &Path,
+    emulator_name: &str,
+    core_path: Option<&PathBuf>,
+    system_name: Option<&String>,
+    options: &LaunchOptions,
+) -> io::Result<Command> {
+    if !emulator_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Emulator executable not found: {}", emulator_path.display()),
+        ));
+    }
+
+    if !emulator_path.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Emulator path is not an executable file: {}", emulator_path.display()),
+            ));
+    }
+
+    let mut command = Command::new(emulator_path);
+    if !options.quirk_args.is_empty() {
+        command.args(&options.quirk_args);
+        println!("  (Arguments for the emulator's quirks: {})", options.quirk_args.join(" "));
+    }
+    let profile = LaunchProfile::detect(emulator_name);
+    let absolute_rom_path;
+    let rom_path = if options.absolute_rom_path {
+        absolute_rom_path = std::path::absolute(rom_path)?;
+        absolute_rom_path.as_path()
+    } else {
+        rom_path
+    };
+
+    let mut append_configs = options.append_configs.clone();
+    let display_config = if options.display.is_empty() {
+        None
+    } else {
+        apply_display_settings(&mut command, profile, emulator_name, &options.display, options.fullscreen_flag.as_deref())?
+    };
+    if options.load_state.is_some() || options.record.is_some() {
+        apply_state_and_recording(&mut command, profile, emulator_name, options);
+    }
+    if profile == LaunchProfile::RetroArch {
+        append_configs.extend(display_config.clone());
+        if let Some(device) = &options.audio_device {
+            let settings = format!("audio_device = \"{}\"\n", device);
+            append_configs.push(write_temp_config("rom_loader_audio.cfg", &settings)?);
+        }
+        if options.cheats {
+            // RetroArch loads the game's cheat file by itself, but only applies it with this set.
+            append_configs.push(write_temp_config("rom_loader_cheats.cfg", "apply_cheats_after_load = \"true\"\n")?);
+        }
+        if time_limit().is_some() {
+            // For the time limit's on-screen warning and quitting cleanly when it runs out.
+            append_configs.push(write_temp_config("rom_loader_network.cfg", "network_cmd_enable = \"true\"\n")?);
+        }
+    }
+
+    if !append_configs.is_empty() {
+        if profile == LaunchProfile::RetroArch {
+            // RetroArch accepts several append configs separated by '|'; later ones win.
+            let joined = append_configs
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join("|");
+            command.arg("--appendconfig").arg(&joined);
+            println!("  (RetroArch append config: \"{}\")", joined);
+        } else {
+            eprintln!("⚠️ Warning: append configs are only supported for RetroArch; ignoring them for {}.", emulator_name);
+        }
+    }
+
+    match profile {
+        LaunchProfile::Mame => {
+            if let Some(sys_name) = system_name {
+                // MAME for consoles: <mame_exe> <system_name> -cart <full_rom_path>
+                // MAME expects the ROM path for -cart, not just the file stem.
+                command.arg(sys_name).arg("-cart").arg(rom_path);
+                println!("  (MAME Console Command: {} {} -cart \"{}\")",
+                         emulator_path.display(),
+                         sys_name,
+                         rom_path.display()
+                );
+            } else {
+                // MAME for arcade: <mame_exe> -rompath <rom_dir> <rom_short_name>
+                if let Some(parent_dir) = rom_path.parent() {
+                    command.arg("-rompath").arg(parent_dir);
+                } else {
+                    eprintln!("⚠️ Warning: Could not determine ROM parent directory for MAME arcade. Launch might fail.");
+                }
+
+                if let Some(rom_file_name) = rom_path.file_stem().and_then(|s| s.to_str()) {
+                    command.arg(rom_file_name);
+                    println!("  (MAME Arcade Command: {} -rompath \"{}\" \"{}\")",
+                             emulator_path.display(),
+                             rom_path.parent().unwrap_or_else(|| Path::new("")).display(),
+                             rom_file_name
+                    );
+                } else {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Could not determine ROM file stem for MAME arcade: {}", rom_path.display()),
+                    ));
+                }
+            }
+        }
+        LaunchProfile::RetroArch => {
+            // RetroArch often needs a core specified with -L
+            if let Some(core) = core_path {
+                if !core.exists() || !core.is_file() {
+                    eprintln!("❌ RetroArch core not found or not a file: {}. Launch might fail.", core.display());
+                }
+                command.arg("-L").arg(core); // Specify the core
+                command.arg(rom_path);       // Then the ROM path
+                println!("  (RetroArch Command: {} -L \"{}\" \"{}\")",
+                         emulator_path.display(),
+                         core.display(),
+                         rom_path.display()
+                );
+            } else {
+                // Fallback for RetroArch if no core path is provided in config
+                command.arg(rom_path);
+                println!("  (RetroArch Command (no core specified): {} \"{}\")",
+                         emulator_path.display(),
+                         rom_path.display()
+                );
+                eprintln!("⚠️ Warning: RetroArch may require a core path (-L argument). Please add 'core_path' to your emulators.json entry for RetroArch.");
+            }
+        }
+        LaunchProfile::DosBox => {
+            // DOSBox: mount the game directory as C: and run it through a generated conf.
+            let game_dir = if rom_path.is_dir() { rom_path } else { rom_path.parent().unwrap_or(rom_path) };
+            let conf_path = write_dosbox_conf(game_dir)?;
+            command.arg("-conf").arg(&conf_path);
+            println!("  (DOSBox Command: {} -conf \"{}\")", emulator_path.display(), conf_path.display());
+            // Loaded after the game's conf so the display preset wins over its [sdl] settings.
+            if let Some(display_conf) = &display_config {
+                command.arg("-conf").arg(display_conf);
+            }
+        }
+        LaunchProfile::ScummVm => {
+            // ScummVM: point at the game data and pass the game id when we can determine it.
+            let game_dir = if rom_path.is_dir() { rom_path } else { rom_path.parent().unwrap_or(rom_path) };
+            command.arg(format!("--path={}", game_dir.display()));
+            match detect_scummvm_game_id(emulator_path, rom_path, game_dir) {
+                Some(game_id) => {
+                    command.arg(&game_id);
+                    println!("  (ScummVM Command: {} --path=\"{}\" {})",
+                             emulator_path.display(),
+                             game_dir.display(),
+                             game_id
+                    );
+                }
+                None => {
+                    command.arg("--auto-detect");
+                    println!("  (ScummVM Command: {} --path=\"{}\" --auto-detect)",
+                             emulator_path.display(),
+                             game_dir.display()
+                    );
+                }
+            }
+        }
+        LaunchProfile::Rpcs3 => {
+            // RPCS3 boots a PS3 folder game from its EBOOT.BIN.
+            command.arg("--no-gui").arg(rom_path);
+            println!("  (RPCS3 Command: {} --no-gui \"{}\")", emulator_path.display(), rom_path.display());
+        }
+        LaunchProfile::Cemu => {
+            // Cemu takes the game (an .rpx from a loadiine dump, or a disc image) via -g.
+            command.arg("-g").arg(rom_path);
+            println!("  (Cemu Command: {} -g \"{}\")", emulator_path.display(), rom_path.display());
+        }
+        LaunchProfile::Generic => {
+            // Generic handling for other emulators: just pass the ROM path
+            command.arg(rom_path);
+            println!("  (Generic Command: {} \"{}\")", emulator_path.display(), rom_path.display());
+        }
+    }
+
+    if !options.extra_args.is_empty() {
+        command.args(&options.extra_args);
+        println!("  (Extra arguments: {})", options.extra_args.join(" "));
+    }
+
+    for (key, value) in &options.env {
+        command.env(key, value);
+    }
+    if let Some((program, args)) = options.wrapper.split_first() {
+        let mut wrapper = Command::new(program);
+        wrapper.args(args);
+        command = wrap_command(wrapper, command);
+        println!("  (Started through: {})", options.wrapper.join(" "));
+    }
+
+    let command = match options.sandbox {
+        Some(sandbox) => sandbox.wrap(command, &options.read_only_dirs)?,
+        None => command,
+    };
+    // Applied outermost; the sandbox and wrappers inherit the priority and affinity.
+    priority::wrap(command, options.nice, &options.cpu_affinity)
+}
+
+/// Appends a command's program and arguments to `wrapper`, which then runs it with the
+/// same working directory and environment.
+pub fn wrap_command(mut wrapper: Command, command: Command) -> Command {
+    wrapper.arg(command.get_program()).args(command.get_args());
+    if let Some(dir) = command.get_current_dir() {
+        wrapper.current_dir(dir);
+    }
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => wrapper.env(key, value),
+            None => wrapper.env_remove(key),
+        };
+    }
+    wrapper
+}
+
+/// Adds the emulator-specific flags for the display settings to the command.
+///
+/// Settings that have no command line flag are written to a temporary config file instead:
+/// RetroArch appends it with `--appendconfig`, DOSBox loads it as a second `-conf`. Emulators
+/// without a launch profile are started in fullscreen with `fullscreen_flag`, if `doctor` found one.
+///
+/// # Returns
+/// The generated config file the caller has to pass to the emulator, if one was written.
+fn apply_display_settings(
+    command: &mut Command,
+    profile: LaunchProfile,
+    emulator_name: &str,
+    display: &DisplaySettings,
+    fullscreen_flag: Option<&str>,
+) -> io::Result<Option<PathBuf>> {
+    let mut unsupported = Vec::new();
+    let mut display_config = None;
+
+    match profile {
+        LaunchProfile::Mame => {
+            match display.fullscreen {
+                Some(true) => { command.arg("-nowindow"); }
+                Some(false) => { command.arg("-window"); }
+                None => {}
+            }
+            if let Some(monitor) = display.monitor {
+                // MAME names monitors after the OS displays: \\.\DISPLAY1 on Windows, screen0 with SDL.
+                let screen = if cfg!(windows) { format!("\\\\.\\DISPLAY{}", monitor + 1) } else { format!("screen{}", monitor) };
+                command.arg("-screen").arg(screen);
+            }
+            if let Some((width, height)) = display.resolution {
+                command.arg("-resolution").arg(format!("{}x{}", width, height));
+            }
+        }
+        LaunchProfile::RetroArch => {
+            if display.fullscreen == Some(true) {
+                command.arg("--fullscreen");
+            }
+            let mut settings = String::new();
+            if let Some(fullscreen) = display.fullscreen {
+                settings.push_str(&format!("video_fullscreen = \"{}\"\n", fullscreen));
+            }
+            if let Some(monitor) = display.monitor {
+                // RetroArch counts monitors from 1; 0 means "automatic".
+                settings.push_str(&format!("video_monitor_index = \"{}\"\n", monitor + 1));
+            }
+            if let Some((width, height)) = display.resolution {
+                settings.push_str(&format!("video_fullscreen_x = \"{}\"\nvideo_fullscreen_y = \"{}\"\n", width, height));
+            }
+            display_config = Some(write_temp_config("rom_loader_display.cfg", &settings)?);
+        }
+        LaunchProfile::DosBox => {
+            if display.fullscreen == Some(true) {
+                command.arg("-fullscreen");
+            }
+            if display.monitor.is_some() || display.resolution.is_some() {
+                let mut settings = String::from("[sdl]\n");
+                if let Some(monitor) = display.monitor {
+                    settings.push_str(&format!("display={}\n", monitor));
+                }
+                if let Some((width, height)) = display.resolution {
+                    settings.push_str(&format!("fullresolution={}x{}\n", width, height));
+                }
+                display_config = Some(write_temp_config("rom_loader_dosbox_display.conf", &settings)?);
+            }
+        }
+        LaunchProfile::ScummVm => {
+            match display.fullscreen {
+                Some(true) => { command.arg("--fullscreen"); }
+                Some(false) => { command.arg("--no-fullscreen"); }
+                None => {}
+            }
+            if display.monitor.is_some() {
+                unsupported.push("monitor");
+            }
+            if display.resolution.is_some() {
+                unsupported.push("resolution");
+            }
+        }
+        LaunchProfile::Cemu => {
+            if display.fullscreen == Some(true) {
+                command.arg("-f");
+            }
+            if display.monitor.is_some() {
+                unsupported.push("monitor");
+            }
+            if display.resolution.is_some() {
+                unsupported.push("resolution");
+            }
+        }
+        LaunchProfile::Rpcs3 | LaunchProfile::Generic => {
+            match (display.fullscreen, fullscreen_flag) {
+                (Some(true), Some(flag)) => { command.arg(flag); }
+                (Some(_), _) => unsupported.push("fullscreen"),
+                (None, _) => {}
+            }
+            if display.monitor.is_some() {
+                unsupported.push("monitor");
+            }
+            if display.resolution.is_some() {
+                unsupported.push("resolution");
+            }
+        }
+    }
+
+    if !unsupported.is_empty() {
+        eprintln!("⚠️ Warning: {} can't be set from the command line for {}; set it in the emulator instead.",
+                  unsupported.join(", "),
+                  emulator_name
+        );
+    }
+    Ok(display_config)
+}
+
+/// Adds the emulator-specific flags for resuming from a save state and recording the session.
+fn apply_state_and_recording(command: &mut Command, profile: LaunchProfile, emulator_name: &str, options: &LaunchOptions) {
+    let mut unsupported = Vec::new();
+    match profile {
+        LaunchProfile::RetroArch => {
+            if let Some(slot) = options.load_state {
+                command.arg("--entryslot").arg(slot.to_string());
+            }
+            if let Some(file) = &options.record {
+                command.arg("--record").arg(file);
+            }
+        }
+        LaunchProfile::Mame => {
+            // MAME names states after the key they were saved to, so slot 1 is state "1".
+            if let Some(slot) = options.load_state {
+                command.arg("-state").arg(slot.to_string());
+            }
+            // Relative paths end up in MAME's snapshot directory.
+            if let Some(file) = &options.record {
+                command.arg("-aviwrite").arg(file);
+            }
+        }
+        _ => {
+            if options.load_state.is_some() {
+                unsupported.push("loading a save state");
+            }
+            if options.record.is_some() {
+                unsupported.push("recording");
+            }
+        }
+    }
+
+    if !unsupported.is_empty() {
+        eprintln!("⚠️ Warning: {} is not supported from the command line for {}; ignoring it.",
+                  unsupported.join(" and "),
+                  emulator_name
+        );
+    }
+}
+
+/// Generated config files of the launch in progress, removed by [`remove_temp_configs`].
+static TEMP_CONFIGS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Writes a generated emulator config file to the temp directory and returns its path.
+///
+/// The process ID goes into the name (`rom_loader_audio.cfg` becomes `rom_loader_audio_<pid>.cfg`),
+/// so two rom-loaders launching at once don't overwrite each other's files.
+pub(crate) fn write_temp_config(file_name: &str, contents: &str) -> io::Result<PathBuf> {
+    let name = Path::new(file_name);
+    let path = std::env::temp_dir().join(format!(
+        "{}_{}.{}",
+        name.file_stem().unwrap_or_default().to_string_lossy(),
+        std::process::id(),
+        name.extension().unwrap_or_default().to_string_lossy(),
+    ));
+    fs::write(&path, contents)?;
+    if let Ok(mut written) = TEMP_CONFIGS.lock() {
+        written.push(path.clone());
+    }
+    Ok(path)
+}
+
+/// Removes the config files [`write_temp_config`] wrote for the launch that just ended.
+pub(crate) fn remove_temp_configs() {
+    let Ok(mut written) = TEMP_CONFIGS.lock() else { return };
+    for path in written.drain(..) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Builds a DOSBox configuration for a game directory and writes it to the temp directory.
+///
+/// The per-game `dosbox.conf` is copied verbatim except for its `[autoexec]` section, which is
+/// regenerated: the game directory is mounted as `C:` (unless the conf mounts something itself)
+/// and, when the conf has no autoexec commands of its own, the detected start program is run.
+fn write_dosbox_conf(game_dir: &Path) -> io::Result<PathBuf> {
+    let per_game_conf = fs::read_dir(game_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .find(|p| p.file_name().is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(DOSBOX_CONF_MARKER)));
+    let contents = match per_game_conf {
+        Some(path) => fs::read_to_string(path)?,
+        None => String::new(),
+    };
+
+    let mut settings = Vec::new();
+    let mut autoexec = Vec::new();
+    let mut in_autoexec = false;
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            in_autoexec = trimmed.eq_ignore_ascii_case("[autoexec]");
+            if in_autoexec {
+                continue;
+            }
+        }
+        if in_autoexec {
+            if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                autoexec.push(trimmed.to_string());
+            }
+        } else {
+            settings.push(line.to_string());
+        }
+    }
+
+    let mut generated = settings.join("\n");
+    generated.push_str("\n[autoexec]\n");
+    if !autoexec.iter().any(|cmd| cmd.to_lowercase().starts_with("mount")) {
+        generated.push_str(&format!("mount c \"{}\"\nc:\n", game_dir.display()));
+    }
+    if autoexec.is_empty() {
+        match find_dos_executable(game_dir)? {
+            Some(exe) => generated.push_str(&format!("{}\nexit\n", exe)),
+            None => eprintln!("⚠️ Warning: No DOS executable found in {}. DOSBox will stop at the prompt.", game_dir.display()),
+        }
+    } else {
+        for cmd in &autoexec {
+            generated.push_str(cmd);
+            generated.push('\n');
+        }
+    }
+
+    let dir_name = game_dir.file_name().unwrap_or_default().to_string_lossy().replace(' ', "_");
+    write_temp_config(&format!("rom_loader_dosbox_{}.conf", dir_name), &generated)
+}
+
+/// Guesses which program starts a DOS game, skipping setup and installer executables.
+fn find_dos_executable(game_dir: &Path) -> io::Result<Option<String>> {
+    let mut candidates: Vec<String> = fs::read_dir(game_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| {
+            p.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| DOS_EXECUTABLE_EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+        })
+        .filter(|p| {
+            p.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| !DOS_NON_GAME_STEMS.iter().any(|s| s.eq_ignore_ascii_case(stem)))
+        })
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect();
+    candidates.sort();
+    Ok(candidates.into_iter().next())
+}
+
+/// Determines the ScummVM game id for a game.
+///
+/// The id is read from a `.scummvm` file (the common frontend convention) and, failing that,
+/// from the first result of `scummvm --detect`.
+fn detect_scummvm_game_id(emulator_path: &Path, rom_path: &Path, game_dir: &Path) -> Option<String> {
+    let id_file = if rom_path.is_file() {
+        Some(rom_path.to_path_buf())
+    } else {
+        fs::read_dir(game_dir).ok()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .find(|p| p.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| ext.eq_ignore_ascii_case(SCUMMVM_ID_EXTENSION)))
+    };
+    if let Some(game_id) = id_file
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|contents| contents.trim().to_string())
+        .filter(|id| !id.is_empty())
+    {
+        return Some(game_id);
+    }
+
+    // `scummvm --detect` prints a table whose first column is the game id, after a dashed separator.
+    let output = Command::new(emulator_path)
+        .arg("--detect")
+        .arg(format!("--path={}", game_dir.display()))
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .skip_while(|line| !line.starts_with("---"))
+        .nth(1)
+        .and_then(|line| line.split_whitespace().next())
+        .map(|id| id.to_string())
+}
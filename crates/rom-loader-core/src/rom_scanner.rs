@@ -0,0 +1,504 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::io;
+use walkdir::WalkDir;
+
+use crate::events::{self, Event};
+use crate::{interrupt, netfs, rom_format};
+use crate::scan_snapshot::ScanDiff;
+use crate::systems::{self, System, SYSTEMS};
+
+/// How many files the scan log waits between progress lines.
+const PROGRESS_INTERVAL: usize = 5000;
+
+/// Distinguishes single-file ROMs from games that live in a whole directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameKind {
+    /// A single ROM file identified by its extension.
+    File,
+    /// A game directory (DOS game, PS3 folder, Wii U loadiine dump, ...) recognized by a marker file.
+    Directory {
+        /// Identifier of the system whose marker matched.
+        system_id: &'static str,
+        /// The marker file that was found; this is what gets handed to the emulator.
+        marker: PathBuf,
+    },
+}
+
+/// Represents a found game: either a single ROM file or a game directory.
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub path: PathBuf,
+    pub kind: GameKind,
+    /// System id worked out from the file's contents, for extensions several systems share
+    /// (see [`rom_format::classify_bin`]).
+    pub detected_system: Option<&'static str>,
+}
+
+impl Game {
+    /// Creates a single-file game, looking into `.bin` files to tell which system they are for.
+    ///
+    /// # Returns
+    /// `None` for a BIOS dump, which is not a game.
+    pub fn file(path: &Path) -> Option<Game> {
+        let is_bin = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bin"));
+        let detected_system = if is_bin {
+            match rom_format::classify_bin(path) {
+                Ok(contents) => Some(contents.system_id()?),
+                // An unreadable file is left to the extension, as for other systems.
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+        Some(Game { path: path.to_path_buf(), kind: GameKind::File, detected_system })
+    }
+
+    /// Gets the file extension of the ROM.
+    ///
+    /// Directory-based games have no meaningful extension, so their system id
+    /// (e.g. `"dos"`, `"ps3"`) is returned instead. This keeps the extension
+    /// based emulator lookup working for them.
+    pub fn get_extension(&self) -> Option<&str> {
+        match &self.kind {
+            GameKind::File => self.path.extension().and_then(|ext| ext.to_str()),
+            GameKind::Directory { system_id, .. } => Some(system_id),
+        }
+    }
+
+    /// Gets the system this game belongs to, if the registry knows it.
+    pub fn system(&self) -> Option<&'static System> {
+        match &self.kind {
+            GameKind::File => match self.detected_system {
+                Some(system_id) => systems::find_by_id(system_id),
+                None => self.get_extension().and_then(systems::find_by_extension),
+            },
+            GameKind::Directory { system_id, .. } => systems::find_by_id(system_id),
+        }
+    }
+
+    /// Gets the path that should be passed to the emulator.
+    ///
+    /// For directory games this is the marker file (EBOOT.BIN, the .rpx, dosbox.conf, ...);
+    /// launch profiles that need the directory itself take the marker's parent.
+    pub fn launch_path(&self) -> &Path {
+        match &self.kind {
+            GameKind::File => &self.path,
+            GameKind::Directory { marker, .. } => marker,
+        }
+    }
+}
+
+/// A file or directory a scan could not look at: an unreadable directory, a broken symbolic
+/// link, a ROM that could not be read.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProblem {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+impl ScanProblem {
+    pub fn new(path: &Path, error: impl std::fmt::Display) -> Self {
+        ScanProblem { path: path.to_path_buf(), error: error.to_string() }
+    }
+
+    /// Turns an error of the directory walk into a problem.
+    pub fn from_walk_error(base_dir: &Path, e: &walkdir::Error) -> Self {
+        let error = match (e.io_error(), e.loop_ancestor()) {
+            (Some(io_error), _) => io_error.to_string(),
+            (None, Some(ancestor)) => format!("symbolic link loop back to {}", ancestor.display()),
+            (None, None) => e.to_string(),
+        };
+        ScanProblem::new(e.path().unwrap_or(base_dir), error)
+    }
+}
+
+/// A game as listed in a scan summary.
+#[derive(Debug, Serialize)]
+pub struct ScannedGame {
+    pub path: PathBuf,
+    /// System id, if the registry knows the game's system.
+    pub system: Option<&'static str>,
+    /// Whether the game is a directory rather than a single file.
+    pub directory: bool,
+}
+
+/// The outcome of scanning a library, printed as JSON by `scan --json`.
+#[derive(Debug, Serialize)]
+pub struct ScanSummary {
+    pub roms_dir: PathBuf,
+    pub total: usize,
+    /// Number of games per system id ("unknown" for games without a known system).
+    pub systems: BTreeMap<&'static str, usize>,
+    pub games: Vec<ScannedGame>,
+    /// Changes since the previous scan of the same library, if there was one.
+    pub diff: Option<ScanDiff>,
+    /// Files and directories that could not be scanned.
+    pub problems: Vec<ScanProblem>,
+}
+
+impl ScanSummary {
+    /// Starts the summary of a scan of `roms_dir`; games are added as the scan finds them.
+    pub fn new(roms_dir: &Path) -> Self {
+        ScanSummary {
+            roms_dir: roms_dir.to_path_buf(),
+            total: 0,
+            systems: BTreeMap::new(),
+            games: Vec::new(),
+            diff: None,
+            problems: Vec::new(),
+        }
+    }
+
+    /// Counts a game found by the scan.
+    pub fn add(&mut self, game: &Game) {
+        let system = game.system().map(|s| s.id);
+        *self.systems.entry(system.unwrap_or("unknown")).or_insert(0) += 1;
+        self.total += 1;
+        self.games.push(ScannedGame {
+            path: game.path.clone(),
+            system,
+            directory: matches!(game.kind, GameKind::Directory { .. }),
+        });
+    }
+}
+
+/// Resolves a marker pattern (e.g. `"PS3_GAME/USRDIR/EBOOT.BIN"` or `"code/*.rpx"`) inside a directory.
+fn find_marker(dir: &Path, pattern: &str) -> Option<PathBuf> {
+    let mut current = dir.to_path_buf();
+    let components: Vec<&str> = pattern.split('/').collect();
+    for (i, component) in components.iter().enumerate() {
+        let is_last = i == components.len() - 1;
+        let wanted_extension = component.strip_prefix("*.");
+        let next = fs::read_dir(&current).ok()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| if is_last { p.is_file() } else { p.is_dir() })
+            .find(|p| match wanted_extension {
+                Some(ext) => p.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case(ext)),
+                None => p.file_name().is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(component)),
+            })?;
+        current = next;
+    }
+    Some(current)
+}
+
+/// Checks whether a directory is a self-contained game rather than a folder of ROMs.
+///
+/// Systems are tried in registry order, so the first system whose marker matches wins.
+fn detect_game_directory(dir: &Path) -> Option<GameKind> {
+    for system in SYSTEMS {
+        for pattern in system.directory_markers {
+            if let Some(marker) = find_marker(dir, pattern) {
+                return Some(GameKind::Directory { system_id: system.id, marker });
+            }
+        }
+    }
+    None
+}
+
+/// Identifies a single ROM file or game directory outside of a library scan.
+///
+/// # Returns
+/// `None` if the path is neither a file with a supported extension nor a recognized game
+/// directory, or is a BIOS dump.
+pub fn identify(path: &Path) -> Option<Game> {
+    if path.is_dir() {
+        return detect_game_directory(path).map(|kind| Game { path: path.to_path_buf(), kind, detected_system: None });
+    }
+    let extension = path.extension()?.to_str()?;
+    systems::find_by_extension(extension)?;
+    if !path.is_file() {
+        return None;
+    }
+    Game::file(path)
+}
+
+/// Limits a scan to some systems and extensions, e.g. to rescan one console's folder of a
+/// large library quickly.
+///
+/// A game is scanned if it is of one of the systems or has one of the extensions; an empty
+/// filter scans everything.
+#[derive(Debug, Clone, Default)]
+pub struct ScanFilter {
+    /// System ids, e.g. "snes".
+    pub systems: Vec<String>,
+    /// Lowercase extensions without the dot.
+    pub extensions: Vec<String>,
+}
+
+impl ScanFilter {
+    /// Creates a filter from system ids and extensions as typed on the command line.
+    ///
+    /// # Returns
+    /// An error naming the first system id the registry doesn't know.
+    pub fn new(systems: &[String], extensions: &[String]) -> Result<Self, String> {
+        let systems: Vec<String> = systems.iter().map(|id| id.trim().to_lowercase()).collect();
+        if let Some(unknown) = systems.iter().find(|id| systems::find_by_id(id).is_none()) {
+            return Err(format!("Unknown system '{}'", unknown));
+        }
+        let extensions = extensions.iter().map(|ext| ext.trim().trim_start_matches('.').to_lowercase()).collect();
+        Ok(ScanFilter { systems, extensions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty() && self.extensions.is_empty()
+    }
+
+    /// Whether files with this extension can pass the filter, before looking into them.
+    fn accepts_extension(&self, extension: &str) -> bool {
+        let extension = extension.to_lowercase();
+        if self.is_empty() || self.extensions.contains(&extension) {
+            return true;
+        }
+        // `.bin` files are told apart by their contents (see `rom_format::classify_bin`).
+        !self.systems.is_empty()
+            && (extension == "bin" || systems::find_by_extension(&extension).is_some_and(|s| self.systems.iter().any(|id| id == s.id)))
+    }
+
+    /// Whether a game passes the filter.
+    pub fn matches(&self, game: &Game) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        game.system().is_some_and(|s| self.systems.iter().any(|id| id == s.id))
+            || game.get_extension().is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()))
+    }
+
+    /// Whether a scan with this filter looks at the given path: whether a game there would be
+    /// found, or a game missing from there would be reported as removed.
+    ///
+    /// Paths whose extension can't pass are decided without touching the disk, so comparing a
+    /// partial scan with a full library stays quick.
+    pub fn covers(&self, path: &Path) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        if !path.extension().and_then(|e| e.to_str()).is_some_and(|e| self.accepts_extension(e)) {
+            return false;
+        }
+        identify(path).is_none_or(|game| self.matches(&game))
+    }
+}
+
+/// Scans a directory for ROM files based on provided extensions, and for game directories
+/// based on the system registry's marker files.
+#[derive(Clone, Copy)]
+pub struct RomScanner<'a> {
+    base_dir: &'a Path,
+    supported_extensions: &'a [&'a str],
+    filter: Option<&'a ScanFilter>,
+    quiet: bool,
+}
+
+impl<'a> RomScanner<'a> {
+    /// Creates a new `RomScanner`.
+    ///
+    /// # Arguments
+    /// * `base_dir` - The directory to start scanning from.
+    /// * `supported_extensions` - A slice of file extensions (e.g., `["nes", "snes"]`) to look for.
+    pub fn new(base_dir: &'a Path, supported_extensions: &'a [&'a str]) -> Self {
+        RomScanner {
+            base_dir,
+            supported_extensions,
+            filter: None,
+            quiet: false,
+        }
+    }
+
+    /// Only looks for the games that pass `filter`.
+    pub fn filter(mut self, filter: &'a ScanFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Turns off the progress log, e.g. when stdout is reserved for machine-readable output.
+    pub fn quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Prints a line of the scan log unless the scanner is quiet.
+    fn log(&self, message: std::fmt::Arguments) {
+        if !self.quiet {
+            println!("{}", message);
+        }
+    }
+
+    /// Scans the `base_dir` recursively for supported ROM files and game directories.
+    ///
+    /// # Returns
+    /// A `Result` containing a `Vec<Game>` if successful, or an `io::Error` on failure.
+    pub fn scan_roms(&self) -> io::Result<Vec<Game>> {
+        self.scan().map(|(roms, _)| roms)
+    }
+
+    /// Scans like [`RomScanner::scan_roms`], also returning the directories and files that
+    /// could not be looked at (unreadable directories, broken symbolic links), which are
+    /// otherwise only mentioned in the scan log.
+    pub fn scan(&self) -> io::Result<(Vec<Game>, Vec<ScanProblem>)> {
+        let mut roms = Vec::new();
+        let mut problems = Vec::new();
+        for entry in self.entries()? {
+            match entry? {
+                ScanEntry::Game(game) => roms.push(game),
+                ScanEntry::Problem(problem) => problems.push(problem),
+            }
+        }
+        Ok((roms, problems))
+    }
+
+    /// Starts a scan that yields games and problems one at a time as the directory tree is
+    /// walked, so consumers (the scan snapshot, the search index) can handle them as they come
+    /// instead of collecting the whole library first.
+    ///
+    /// An error ends the scan: a partial scan would look like removed games, so consumers
+    /// should drop what they got so far.
+    pub fn entries(&self) -> io::Result<ScanEntries<'a>> {
+        // Check if the base directory exists.
+        if !self.base_dir.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("ROMs directory not found: {}", self.base_dir.display()),
+            ));
+        }
+        if !self.base_dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Path is not a directory: {}", self.base_dir.display()),
+            ));
+        }
+
+        self.log(format_args!("🔍 Scanning for ROMs in: {}", self.base_dir.display()));
+        events::emit(Event::ScanStarted { roms_dir: self.base_dir });
+        Ok(ScanEntries {
+            scanner: *self,
+            walkers: vec![WalkDir::new(self.base_dir).into_iter()],
+            files_checked: 0,
+            games_found: 0,
+            _busy: interrupt::Busy::start(),
+        })
+    }
+}
+
+/// What a scan found: a game, or something that could not be looked at.
+#[derive(Debug)]
+pub enum ScanEntry {
+    Game(Game),
+    Problem(ScanProblem),
+}
+
+/// A scan in progress, from [`RomScanner::entries`].
+pub struct ScanEntries<'a> {
+    scanner: RomScanner<'a>,
+    /// Directory walks in progress; a directory a network share failed to list is walked
+    /// again on its own, on top of the walk that hit the error.
+    walkers: Vec<walkdir::IntoIter>,
+    files_checked: usize,
+    games_found: usize,
+    _busy: interrupt::Busy,
+}
+
+impl ScanEntries<'_> {
+    /// Returns a game directory as a game, unless the scan's filter leaves it out.
+    fn game_directory(&self, path: &Path, kind: GameKind) -> Option<ScanEntry> {
+        let game = Game { path: path.to_path_buf(), kind, detected_system: None };
+        if self.scanner.filter.is_some_and(|filter| !filter.matches(&game)) {
+            return None;
+        }
+        if let GameKind::Directory { system_id, .. } = &game.kind {
+            self.scanner.log(format_args!("  -- Found {} game directory: {}", system_id, path.display()));
+        }
+        Some(ScanEntry::Game(game))
+    }
+
+    /// Looks at one directory entry, returning the game or problem it is, if any.
+    fn check(&mut self, entry: walkdir::DirEntry) -> Option<ScanEntry> {
+        let scanner = self.scanner;
+        let path = entry.path();
+        if entry.path_is_symlink() && !path.exists() {
+            let target = fs::read_link(path).map_or_else(|_| "nothing".to_string(), |target| target.display().to_string());
+            let problem = ScanProblem::new(path, format!("broken symbolic link to {}", target));
+            scanner.log(format_args!("  -- ⚠️ Skipping {}: {}", path.display(), problem.error));
+            return Some(ScanEntry::Problem(problem));
+        }
+        if path.is_dir() && entry.depth() > 0 {
+            // Game directories (DOS, PS3, Wii U, ...) are a single entry; don't descend into them.
+            let kind = detect_game_directory(path)?;
+            if let Some(walker) = self.walkers.last_mut() {
+                walker.skip_current_dir();
+            }
+            return self.game_directory(path, kind);
+        }
+        if !path.is_file() {
+            return None;
+        }
+        // Files are counted rather than logged one by one, so huge trees don't flood the log.
+        self.files_checked += 1;
+        if self.files_checked.is_multiple_of(PROGRESS_INTERVAL) {
+            scanner.log(format_args!("  -- {} files checked, {} games found", self.files_checked, self.games_found));
+        }
+        let extension = path.extension().and_then(|s| s.to_str())?;
+        if !scanner.supported_extensions.iter().any(|&ext| ext.eq_ignore_ascii_case(extension)) {
+            return None;
+        }
+        if scanner.filter.is_some_and(|filter| !filter.accepts_extension(extension)) {
+            return None;
+        }
+        // `None` for BIOS dumps.
+        let game = Game::file(path)?;
+        if scanner.filter.is_some_and(|filter| !filter.matches(&game)) {
+            return None;
+        }
+        Some(ScanEntry::Game(game))
+    }
+}
+
+impl Iterator for ScanEntries<'_> {
+    type Item = io::Result<ScanEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let walker = self.walkers.last_mut()?;
+            let Some(entry) = walker.next() else {
+                self.walkers.pop();
+                continue;
+            };
+            // A partial scan would look like removed games, so the scan ends with an error.
+            if interrupt::requested() {
+                self.walkers.clear();
+                return Some(Err(interrupt::error()));
+            }
+            let found = match entry {
+                Ok(entry) => self.check(entry),
+                Err(e) => {
+                    // A directory a network share failed to list is tried again on its own.
+                    let transient = e.io_error().is_some_and(netfs::is_transient);
+                    if let Some(dir) = e.path().filter(|dir| transient && netfs::retry(|| fs::read_dir(dir)).is_ok()) {
+                        self.scanner.log(format_args!("  -- Listing {} again after: {}", dir.display(), e));
+                        match detect_game_directory(dir) {
+                            Some(kind) => self.game_directory(dir, kind),
+                            None => {
+                                self.walkers.push(WalkDir::new(dir).into_iter());
+                                None
+                            }
+                        }
+                    } else {
+                        let problem = ScanProblem::from_walk_error(self.scanner.base_dir, &e);
+                        self.scanner.log(format_args!("  -- ⚠️ Could not read {}: {}", problem.path.display(), problem.error));
+                        Some(ScanEntry::Problem(problem))
+                    }
+                }
+            };
+            if let Some(found) = found {
+                if let ScanEntry::Game(game) = &found {
+                    self.games_found += 1;
+                    events::emit(Event::RomFound { path: &game.path, system: game.system().map(|system| system.id) });
+                }
+                return Some(Ok(found));
+            }
+        }
+    }
+}
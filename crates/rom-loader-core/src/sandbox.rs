@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use crate::rom_launcher;
+
+/// A tool that runs an emulator with restricted access to the system.
+///
+/// Emulators run without network access, and the game files are mounted read-only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sandbox {
+    /// firejail, using its default profile.
+    Firejail,
+    /// bubblewrap (`bwrap`): the whole file system is read-only except home, /tmp and /run.
+    Bubblewrap,
+}
+
+impl FromStr for Sandbox {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "firejail" => Ok(Sandbox::Firejail),
+            "bubblewrap" | "bwrap" => Ok(Sandbox::Bubblewrap),
+            other => Err(format!("Unknown sandbox '{}' (expected firejail or bubblewrap)", other)),
+        }
+    }
+}
+
+impl Sandbox {
+    /// Wraps an emulator command so it runs inside the sandbox.
+    ///
+    /// # Arguments
+    /// * `command` - The fully built emulator command.
+    /// * `read_only_dirs` - Directories the emulator may read but not modify (the game files).
+    ///
+    /// # Returns
+    /// The sandbox command running the original one, with its working directory and environment.
+    pub fn wrap(self, command: Command, read_only_dirs: &[PathBuf]) -> io::Result<Command> {
+        if !cfg!(target_os = "linux") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("The {:?} sandbox is only available on Linux", self),
+            ));
+        }
+
+        let mut wrapped = match self {
+            Sandbox::Firejail => {
+                let mut wrapped = Command::new("firejail");
+                wrapped.arg("--quiet").arg("--net=none");
+                for dir in read_only_dirs {
+                    wrapped.arg(format!("--read-only={}", dir.display()));
+                }
+                wrapped
+            }
+            Sandbox::Bubblewrap => {
+                let mut wrapped = Command::new("bwrap");
+                wrapped
+                    .args(["--ro-bind", "/", "/"])
+                    .args(["--dev-bind", "/dev", "/dev"]) // GPU and input devices
+                    .args(["--proc", "/proc"])
+                    .args(["--bind", "/tmp", "/tmp"]) // X11 socket and generated configs
+                    .args(["--bind", "/run", "/run"]) // Wayland and PulseAudio/PipeWire sockets
+                    .args(["--unshare-net", "--unshare-pid", "--die-with-parent"]);
+                // Emulators keep their settings and saves under home.
+                if let Some(home) = std::env::var_os("HOME") {
+                    wrapped.arg("--bind").arg(&home).arg(&home);
+                }
+                // Later mounts win, so game files below home stay read-only.
+                for dir in read_only_dirs {
+                    wrapped.arg("--ro-bind").arg(dir).arg(dir);
+                }
+                wrapped
+            }
+        };
+
+        wrapped.arg("--");
+        let wrapped = rom_launcher::wrap_command(wrapped, command);
+        let dirs: Vec<String> = read_only_dirs.iter().map(|d| d.display().to_string()).collect();
+        println!("  (Sandboxed with {}, read-only: {})", wrapped.get_program().to_string_lossy(), dirs.join(", "));
+        Ok(wrapped)
+    }
+}
+
+/// Returns the directory a sandboxed launch should only be able to read: the game's own
+/// directory for folder-based games, or the directory holding the ROM file.
+///
+/// The path is made absolute because the sandbox tools mount it at the same location.
+pub fn game_files_dir(game_path: &Path) -> PathBuf {
+    let dir = if game_path.is_dir() { game_path } else { game_path.parent().unwrap_or(game_path) };
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
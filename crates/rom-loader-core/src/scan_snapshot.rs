@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use crate::{hashing, netfs};
+use crate::rom_scanner::{Game, GameKind, ScanProblem};
+
+/// File name the last scan snapshot was kept under by earlier versions, which had no library
+/// database; it is migrated by [`crate::library_db::LibraryDb::load_snapshot`].
+pub const SNAPSHOT_FILE_NAME: &str = "scan_snapshot.json";
+
+/// What a scan saw of one game.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// Size in bytes; the total size for game directories.
+    pub size: u64,
+    /// Modification time (Unix seconds); the newest file for game directories.
+    pub modified: u64,
+    /// CRC32 of the file; not taken for game directories.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crc32: Option<String>,
+}
+
+/// The games found by a scan, compared with the next scan to see how the library changed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ScanSnapshot {
+    /// Unix timestamp of the scan.
+    pub created: u64,
+    /// The library that was scanned.
+    #[serde(default)]
+    pub roms_dir: PathBuf,
+    #[serde(default)]
+    pub games: BTreeMap<PathBuf, SnapshotEntry>,
+}
+
+/// A game that moved to a new path with unchanged contents.
+#[derive(Debug, Serialize)]
+pub struct RenamedGame {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// How the library changed between two scans.
+#[derive(Debug, Default, Serialize)]
+pub struct ScanDiff {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub renamed: Vec<RenamedGame>,
+    /// Games at the same path whose contents changed.
+    pub changed: Vec<PathBuf>,
+}
+
+impl ScanDiff {
+    /// Whether the two scans found the same library.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl ScanSnapshot {
+    /// Loads a snapshot saved as JSON by earlier versions, or `None` if there is none.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse scan snapshot {}: {}", path.display(), e),
+            ))
+    }
+
+    /// Starts an empty snapshot of a library; games are added as the scan finds them.
+    pub fn new(roms_dir: &Path, now: u64) -> Self {
+        ScanSnapshot { created: now, roms_dir: roms_dir.to_path_buf(), games: BTreeMap::new() }
+    }
+
+    /// Adds a scanned game to the snapshot.
+    ///
+    /// Files are only hashed if they are new or their size or modification time changed since
+    /// `previous`, so repeated scans of a large library stay fast. Unreadable files are
+    /// recorded without a hash and added to `problems`.
+    pub fn add(&mut self, game: &Game, previous: Option<&ScanSnapshot>, problems: &mut Vec<ScanProblem>) {
+        let entry = match game.kind {
+            GameKind::File => {
+                let metadata = match netfs::retry(|| fs::metadata(&game.path)) {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        problems.push(ScanProblem::new(&game.path, e));
+                        return;
+                    }
+                };
+                let size = metadata.len();
+                let modified = modified_secs(&metadata);
+                let known = previous
+                    .and_then(|p| p.games.get(&game.path))
+                    .filter(|e| e.size == size && e.modified == modified && e.crc32.is_some());
+                let crc32 = match known {
+                    Some(entry) => entry.crc32.clone(),
+                    None => netfs::retry(|| hashing::crc32_file(&game.path)).inspect_err(|e| problems.push(ScanProblem::new(&game.path, e))).ok(),
+                };
+                SnapshotEntry { size, modified, crc32 }
+            }
+            GameKind::Directory { .. } => {
+                let mut entry = SnapshotEntry { size: 0, modified: 0, crc32: None };
+                for file in WalkDir::new(&game.path).into_iter() {
+                    let file = match file {
+                        Ok(file) => file,
+                        Err(e) => {
+                            problems.push(ScanProblem::from_walk_error(&game.path, &e));
+                            continue;
+                        }
+                    };
+                    if let Ok(metadata) = file.metadata() {
+                        if metadata.is_file() {
+                            entry.size += metadata.len();
+                            entry.modified = entry.modified.max(modified_secs(&metadata));
+                        }
+                    }
+                }
+                entry
+            }
+        };
+        self.games.insert(game.path.clone(), entry);
+    }
+
+    /// Copies the games of `previous` that a partial scan didn't look at (those `scanned`
+    /// says no to), so they are neither reported as removed nor dropped from the library.
+    pub fn keep_unscanned(&mut self, previous: &ScanSnapshot, scanned: impl Fn(&Path) -> bool) {
+        for (path, entry) in &previous.games {
+            if !self.games.contains_key(path) && !scanned(path) {
+                self.games.insert(path.clone(), entry.clone());
+            }
+        }
+    }
+
+    /// Compares this (older) snapshot with a newer one.
+    ///
+    /// A removed and an added game with the same size and hash count as a rename.
+    pub fn diff(&self, newer: &ScanSnapshot) -> ScanDiff {
+        let mut diff = ScanDiff::default();
+        let mut removed: Vec<&PathBuf> = self.games.keys().filter(|path| !newer.games.contains_key(*path)).collect();
+        for (path, entry) in &newer.games {
+            match self.games.get(path) {
+                Some(old) => {
+                    let changed = match (&old.crc32, &entry.crc32) {
+                        (Some(old_crc), Some(new_crc)) => old_crc != new_crc,
+                        _ => old.size != entry.size,
+                    };
+                    if changed {
+                        diff.changed.push(path.clone());
+                    }
+                }
+                None => {
+                    let original = removed.iter().position(|old_path| {
+                        let old = &self.games[*old_path];
+                        entry.crc32.is_some() && old.crc32 == entry.crc32 && old.size == entry.size
+                    });
+                    match original {
+                        Some(index) => diff.renamed.push(RenamedGame { from: removed.remove(index).clone(), to: path.clone() }),
+                        None => diff.added.push(path.clone()),
+                    }
+                }
+            }
+        }
+        diff.removed = removed.into_iter().cloned().collect();
+        diff
+    }
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok()).map_or(0, |d| d.as_secs())
+}
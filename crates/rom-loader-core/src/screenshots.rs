@@ -0,0 +1,83 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+use crate::rom_scanner::Game;
+
+/// Name of the directory next to the emulator configuration that holds per-game assets.
+pub const ASSETS_DIR_NAME: &str = "assets";
+
+/// File extensions emulators save screenshots with.
+const SCREENSHOT_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "webp"];
+
+/// Returns the folder holding a game's assets (screenshots, art, ...), e.g. `assets/snes/Super Mario World`.
+pub fn game_assets_dir(config_path: &Path, game: &Game) -> PathBuf {
+    config_path
+        .with_file_name(ASSETS_DIR_NAME)
+        .join(game.system().map_or("other", |s| s.id))
+        .join(game.path.file_stem().unwrap_or_default())
+}
+
+/// Returns the folder collecting a game's screenshots, e.g. `assets/snes/Super Mario World/screenshots`.
+pub fn screenshots_dir(config_path: &Path, game: &Game) -> PathBuf {
+    game_assets_dir(config_path, game).join("screenshots")
+}
+
+/// Finds the images directly in `dir`, sorted by path; none if it doesn't exist.
+///
+/// In a game's screenshots folder these are its screenshots; directly in its assets folder,
+/// its art (box art, title screens, ...).
+pub fn images_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+    let mut images: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.is_file() && is_image(path))
+        .collect();
+    images.sort();
+    images
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| SCREENSHOT_EXTENSIONS.iter().any(|s| ext.eq_ignore_ascii_case(s)))
+}
+
+/// Copies the screenshots an emulator saved during a session into the game's screenshot folder.
+///
+/// Screenshots are picked up from `screenshot_dir` (and its subdirectories, for emulators that
+/// sort them per game) if they were written after `session_start`. Each copy is prefixed with
+/// the session's start time so screenshots from one session stay together.
+///
+/// # Returns
+/// The copied screenshots.
+pub fn harvest(screenshot_dir: &Path, target_dir: &Path, session_start: u64) -> io::Result<Vec<PathBuf>> {
+    if !screenshot_dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Screenshot directory not found: {}", screenshot_dir.display()),
+        ));
+    }
+    let session_tag = chrono::DateTime::from_timestamp(session_start as i64, 0)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H-%M-%S").to_string())
+        .unwrap_or_else(|| session_start.to_string());
+
+    let mut harvested = Vec::new();
+    for entry in WalkDir::new(screenshot_dir).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if !entry.file_type().is_file() || !is_image(path) {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        if modified < session_start {
+            continue;
+        }
+
+        fs::create_dir_all(target_dir)?;
+        let target = target_dir.join(format!("{} {}", session_tag, entry.file_name().to_string_lossy()));
+        fs::copy(path, &target)?;
+        harvested.push(target);
+    }
+    Ok(harvested)
+}
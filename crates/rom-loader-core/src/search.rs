@@ -0,0 +1,167 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::metadata::{GameMetadata, YearRange};
+use crate::rom_scanner::Game;
+
+/// Metadata fields a search term can name, e.g. `genre:rpg`.
+const TEXT_FIELDS: &[&str] = &["title", "genre", "developer", "publisher", "region", "rating", "description", "system", "file"];
+
+/// One part of a search query.
+#[derive(Debug, Clone)]
+enum Term {
+    /// Text that must appear in the file name, title, genre, developer, publisher or description.
+    Text(String),
+    /// Text that must appear in one field.
+    Field(&'static str, String),
+    /// A release year condition such as `year:<1995` or `year:1994..1998`.
+    Year(YearRange),
+    /// A minimum (`players:2`) or exact-range player count condition.
+    Players(YearRange),
+    /// `is:homebrew`, for games downloaded by `discover homebrew`.
+    Homebrew,
+}
+
+/// A game list search such as `genre:rpg year:<1995 mario`.
+///
+/// Every term must match. Bare words are looked for in the file name and the usual metadata
+/// text; `field:value` looks in one field (title, genre, developer, publisher, region, rating,
+/// description, system, file). `year:` and `players:` take a number, a comparison (`<1995`,
+/// `>=2`) or a range (`1994..1998`). Double quotes group words (`developer:"rare ltd"`) and a
+/// leading `-` excludes games matching the term. `is:homebrew` matches the games downloaded by
+/// `discover homebrew`. Matching ignores case.
+#[derive(Debug, Clone)]
+pub struct Query {
+    /// The terms and whether each one is negated.
+    terms: Vec<(Term, bool)>,
+    source: String,
+}
+
+impl Query {
+    /// Whether a game matches every term of the query.
+    ///
+    /// # Arguments
+    /// * `game` - The game, for its file name and system.
+    /// * `metadata` - Its imported metadata, if known.
+    /// * `title` - The title it is listed under, which may come from its cartridge header or
+    ///   the title rules.
+    pub fn matches(&self, game: &Game, metadata: Option<&GameMetadata>, title: &str) -> bool {
+        self.terms.iter().all(|(term, negated)| term_matches(term, game, metadata, title) != *negated)
+    }
+}
+
+fn term_matches(term: &Term, game: &Game, metadata: Option<&GameMetadata>, title: &str) -> bool {
+    let file_name = game.path.file_name().unwrap_or_default().to_string_lossy().to_lowercase();
+    let field = |name: &str| -> Option<String> {
+        let value = match name {
+            "title" => return Some(title.to_lowercase()),
+            "file" => return Some(file_name.clone()),
+            "system" => return game.system().map(|s| format!("{} {}", s.id, s.name).to_lowercase()),
+            _ => metadata?,
+        };
+        match name {
+            "genre" => value.genre.clone(),
+            "developer" => value.developer.clone(),
+            "publisher" => value.publisher.clone(),
+            "region" => value.region.clone(),
+            "rating" => value.rating.clone(),
+            "description" => value.description.clone(),
+            _ => None,
+        }
+        .map(|v| v.to_lowercase())
+    };
+    match term {
+        Term::Text(text) => ["file", "title", "genre", "developer", "publisher", "description"]
+            .iter()
+            .any(|name| field(name).is_some_and(|value| value.contains(text.as_str()))),
+        Term::Field(name, text) => field(name).is_some_and(|value| value.contains(text.as_str())),
+        Term::Year(range) => metadata.and_then(|m| m.release_year()).is_some_and(|year| range.contains(year)),
+        Term::Players(range) => metadata.and_then(|m| m.max_players()).is_some_and(|players| range.contains(players)),
+        Term::Homebrew => metadata.is_some_and(|m| m.homebrew),
+    }
+}
+
+/// Parses a number condition: `1995`, `<1995`, `<=1995`, `>1990`, `>=1990` or `1994..1998`.
+fn parse_range(value: &str) -> Result<YearRange, String> {
+    let number = |s: &str| s.trim().parse::<u32>().map_err(|_| format!("'{}' is not a number", s.trim()));
+    if let Some(rest) = value.strip_prefix("<=") {
+        Ok(YearRange { start: None, end: Some(number(rest)?) })
+    } else if let Some(rest) = value.strip_prefix(">=") {
+        Ok(YearRange { start: Some(number(rest)?), end: None })
+    } else if let Some(rest) = value.strip_prefix('<') {
+        Ok(YearRange { start: None, end: Some(number(rest)?.saturating_sub(1)) })
+    } else if let Some(rest) = value.strip_prefix('>') {
+        Ok(YearRange { start: Some(number(rest)? + 1), end: None })
+    } else {
+        value.parse().map_err(|_| format!("'{}' is not a number, comparison or range", value))
+    }
+}
+
+/// Splits a query into words, keeping double-quoted parts together.
+fn split_words(query: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if quoted {
+        return Err("unbalanced quote".to_string());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut terms = Vec::new();
+        for word in split_words(s)? {
+            let (negated, word) = match word.strip_prefix('-').filter(|rest| !rest.is_empty()) {
+                Some(rest) => (true, rest),
+                None => (false, word.as_str()),
+            };
+            let term = match word.split_once(':') {
+                Some(("year", value)) => Term::Year(parse_range(value)?),
+                Some(("players", value)) => {
+                    // A bare count means "at least", as for --players.
+                    let range = parse_range(value)?;
+                    if value.chars().all(|c| c.is_ascii_digit()) {
+                        Term::Players(YearRange { start: range.start, end: None })
+                    } else {
+                        Term::Players(range)
+                    }
+                }
+                Some(("is", flag)) if flag.eq_ignore_ascii_case("homebrew") => Term::Homebrew,
+                // Other colons are part of the text, as in "castlevania:".
+                Some((name, value)) => match TEXT_FIELDS.iter().find(|field| field.eq_ignore_ascii_case(name)) {
+                    Some(field) => Term::Field(field, value.to_lowercase()),
+                    None => Term::Text(word.to_lowercase()),
+                },
+                None => Term::Text(word.to_lowercase()),
+            };
+            terms.push((term, negated));
+        }
+        if terms.is_empty() {
+            return Err("the search is empty".to_string());
+        }
+        Ok(Query { terms, source: s.trim().to_string() })
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+/// What to do once a play session (a single launch or a whole launch queue) has ended.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PostSessionAction {
+    /// Go back to the game list (the default).
+    #[default]
+    Return,
+    /// Exit ROM Loader.
+    Quit,
+    /// Power off the computer.
+    Shutdown,
+    /// Suspend the computer to RAM.
+    Suspend,
+    /// Run a script, passing it the path of the last game played.
+    Script(PathBuf),
+}
+
+impl FromStr for PostSessionAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "return" | "menu" => Ok(PostSessionAction::Return),
+            "quit" | "exit" => Ok(PostSessionAction::Quit),
+            "shutdown" | "poweroff" => Ok(PostSessionAction::Shutdown),
+            "suspend" | "sleep" => Ok(PostSessionAction::Suspend),
+            _ => match s.strip_prefix("script:") {
+                Some(path) if !path.is_empty() => Ok(PostSessionAction::Script(PathBuf::from(path))),
+                _ => Err(format!(
+                    "Unknown post-session action '{}' (expected return, quit, shutdown, suspend or script:<path>)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PostSessionAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostSessionAction::Return => write!(f, "return"),
+            PostSessionAction::Quit => write!(f, "quit"),
+            PostSessionAction::Shutdown => write!(f, "shutdown"),
+            PostSessionAction::Suspend => write!(f, "suspend"),
+            PostSessionAction::Script(path) => write!(f, "script:{}", path.display()),
+        }
+    }
+}
+
+impl PostSessionAction {
+    /// Carries out the action after a session ended with `last_game`.
+    ///
+    /// # Returns
+    /// `false` if ROM Loader should exit, `true` to go back to the game list.
+    pub fn run(&self, last_game: &Path) -> io::Result<bool> {
+        match self {
+            PostSessionAction::Return => Ok(true),
+            PostSessionAction::Quit => Ok(false),
+            PostSessionAction::Shutdown => {
+                println!("🔌 Session over, shutting down...");
+                run_power_command(shutdown_command())?;
+                Ok(false)
+            }
+            PostSessionAction::Suspend => {
+                println!("💤 Session over, suspending...");
+                run_power_command(suspend_command())?;
+                // Execution continues here after wake-up.
+                Ok(true)
+            }
+            PostSessionAction::Script(script) => {
+                println!("📜 Session over, running {}", script.display());
+                let status = Command::new(script).arg(last_game).status()?;
+                if !status.success() {
+                    eprintln!("⚠️ Post-session script exited with {}", status);
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+fn run_power_command(mut command: Command) -> io::Result<()> {
+    let status = command.status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("{:?} exited with {}", command.get_program(), status)))
+    }
+}
+
+fn shutdown_command() -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("shutdown");
+        command.args(["/s", "/t", "0"]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.args(["-e", "tell application \"System Events\" to shut down"]);
+        command
+    } else {
+        let mut command = Command::new("systemctl");
+        command.arg("poweroff");
+        command
+    }
+}
+
+fn suspend_command() -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("rundll32.exe");
+        command.arg("powrprof.dll,SetSuspendState").args(["0", "1", "0"]);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("pmset");
+        command.arg("sleepnow");
+        command
+    } else {
+        let mut command = Command::new("systemctl");
+        command.arg("suspend");
+        command
+    }
+}
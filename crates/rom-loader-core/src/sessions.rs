@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::{atomic_file, history, interrupt};
+
+/// File name of the running-session list, stored next to the emulator configuration.
+pub const SESSIONS_FILE_NAME: &str = "sessions.json";
+
+/// An emulator started by rom-loader that was still running when last recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Process id of the emulator (or of the wrapper it was started through).
+    pub pid: u32,
+    /// Process id of the rom-loader process that started it.
+    pub loader_pid: u32,
+    /// File names the process may show up as (the started program, which may be a wrapper,
+    /// and the emulator executable), to tell it from an unrelated process that got the same
+    /// id later.
+    pub programs: Vec<String>,
+    pub emulator: String,
+    pub game: PathBuf,
+    /// Unix timestamp of the launch.
+    pub started_at: u64,
+}
+
+/// The emulators rom-loader instances have started and not yet seen exit.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionFile {
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+}
+
+impl SessionFile {
+    /// Returns the session file path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(SESSIONS_FILE_NAME)
+    }
+
+    /// Loads the session file, returning an empty list if it doesn't exist yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(SessionFile::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse session file {}: {}", path.display(), e),
+            ))
+    }
+
+    /// Saves the session file as pretty-printed JSON, removing it once no session is left.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if self.sessions.is_empty() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize sessions to JSON: {}", e),
+            ))?;
+        atomic_file::write(path, contents)
+    }
+}
+
+/// Records an emulator this process just started.
+pub fn track(path: &Path, pid: u32, programs: &[&Path], emulator: &str, game: &Path) -> io::Result<()> {
+    let mut file = SessionFile::load(path)?;
+    file.sessions.retain(|s| s.pid != pid);
+    file.sessions.push(Session {
+        pid,
+        loader_pid: std::process::id(),
+        programs: programs.iter().map(|p| p.file_name().unwrap_or_default().to_string_lossy().to_string()).collect::<BTreeSet<_>>().into_iter().collect(),
+        emulator: emulator.to_string(),
+        game: game.to_path_buf(),
+        started_at: history::now_timestamp(),
+    });
+    file.save(path)
+}
+
+/// Removes an emulator that exited from the session file.
+pub fn untrack(path: &Path, pid: u32) -> io::Result<()> {
+    let mut file = SessionFile::load(path)?;
+    file.sessions.retain(|s| s.pid != pid);
+    file.save(path)
+}
+
+/// Finds emulators left running by a rom-loader process that no longer exists, e.g. after it
+/// crashed or was killed.
+///
+/// Sessions whose emulator is gone are dropped from the file on the way; sessions of
+/// rom-loader processes that are still running are left alone.
+pub fn find_orphans(path: &Path) -> io::Result<Vec<Session>> {
+    let mut file = SessionFile::load(path)?;
+    let before = file.sessions.len();
+    file.sessions.retain(still_running);
+    if file.sessions.len() != before {
+        file.save(path)?;
+    }
+    Ok(file.sessions
+        .iter()
+        .filter(|s| s.loader_pid != std::process::id() && !is_running(s.loader_pid))
+        .cloned()
+        .collect())
+}
+
+/// Kills an orphaned emulator and removes it from the session file.
+pub fn reap(path: &Path, session: &Session) -> io::Result<()> {
+    interrupt::kill(session.pid);
+    untrack(path, session.pid)
+}
+
+/// Whether the session's emulator is still running, and not an unrelated process that got
+/// the same id later.
+///
+/// On Unix the process must have started no later than the launch (an emulator may `exec`
+/// into a program with another name); Windows has no cheap way to get the start time, so
+/// the process name is compared instead.
+fn still_running(session: &Session) -> bool {
+    if cfg!(windows) {
+        return process_name(session.pid).is_some_and(|name| session.programs.iter().any(|p| same_program(&name, p)));
+    }
+    // A second of slack for the clock ticking over between the spawn and the record.
+    process_started_at(session.pid).is_some_and(|started| started <= session.started_at + 1)
+}
+
+/// Whether a process with the given id exists.
+fn is_running(pid: u32) -> bool {
+    if cfg!(windows) {
+        process_name(pid).is_some()
+    } else {
+        process_started_at(pid).is_some()
+    }
+}
+
+/// Returns the image name of a running Windows process, or `None` if there is no such process.
+fn process_name(pid: u32) -> Option<String> {
+    let output = Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    // `"retroarch.exe","1234",...`, or an INFO line if there is no such process.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|line| line.starts_with('"'))?;
+    line.split(',').next().map(|name| name.trim_matches('"').to_string())
+}
+
+/// Compares a process image name with a program file name, ignoring case and `.exe`.
+fn same_program(process: &str, program: &str) -> bool {
+    let normalize = |name: &str| {
+        let name = name.to_lowercase();
+        name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+    };
+    !program.is_empty() && normalize(process) == normalize(program)
+}
+
+/// Returns the Unix timestamp a process started at, or `None` if there is no such process.
+fn process_started_at(pid: u32) -> Option<u64> {
+    // `etime` ([[dd-]hh:]mm:ss) is the elapsed time both procps and BSD ps know.
+    let output = Command::new("ps").args(["-o", "etime=", "-p", &pid.to_string()]).stderr(Stdio::null()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let etime = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let (days, time) = match etime.split_once('-') {
+        Some((days, time)) => (days.parse::<u64>().ok()?, time),
+        None => (0, etime.as_str()),
+    };
+    let mut seconds = 0;
+    for part in time.split(':') {
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    Some(history::now_timestamp().saturating_sub(days * 86400 + seconds))
+}
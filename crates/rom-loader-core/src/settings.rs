@@ -0,0 +1,366 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::titles::TitleRule;
+
+/// Cache directory used when `cache_dir` is not set, next to the configuration.
+pub const CACHE_DIR_NAME: &str = "cache";
+
+/// Defaults kept in the `settings` section of the configuration file, next to the emulators:
+///
+/// ```json
+/// {
+///   "settings": { "roms_dirs": ["/mnt/roms", "/media/usb/roms"], "region_priority": ["eu", "us"] },
+///   "emulators": [ ... ]
+/// }
+/// ```
+///
+/// Command-line flags win over environment variables (`ROM_LOADER_*`, see [`Settings::load`]),
+/// which win over these settings. A configuration that is just the array of emulators has no
+/// settings section and uses the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// ROM libraries; the first one that exists is used when no `--roms-dir` is given, so the
+    /// same configuration works wherever a drive happens to be mounted.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roms_dirs: Vec<PathBuf>,
+    /// Where derived data (extracted archives, downloaded art, ...) is kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+    /// Size in megabytes the cache directory is kept under; the least recently used items
+    /// are removed to make room [default: no limit, keeping only the latest preloaded image
+    /// and unpacked ROM]. `cache prune` applies it on demand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_max_mb: Option<u64>,
+    /// Directory that relative emulator and core paths start from, itself relative to the
+    /// configuration file [default: the configuration's directory], e.g. `"emulators"` for a
+    /// portable drive carrying its emulators next to the configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emulators_root: Option<PathBuf>,
+    /// Directories holding BIOS files, checked before launching systems that need them (in
+    /// addition to RetroArch's system directory for RetroArch entries).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bios_dirs: Vec<PathBuf>,
+    /// Preferred regions, most preferred first (e.g. `["eu", "us", "jp"]`), for picking
+    /// between versions of the same game.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub region_priority: Vec<String>,
+    /// Preferred languages as two-letter codes, most preferred first (e.g. `["en", "es"]`), for
+    /// picking the default among language and fan-translation variants of a game. Defaults to
+    /// the language of the `LANG` locale.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub language_priority: Vec<String>,
+    /// Built-in ROM extensions to ignore (e.g. `["bin"]` when every .bin file in the library
+    /// is a BIOS dump), without the leading dot.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_extensions: Vec<String>,
+    /// Files hashed at once by `verify`, `export` and `sync` [default: one per CPU]; a network
+    /// share may be faster with fewer. `bench hash` shows which count is fastest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash_threads: Option<usize>,
+    /// Homebrew feeds `discover homebrew` lists games from: URLs or files of JSON lists of
+    /// free games, each with a title, system and download url.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub homebrew_sources: Vec<String>,
+    #[serde(default, skip_serializing_if = "UiSettings::is_default")]
+    pub ui: UiSettings,
+    #[serde(default, skip_serializing_if = "LargeImageSettings::is_default")]
+    pub large_images: LargeImageSettings,
+    /// Cabinet buttons on a Raspberry Pi's GPIO header; builds without the `gpio` feature ignore them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpio: Option<GpioPins>,
+    /// Credentials for online metadata scrapers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scraper: Option<ScraperCredentials>,
+}
+
+/// Preferences for the game list and the interactive browser.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UiSettings {
+    /// What the interactive browser does after a session, as for `--after-session`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_session: Option<String>,
+    /// Leave arcade clones out of the game list, as if `--hide-clones` was always given.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hide_clones: bool,
+    /// Minutes a game may be played before the emulator is closed and the menu comes back, as
+    /// for `--time-limit`; for arcade cabinets at events where players take turns.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_limit_minutes: Option<u64>,
+    #[serde(default, skip_serializing_if = "ListDisplay::is_default")]
+    pub list: ListDisplay,
+}
+
+impl UiSettings {
+    fn is_default(&self) -> bool {
+        *self == UiSettings::default()
+    }
+}
+
+/// How each game in the list is shown, e.g.
+/// `{ "columns": ["system", "year", "last_played"], "title": "title", "max_width": 40 }`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ListDisplay {
+    /// Details shown after the title, in order [default: type, system, emulator].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns: Option<Vec<ListColumn>>,
+    #[serde(default, skip_serializing_if = "TitleSource::is_default")]
+    pub title: TitleSource,
+    /// chrono format of dates such as the last played one, e.g. "%d.%m.%Y" [default: %Y-%m-%d %H:%M].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Titles longer than this many characters are cut off with "…".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<usize>,
+    /// Regex rules turning file names into titles for games without a metadata title, applied
+    /// in order; `[]` keeps file names as they are [default: drop tags such as "(USA)", turn
+    /// underscores into spaces and move a trailing ", The" to the front].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_rules: Option<Vec<TitleRule>>,
+}
+
+impl ListDisplay {
+    fn is_default(&self) -> bool {
+        *self == ListDisplay::default()
+    }
+
+    /// The configured columns, or the default ones.
+    pub fn columns(&self) -> &[ListColumn] {
+        self.columns.as_deref().unwrap_or(&[ListColumn::Type, ListColumn::System, ListColumn::Emulator])
+    }
+}
+
+/// A detail shown for each game in the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListColumn {
+    /// The file extension.
+    Type,
+    /// The system id.
+    System,
+    /// The emulator the game would be launched with.
+    Emulator,
+    Year,
+    Genre,
+    Players,
+    LastPlayed,
+    /// The game's ID, which other commands accept instead of its list number.
+    Id,
+}
+
+/// What the list shows as a game's title.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleSource {
+    /// The file name, followed by the metadata title when the game is known.
+    #[default]
+    Filename,
+    /// The metadata title (or the cartridge header's), falling back to the file name cleaned
+    /// up by the title rules.
+    Title,
+}
+
+impl TitleSource {
+    fn is_default(&self) -> bool {
+        *self == TitleSource::default()
+    }
+}
+
+/// What happens before launching a big disc image, which can freeze the emulator for a long
+/// time when it is read from slow media (a NAS, a USB 2 drive, an SD card).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LargeImageSettings {
+    /// Images larger than this many megabytes ask for confirmation before launching.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm_above_mb: Option<u64>,
+    /// Copy such images into the cache directory first (with a progress bar) and launch the copy.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub preload: bool,
+}
+
+impl LargeImageSettings {
+    fn is_default(&self) -> bool {
+        *self == LargeImageSettings::default()
+    }
+}
+
+/// GPIO pins (BCM numbering) of cabinet buttons driving the interactive browser, e.g.
+/// `{ "next": 17, "previous": 27, "launch": 22, "exit": 23 }`. Any of them may be left out.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GpioPins {
+    /// Highlights the next game of the last listing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<u8>,
+    /// Highlights the previous game of the last listing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<u8>,
+    /// Launches the highlighted game.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub launch: Option<u8>,
+    /// Quits rom-loader, e.g. wired to the coin door's service switch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit: Option<u8>,
+}
+
+static LOW_MEMORY: OnceLock<bool> = OnceLock::new();
+
+/// Turns on the low-memory profile (`--low-mem`) for boards such as the Raspberry Pi Zero,
+/// trading speed and conveniences for a smaller footprint. Call it before loading the
+/// settings; only the first call has an effect.
+pub fn set_low_memory(low_memory: bool) {
+    let _ = LOW_MEMORY.set(low_memory);
+}
+
+/// Whether the low-memory profile is on.
+pub fn low_memory() -> bool {
+    LOW_MEMORY.get().copied().unwrap_or(false)
+}
+
+/// Login for a metadata scraper such as ScreenScraper.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScraperCredentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Settings {
+    /// Loads the settings section of the configuration file and applies the environment
+    /// overrides on top.
+    ///
+    /// A missing file or a configuration without a settings section gives the defaults, so
+    /// tools that don't need the emulators (such as `rom-scan`) can read the settings alone.
+    ///
+    /// Overrides: `ROM_LOADER_ROMS_DIRS` (separated like `PATH`), `ROM_LOADER_CACHE_DIR`,
+    /// `ROM_LOADER_REGION_PRIORITY` and `ROM_LOADER_LANGUAGE_PRIORITY` (comma separated), `ROM_LOADER_HIDE_CLONES` (`1`/`true` or
+    /// `0`/`false`), `ROM_LOADER_SCRAPER_USERNAME` and `ROM_LOADER_SCRAPER_PASSWORD`.
+    /// `ROM_LOADER_ROMS_DIR`, `ROM_LOADER_CONFIG` and `ROM_LOADER_AFTER_SESSION` stand in for
+    /// the command-line flags of the same name instead.
+    ///
+    /// Relative directories in the file start from the configuration's directory, so a
+    /// portable drive carrying its configuration and ROMs works wherever it is mounted.
+    ///
+    /// The low-memory profile hashes one file at a time, whatever `hash_threads` says.
+    pub fn load(config_path: &Path) -> io::Result<Self> {
+        let mut settings = Settings::load_file(config_path)?;
+        settings.resolve_relative_dirs(config_path);
+        settings.apply_env()?;
+        if low_memory() {
+            settings.hash_threads = Some(1);
+        }
+        Ok(settings)
+    }
+
+    /// Makes the relative directories of the settings start from the configuration's directory.
+    fn resolve_relative_dirs(&mut self, config_path: &Path) {
+        let config_dir = config_path.parent().unwrap_or(Path::new(""));
+        let resolve = |dir: &mut PathBuf| {
+            if dir.is_relative() {
+                *dir = config_dir.join(&*dir);
+            }
+        };
+        self.roms_dirs.iter_mut().for_each(resolve);
+        self.bios_dirs.iter_mut().for_each(resolve);
+        self.cache_dir.iter_mut().for_each(resolve);
+    }
+
+    /// Loads the settings section of the configuration file as written.
+    fn load_file(config_path: &Path) -> io::Result<Self> {
+        if !config_path.exists() {
+            return Ok(Settings::default());
+        }
+        let contents = fs::read_to_string(config_path)?;
+        let value: Value = serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse configuration {}: {}", config_path.display(), e),
+            ))?;
+        match value.get("settings") {
+            Some(settings) => Settings::deserialize(settings)
+                .map_err(|e| io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid settings in {}: {}", config_path.display(), e),
+                )),
+            None => Ok(Settings::default()),
+        }
+    }
+
+    /// Replaces settings with the values of the `ROM_LOADER_*` environment variables that are set.
+    fn apply_env(&mut self) -> io::Result<()> {
+        if let Some(dirs) = env::var_os("ROM_LOADER_ROMS_DIRS") {
+            self.roms_dirs = env::split_paths(&dirs).collect();
+        }
+        if let Some(dir) = env::var_os("ROM_LOADER_CACHE_DIR") {
+            self.cache_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(regions) = env_var("ROM_LOADER_REGION_PRIORITY")? {
+            self.region_priority = split_list(&regions);
+        }
+        if let Some(languages) = env_var("ROM_LOADER_LANGUAGE_PRIORITY")? {
+            self.language_priority = split_list(&languages);
+        }
+        if let Some(value) = env_var("ROM_LOADER_HIDE_CLONES")? {
+            self.ui.hide_clones = match value.to_lowercase().as_str() {
+                "1" | "true" | "yes" => true,
+                "0" | "false" | "no" | "" => false,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("ROM_LOADER_HIDE_CLONES must be true or false, not '{}'", value),
+                    ))
+                }
+            };
+        }
+        let username = env_var("ROM_LOADER_SCRAPER_USERNAME")?;
+        let password = env_var("ROM_LOADER_SCRAPER_PASSWORD")?;
+        if username.is_some() || password.is_some() {
+            let current = self.scraper.take();
+            self.scraper = Some(ScraperCredentials {
+                username: username.or_else(|| current.as_ref().map(|c| c.username.clone())).unwrap_or_default(),
+                password: password.or_else(|| current.map(|c| c.password)).unwrap_or_default(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the cache directory: `cache_dir`, or `cache` next to the configuration.
+    pub fn cache_dir_for(&self, config_path: &Path) -> PathBuf {
+        self.cache_dir.clone().unwrap_or_else(|| config_path.with_file_name(CACHE_DIR_NAME))
+    }
+
+    /// Returns the directory relative emulator paths start from: `emulators_root` (relative to
+    /// the configuration's directory), or the configuration's directory.
+    pub fn emulators_root_for(&self, config_path: &Path) -> PathBuf {
+        let config_dir = config_path.parent().unwrap_or(Path::new(""));
+        match &self.emulators_root {
+            Some(root) => config_dir.join(root),
+            None => config_dir.to_path_buf(),
+        }
+    }
+
+    /// Returns the first configured ROMs directory that exists.
+    pub fn default_roms_dir(&self) -> Option<&Path> {
+        self.roms_dirs.iter().map(PathBuf::as_path).find(|dir| dir.is_dir())
+    }
+}
+
+/// Splits a comma-separated list, dropping empty items.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect()
+}
+
+/// Reads an environment variable, treating an unset variable as `None`.
+fn env_var(name: &str) -> io::Result<Option<String>> {
+    match env::var(name) {
+        Ok(value) => Ok(Some(value)),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(_)) => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not valid UTF-8", name),
+        )),
+    }
+}
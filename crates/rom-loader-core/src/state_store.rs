@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::atomic_file;
+
+/// File name of the persistent state store, stored next to the emulator configuration.
+pub const STATE_FILE_NAME: &str = "state.json";
+
+/// Number of consecutive overrides to the same emulator before it becomes the default.
+pub const LEARN_AFTER_OVERRIDES: u32 = 2;
+
+/// An emulator the user keeps choosing over the suggested one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedPreference {
+    pub emulator: String,
+    /// How many times in a row the user picked this emulator over the suggestion.
+    pub streak: u32,
+}
+
+impl LearnedPreference {
+    /// Whether the preference has been confirmed often enough to be used as the default.
+    pub fn is_learned(&self) -> bool {
+        self.streak >= LEARN_AFTER_OVERRIDES
+    }
+}
+
+/// Persistent application state that is not part of the user-maintained configuration.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StateStore {
+    /// Learned emulator preferences keyed by ROM path.
+    #[serde(default)]
+    pub rom_preferences: HashMap<String, LearnedPreference>,
+    /// Learned emulator preferences keyed by system id.
+    #[serde(default)]
+    pub system_preferences: HashMap<String, LearnedPreference>,
+    /// ROM paths hidden from the game list (prototypes, bad dumps, BIOS files, ...).
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub hidden: BTreeSet<String>,
+}
+
+impl StateStore {
+    /// Returns the state file path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(STATE_FILE_NAME)
+    }
+
+    /// Loads the state store, returning an empty store if the file does not exist yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(StateStore::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse state file {}: {}", path.display(), e),
+            ))
+    }
+
+    /// Saves the state store as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize state to JSON: {}", e),
+            ))?;
+        atomic_file::write_with_backups(path, contents)
+    }
+
+    /// Returns the learned emulator for a ROM, falling back to the one learned for its system.
+    pub fn preferred_emulator(&self, rom: &Path, system_id: Option<&str>) -> Option<&str> {
+        let rom_key = rom.to_string_lossy();
+        self.rom_preferences
+            .get(rom_key.as_ref())
+            .filter(|p| p.is_learned())
+            .or_else(|| {
+                system_id
+                    .and_then(|id| self.system_preferences.get(id))
+                    .filter(|p| p.is_learned())
+            })
+            .map(|p| p.emulator.as_str())
+    }
+
+    /// Whether a ROM is hidden from the game list.
+    pub fn is_hidden(&self, rom: &Path) -> bool {
+        self.hidden.contains(rom.to_string_lossy().as_ref())
+    }
+
+    /// Hides a ROM from the game list, or shows it again.
+    ///
+    /// # Returns
+    /// `false` if the ROM already was in the requested state.
+    pub fn set_hidden(&mut self, rom: &Path, hidden: bool) -> bool {
+        let key = rom.to_string_lossy().to_string();
+        if hidden {
+            self.hidden.insert(key)
+        } else {
+            self.hidden.remove(&key)
+        }
+    }
+
+    /// Drops everything stored about a ROM, e.g. after it was deleted.
+    pub fn forget(&mut self, rom: &Path) {
+        let key = rom.to_string_lossy();
+        self.rom_preferences.remove(key.as_ref());
+        self.hidden.remove(key.as_ref());
+    }
+
+    /// Moves everything stored about a ROM to its new path, e.g. after it was renamed.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        let (from, to) = (from.to_string_lossy(), to.to_string_lossy().to_string());
+        if let Some(preference) = self.rom_preferences.remove(from.as_ref()) {
+            self.rom_preferences.insert(to.clone(), preference);
+        }
+        if self.hidden.remove(from.as_ref()) {
+            self.hidden.insert(to);
+        }
+    }
+
+    /// Makes an emulator the default for a ROM right away, as when the user asks to remember
+    /// the emulator they picked.
+    pub fn remember_emulator(&mut self, rom: &Path, emulator: &str) {
+        self.rom_preferences.insert(rom.to_string_lossy().to_string(), LearnedPreference {
+            emulator: emulator.to_string(),
+            streak: LEARN_AFTER_OVERRIDES,
+        });
+    }
+
+    /// Records that the user launched a ROM with an emulator other than the suggested one.
+    ///
+    /// Overrides are tracked both for the ROM and for its system; picking a different
+    /// emulator than last time restarts the streak.
+    pub fn record_override(&mut self, rom: &Path, system_id: Option<&str>, emulator: &str) {
+        bump_streak(&mut self.rom_preferences, rom.to_string_lossy().to_string(), emulator);
+        if let Some(id) = system_id {
+            bump_streak(&mut self.system_preferences, id.to_string(), emulator);
+        }
+    }
+
+    /// Records that the user launched a ROM with the suggested emulator, which ends the streak
+    /// of overrides not yet learned for the ROM and its system.
+    ///
+    /// # Returns
+    /// `true` if a streak was reset and the store needs saving.
+    pub fn record_accepted(&mut self, rom: &Path, system_id: Option<&str>) -> bool {
+        let mut reset = reset_streak(&mut self.rom_preferences, rom.to_string_lossy().as_ref());
+        if let Some(id) = system_id {
+            reset |= reset_streak(&mut self.system_preferences, id);
+        }
+        reset
+    }
+}
+
+/// Drops a preference that has not been learned yet; learned ones stay until overridden.
+fn reset_streak(preferences: &mut HashMap<String, LearnedPreference>, key: &str) -> bool {
+    if preferences.get(key).is_some_and(|p| !p.is_learned()) {
+        preferences.remove(key);
+        return true;
+    }
+    false
+}
+
+fn bump_streak(preferences: &mut HashMap<String, LearnedPreference>, key: String, emulator: &str) {
+    let preference = preferences.entry(key).or_insert_with(|| LearnedPreference {
+        emulator: emulator.to_string(),
+        streak: 0,
+    });
+    if preference.emulator == emulator {
+        preference.streak += 1;
+    } else {
+        preference.emulator = emulator.to_string();
+        preference.streak = 1;
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use walkdir::WalkDir;
+
+use crate::library_bundle::{BundledGame, LibraryBundle};
+
+/// Which way `sync` copies games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SyncDirection {
+    /// Copy local games the other library is missing.
+    Push,
+    /// Copy games from the other library that are missing here.
+    Pull,
+    /// Both.
+    Both,
+}
+
+/// The other library of a sync: a directory (e.g. a mounted handheld SD card) or an SSH
+/// destination written like scp's `[user@]host:path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remote {
+    Local(PathBuf),
+    Ssh { host: String, path: String },
+}
+
+impl Remote {
+    /// Parses a remote; `host:path` is taken for SSH unless the part before the colon looks
+    /// like a path or a Windows drive letter.
+    pub fn parse(remote: &str) -> Self {
+        match remote.split_once(':') {
+            Some((host, path)) if host.len() > 1 && !host.contains(['/', '\\']) => {
+                Remote::Ssh { host: host.to_string(), path: path.to_string() }
+            }
+            _ => Remote::Local(PathBuf::from(remote)),
+        }
+    }
+}
+
+impl fmt::Display for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Remote::Local(path) => write!(f, "{}", path.display()),
+            Remote::Ssh { host, path } => write!(f, "{}:{}", host, path),
+        }
+    }
+}
+
+/// Games to copy, as paths relative to the ROMs directories.
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    /// Local games the other library is missing.
+    pub push: Vec<PathBuf>,
+    /// Games of the other library missing here.
+    pub pull: Vec<PathBuf>,
+}
+
+/// Compares two portable library indexes and lists the games each side is missing.
+///
+/// Games are matched by CRC32 and size, so a game renamed or sorted into another folder on one
+/// side is not copied again; games without a hash (game directories) are matched by path.
+/// Missing games keep their relative path on the other side and take their companion files
+/// (MSU-1 packs, soft patches) along.
+pub fn plan(local: &LibraryBundle, remote: &LibraryBundle, direction: SyncDirection) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    if direction != SyncDirection::Pull {
+        plan.push = missing(&local.games, &remote.games);
+    }
+    if direction != SyncDirection::Push {
+        plan.pull = missing(&remote.games, &local.games);
+    }
+    plan
+}
+
+/// Returns the games of `from` that `to` doesn't have.
+fn missing(from: &[BundledGame], to: &[BundledGame]) -> Vec<PathBuf> {
+    let hashes: HashSet<(&str, u64)> = to.iter().filter_map(|g| Some((g.crc32.as_deref()?, g.size))).collect();
+    let paths: HashSet<&Path> = to.iter().map(|g| g.path.as_path()).collect();
+    from.iter()
+        .filter(|game| match &game.crc32 {
+            Some(crc32) => !hashes.contains(&(crc32.as_str(), game.size)),
+            None => !paths.contains(game.path.as_path()),
+        })
+        .flat_map(|game| std::iter::once(&game.path).chain(&game.companions).cloned())
+        .collect()
+}
+
+/// Copies one game between the local ROMs directory and the remote, keeping its relative path.
+///
+/// SSH remotes are reached with the system's `ssh` and `scp`, so keys and `~/.ssh/config`
+/// apply as usual. Like local copies, an existing file or directory on either side is never
+/// overwritten.
+pub fn copy_game(roms_dir: &Path, remote: &Remote, relative: &Path, push: bool) -> io::Result<()> {
+    // Paths come from an index that may have been made elsewhere; never leave the library.
+    if relative.as_os_str().is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Refusing to copy suspicious path '{}'", relative.display()),
+        ));
+    }
+    let local = roms_dir.join(relative);
+    match remote {
+        Remote::Local(remote_dir) => {
+            let remote = remote_dir.join(relative);
+            if push { copy_recursive(&local, &remote) } else { copy_recursive(&remote, &local) }
+        }
+        Remote::Ssh { host, path } => {
+            let remote_path = format!("{}/{}", path.trim_end_matches('/'), slash_path(relative));
+            // -O picks the original scp protocol, where the remote shell expands the path; quoted,
+            // names with spaces or quotes reach the remote unchanged.
+            let remote_arg = format!("{}:{}", host, shell_quote(&remote_path));
+            if push {
+                if remote_exists(host, &remote_path)? {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{}:{} already exists", host, remote_path),
+                    ));
+                }
+                if let Some(parent) = remote_path.rsplit_once('/').map(|(parent, _)| parent).filter(|p| !p.is_empty()) {
+                    run(Command::new("ssh").arg(host).arg(format!("mkdir -p {}", shell_quote(parent))))?;
+                }
+                run(Command::new("scp").arg("-rpqO").arg(&local).arg(remote_arg))
+            } else {
+                if local.exists() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} already exists", local.display()),
+                    ));
+                }
+                if let Some(parent) = local.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                run(Command::new("scp").arg("-rpqO").arg(remote_arg).arg(&local))
+            }
+        }
+    }
+}
+
+/// Copies a file, or a game directory with everything in it, creating parent directories.
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    if to.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", to.display()),
+        ));
+    }
+    if from.is_file() {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from, to)?;
+        return Ok(());
+    }
+    for entry in WalkDir::new(from) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from).unwrap_or(entry.path()));
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a file or directory exists at `path` on an SSH host.
+fn remote_exists(host: &str, path: &str) -> io::Result<bool> {
+    let status = Command::new("ssh")
+        .arg(host)
+        .arg(format!("test -e {}", shell_quote(path)))
+        .status()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run ssh: {}", e)))?;
+    // `test` exits with 1 when the path doesn't exist; ssh itself fails with 255.
+    match status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => Err(io::Error::other(format!("ssh failed ({})", status))),
+    }
+}
+
+fn run(command: &mut Command) -> io::Result<()> {
+    let program = command.get_program().to_string_lossy().to_string();
+    let status = command.status()
+        .map_err(|e| io::Error::new(e.kind(), format!("Could not run {}: {}", program, e)))?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed ({})", program, status)));
+    }
+    Ok(())
+}
+
+/// Joins path components with forward slashes, as the remote side expects.
+fn slash_path(path: &Path) -> String {
+    path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// Quotes a string for a POSIX shell.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
@@ -0,0 +1,123 @@
+use std::sync::OnceLock;
+
+/// Extensions turned off by the `disabled_extensions` setting, see [`disable_extensions`].
+static DISABLED_EXTENSIONS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Describes a console or platform the loader knows how to recognize.
+#[derive(Debug)]
+pub struct System {
+    /// Short identifier used in output and configuration (e.g., "snes").
+    pub id: &'static str,
+    /// Human readable name (e.g., "Super Nintendo").
+    pub name: &'static str,
+    /// File extensions that belong to this system, without the leading dot.
+    pub extensions: &'static [&'static str],
+    /// MAME short name for the system, if MAME can run it as a console.
+    pub mame_system: Option<&'static str>,
+    /// RetroArch core suggested when no emulator has been configured.
+    pub default_core: Option<&'static str>,
+    /// Marker paths that identify a directory as a single game of this system.
+    ///
+    /// Patterns are relative to the candidate directory and matched case-insensitively;
+    /// the last component may be `*.ext` to match any file with that extension.
+    pub directory_markers: &'static [&'static str],
+}
+
+/// Built-in registry of known systems.
+///
+/// The order matters for extensions shared by several systems: the first system
+/// listing an extension is the one it resolves to.
+pub const SYSTEMS: &[System] = &[
+    System { id: "nes", name: "Nintendo Entertainment System", extensions: &["nes"], mame_system: Some("nes"), default_core: Some("fceumm_libretro"), directory_markers: &[] },
+    System { id: "snes", name: "Super Nintendo", extensions: &["snes", "smc", "sfc"], mame_system: Some("snes"), default_core: Some("snes9x_libretro"), directory_markers: &[] },
+    System { id: "gb", name: "Game Boy", extensions: &["gb"], mame_system: Some("gameboy"), default_core: Some("gambatte_libretro"), directory_markers: &[] },
+    System { id: "gba", name: "Game Boy Advance", extensions: &["gba"], mame_system: Some("gba"), default_core: Some("mgba_libretro"), directory_markers: &[] },
+    System { id: "vb", name: "Virtual Boy", extensions: &["vb"], mame_system: Some("vboy"), default_core: Some("mednafen_vb_libretro"), directory_markers: &[] },
+    System { id: "n64", name: "Nintendo 64", extensions: &["n64", "z64", "v64"], mame_system: Some("n64"), default_core: Some("mupen64plus_next_libretro"), directory_markers: &[] },
+    System { id: "psx", name: "PlayStation", extensions: &["ps1"], mame_system: Some("psx"), default_core: Some("pcsx_rearmed_libretro"), directory_markers: &[] },
+    System { id: "genesis", name: "Sega Genesis / Mega Drive", extensions: &["md", "gen", "bin"], mame_system: Some("genesis"), default_core: Some("genesis_plus_gx_libretro"), directory_markers: &[] },
+    System { id: "segacd", name: "Sega CD / Mega-CD", extensions: &[], mame_system: Some("segacd"), default_core: Some("genesis_plus_gx_libretro"), directory_markers: &[] },
+    System { id: "32x", name: "Sega 32X", extensions: &["32x"], mame_system: Some("32x"), default_core: Some("picodrive_libretro"), directory_markers: &[] },
+    System { id: "sms", name: "Sega Master System", extensions: &["sms"], mame_system: Some("sms"), default_core: Some("genesis_plus_gx_libretro"), directory_markers: &[] },
+    System { id: "gamegear", name: "Sega Game Gear", extensions: &["gg"], mame_system: Some("gamegear"), default_core: Some("genesis_plus_gx_libretro"), directory_markers: &[] },
+    System { id: "a2600", name: "Atari 2600", extensions: &["a26"], mame_system: Some("a2600"), default_core: Some("stella_libretro"), directory_markers: &[] },
+    System { id: "a5200", name: "Atari 5200", extensions: &["a52"], mame_system: Some("a5200"), default_core: Some("a5200_libretro"), directory_markers: &[] },
+    System { id: "a7800", name: "Atari 7800", extensions: &["a78"], mame_system: Some("a7800"), default_core: Some("prosystem_libretro"), directory_markers: &[] },
+    System { id: "lynx", name: "Atari Lynx", extensions: &["lnx"], mame_system: Some("lynx"), default_core: Some("handy_libretro"), directory_markers: &[] },
+    System { id: "pce", name: "PC Engine / TurboGrafx-16", extensions: &["pce"], mame_system: Some("pce"), default_core: Some("mednafen_pce_fast_libretro"), directory_markers: &[] },
+    System { id: "sgx", name: "PC Engine SuperGrafx", extensions: &["sgx"], mame_system: Some("sgx"), default_core: Some("mednafen_supergrafx_libretro"), directory_markers: &[] },
+    System { id: "wswan", name: "WonderSwan", extensions: &["ws"], mame_system: Some("wswan"), default_core: Some("mednafen_wswan_libretro"), directory_markers: &[] },
+    System { id: "wscolor", name: "WonderSwan Color", extensions: &["wsc"], mame_system: Some("wscolor"), default_core: Some("mednafen_wswan_libretro"), directory_markers: &[] },
+    System { id: "ngp", name: "Neo Geo Pocket", extensions: &["ngp"], mame_system: Some("ngp"), default_core: Some("mednafen_ngp_libretro"), directory_markers: &[] },
+    System { id: "ngpc", name: "Neo Geo Pocket Color", extensions: &["ngc"], mame_system: Some("ngpc"), default_core: Some("mednafen_ngp_libretro"), directory_markers: &[] },
+    System { id: "coleco", name: "ColecoVision", extensions: &["col"], mame_system: Some("coleco"), default_core: Some("gearcoleco_libretro"), directory_markers: &[] },
+    System { id: "intv", name: "Intellivision", extensions: &["int"], mame_system: Some("intv"), default_core: Some("freeintv_libretro"), directory_markers: &[] },
+    System { id: "dos", name: "MS-DOS", extensions: &[], mame_system: None, default_core: Some("dosbox_pure_libretro"), directory_markers: &["dosbox.conf"] },
+    System { id: "scummvm", name: "ScummVM", extensions: &["scummvm"], mame_system: None, default_core: Some("scummvm_libretro"), directory_markers: &["*.scummvm"] },
+    System { id: "ps3", name: "PlayStation 3", extensions: &[], mame_system: None, default_core: None, directory_markers: &["PS3_GAME/USRDIR/EBOOT.BIN", "USRDIR/EBOOT.BIN"] },
+    System { id: "wiiu", name: "Wii U", extensions: &["wud", "wux", "wua"], mame_system: None, default_core: None, directory_markers: &["code/*.rpx"] },
+    System { id: "audio", name: "Game music rips", extensions: &["nsf", "nsfe", "spc", "gbs", "vgm", "vgz", "gym", "hes", "kss", "ay", "sap"], mame_system: None, default_core: Some("gme_libretro"), directory_markers: &[] },
+    System { id: "arcade", name: "Arcade", extensions: &["zip", "7z"], mame_system: None, default_core: Some("mame_libretro"), directory_markers: &[] },
+];
+
+/// Stops treating the given extensions as ROMs for the rest of the process, e.g. `bin` for a
+/// library whose .bin files are all BIOS dumps.
+///
+/// Only the first call has an effect; binaries call it once with the settings they loaded.
+pub fn disable_extensions(extensions: &[String]) {
+    let extensions = extensions.iter().map(|ext| ext.trim_start_matches('.').to_lowercase()).collect();
+    let _ = DISABLED_EXTENSIONS.set(extensions);
+}
+
+/// Whether the extension was turned off with [`disable_extensions`].
+pub fn is_disabled(extension: &str) -> bool {
+    DISABLED_EXTENSIONS.get().is_some_and(|disabled| disabled.iter().any(|ext| ext.eq_ignore_ascii_case(extension)))
+}
+
+/// Returns every enabled extension known to the registry, in registry order and without
+/// duplicates.
+pub fn supported_extensions() -> Vec<&'static str> {
+    let mut extensions: Vec<&'static str> = Vec::new();
+    for system in SYSTEMS {
+        for ext in system.extensions {
+            if !extensions.contains(ext) && !is_disabled(ext) {
+                extensions.push(ext);
+            }
+        }
+    }
+    extensions
+}
+
+/// Console whose sound chip a music rip format was made for, by extension (case-insensitive).
+///
+/// # Returns
+/// `None` for extensions that aren't music rips, and for formats that span several machines:
+/// VGM (Sega, NEC, arcade sound chips), KSS (MSX and Sega 8-bit), AY and SAP (home computers).
+pub fn music_source(extension: &str) -> Option<&'static str> {
+    let source = match extension.to_lowercase().as_str() {
+        "nsf" | "nsfe" => "nes",
+        "spc" => "snes",
+        "gbs" => "gb",
+        "gym" => "genesis",
+        "hes" => "pce",
+        _ => return None,
+    };
+    Some(source)
+}
+
+/// Looks up a system by its short identifier.
+pub fn find_by_id(id: &str) -> Option<&'static System> {
+    SYSTEMS.iter().find(|system| system.id.eq_ignore_ascii_case(id))
+}
+
+/// Looks up the system a file extension belongs to (case-insensitive).
+///
+/// Disabled extensions belong to no system.
+pub fn find_by_extension(extension: &str) -> Option<&'static System> {
+    if is_disabled(extension) {
+        return None;
+    }
+    SYSTEMS
+        .iter()
+        .find(|system| system.extensions.iter().any(|ext| ext.eq_ignore_ascii_case(extension)))
+}
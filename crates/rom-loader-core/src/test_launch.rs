@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::interrupt;
+
+/// Folder next to the configuration holding the homebrew ROMs `core-smoke-test` boots, one
+/// per system.
+pub const SMOKE_TEST_DIR_NAME: &str = "smoke_test_roms";
+
+/// Name of the built-in NES test ROM written into the smoke test folder.
+const BUILTIN_NES_ROM_NAME: &str = "rom_loader_test.nes";
+
+/// How often a test-launched emulator is polled for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Number of stderr lines kept in the report for emulators that exited early.
+const STDERR_TAIL_LINES: usize = 5;
+
+/// How a test launch ended.
+#[derive(Debug, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum TestOutcome {
+    /// Still running when the time was up, so it was killed: the game most likely started.
+    Running,
+    /// Exited on its own before the time was up, which usually means the ROM didn't load.
+    ExitedEarly { exit_code: Option<i32>, stderr_tail: String },
+    /// The emulator could not be started at all.
+    FailedToStart { error: String },
+    /// No emulator is configured for the ROM, so it was not tested.
+    NoEmulator,
+}
+
+impl TestOutcome {
+    /// Whether this outcome should be listed in the broken-ROM report.
+    pub fn is_broken(&self) -> bool {
+        matches!(self, TestOutcome::ExitedEarly { .. } | TestOutcome::FailedToStart { .. })
+    }
+}
+
+/// One line of the test-launch report.
+#[derive(Debug, Serialize)]
+pub struct TestResult {
+    pub rom: PathBuf,
+    pub emulator: Option<String>,
+    #[serde(flatten)]
+    pub outcome: TestOutcome,
+}
+
+/// Spawns the command, waits up to `seconds`, and kills the emulator if it is still running.
+///
+/// The emulator's stdout is discarded and its stderr captured to a temporary file, so that a
+/// chatty emulator can never block on a full pipe while we wait.
+pub fn run_test(mut command: Command, seconds: u64) -> TestOutcome {
+    let stderr_path = std::env::temp_dir().join(format!("rom_loader_test_launch_{}.log", std::process::id()));
+    let stderr_file = match File::create(&stderr_path) {
+        Ok(file) => file,
+        Err(e) => return TestOutcome::FailedToStart { error: format!("Could not create log file: {}", e) },
+    };
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::from(stderr_file));
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => return TestOutcome::FailedToStart { error: e.to_string() },
+    };
+    interrupt::set_child(child.id());
+
+    let deadline = Instant::now() + Duration::from_secs(seconds);
+    let outcome = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                break TestOutcome::ExitedEarly {
+                    exit_code: status.code(),
+                    stderr_tail: read_tail(&stderr_path),
+                };
+            }
+            // Ctrl-C ends the wait early; the emulator is stopped either way.
+            Ok(None) if Instant::now() >= deadline || interrupt::requested() => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break TestOutcome::Running;
+            }
+            Ok(None) => thread::sleep(POLL_INTERVAL),
+            Err(e) => break TestOutcome::FailedToStart { error: e.to_string() },
+        }
+    };
+    interrupt::set_child(0);
+    let _ = fs::remove_file(&stderr_path);
+    outcome
+}
+
+fn read_tail(path: &Path) -> String {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let lines: Vec<&str> = contents.lines().collect();
+    lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n")
+}
+
+/// Writes the results as pretty-printed JSON.
+pub fn write_report(results: &[TestResult], path: &Path) -> io::Result<()> {
+    let contents = serde_json::to_string_pretty(results)
+        .map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Failed to serialize test-launch report: {}", e),
+        ))?;
+    fs::write(path, contents)
+}
+
+/// Returns the folder of smoke test ROMs that belongs to a configuration.
+pub fn smoke_test_dir(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(SMOKE_TEST_DIR_NAME)
+}
+
+/// Writes the built-in test ROMs into the smoke test folder, unless they are there already.
+///
+/// Only the NES has one: a 24 KB ROM whose program sets up the CPU and loops forever, which
+/// any NES emulator boots to a black screen. Other systems need a homebrew ROM put in the
+/// folder.
+pub fn write_builtin_roms(dir: &Path) -> io::Result<()> {
+    let path = dir.join(BUILTIN_NES_ROM_NAME);
+    if path.exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(dir)?;
+    // iNES header: one 16 KB program bank, one 8 KB graphics bank, mapper 0.
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut program = vec![0xEA; 16 * 1024];
+    // At $C000: SEI; CLD; loop: JMP loop; and an RTI for interrupts at $C005.
+    program[..6].copy_from_slice(&[0x78, 0xD8, 0x4C, 0x02, 0xC0, 0x40]);
+    // NMI, reset and IRQ vectors at $FFFA.
+    program[0x3FFA..].copy_from_slice(&[0x05, 0xC0, 0x00, 0xC0, 0x05, 0xC0]);
+    rom.extend(program);
+    rom.extend(vec![0; 8 * 1024]);
+    fs::write(path, rom)
+}
+
+/// Returns a wrapper that gives the emulator a virtual display when there is no real one
+/// (`xvfb-run` on a headless Linux machine), so smoke tests can run over SSH or in CI.
+pub fn headless_wrapper() -> Option<Command> {
+    if !cfg!(all(unix, not(target_os = "macos"))) || std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        return None;
+    }
+    let found = std::env::var_os("PATH").is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join("xvfb-run").is_file()));
+    found.then(|| {
+        let mut wrapper = Command::new("xvfb-run");
+        wrapper.arg("-a");
+        wrapper
+    })
+}
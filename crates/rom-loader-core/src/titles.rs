@@ -0,0 +1,71 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Rules used when `ui.list.title_rules` is not set: underscores become spaces, tags such as
+/// `(USA) (Rev 1)` or `[!]` are removed, and "Legend of Zelda, The" becomes "The Legend of Zelda".
+const DEFAULT_RULES: &[(&str, &str)] = &[
+    (r"_", " "),
+    (r"\s*(\([^)]*\)|\[[^\]]*\])", ""),
+    (r"^(.+?), (The|A|An)( - .*)?$", "$2 $1$3"),
+];
+
+/// A regex replacement applied to file names to turn them into display titles, e.g.
+/// `{ "pattern": "\\s*\\(Rev \\d+\\)", "replace": "" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TitleRule {
+    /// Regular expression (Rust `regex` syntax) to look for.
+    pub pattern: String,
+    /// Replacement for every match; `$1` etc. insert capture groups.
+    #[serde(default)]
+    pub replace: String,
+}
+
+/// Turns ROM file names like `Legend_of_Zelda,_The_(USA)_(Rev_1).sfc` into titles like
+/// `The Legend of Zelda`.
+#[derive(Debug)]
+pub struct TitleCleaner {
+    rules: Vec<(Regex, String)>,
+}
+
+impl TitleCleaner {
+    /// Compiles the configured rules, or the default ones if none are configured.
+    ///
+    /// Rules with an invalid pattern are reported and skipped.
+    pub fn new(rules: Option<&[TitleRule]>) -> Self {
+        let rules = match rules {
+            Some(rules) => rules
+                .iter()
+                .filter_map(|rule| match Regex::new(&rule.pattern) {
+                    Ok(regex) => Some((regex, rule.replace.clone())),
+                    Err(e) => {
+                        // The parse error spans several lines (pattern, marker, message); keep the message.
+                        let message = e.to_string().lines().last().unwrap_or_default().trim_start_matches("error: ").to_string();
+                        eprintln!("⚠️ Ignoring the title rule '{}': {}", rule.pattern, message);
+                        None
+                    }
+                })
+                .collect(),
+            None => DEFAULT_RULES
+                .iter()
+                .map(|(pattern, replace)| (Regex::new(pattern).expect("default title rules are valid"), replace.to_string()))
+                .collect(),
+        };
+        TitleCleaner { rules }
+    }
+
+    /// Applies the rules, in order, to a file name without its extension.
+    ///
+    /// # Returns
+    /// The cleaned title with runs of whitespace collapsed, or the plain stem if the rules
+    /// leave nothing.
+    pub fn clean(&self, file_name: &str) -> String {
+        let stem = Path::new(file_name).file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let mut title = stem.clone();
+        for (regex, replace) in &self.rules {
+            title = regex.replace_all(&title, replace.as_str()).into_owned();
+        }
+        let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+        if title.is_empty() { stem } else { title }
+    }
+}
@@ -0,0 +1,167 @@
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Prefix of the archive comment that marks a TorrentZip-normalized zip.
+const TORRENTZIP_COMMENT_PREFIX: &str = "TORRENTZIPPED-";
+/// DOS time and date every TorrentZip entry carries (1996-12-24 23:32:00).
+const TORRENTZIP_DOS_TIME: u16 = 0xBC00;
+const TORRENTZIP_DOS_DATE: u16 = 0x2198;
+/// General purpose flag TorrentZip sets: "maximum compression".
+const TORRENTZIP_FLAGS: u16 = 0x0002;
+/// Signature of the end of central directory record.
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+
+/// Checks whether a zip file is TorrentZip-normalized.
+///
+/// A TorrentZip archive ends with the comment `TORRENTZIPPED-XXXXXXXX`, where the hex value is
+/// the CRC32 of its central directory, so any change after normalization is detected.
+pub fn is_torrentzipped(path: &Path) -> io::Result<bool> {
+    // Only the tail (end record plus comment) and the central directory are read.
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let tail_len = file_len.min(22 + u16::MAX as u64);
+    file.seek(SeekFrom::Start(file_len - tail_len))?;
+    let mut tail = vec![0u8; tail_len as usize];
+    file.read_exact(&mut tail)?;
+
+    let Some(eocd) = find_eocd(&tail) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a zip file", path.display()),
+        ));
+    };
+    let cd_size = read_u32(&tail, eocd + 12) as usize;
+    let cd_offset = read_u32(&tail, eocd + 16) as u64;
+    let comment_len = read_u16(&tail, eocd + 20) as usize;
+    let comment = tail.get(eocd + 22..eocd + 22 + comment_len).unwrap_or_default();
+    if !comment.starts_with(TORRENTZIP_COMMENT_PREFIX.as_bytes()) || cd_offset + cd_size as u64 > file_len {
+        return Ok(false);
+    }
+    let mut central_directory = vec![0u8; cd_size];
+    file.seek(SeekFrom::Start(cd_offset))?;
+    file.read_exact(&mut central_directory)?;
+
+    let expected = format!("{}{:08X}", TORRENTZIP_COMMENT_PREFIX, crc32fast::hash(&central_directory));
+    Ok(comment == expected.as_bytes())
+}
+
+/// Rewrites a zip file in TorrentZip form: entries sorted by lower-case name, deflated at
+/// maximum compression, with fixed timestamps, no extra fields and the TorrentZip comment.
+///
+/// The new archive is written next to the original and only replaces it once complete.
+pub fn rezip(path: &Path) -> io::Result<()> {
+    // TorrentZip is defined by zlib's output at level 9; flate2's default miniz backend
+    // compresses differently, so the archive would not match other TorrentZip tools.
+    if !cfg!(feature = "zlib") {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "this build of rom-loader can't re-zip archives (it needs the zlib feature)",
+        ));
+    }
+    let mut archive = ZipArchive::new(File::open(path)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Failed to read {}: {}", path.display(), e)))?;
+
+    let mut entries = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        entries.push((file.name().replace('\\', "/"), data));
+    }
+    entries.sort_by_key(|(name, _)| name.to_lowercase());
+    if entries.len() > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} has too many files to TorrentZip (zip64 is not supported)", path.display()),
+        ));
+    }
+
+    let mut output = Vec::new();
+    let mut central_directory = Vec::new();
+    for (name, data) in &entries {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(data)?;
+        let compressed = encoder.finish()?;
+        let crc = crc32fast::hash(data);
+        let (Ok(compressed_size), Ok(size), Ok(offset)) =
+            (u32::try_from(compressed.len()), u32::try_from(data.len()), u32::try_from(output.len()))
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} is too large to TorrentZip (zip64 is not supported)", path.display()),
+            ));
+        };
+
+        // Local file header
+        output.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        write_entry_fields(&mut output, crc, compressed_size, size, name);
+        output.extend_from_slice(name.as_bytes());
+        output.extend_from_slice(&compressed);
+
+        // Central directory header
+        central_directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // version made by
+        write_entry_fields(&mut central_directory, crc, compressed_size, size, name);
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        central_directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let cd_offset = output.len() as u32;
+    let comment = format!("{}{:08X}", TORRENTZIP_COMMENT_PREFIX, crc32fast::hash(&central_directory));
+    output.extend_from_slice(&central_directory);
+    output.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+    output.extend_from_slice(&0u16.to_le_bytes()); // this disk
+    output.extend_from_slice(&0u16.to_le_bytes()); // disk with the central directory
+    output.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    output.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    output.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    output.extend_from_slice(&cd_offset.to_le_bytes());
+    output.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+    output.extend_from_slice(comment.as_bytes());
+
+    let temp_path = path.with_extension("torrentzip.tmp");
+    fs::write(&temp_path, &output)?;
+    fs::rename(&temp_path, path)
+}
+
+/// Writes the header fields shared by local and central directory entries, from
+/// "version needed" through the extra field length.
+fn write_entry_fields(buffer: &mut Vec<u8>, crc: u32, compressed_size: u32, size: u32, name: &str) {
+    buffer.extend_from_slice(&20u16.to_le_bytes()); // version needed to extract
+    buffer.extend_from_slice(&TORRENTZIP_FLAGS.to_le_bytes());
+    buffer.extend_from_slice(&8u16.to_le_bytes()); // deflate
+    buffer.extend_from_slice(&TORRENTZIP_DOS_TIME.to_le_bytes());
+    buffer.extend_from_slice(&TORRENTZIP_DOS_DATE.to_le_bytes());
+    buffer.extend_from_slice(&crc.to_le_bytes());
+    buffer.extend_from_slice(&compressed_size.to_le_bytes());
+    buffer.extend_from_slice(&size.to_le_bytes());
+    buffer.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    buffer.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+}
+
+/// Finds the end of central directory record, searching backwards past a possible comment.
+fn find_eocd(bytes: &[u8]) -> Option<usize> {
+    let min_start = bytes.len().saturating_sub(22 + u16::MAX as usize);
+    (min_start..=bytes.len().checked_sub(22)?)
+        .rev()
+        .find(|&i| read_u32(bytes, i) == EOCD_SIGNATURE)
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+}
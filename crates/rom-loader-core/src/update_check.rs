@@ -0,0 +1,174 @@
+use chrono::{DateTime, Local, NaiveDate};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::capabilities::{command_output, compare_versions, find_version};
+use crate::emulator_config::Emulator;
+use crate::emulator_install::{fetch_text, latest_release, platform};
+
+/// Emulators with releases on GitHub: text matched against the entry's name or executable,
+/// repository, and the argument that makes the emulator print its version.
+///
+/// More specific names come first, so "dosbox-x" isn't taken for another DOSBox.
+const RELEASE_SOURCES: &[(&str, &str, &str)] = &[
+    ("retroarch", "libretro/RetroArch", "--version"),
+    ("mame", "mamedev/mame", "-version"),
+    ("mgba", "mgba-emu/mgba", "--version"),
+    ("snes9x", "snes9xgit/snes9x", "--version"),
+    ("dosbox-x", "joncampbell123/dosbox-x", "--version"),
+    ("dosbox-staging", "dosbox-staging/dosbox-staging", "--version"),
+    ("scummvm", "scummvm/scummvm", "--version"),
+    ("cemu", "cemu-project/Cemu", "--version"),
+    ("ppsspp", "hrydgard/ppsspp", "--version"),
+    ("pcsx2", "PCSX2/pcsx2", "--version"),
+];
+
+/// Result of checking one emulator or RetroArch core.
+#[derive(Debug)]
+pub enum UpdateStatus {
+    UpToDate { installed: String },
+    Outdated { installed: String, latest: String },
+    /// The check could not be made, e.g. no known release source or no network.
+    Unknown { reason: String },
+}
+
+/// One line of the `update-check` report.
+#[derive(Debug)]
+pub struct UpdateCheck {
+    /// Emulator entry name, or the core file name for RetroArch cores.
+    pub name: String,
+    pub status: UpdateStatus,
+}
+
+/// Compares the installed versions of the configured emulators and RetroArch cores against
+/// their latest releases.
+///
+/// Emulators are asked for their version (`--version`); cores are compared by date against
+/// the libretro buildbot's nightly builds. Executables and cores shared by several entries
+/// are checked once.
+pub fn check_updates(emulators: &[Emulator]) -> Vec<UpdateCheck> {
+    let mut checks = Vec::new();
+    let mut releases: HashMap<&str, io::Result<String>> = HashMap::new();
+    let mut checked_paths = Vec::new();
+    let mut core_dates: Option<io::Result<HashMap<String, NaiveDate>>> = None;
+
+    for emulator in emulators {
+        if !checked_paths.contains(&emulator.path) {
+            checked_paths.push(emulator.path.clone());
+            let status = match release_source(emulator) {
+                None => UpdateStatus::Unknown { reason: "no known release source".to_string() },
+                Some((repo, version_arg)) => {
+                    let latest = releases.entry(repo).or_insert_with(|| latest_version(repo));
+                    check_emulator(&emulator.executable(), version_arg, latest)
+                }
+            };
+            checks.push(UpdateCheck { name: emulator.name.clone(), status });
+        }
+
+        let Some(core_path) = emulator.core() else { continue };
+        if checked_paths.contains(&core_path) {
+            continue;
+        }
+        checked_paths.push(core_path.clone());
+        let dates = core_dates.get_or_insert_with(buildbot_core_dates);
+        let name = core_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let status = check_core(&core_path, &name, dates);
+        checks.push(UpdateCheck { name, status });
+    }
+    checks
+}
+
+fn release_source(emulator: &Emulator) -> Option<(&'static str, &'static str)> {
+    let name = emulator.name.to_lowercase();
+    let executable = emulator.path.file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+    RELEASE_SOURCES
+        .iter()
+        .find(|(key, _, _)| executable.contains(key) || name.contains(key))
+        .map(|&(_, repo, version_arg)| (repo, version_arg))
+}
+
+fn check_emulator(path: &Path, version_arg: &str, latest: &io::Result<String>) -> UpdateStatus {
+    let installed = match installed_version(path, version_arg) {
+        Ok(Some(version)) => version,
+        Ok(None) => return UpdateStatus::Unknown { reason: "could not read the installed version".to_string() },
+        Err(e) => return UpdateStatus::Unknown { reason: e.to_string() },
+    };
+    let latest = match latest {
+        Ok(latest) => latest,
+        Err(e) => return UpdateStatus::Unknown { reason: e.to_string() },
+    };
+    if compare_versions(&installed, latest) == Ordering::Less {
+        UpdateStatus::Outdated { installed, latest: latest.clone() }
+    } else {
+        UpdateStatus::UpToDate { installed }
+    }
+}
+
+fn check_core(path: &Path, name: &str, dates: &io::Result<HashMap<String, NaiveDate>>) -> UpdateStatus {
+    let installed: NaiveDate = match fs::metadata(path).and_then(|m| m.modified()) {
+        Ok(modified) => DateTime::<Local>::from(modified).date_naive(),
+        Err(e) => return UpdateStatus::Unknown { reason: e.to_string() },
+    };
+    let latest = match dates {
+        Ok(dates) => dates.get(name),
+        Err(e) => return UpdateStatus::Unknown { reason: e.to_string() },
+    };
+    match latest {
+        None => UpdateStatus::Unknown { reason: "not on the libretro buildbot".to_string() },
+        Some(latest) if *latest > installed => UpdateStatus::Outdated {
+            installed: installed.to_string(),
+            latest: latest.to_string(),
+        },
+        Some(_) => UpdateStatus::UpToDate { installed: installed.to_string() },
+    }
+}
+
+/// Runs the emulator with `version_arg` and finds the version number in what it prints.
+fn installed_version(path: &Path, version_arg: &str) -> io::Result<Option<String>> {
+    Ok(find_version(&command_output(path, version_arg)?))
+}
+
+/// Looks up the version of the latest release of a GitHub repository from its tag.
+fn latest_version(repo: &str) -> io::Result<String> {
+    let release = latest_release(repo)?;
+    let tag = release["tag_name"].as_str().unwrap_or_default();
+    tag_version(tag).ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Could not read a version from the release tag '{}'", tag),
+    ))
+}
+
+/// Reads the dates of the nightly cores from the libretro buildbot's index.
+fn buildbot_core_dates() -> io::Result<HashMap<String, NaiveDate>> {
+    let (os, arch) = platform().ok_or_else(|| io::Error::new(
+        io::ErrorKind::Unsupported,
+        "no RetroArch cores are published for this platform",
+    ))?;
+    let url = format!("https://buildbot.libretro.com/nightly/{}/{}/latest/.index-extended", os, arch);
+    let index = fetch_text(&url)?;
+    // Each line is "<date> <hash> <core file>.zip".
+    Ok(index
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let date = NaiveDate::parse_from_str(fields.next()?, "%Y-%m-%d").ok()?;
+            let file = fields.nth(1)?.strip_suffix(".zip")?;
+            Some((file.to_string(), date))
+        })
+        .collect())
+}
+
+/// Reads the version from a release tag such as "v1.19.1" or MAME's "mame0270" (0.270).
+fn tag_version(tag: &str) -> Option<String> {
+    if let Some(version) = find_version(tag) {
+        return Some(version);
+    }
+    let digits: String = tag.chars().filter(char::is_ascii_digit).collect();
+    match digits.strip_prefix('0') {
+        Some(minor) if !minor.is_empty() => Some(format!("0.{}", minor)),
+        _ => None,
+    }
+}
@@ -0,0 +1,155 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::cache::{Cache, DOWNLOADS_DIR_NAME};
+use crate::emulator_install::download_file;
+use crate::homebrew::download_name;
+use crate::hashing;
+use crate::rom_scanner::Game;
+use crate::systems::{self, System};
+
+/// A checksum a download has to match, as lower-case hex: 8 digits for CRC32, 40 for SHA-1
+/// and 64 for SHA-256, the hashes No-Intro and Redump list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectedHash {
+    Crc32(String),
+    Sha1(String),
+    Sha256(String),
+}
+
+impl FromStr for ExpectedHash {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.trim().to_lowercase();
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("'{}' is not a hex checksum", s.trim()));
+        }
+        match hex.len() {
+            8 => Ok(ExpectedHash::Crc32(hex)),
+            40 => Ok(ExpectedHash::Sha1(hex)),
+            64 => Ok(ExpectedHash::Sha256(hex)),
+            n => Err(format!("a checksum has 8 (CRC32), 40 (SHA-1) or 64 (SHA-256) hex digits, not {}", n)),
+        }
+    }
+}
+
+impl ExpectedHash {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExpectedHash::Crc32(_) => "CRC32",
+            ExpectedHash::Sha1(_) => "SHA-1",
+            ExpectedHash::Sha256(_) => "SHA-256",
+        }
+    }
+
+    pub fn hex(&self) -> &str {
+        match self {
+            ExpectedHash::Crc32(hex) | ExpectedHash::Sha1(hex) | ExpectedHash::Sha256(hex) => hex,
+        }
+    }
+
+    /// Hashes a file with the same algorithm, as lower-case hex.
+    pub fn hash_file(&self, path: &Path) -> io::Result<String> {
+        match self {
+            ExpectedHash::Crc32(_) => Ok(hashing::crc32_file(path)?.to_lowercase()),
+            ExpectedHash::Sha1(_) => digest_file::<Sha1>(path),
+            ExpectedHash::Sha256(_) => digest_file::<Sha256>(path),
+        }
+    }
+}
+
+fn digest_file<D: Digest>(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// A ROM [`import`] put in the library.
+#[derive(Debug)]
+pub struct ImportedRom {
+    pub game: Game,
+    pub system: &'static System,
+}
+
+/// Downloads a ROM, checks it against `expected` and moves it into its system's folder in the
+/// library.
+///
+/// The system is `system` when given, otherwise worked out from the file as a scan would. The
+/// download waits in the cache until it has been checked, so a corrupt or tampered file never
+/// shows up in the library.
+pub fn import(url: &str, expected: Option<&ExpectedHash>, system: Option<&str>, roms_dir: &Path, cache: &Cache) -> io::Result<ImportedRom> {
+    let dir = cache.dir().join(DOWNLOADS_DIR_NAME);
+    fs::create_dir_all(&dir)?;
+    let download = dir.join(download_name(url));
+    let result = download_and_place(url, expected, system, roms_dir, &download);
+    if download.exists() {
+        let _ = fs::remove_file(&download);
+    }
+    result
+}
+
+fn download_and_place(url: &str, expected: Option<&ExpectedHash>, system: Option<&str>, roms_dir: &Path, download: &Path) -> io::Result<ImportedRom> {
+    let file_name = download.file_name().unwrap_or_default();
+    download_file(url, download)?;
+    if let Some(expected) = expected {
+        let actual = expected.hash_file(download)?;
+        if actual != expected.hex() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} mismatch: expected {}, downloaded {}; the file was not imported", expected.name(), expected.hex(), actual),
+            ));
+        }
+    }
+    let Some(detected) = Game::file(download) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is a BIOS dump, not a game", file_name.to_string_lossy())));
+    };
+    let system = match system {
+        Some(id) => systems::find_by_id(&id.to_lowercase())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Unknown system '{}'", id)))?,
+        None => detected.system().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Can't tell which system {} is for; pass --system", file_name.to_string_lossy()),
+            )
+        })?,
+    };
+    let target = system_dir(roms_dir, system).join(file_name);
+    if target.exists() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} is already in the library", target.display())));
+    }
+    fs::create_dir_all(target.parent().unwrap_or(roms_dir))?;
+    // The cache may be on another drive than the library.
+    if fs::rename(download, &target).is_err() {
+        fs::copy(download, &target)?;
+    }
+    Ok(ImportedRom { game: Game { path: target, ..detected }, system })
+}
+
+/// Returns the folder of the library a system's games go in: an existing one named after the
+/// system's id or name (e.g. `snes` or `Super Nintendo`, in any case), or a new one named after
+/// the id.
+pub fn system_dir(roms_dir: &Path, system: &System) -> PathBuf {
+    let matches = |path: &Path| {
+        path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.eq_ignore_ascii_case(system.id) || name.eq_ignore_ascii_case(system.name)
+        })
+    };
+    fs::read_dir(roms_dir)
+        .ok()
+        .and_then(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).find(|path| path.is_dir() && matches(path)))
+        .unwrap_or_else(|| roms_dir.join(system.id))
+}
@@ -0,0 +1,161 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::Path;
+
+use crate::rom_scanner::Game;
+
+/// Regions in No-Intro/GoodTools names and the language their releases are in.
+const REGION_LANGUAGES: &[(&str, &str)] = &[
+    ("japan", "ja"), ("usa", "en"), ("uk", "en"), ("australia", "en"), ("canada", "en"),
+    ("germany", "de"), ("france", "fr"), ("spain", "es"), ("italy", "it"), ("brazil", "pt"),
+    ("portugal", "pt"), ("korea", "ko"), ("china", "zh"), ("taiwan", "zh"), ("netherlands", "nl"),
+    ("sweden", "sv"), ("russia", "ru"), ("poland", "pl"),
+];
+
+/// Three-letter language names used in fan-translation tags such as `[T+Eng]` or `[T-Spa]`.
+const TRANSLATION_LANGUAGES: &[(&str, &str)] = &[
+    ("eng", "en"), ("spa", "es"), ("fre", "fr"), ("fra", "fr"), ("ger", "de"), ("deu", "de"),
+    ("ita", "it"), ("por", "pt"), ("bra", "pt"), ("rus", "ru"), ("chi", "zh"), ("kor", "ko"),
+    ("jap", "ja"), ("dut", "nl"), ("nld", "nl"), ("swe", "sv"), ("pol", "pl"),
+];
+
+/// The language information found in a ROM's file name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LanguageTags {
+    /// Two-letter language codes, e.g. `["en", "fr"]`.
+    pub languages: Vec<String>,
+    /// Whether this is a fan translation (`[T+Eng]`, `[T-Spa]`, ...) rather than an official release.
+    pub translation: bool,
+}
+
+/// Reads the languages of a ROM from its No-Intro or GoodTools style name.
+///
+/// Explicit language lists such as `(En,Fr,De)` and translation tags win over the region:
+/// `Game (Japan) [T+Eng]` is in English, `Game (Europe) (En,Fr)` in English and French.
+pub fn language_tags(file_name: &str) -> LanguageTags {
+    let mut tags = LanguageTags::default();
+    let mut region_languages = Vec::new();
+    for tag in bracketed_tags(file_name) {
+        let lower = tag.to_lowercase();
+        if let Some(language) = lower.strip_prefix("t+").or_else(|| lower.strip_prefix("t-")) {
+            let code = language.get(..3).unwrap_or(language);
+            if let Some((_, code)) = TRANSLATION_LANGUAGES.iter().find(|(name, _)| *name == code) {
+                tags.translation = true;
+                tags.languages = vec![code.to_string()];
+            }
+            continue;
+        }
+        if tags.translation {
+            continue;
+        }
+        let parts: Vec<&str> = lower.split([',', '+']).map(str::trim).collect();
+        if parts.iter().all(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic())) {
+            tags.languages = parts.iter().map(|part| part.to_string()).collect();
+        } else {
+            region_languages.extend(parts.iter().filter_map(|part| {
+                REGION_LANGUAGES.iter().find(|(region, _)| region == part).map(|(_, code)| code.to_string())
+            }));
+        }
+    }
+    if tags.languages.is_empty() {
+        region_languages.dedup();
+        tags.languages = region_languages;
+    }
+    tags
+}
+
+/// Returns the title a ROM is grouped under: its name without tags and extension, lower-cased.
+pub fn base_title(file_name: &str) -> String {
+    let stem = Path::new(file_name).file_stem().unwrap_or_default().to_string_lossy();
+    let mut title = String::new();
+    let mut depth = 0;
+    for c in stem.chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = (depth - 1).max(0),
+            _ if depth == 0 => title.push(c),
+            _ => {}
+        }
+    }
+    title.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Returns the preferred languages: the configured list, or the language of the `LANG` locale.
+pub fn preferred_languages(configured: &[String]) -> Vec<String> {
+    if !configured.is_empty() {
+        return configured.iter().map(|language| language.to_lowercase()).collect();
+    }
+    env::var("LANG")
+        .ok()
+        .and_then(|lang| lang.get(..2).map(str::to_lowercase))
+        .filter(|code| code.chars().all(|c| c.is_ascii_alphabetic()) && code != "c")
+        .into_iter()
+        .collect()
+}
+
+/// Language and translation variants of the same title in one folder, e.g.
+/// `Game (Japan).sfc`, `Game (USA).sfc` and `Game (Japan) [T+Spa].sfc`.
+///
+/// Each group has a default variant, picked by the preferred languages; the others are listed
+/// separately so the game list shows each title once.
+#[derive(Debug, Default)]
+pub struct Variants {
+    /// Indices into the game list, default variant first.
+    groups: Vec<Vec<usize>>,
+    /// Group of every game that has variants.
+    group_of: HashMap<usize, usize>,
+}
+
+impl Variants {
+    /// Groups the games by folder, system and title and picks each group's default variant.
+    ///
+    /// The default is the variant in the most preferred language; official releases win over
+    /// fan translations in the same language, and otherwise the list order is kept.
+    pub fn group(games: &[Game], preferred_languages: &[String]) -> Self {
+        let mut by_title: BTreeMap<(&Path, Option<&str>, String), Vec<usize>> = BTreeMap::new();
+        for (i, game) in games.iter().enumerate() {
+            let Some(file_name) = game.path.file_name().map(|name| name.to_string_lossy()) else { continue };
+            let title = base_title(&file_name);
+            if title.is_empty() {
+                continue;
+            }
+            let folder = game.path.parent().unwrap_or(Path::new(""));
+            by_title.entry((folder, game.system().map(|s| s.id), title)).or_default().push(i);
+        }
+
+        let mut variants = Variants::default();
+        for (_, mut members) in by_title {
+            if members.len() < 2 {
+                continue;
+            }
+            members.sort_by_key(|&i| {
+                let tags = language_tags(&games[i].path.file_name().unwrap_or_default().to_string_lossy());
+                let rank = preferred_languages
+                    .iter()
+                    .position(|wanted| tags.languages.contains(wanted))
+                    .unwrap_or(preferred_languages.len());
+                (rank, tags.translation, i)
+            });
+            for &i in &members {
+                variants.group_of.insert(i, variants.groups.len());
+            }
+            variants.groups.push(members);
+        }
+        variants
+    }
+
+    /// Whether a game is a variant other than its group's default, so the list can leave it out.
+    pub fn is_alternate(&self, index: usize) -> bool {
+        self.group_of.get(&index).is_some_and(|&group| self.groups[group][0] != index)
+    }
+
+    /// Returns every variant of a game's title, default first; empty for games without variants.
+    pub fn of(&self, index: usize) -> &[usize] {
+        self.group_of.get(&index).map_or(&[], |&group| &self.groups[group])
+    }
+}
+
+/// Yields the contents of every `(...)` and `[...]` tag in a file name.
+fn bracketed_tags(file_name: &str) -> impl Iterator<Item = &str> {
+    file_name.split(['(', '[']).skip(1).filter_map(|part| part.split([')', ']']).next())
+}
@@ -0,0 +1,242 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use crate::{atomic_file, hashing, interrupt, netfs};
+
+/// File name of the stored ROM hashes, kept next to the emulator configuration.
+pub const HASH_STORE_FILE_NAME: &str = "hashes.json";
+
+/// The hash of a file as it was when it was last known to be good.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredHash {
+    pub size: u64,
+    /// Modification time (Unix seconds) when the hash was taken.
+    pub modified: u64,
+    pub crc32: String,
+    /// Unix timestamp of the last time the file was hashed and matched.
+    pub verified: u64,
+    /// Whether `crc32` is the hash of the files inside the archive rather than of the archive.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub inner: bool,
+}
+
+/// Known-good hashes of library files, keyed by path.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HashStore {
+    #[serde(default)]
+    pub files: BTreeMap<PathBuf, StoredHash>,
+}
+
+impl HashStore {
+    /// Returns the hash store path that belongs to the given emulator configuration file.
+    pub fn path_for_config(config_path: &Path) -> PathBuf {
+        config_path.with_file_name(HASH_STORE_FILE_NAME)
+    }
+
+    /// Loads the hash store, returning an empty store if no file was hashed yet.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        if !path.exists() {
+            return Ok(HashStore::default());
+        }
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to parse hash store {}: {}", path.display(), e),
+            ))
+    }
+
+    /// Drops the stored hashes of a file, or of every file in a game directory.
+    pub fn forget(&mut self, path: &Path) {
+        self.files.retain(|file, _| !file.starts_with(path));
+    }
+
+    /// Saves the hash store as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Failed to serialize hash store to JSON: {}", e),
+            ))?;
+        atomic_file::write(path, contents)
+    }
+}
+
+/// The result of checking one file against its stored hash.
+#[derive(Debug)]
+pub enum VerifyStatus {
+    /// Not seen before, or hashed the other way (see [`VerifyOptions::inner`]); hashed and recorded.
+    New,
+    /// Size and modification time match the stored values (not re-hashed).
+    Unchanged,
+    /// Re-hashed and the hash still matches.
+    Verified,
+    /// Verified recently enough that it was not re-hashed.
+    Skipped,
+    /// The file was changed on purpose (new size or modification time); the new hash was recorded.
+    Modified,
+    /// Same size and modification time, but different contents: silent corruption.
+    Corrupted { expected: String, actual: String },
+}
+
+/// A file whose contents no longer match its stored hash.
+#[derive(Debug, Serialize)]
+pub struct CorruptedFile {
+    pub path: PathBuf,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// A file that could not be hashed.
+#[derive(Debug, Serialize)]
+pub struct UnreadableFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// The outcome of verifying a whole library, printed as JSON by `verify --json`.
+#[derive(Debug, Default, Serialize)]
+pub struct VerifySummary {
+    /// Number of files checked.
+    pub checked: usize,
+    /// Number of files per status ("new", "unchanged", "verified", ...).
+    pub counts: BTreeMap<&'static str, usize>,
+    pub modified: Vec<PathBuf>,
+    pub corrupted: Vec<CorruptedFile>,
+    pub unreadable: Vec<UnreadableFile>,
+    /// Files hashed before that are no longer in the library.
+    pub missing: Vec<PathBuf>,
+    /// SNES ROMs with a copier header, whose hashes won't match dats (see `strip-headers`).
+    pub headered: Vec<PathBuf>,
+}
+
+impl VerifySummary {
+    /// Whether anything needs attention: corrupted, unreadable or missing files.
+    pub fn has_failures(&self) -> bool {
+        !self.corrupted.is_empty() || !self.unreadable.is_empty() || !self.missing.is_empty()
+    }
+}
+
+/// How a library is verified.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifyOptions {
+    /// Re-hash files even if their size and modification time are unchanged.
+    pub deep: bool,
+    /// With `deep`, skip files verified less than this many seconds ago.
+    pub min_age_secs: Option<u64>,
+    /// Hash the files inside zip and 7z archives instead of the archives themselves.
+    pub inner: bool,
+    /// The current Unix timestamp.
+    pub now: u64,
+    /// Files hashed at once [default: one per CPU].
+    pub threads: Option<usize>,
+}
+
+/// The status of a checked file and, if it changed, the hash to store for it.
+type FileCheck = (VerifyStatus, Option<StoredHash>);
+
+/// Checks files against the hash store, hashing them in parallel.
+///
+/// The store is updated for new and modified files; corrupted files keep their old,
+/// known-good entry. Ctrl-C stops the hashing; files not checked by then get an
+/// [`io::ErrorKind::Interrupted`] error.
+///
+/// # Returns
+/// The status of each file, in the order of `files`.
+pub fn verify_files(files: &[PathBuf], store: &mut HashStore, options: &VerifyOptions) -> Vec<io::Result<VerifyStatus>> {
+    let workers = options
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+        .clamp(1, files.len().max(1));
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<io::Result<FileCheck>>>> = Mutex::new((0..files.len()).map(|_| None).collect());
+    let shared: &HashStore = store;
+    let _busy = interrupt::Busy::start();
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                if interrupt::requested() {
+                    break;
+                }
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = files.get(index) else { break };
+                let result = check_file(path, shared.files.get(path), options);
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+            });
+        }
+    });
+
+    let results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    files
+        .iter()
+        .zip(results)
+        .map(|(path, result)| {
+            // Files not reached before Ctrl-C are reported as interrupted.
+            let (status, update) = result.unwrap_or_else(|| Err(interrupt::error()))?;
+            if let Some(hash) = update {
+                store.files.insert(path.clone(), hash);
+            }
+            Ok(status)
+        })
+        .collect()
+}
+
+/// Checks a single file against its stored hash.
+fn check_file(
+    path: &Path,
+    stored: Option<&StoredHash>,
+    options: &VerifyOptions,
+) -> io::Result<FileCheck> {
+    let metadata = netfs::retry(|| fs::metadata(path))?;
+    let size = metadata.len();
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let now = options.now;
+    // Only archives have contents to hash; other files look the same either way.
+    let inner = options.inner && hashing::is_archive(path);
+    let fresh_hash = || -> io::Result<StoredHash> {
+        Ok(StoredHash { size, modified, crc32: netfs::retry(|| hashing::content_crc32(path, inner))?, verified: now, inner })
+    };
+
+    let Some(stored) = stored.filter(|s| s.inner == inner) else {
+        return Ok((VerifyStatus::New, Some(fresh_hash()?)));
+    };
+    if stored.size != size || stored.modified != modified {
+        return Ok((VerifyStatus::Modified, Some(fresh_hash()?)));
+    }
+    if !options.deep {
+        return Ok((VerifyStatus::Unchanged, None));
+    }
+    if options.min_age_secs.is_some_and(|age| now.saturating_sub(stored.verified) < age) {
+        return Ok((VerifyStatus::Skipped, None));
+    }
+
+    let actual = netfs::retry(|| hashing::content_crc32(path, inner))?;
+    if actual == stored.crc32 {
+        Ok((VerifyStatus::Verified, Some(StoredHash { verified: now, ..stored.clone() })))
+    } else {
+        Ok((VerifyStatus::Corrupted { expected: stored.crc32.clone(), actual }, None))
+    }
+}
+
+/// Parses an age such as "30d", "12h", "2w" or "90m" into seconds.
+pub fn parse_age(age: &str) -> Result<u64, String> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (number, unit) = age.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("'{}' is not an age like 30d", age))?;
+    let unit_secs = match unit {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" | "" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("Unknown unit '{}' in '{}' (use m, h, d or w)", unit, age)),
+    };
+    Ok(number * unit_secs)
+}
@@ -0,0 +1,54 @@
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use rom_loader_core::{interrupt, systems};
+use rom_loader_core::events::EventOptions;
+use rom_loader_core::library::LibraryCommand;
+use rom_loader_core::settings::Settings;
+use std::io;
+use std::path::PathBuf;
+
+/// Indexes and verifies a ROM library without launching anything, e.g. on a NAS or server
+/// that has no emulators installed.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path to the directory containing ROMs [default: the first of the configured roms_dirs that exists].
+    #[arg(short, long, value_name = "DIR", env = "ROM_LOADER_ROMS_DIR")]
+    roms_dir: Option<String>,
+
+    /// Path to the emulator configuration file; hashes and scan snapshots are kept next to it.
+    #[arg(short, long, value_name = "FILE", default_value = "emulators.json", env = "ROM_LOADER_CONFIG")]
+    config_file: String,
+
+    #[command(flatten)]
+    events: EventOptions,
+
+    #[command(subcommand)]
+    command: LibraryCommand,
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    if let Err(e) = interrupt::install() {
+        eprintln!("⚠️ Ctrl-C won't stop things gracefully: {}", e);
+    }
+    let config_path = PathBuf::from(&args.config_file);
+    let settings = Settings::load(&config_path).unwrap_or_else(|e| {
+        eprintln!("⚠️ Ignoring the configuration's settings: {}", e);
+        Settings::default()
+    });
+    systems::disable_extensions(&settings.disabled_extensions);
+    if let Err(e) = args.events.open() {
+        eprintln!("⚠️ No events will be written: {}", e);
+    }
+    let roms_dir = match args.roms_dir.or_else(|| settings.default_roms_dir().map(|dir| dir.to_string_lossy().to_string())) {
+        Some(dir) => dir,
+        None => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "the ROMs directory is required: --roms-dir <DIR>, or roms_dirs in the configuration's settings",
+            )
+            .exit(),
+    };
+    args.command.run(&config_path, &roms_dir)
+}
@@ -0,0 +1,65 @@
+#[cfg(feature = "watch")]
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::io;
+use std::path::Path;
+#[cfg(feature = "watch")]
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+
+/// Watches the emulator configuration file for changes made while the loader is running.
+///
+/// The parent directory is watched rather than the file itself, because many editors save
+/// by writing a new file and renaming it over the old one.
+pub struct ConfigWatcher {
+    // Kept alive for as long as we want to receive events.
+    #[cfg(feature = "watch")]
+    _watcher: RecommendedWatcher,
+    changes: Receiver<()>,
+}
+
+impl ConfigWatcher {
+    /// Starts watching the given configuration file.
+    #[cfg(feature = "watch")]
+    pub fn new(config_path: &Path) -> io::Result<Self> {
+        let file_name = config_path.file_name().map(|n| n.to_os_string()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Not a file path: {}", config_path.display()),
+            )
+        })?;
+        let dir = match config_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+
+        let (sender, changes) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                let touches_config = event.paths.iter().any(|p| p.file_name() == Some(file_name.as_os_str()));
+                if touches_config && (event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove()) {
+                    let _ = sender.send(());
+                }
+            }
+        })
+        .map_err(io::Error::other)?;
+        watcher.watch(&dir, RecursiveMode::NonRecursive).map_err(io::Error::other)?;
+
+        Ok(ConfigWatcher { _watcher: watcher, changes })
+    }
+
+    /// Builds without the `watch` feature don't watch the file, so no change is ever seen.
+    #[cfg(not(feature = "watch"))]
+    pub fn new(_config_path: &Path) -> io::Result<Self> {
+        let (_, changes) = mpsc::channel();
+        Ok(ConfigWatcher { changes })
+    }
+
+    /// Returns true if the file changed since the last call. A burst of events counts once.
+    pub fn has_changed(&self) -> bool {
+        let mut changed = false;
+        while self.changes.try_recv().is_ok() {
+            changed = true;
+        }
+        changed
+    }
+}
@@ -1,153 +1,2915 @@
-mod emulator_config;
-mod rom_launcher;
-mod rom_scanner;
-
-use clap::Parser;
-use emulator_config::{Emulator, EmulatorConfig};
-use rom_scanner::{Rom, RomScanner};
-use std::collections::HashMap;
+mod config_watcher;
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use config_watcher::ConfigWatcher;
+use rom_loader_core::cache::Cache;
+use rom_loader_core::capabilities::{self, Capabilities};
+use rom_loader_core::emulator_config::{Emulator, EmulatorConfig};
+use rom_loader_core::enhancements::Companions;
+use rom_loader_core::events::EventOptions;
+use rom_loader_core::game_ids::{self, GameIds, GameRef};
+#[cfg(feature = "gpio")]
+use rom_loader_core::gpio::{self, ButtonAction};
+use rom_loader_core::history::{self, HistoryEntry, LaunchHistory};
+use rom_loader_core::journal::{BatchRecorder, Operation};
+use rom_loader_core::launch::{
+    check_orphans, effective_system_name, emulator_candidates, launch_game, load_emulator_config, prompt_emulator_choice,
+    prompt_remember_choice, report_missing_emulator, suggested_emulator, LaunchOverrides, RememberChoice,
+};
+use rom_loader_core::launch_hooks::LaunchHooks;
+use rom_loader_core::library::{exit_on_failure, library_files, scan_library, LibraryCommand};
+use rom_loader_core::macros::{self, CommandInput, MacroStore};
+use rom_loader_core::playlists::{self, PlaylistStore};
+use rom_loader_core::metadata::{GameMetadata, MetadataDb, YearRange};
+use rom_loader_core::prompts::{self, Answers};
+use rom_loader_core::rom_scanner::{Game, GameKind};
+use rom_loader_core::search::Query;
+use rom_loader_core::session_actions::PostSessionAction;
+use rom_loader_core::settings::{self, GpioPins, ListColumn, ListDisplay, Settings, TitleSource};
+use rom_loader_core::state_store::StateStore;
+use rom_loader_core::test_launch::{self, TestOutcome, TestResult};
+use rom_loader_core::titles::TitleCleaner;
+#[cfg(feature = "install")]
+use rom_loader_core::update_check::{self, UpdateStatus};
+use rom_loader_core::variants::{self, Variants};
+use rom_loader_core::verify::{self, HashStore, VerifyOptions, VerifyStatus};
+use rom_loader_core::{arcade, backup, bug_report, cheats, first_run, hashing, interrupt, manuals, preload, quirks, rom_format, rom_launcher, screenshots, systems};
+#[cfg(feature = "install")]
+use rom_loader_core::{emulator_install, homebrew};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Command-line arguments for the ROM Loader.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path to the directory containing ROMs (required for browsing and launching, unless
+    /// `roms_dirs` is set in the configuration's settings).
+    #[arg(short, long, value_name = "DIR", global = true, env = "ROM_LOADER_ROMS_DIR")]
+    roms_dir: Option<String>,
+
+    /// Path to the JSON configuration file for emulators.
+    #[arg(short, long, value_name = "FILE", default_value = "emulators.json", global = true, env = "ROM_LOADER_CONFIG")]
+    config_file: String,
+
+    /// What the interactive browser does after a game (or the launch queue) exits:
+    /// return, quit, shutdown, suspend or script:<path> [default: ui.after_session setting, or return].
+    #[arg(long, value_name = "ACTION", env = "ROM_LOADER_AFTER_SESSION")]
+    after_session: Option<PostSessionAction>,
+
+    /// Stop emulators left running by an earlier rom-loader that crashed, without asking.
+    #[arg(long, global = true, env = "ROM_LOADER_REAP_ORPHANS")]
+    reap_orphans: bool,
+
+    /// Answer "yes" to every confirmation (deleting games, stopping leftover emulators, ...)
+    /// instead of asking.
+    #[arg(short, long, global = true, conflicts_with = "no_input")]
+    yes: bool,
+
+    /// Never wait for input: every question takes its default answer, which keeps files
+    /// rather than deleting them.
+    #[arg(long, global = true, env = "ROM_LOADER_NO_INPUT")]
+    no_input: bool,
+
+    /// Show the emulator's output as it runs, each line prefixed with [emu].
+    #[arg(short, long, global = true, env = "ROM_LOADER_VERBOSE")]
+    verbose: bool,
+
+    #[command(flatten)]
+    events: EventOptions,
+
+    /// Use less memory, for boards such as the Raspberry Pi Zero: hash one file at a time, don't
+    /// watch the configuration for changes and keep only the metadata of the games in the library.
+    #[arg(long, global = true, env = "ROM_LOADER_LOW_MEM")]
+    low_mem: bool,
+
+    /// Close the emulator after this many minutes of play, warning a minute before, e.g. for a
+    /// cabinet at an event where players take turns [default: ui.time_limit_minutes setting, or
+    /// no limit].
+    #[arg(long, value_name = "MINUTES", global = true, env = "ROM_LOADER_TIME_LIMIT")]
+    time_limit: Option<u64>,
+
+    /// Optional subcommand; without one the interactive ROM browser starts.
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+/// Metadata filters for the game list, shared by the `list` subcommand and the interactive `l` command.
+#[derive(clap::Args, Debug, Clone, Default)]
+struct ListFilter {
+    /// Only show games whose genre contains this text (case-insensitive).
+    #[arg(long)]
+    genre: Option<String>,
+
+    /// Only show games that support at least this many players.
+    #[arg(long)]
+    players: Option<u32>,
+
+    /// Only show games released in this year or range (e.g. 1994, 1994..1998, ..1990).
+    #[arg(long)]
+    year: Option<YearRange>,
+
+    /// Hide arcade clones, showing only parent sets.
+    #[arg(long)]
+    hide_clones: bool,
+
+    /// Show only the games hidden with `hide`, instead of leaving them out.
+    #[arg(long)]
+    hidden: bool,
+
+    /// List every language and translation variant, not just the preferred one of each title.
+    #[arg(long)]
+    all_variants: bool,
+
+    /// Only show games matching a search, e.g. "genre:rpg year:<1995 mario" (fields: title, genre,
+    /// developer, publisher, region, rating, description, system, file, year, players).
+    #[arg(short, long, value_name = "QUERY", allow_hyphen_values = true)]
+    search: Option<Query>,
+
+    /// Only show games whose title starts with this letter ('#' for anything else); set by the
+    /// interactive `g` command.
+    #[arg(skip)]
+    letter: Option<char>,
+}
+
+impl ListFilter {
+    /// Whether any filter is set.
+    fn is_active(&self) -> bool {
+        self.genre.is_some() || self.players.is_some() || self.year.is_some() || self.hide_clones || self.hidden || self.search.is_some() || self.letter.is_some()
+    }
+
+    /// Whether a game with the given metadata passes every filter; games without metadata only pass an empty filter.
+    ///
+    /// Hiding clones alone keeps games without metadata, since they can't be known clones.
+    fn matches(&self, metadata: Option<&GameMetadata>) -> bool {
+        if self.hide_clones && metadata.is_some_and(arcade::is_clone) {
+            return false;
+        }
+        if self.genre.is_none() && self.players.is_none() && self.year.is_none() {
+            return true;
+        }
+        let Some(metadata) = metadata else { return false };
+        let genre_ok = self.genre.as_ref().is_none_or(|wanted| {
+            metadata.genre.as_ref().is_some_and(|genre| genre.to_lowercase().contains(&wanted.to_lowercase()))
+        });
+        let players_ok = self.players.is_none_or(|wanted| metadata.max_players().is_some_and(|max| max >= wanted));
+        let year_ok = self.year.is_none_or(|range| metadata.release_year().is_some_and(|year| range.contains(year)));
+        genre_ok && players_ok && year_ok
+    }
+}
+
+impl ListFilter {
+    /// Whether a game passes the search and the `g` letter, which also look at the title it is listed under.
+    fn matches_title(&self, rom: &Game, metadata: Option<&GameMetadata>, title: &str) -> bool {
+        self.letter.is_none_or(|letter| index_letter(title) == letter)
+            && self.search.as_ref().is_none_or(|query| query.matches(rom, metadata, title))
+    }
+}
+
+/// Arguments accepted by the interactive `l` command.
+#[derive(Parser, Debug)]
+#[command(name = "l", no_binary_name = true, disable_help_flag = true)]
+struct InteractiveListArgs {
+    #[command(flatten)]
+    filter: ListFilter,
+}
+
+/// Non-interactive subcommands.
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Prints the game list, optionally filtered by imported metadata.
+    List {
+        #[command(flatten)]
+        filter: ListFilter,
+    },
+
+    /// Shows previous launches, most recent first.
+    History {
+        /// Only show ROMs whose file name contains this text (case-insensitive).
+        #[arg(long)]
+        rom: Option<String>,
+
+        /// Only show launches made with emulators whose name contains this text (case-insensitive).
+        #[arg(long)]
+        emulator: Option<String>,
+
+        /// Only show launches that failed.
+        #[arg(long)]
+        failed: bool,
+
+        /// Show only the most recent launch of each ROM, with its launch count.
+        #[arg(long)]
+        unique: bool,
+
+        /// Maximum number of entries to show.
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Launches one game and exits once the emulator does.
+    Launch {
+        /// ROM number, as shown in the game list, or game ID (as shown by `info`).
+        rom: GameRef,
+
+        /// Emulator to use for this launch instead of the suggested one (by name, case-insensitive).
+        #[arg(long = "with", value_name = "EMULATOR")]
+        with: Option<String>,
+
+        #[command(flatten)]
+        overrides: LaunchOverrides,
+    },
+
+    /// Shows everything known about one game: metadata, play time, hashes and file details.
+    Info {
+        /// ROM number, as shown in the game list, or game ID.
+        rom: GameRef,
+    },
+
+    /// Opens a game's manual (or map, or other extra) in the system's document viewer.
+    ///
+    /// Manuals are PDFs (or CBZ, DjVu, EPUB) next to the ROM whose name starts with the ROM's,
+    /// or files in the game's assets folder under `manuals`.
+    OpenManual {
+        /// ROM number, as shown in the game list, or game ID (as shown by `info`).
+        rom: GameRef,
+
+        /// Which of the game's manuals to open, as numbered by `info` order (default: the first).
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        which: usize,
+    },
+
+    /// Briefly launches ROMs to find entries whose emulator fails to start them.
+    TestLaunch {
+        /// ROM numbers to test, as shown in the interactive list, or game IDs.
+        roms: Vec<GameRef>,
+
+        /// Test every ROM in the library.
+        #[arg(long, conflicts_with = "roms")]
+        all: bool,
+
+        /// How long an emulator must stay up for the ROM to count as working.
+        #[arg(long, default_value_t = 10)]
+        seconds: u64,
+
+        /// Also write the full results as JSON to this file.
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+
+    /// Boots a test ROM for each system with its default emulator and checks it stays up,
+    /// to validate a setup (e.g. in CI). Test ROMs are kept in `smoke_test_roms` next to the
+    /// configuration; a NES one is built in.
+    CoreSmokeTest {
+        /// How long an emulator must stay up for the system to count as working.
+        #[arg(long, default_value_t = 5)]
+        seconds: u64,
+
+        /// Also write the full results as JSON to this file.
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+
+    #[command(flatten)]
+    Library(LibraryCommand),
+
+    /// Hides games from the game list without deleting them (e.g. prototypes, bad dumps, BIOS files).
+    Hide {
+        /// ROM numbers, as shown in the game list, or game IDs (as shown by `info`).
+        #[arg(required = true)]
+        roms: Vec<GameRef>,
+
+        /// Show the games in the list again.
+        #[arg(long)]
+        undo: bool,
+    },
+
+    /// Moves games to the system trash and forgets them, after asking for confirmation (skipped with --yes).
+    Delete {
+        /// ROM numbers, as shown in the game list, or game IDs (as shown by `info`).
+        #[arg(required = true)]
+        roms: Vec<GameRef>,
+    },
+
+    /// Edits the emulator configuration file, showing a diff of the changes and asking before
+    /// saving them.
+    Config {
+        /// Only show what would change; nothing is saved.
+        #[arg(long, global = true)]
+        dry_run: bool,
+
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Backs up or restores the configuration, play history, learned preferences and metadata.
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+
+    /// Manages the offline game metadata database.
+    Metadata {
+        #[command(subcommand)]
+        action: MetadataAction,
+    },
+
+    /// Shows or prunes the cache of preloaded disc images and unpacked ROMs.
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+
+    /// Manages cheat files, which `launch --cheats` deploys into the emulator's cheat_dir.
+    Cheats {
+        #[command(subcommand)]
+        action: CheatsAction,
+    },
+
+    /// Launches games one after another, without the interactive browser.
+    Play {
+        #[command(subcommand)]
+        action: PlayAction,
+    },
+
+    /// Lists or edits playlists: named lists of games for `play playlist`.
+    Playlist {
+        #[command(subcommand)]
+        action: PlaylistAction,
+    },
+
+    /// Runs or manages macros: recorded sequences of interactive commands.
+    Macro {
+        #[command(subcommand)]
+        action: MacroAction,
+    },
+
+    /// Finds free games to add to the library.
+    #[cfg(feature = "install")]
+    Discover {
+        #[command(subcommand)]
+        action: DiscoverAction,
+    },
+
+    /// Downloads the official build of an emulator (retroarch, mgba or snes9x) next to the
+    /// configuration and adds it to the configuration.
+    #[cfg(feature = "install")]
+    InstallEmulator {
+        /// Emulator to install.
+        name: String,
+
+        /// RetroArch core to install as well, e.g. "snes9x"; gets its own configuration entry
+        /// for the systems it is suggested for. May be repeated.
+        #[arg(long = "core", value_name = "CORE")]
+        cores: Vec<String>,
+    },
+
+    /// Reports configured emulators and RetroArch cores that have newer releases.
+    #[cfg(feature = "install")]
+    UpdateCheck,
+
+    /// Checks each configured emulator: that it can be found, its version against min_version,
+    /// and whether it opens zipped ROMs and has a fullscreen flag. What it finds is saved in the
+    /// configuration and used when building launch commands.
+    Doctor,
+
+    /// Bundles version information, the configuration and recent launches (with paths and
+    /// credentials redacted) into a tarball to attach to a bug report. Nothing is uploaded.
+    ReportBug {
+        /// Archive to write (default: rom_loader_bug_report_<date>.tar.gz).
+        archive: Option<PathBuf>,
+    },
+
+    /// Replaces this program with the latest published release for this platform.
+    #[cfg(feature = "self-update")]
+    SelfUpdate,
+}
+
+/// Operations of the `macro` subcommand.
+#[derive(Subcommand, Debug)]
+enum MacroAction {
+    /// Starts the interactive browser and replays a macro in it, e.g. for scripted kiosk flows.
+    Run {
+        /// Name of the macro.
+        name: String,
+    },
+
+    /// Lists the saved macros.
+    List,
+
+    /// Saves a macro from commands separated by ';', e.g. "l --genre rpg; 3".
+    Add {
+        /// Name of the macro; an existing macro with this name is replaced.
+        name: String,
+
+        /// Interactive commands, separated by ';'.
+        commands: String,
+    },
+
+    /// Deletes a macro.
+    Remove {
+        /// Name of the macro.
+        name: String,
+    },
+}
+
+/// Operations of the `play` subcommand.
+#[derive(Subcommand, Debug)]
+enum PlayAction {
+    /// Launches the games of a playlist one after another, each as soon as the previous
+    /// emulator exits. Ctrl-C during a game stops after it.
+    Playlist {
+        /// Name of the playlist.
+        name: String,
+
+        /// Play the games in a random order, e.g. for a party or arcade-night rotation.
+        #[arg(long)]
+        shuffle: bool,
+    },
+}
+
+/// Operations of the `playlist` subcommand.
+#[derive(Subcommand, Debug)]
+enum PlaylistAction {
+    /// Lists the playlists and their games.
+    List,
+
+    /// Adds games to a playlist, making the playlist if it doesn't exist yet.
+    Add {
+        /// Name of the playlist.
+        name: String,
+
+        /// ROM numbers, as shown in the game list, or game IDs.
+        #[arg(required = true)]
+        roms: Vec<GameRef>,
+    },
+
+    /// Removes games from a playlist, or the whole playlist when no games are given.
+    Remove {
+        /// Name of the playlist.
+        name: String,
+
+        /// ROM numbers, as shown in the game list, or game IDs.
+        roms: Vec<GameRef>,
+    },
+}
+
+/// Operations of the `backup` subcommand.
+#[derive(Subcommand, Debug)]
+enum BackupAction {
+    /// Bundles the setup into a single zip archive.
+    Create {
+        /// Archive to write (default: rom_loader_backup_<date>.zip).
+        archive: Option<PathBuf>,
+    },
+
+    /// Restores a backup archive next to the configuration file.
+    Restore {
+        /// Archive made by `backup create`.
+        archive: PathBuf,
+
+        /// Replace existing files.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// Operations of the `metadata` subcommand.
+#[derive(Subcommand, Debug)]
+enum MetadataAction {
+    /// Imports libretro-database files (.dat or .rdb) so titles, years and genres work offline.
+    Import {
+        /// Database files to import; later files add to or override earlier ones.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+/// Operations of the `cache` subcommand.
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Lists the items in the cache, least recently used first.
+    Show,
+
+    /// Removes the least recently used items until the cache fits in its size limit.
+    Prune {
+        /// Size to prune to, in megabytes [default: the cache_max_mb setting]; 0 empties the cache.
+        #[arg(long, value_name = "MB")]
+        max_mb: Option<u64>,
+
+        /// Only list the items that would be removed.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Operations of the `cheats` subcommand.
+#[derive(Subcommand, Debug)]
+enum CheatsAction {
+    /// Downloads the libretro cheat collection next to the configuration, replacing an earlier download.
+    #[cfg(feature = "install")]
+    Update,
+
+    /// Shows which cheat file a game would get.
+    Show {
+        /// ROM number, as shown in the game list, or game ID (as shown by `info`).
+        rom: GameRef,
+    },
+}
+
+/// Operations of the `discover` subcommand.
+#[cfg(feature = "install")]
+#[derive(Subcommand, Debug)]
+enum DiscoverAction {
+    /// Lists the games of the homebrew feeds (`homebrew_sources` in the settings) and downloads
+    /// the chosen ones into `homebrew/<system>` in the library, marked as homebrew so the
+    /// search `is:homebrew` finds them.
+    Homebrew {
+        /// Games to download, by their number in the list; asked for when not given.
+        picks: Vec<usize>,
+
+        /// Only list games for this system, e.g. "nes".
+        #[arg(long)]
+        system: Option<String>,
+
+        /// Feed to use instead of the configured ones, as a URL or file. May be repeated.
+        #[arg(long = "source", value_name = "FEED")]
+        sources: Vec<String>,
+    },
+}
+
+/// Edits applied to the emulator configuration by the `config` subcommand.
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Writes a starter configuration with the emulators found on this computer.
+    Init,
+
+    /// Adds a new emulator entry.
+    AddEmulator {
+        /// Display name of the emulator; also used to pick its launch profile (e.g. "MAME", "RetroArch").
+        name: String,
+
+        /// Path to the emulator executable, or its command name (e.g. "retroarch") to look it up on the PATH.
+        #[arg(long)]
+        path: PathBuf,
+
+        /// ROM extensions handled by the emulator (comma separated, e.g. "smc,sfc").
+        #[arg(long, value_delimiter = ',', required = true)]
+        extensions: Vec<String>,
+
+        /// RetroArch core to load with -L.
+        #[arg(long)]
+        core_path: Option<PathBuf>,
+
+        /// MAME system short name for console ROMs (e.g. "snes").
+        #[arg(long)]
+        system_name: Option<String>,
+    },
+
+    /// Removes an emulator entry by name.
+    RemoveEmulator {
+        /// Name of the emulator to remove (case-insensitive).
+        name: String,
+    },
+
+    /// Sets which emulator entry runs a system's games, whatever their extensions.
+    SetSystem {
+        /// System id, e.g. "snes" or "psx".
+        system: String,
+
+        /// Name of the emulator entry; leave out to remove the system's mapping.
+        emulator: Option<String>,
+    },
+
+    /// Sets one field of an emulator entry.
+    Set {
+        /// Name of the emulator to change (case-insensitive).
+        name: String,
+
+        /// Field to change: name, path, extensions, core_path, system_name, sandbox, gpu,
+        /// audio_device, audio_driver, screenshot_dir, cheat_dir, nice, cpu_affinity or
+        /// min_version.
+        field: String,
+
+        /// New value; use an empty string or "null" to clear optional fields.
+        value: String,
+    },
+}
+
+fn main() -> io::Result<()> {
+    // Parse command-line arguments.
+    let args = Args::parse();
+    if let Err(e) = interrupt::install() {
+        eprintln!("⚠️ Ctrl-C won't stop things gracefully: {}", e);
+    }
+    let config_path = PathBuf::from(&args.config_file);
+    settings::set_low_memory(args.low_mem);
+    let settings = Settings::load(&config_path).unwrap_or_else(|e| {
+        eprintln!("⚠️ Ignoring the configuration's settings: {}", e);
+        Settings::default()
+    });
+    systems::disable_extensions(&settings.disabled_extensions);
+    prompts::set_answers(match (args.yes, args.no_input) {
+        (true, _) => Answers::Yes,
+        (false, true) => Answers::Defaults,
+        (false, false) => Answers::Ask,
+    });
+    rom_launcher::set_verbose(args.verbose);
+    if let Err(e) = args.events.open() {
+        eprintln!("⚠️ No events will be written: {}", e);
+    }
+    let time_limit = args.time_limit.or(settings.ui.time_limit_minutes).filter(|&minutes| minutes > 0);
+    rom_launcher::set_time_limit(time_limit.map(|minutes| Duration::from_secs(minutes.saturating_mul(60))));
+    let after_session = args.after_session.unwrap_or_else(|| after_session_setting(&settings));
+
+    match args.command {
+        Some(Commands::History { rom, emulator, failed, unique, limit }) => {
+            show_history(&config_path, rom.as_deref(), emulator.as_deref(), failed, unique, limit)
+        }
+        Some(Commands::List { mut filter }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            filter.hide_clones |= settings.ui.hide_clones;
+            list_games(&config_path, &roms_dir, &settings, &filter)
+        }
+        Some(Commands::Launch { rom, with, overrides }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            check_orphans(&config_path, args.reap_orphans);
+            launch_single(&config_path, &roms_dir, &rom, with.as_deref(), &overrides)
+        }
+        Some(Commands::OpenManual { rom, which }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            open_manual(&config_path, &roms_dir, &rom, which)
+        }
+        Some(Commands::Info { rom }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            let Some(roms) = scan_library(Path::new(&roms_dir)) else { return Ok(()) };
+            match rom.resolve(Path::new(&roms_dir), &roms) {
+                Ok(index) => {
+                    let metadata = MetadataDb::load(&MetadataDb::path_for_config(&config_path)).unwrap_or_default();
+                    let ids = GameIds::new(Path::new(&roms_dir), &roms);
+                    print_game_info(&config_path, &roms[index], ids.short(index), &metadata, &LaunchHistory::for_config(&config_path));
+                }
+                Err(e) => eprintln!("🚫 {}", e),
+            }
+            Ok(())
+        }
+        Some(Commands::Library(command)) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            command.run(&config_path, &roms_dir)
+        }
+        Some(Commands::Hide { roms, undo }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            hide_games(&config_path, &roms_dir, &roms, !undo)
+        }
+        Some(Commands::Delete { roms }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            delete_games(&config_path, &roms_dir, &roms)
+        }
+        Some(Commands::Config { dry_run, action }) => run_config_command(&config_path, action, dry_run),
+        Some(Commands::Backup { action }) => run_backup_command(&config_path, action),
+        Some(Commands::Metadata { action: MetadataAction::Import { files } }) => import_metadata(&config_path, &files),
+        Some(Commands::TestLaunch { roms, all, seconds, report }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            run_test_launch(&config_path, &roms_dir, &roms, all, seconds, report.as_deref())
+        }
+        Some(Commands::CoreSmokeTest { seconds, report }) => run_core_smoke_test(&config_path, seconds, report.as_deref()),
+        Some(Commands::Macro { action: MacroAction::Run { name } }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            let macros = MacroStore::load(&MacroStore::path_for_config(&config_path))?;
+            match macros.macros.get(&name) {
+                Some(commands) => {
+                    check_orphans(&config_path, args.reap_orphans);
+                    run_interactive(&config_path, &roms_dir, &settings, after_session, commands)
+                }
+                None => {
+                    eprintln!("❌ No macro named '{}'. Record one with 'record {}' in the interactive browser.", name, name);
+                    Ok(())
+                }
+            }
+        }
+        Some(Commands::Macro { action }) => run_macro_command(&config_path, action),
+        Some(Commands::Play { action: PlayAction::Playlist { name, shuffle } }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            check_orphans(&config_path, args.reap_orphans);
+            play_playlist(&config_path, &roms_dir, &name, shuffle)
+        }
+        Some(Commands::Playlist { action }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            run_playlist_command(&config_path, &roms_dir, action)
+        }
+        #[cfg(feature = "install")]
+        Some(Commands::Cheats { action: CheatsAction::Update }) => {
+            println!("⬇️ Downloading the libretro cheat collection...");
+            match cheats::update_database(&config_path) {
+                Ok(count) => println!("✅ {} cheat files in {}", count, cheats::dir_for_config(&config_path).display()),
+                Err(e) => eprintln!("❌ Could not download the cheat collection: {}", e),
+            }
+            Ok(())
+        }
+        Some(Commands::Cache { action }) => run_cache_command(&Cache::for_config(&config_path, &settings), action),
+        Some(Commands::Cheats { action: CheatsAction::Show { rom } }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            let Some(roms) = scan_library(Path::new(&roms_dir)) else { return Ok(()) };
+            match rom.resolve(Path::new(&roms_dir), &roms).map(|i| &roms[i]) {
+                Ok(game) => match cheats::find(game, &config_path) {
+                    Some(file) => println!("🃏 {}", file.display()),
+                    None => println!("📭 No cheat file for {}; put a .cht next to it or run 'cheats update'.", game.path.display()),
+                },
+                Err(e) => eprintln!("🚫 {}", e),
+            }
+            Ok(())
+        }
+        #[cfg(feature = "install")]
+        Some(Commands::Discover { action: DiscoverAction::Homebrew { picks, system, sources } }) => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            discover_homebrew(&config_path, &roms_dir, &settings, &sources, system.as_deref(), &picks)
+        }
+        #[cfg(feature = "install")]
+        Some(Commands::InstallEmulator { name, cores }) => install_emulator(&config_path, &name, &cores),
+        #[cfg(feature = "install")]
+        Some(Commands::UpdateCheck) => check_for_updates(&config_path),
+        Some(Commands::Doctor) => run_doctor(&config_path),
+        Some(Commands::ReportBug { archive }) => report_bug(&config_path, archive),
+        #[cfg(feature = "self-update")]
+        Some(Commands::SelfUpdate) => run_self_update(),
+        None => {
+            let roms_dir = require_roms_dir(args.roms_dir, &settings);
+            check_orphans(&config_path, args.reap_orphans);
+            run_interactive(&config_path, &roms_dir, &settings, after_session, &[])
+        }
+    }
+}
+
+/// Returns the ROMs directory, falling back to the first configured one that exists, and exits
+/// with a usage error if there is none.
+fn require_roms_dir(roms_dir: Option<String>, settings: &Settings) -> String {
+    match roms_dir.or_else(|| settings.default_roms_dir().map(|dir| dir.to_string_lossy().to_string())) {
+        Some(dir) => dir,
+        None => Args::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "the ROMs directory is required: --roms-dir <DIR>, or roms_dirs in the configuration's settings",
+            )
+            .exit(),
+    }
+}
+
+/// Returns the post-session action configured in the settings, or `return`.
+fn after_session_setting(settings: &Settings) -> PostSessionAction {
+    let Some(action) = &settings.ui.after_session else { return PostSessionAction::default() };
+    action.parse().unwrap_or_else(|e| {
+        eprintln!("⚠️ Ignoring the ui.after_session setting: {}", e);
+        PostSessionAction::default()
+    })
+}
+
+/// Handles `install-emulator`: downloads and unpacks the emulator (and RetroArch cores), then
+/// adds or updates their configuration entries.
+#[cfg(feature = "install")]
+fn install_emulator(config_path: &Path, name: &str, cores: &[String]) -> io::Result<()> {
+    let Some(emulator) = emulator_install::find(name) else {
+        let known: Vec<&str> = emulator_install::INSTALLABLE_EMULATORS.iter().map(|e| e.id).collect();
+        eprintln!("❌ Don't know how to install '{}'. Installable emulators: {}", name, known.join(", "));
+        return Ok(());
+    };
+    if !cores.is_empty() && emulator.id != "retroarch" {
+        eprintln!("❌ --core only applies to RetroArch.");
+        return Ok(());
+    }
+    let url = match emulator.download_url() {
+        Ok(Some(url)) => url,
+        Ok(None) => {
+            println!("📦 No {} build can be installed automatically on this platform; get it from {}", emulator.name, emulator.download_page);
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let mut config = match EmulatorConfig::load(config_path) {
+        Ok(config) => config,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => EmulatorConfig::default(),
+        Err(e) => {
+            eprintln!("❌ Error loading emulator configuration from {}: {}", config_path.display(), e);
+            return Ok(());
+        }
+    };
+
+    let dir = emulator_install::install_dir(config_path, emulator);
+    println!("⬇️ Downloading {} from {}", emulator.name, url);
+    let executable = match emulator.install(&url, &dir) {
+        Ok(executable) => executable,
+        Err(e) => {
+            eprintln!("❌ Could not install {}: {}", emulator.name, e);
+            return Ok(());
+        }
+    };
+    println!("📦 Installed {} to {}", emulator.name, dir.display());
+
+    let mut entries = Vec::new();
+    if !emulator.extensions.is_empty() {
+        entries.push(Emulator {
+            name: emulator.name.to_string(),
+            path: executable.clone(),
+            extensions: emulator.extensions.iter().map(|ext| ext.to_string()).collect(),
+            ..Default::default()
+        });
+    }
+    let core_extensions = first_run::default_core_extensions();
+    for core in cores {
+        let core = core.trim_end_matches("_libretro");
+        println!("⬇️ Downloading the {} core", core);
+        let core_path = match emulator_install::install_retroarch_core(&dir, core) {
+            Ok(core_path) => core_path,
+            Err(e) => {
+                eprintln!("❌ Could not install the {} core: {}", core, e);
+                continue;
+            }
+        };
+        let Some(extensions) = core_extensions.get(format!("{}_libretro", core).as_str()) else {
+            println!("💡 {} is not suggested for any system; add an entry with 'rom-loader config add-emulator --core-path {}'", core, core_path.display());
+            continue;
+        };
+        entries.push(Emulator {
+            name: format!("{} ({})", emulator.name, core),
+            path: executable.clone(),
+            extensions: extensions.clone(),
+            core_path: Some(core_path),
+            ..Default::default()
+        });
+    }
+    if entries.is_empty() {
+        println!("💡 Install a core with --core to use RetroArch, e.g. 'rom-loader install-emulator retroarch --core snes9x'.");
+        return Ok(());
+    }
+
+    for entry in entries {
+        // Reinstalling only points an existing entry at the new files, keeping its other settings.
+        if let Some(existing) = config.find_emulator_mut(&entry.name) {
+            existing.path = entry.path;
+            existing.core_path = entry.core_path;
+            println!("🔧 Updated emulator '{}'.", existing.name);
+        } else {
+            println!("➕ Added emulator '{}'.", entry.name);
+            config.add_emulator(entry)?;
+        }
+    }
+    if let Err(e) = config.save_with_preview(config_path, false) {
+        eprintln!("❌ Error saving emulator configuration to {}: {}", config_path.display(), e);
+    }
+    Ok(())
+}
+
+/// Handles `self-update`: downloads the latest GitHub release binary and replaces this one.
+#[cfg(feature = "self-update")]
+fn run_self_update() -> io::Result<()> {
+    let result = self_update::backends::github::Update::configure()
+        .repo_owner("marcoiai")
+        .repo_name("rom-loader-cli")
+        .bin_name("rom-loader")
+        .current_version(self_update::cargo_crate_version!())
+        .no_confirm(!prompts::interactive())
+        .build()
+        .and_then(|update| update.update());
+    match result {
+        Ok(status) if status.is_updated() => println!("⬆️ Updated rom-loader to {}.", status.version()),
+        Ok(status) => println!("✅ rom-loader {} is the latest release.", status.version()),
+        Err(e) => eprintln!("❌ Self-update failed: {}", e),
+    }
+    Ok(())
+}
+
+/// Handles `report-bug`: writes the sanitized bug report archive.
+fn report_bug(config_path: &Path, archive: Option<PathBuf>) -> io::Result<()> {
+    let archive = archive.unwrap_or_else(|| {
+        PathBuf::from(format!("rom_loader_bug_report_{}.tar.gz", chrono::Local::now().format("%Y-%m-%d")))
+    });
+    let features: Vec<&str> = [
+        ("install", cfg!(feature = "install")),
+        ("obs", cfg!(feature = "obs")),
+        ("watch", cfg!(feature = "watch")),
+        ("self-update", cfg!(feature = "self-update")),
+        ("gpio", cfg!(feature = "gpio")),
+        ("zlib", cfg!(feature = "zlib")),
+    ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect();
+    let version_info = format!(
+        "rom-loader {}\nfeatures: {}",
+        env!("CARGO_PKG_VERSION"),
+        if features.is_empty() { "none".to_string() } else { features.join(", ") }
+    );
+    match bug_report::create(config_path, &archive, &version_info) {
+        Ok(files) => {
+            for file in &files {
+                println!("  {}", file);
+            }
+            println!("🐞 Wrote {} ({} files). Paths and credentials are redacted; check it before attaching it to an issue.", archive.display(), files.len());
+        }
+        Err(e) => eprintln!("❌ Could not write the bug report: {}", e),
+    }
+    Ok(())
+}
+
+/// Handles `update-check`: prints which emulators and cores are outdated.
+#[cfg(feature = "install")]
+fn check_for_updates(config_path: &Path) -> io::Result<()> {
+    let config = match EmulatorConfig::load(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Error loading emulator configuration from {}: {}", config_path.display(), e);
+            return Ok(());
+        }
+    };
+    println!("🔎 Checking {} emulators for updates...", config.emulators.len());
+    let checks = update_check::check_updates(&config.emulators);
+    let (mut outdated, mut unknown) = (0, 0);
+    for check in &checks {
+        match &check.status {
+            UpdateStatus::UpToDate { installed } => println!("  ✅ {} {} is up to date", check.name, installed),
+            UpdateStatus::Outdated { installed, latest } => {
+                outdated += 1;
+                println!("  ⬆️ {} {} → {} available", check.name, installed, latest);
+            }
+            UpdateStatus::Unknown { reason } => {
+                unknown += 1;
+                println!("  ❓ {}: {}", check.name, reason);
+            }
+        }
+    }
+    if outdated > 0 {
+        println!("⬆️ {} of {} checked are outdated.", outdated, checks.len());
+    } else if unknown == 0 {
+        println!("✅ Nothing to update.");
+    }
+    if unknown > 0 {
+        println!("⚠️ {} could not be checked.", unknown);
+    }
+    Ok(())
+}
+
+/// Handles `doctor`: detects what each emulator supports and saves it in the configuration.
+fn run_doctor(config_path: &Path) -> io::Result<()> {
+    let mut config = match EmulatorConfig::load(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Error loading emulator configuration from {}: {}", config_path.display(), e);
+            return Ok(());
+        }
+    };
+    println!("🩺 Checking {} emulators...", config.emulators.len());
+    let mut failed = 0;
+    for emulator in &mut config.emulators {
+        let executable = emulator.executable();
+        if !executable.is_file() {
+            failed += 1;
+            println!("  ❌ {}: executable not found: {}", emulator.name, emulator.path.display());
+            continue;
+        }
+        match Capabilities::detect(emulator) {
+            Ok(detected) => emulator.capabilities = Some(detected),
+            Err(e) => {
+                failed += 1;
+                println!("  ❌ {}: could not run {}: {}", emulator.name, executable.display(), e);
+                continue;
+            }
+        }
+        let Some(detected) = &emulator.capabilities else { continue };
+        let mut found = vec![detected.version.clone().unwrap_or_else(|| "unknown version".to_string())];
+        found.push(if detected.zip { "opens zipped ROMs" } else { "zipped ROMs are unpacked first" }.to_string());
+        if let Some(flag) = &detected.fullscreen_flag {
+            found.push(format!("fullscreen with {}", flag));
+        }
+        let quirks = quirks::for_emulator(emulator);
+        if quirks.absolute_path {
+            found.push("gets absolute ROM paths".to_string());
+        }
+        if quirks.strip_copier_header {
+            found.push("gets SNES ROMs without copier headers".to_string());
+        }
+        if !quirks.args.is_empty() {
+            found.push(format!("started with {}", quirks.args.join(" ")));
+        }
+        match capabilities::meets_min_version(emulator) {
+            Some(false) => {
+                failed += 1;
+                println!("  ❌ {}: {}; older than min_version {}",
+                    emulator.name,
+                    found.join(", "),
+                    emulator.min_version.as_deref().unwrap_or_default()
+                );
+            }
+            _ => println!("  ✅ {}: {}", emulator.name, found.join(", ")),
+        }
+    }
+    if failed > 0 {
+        println!("⚠️ {} of {} emulators need attention.", failed, config.emulators.len());
+    } else {
+        println!("✅ All emulators look fine.");
+    }
+    if config.save_with_preview(config_path, false)? {
+        println!("💾 Saved what was found to {}.", config_path.display());
+    }
+    Ok(())
+}
+
+/// Applies a `config` subcommand to the emulator configuration and saves it.
+///
+/// The file is only written after the whole edit succeeded, so a rejected change
+/// never leaves a half-edited configuration behind.
+fn run_config_command(config_path: &Path, action: ConfigAction, dry_run: bool) -> io::Result<()> {
+    if let ConfigAction::Init = action {
+        let emulators = first_run::detect_emulators();
+        for emulator in &emulators {
+            println!("  🔍 Found {}: {}", emulator.name, emulator.path.display());
+        }
+        match first_run::write_starter_config(config_path, &emulators) {
+            Ok(()) => println!("📝 Wrote a starter configuration with {} emulators to {}", emulators.len(), config_path.display()),
+            Err(e) => eprintln!("❌ Could not write the starter configuration: {}", e),
+        }
+        return Ok(());
+    }
+    let mut config = match EmulatorConfig::load(config_path) {
+        Ok(config) => config,
+        // Adding the first emulator is allowed to create the file.
+        Err(e) if e.kind() == io::ErrorKind::NotFound && matches!(action, ConfigAction::AddEmulator { .. }) => {
+            EmulatorConfig::default()
+        }
+        Err(e) => {
+            eprintln!("❌ Error loading emulator configuration from {}: {}", config_path.display(), e);
+            return Ok(());
+        }
+    };
+
+    let result = match action {
+        ConfigAction::Init => unreachable!("config init runs before the configuration is loaded"),
+        ConfigAction::AddEmulator { name, path, extensions, core_path, system_name } => {
+            let extensions = extensions
+                .iter()
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .filter(|ext| !ext.is_empty())
+                .collect();
+            let emulator = Emulator { name, path, extensions, core_path, system_name, ..Default::default() };
+            if !emulator.executable().exists() {
+                eprintln!("⚠️ Warning: Emulator executable not found: {}", emulator.path.display());
+            }
+            let message = format!("➕ Added emulator '{}'.", emulator.name);
+            config.add_emulator(emulator).map(|_| message)
+        }
+        ConfigAction::RemoveEmulator { name } => config
+            .remove_emulator(&name)
+            .map(|removed| format!("➖ Removed emulator '{}'.", removed.name)),
+        ConfigAction::Set { name, field, value } => config
+            .set_field(&name, &field, &value)
+            .map(|_| format!("✏️ Set {} of '{}' to '{}'.", field, name, value)),
+        ConfigAction::SetSystem { system, emulator } => {
+            let emulator = emulator.unwrap_or_default();
+            config.set_system_emulator(&system, &emulator).map(|_| match emulator.as_str() {
+                "" => format!("✏️ {} games are matched by extension again.", system),
+                name => format!("✏️ {} games now run with '{}'.", system, name),
+            })
+        }
+    };
+
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            if config.save_with_preview(config_path, dry_run)? {
+                println!("💾 Saved emulator configuration to: {}", config_path.display());
+            }
+        }
+        Err(e) => eprintln!("❌ {}", e),
+    }
+    Ok(())
+}
+
+/// Offers to keep using an emulator the user picked from several, for the game's system (in
+/// the configuration's `systems` section) or for the game (in the state store).
+fn remember_choice(
+    config_path: &Path,
+    state: &mut StateStore,
+    state_path: &Path,
+    emulator: &Emulator,
+    game: &Game,
+    command_input: &mut CommandInput,
+) -> io::Result<()> {
+    match prompt_remember_choice(emulator, game, command_input)? {
+        RememberChoice::No => {}
+        RememberChoice::System => {
+            let Some(system) = game.system() else { return Ok(()) };
+            // Edited on disk, so the configuration watcher picks the mapping up.
+            let saved = EmulatorConfig::load(config_path)
+                .and_then(|mut config| config.set_system_emulator(system.id, &emulator.name).map(|_| config))
+                .and_then(|config| config.save(config_path));
+            match saved {
+                Ok(()) => println!("🧠 {} games will run with {} from now on ('config set-system {}' undoes it).", system.name, emulator.name, system.id),
+                Err(e) => eprintln!("⚠️ Could not save the configuration: {}", e),
+            }
+        }
+        RememberChoice::Game => {
+            state.remember_emulator(&game.path, &emulator.name);
+            match state.save(state_path) {
+                Ok(()) => println!("🧠 {} will be suggested for this game from now on.", emulator.name),
+                Err(e) => eprintln!("⚠️ Could not save state: {}", e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the game list once, without starting the interactive browser.
+fn list_games(config_path: &Path, roms_dir: &str, settings: &Settings, filter: &ListFilter) -> io::Result<()> {
+    let Some(emulator_config) = load_emulator_config(config_path) else { return Ok(()) };
+    let state = StateStore::load(&StateStore::path_for_config(config_path)).unwrap_or_default();
+    let metadata = MetadataDb::load(&MetadataDb::path_for_config(config_path)).unwrap_or_else(|e| {
+        eprintln!("⚠️ Could not load game metadata, showing file names only: {}", e);
+        MetadataDb::default()
+    });
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let variants = Variants::group(&roms, &variants::preferred_languages(&settings.language_priority));
+    let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, BTreeSet::new());
+    display_rom_list(&roms, &emulator_config, &state, &metadata, &variants, filter, &appearance);
+    Ok(())
+}
+
+/// Hides games from the game list, or shows them again, and saves the choice in the state store.
+fn hide_games(config_path: &Path, roms_dir: &str, selection: &[GameRef], hide: bool) -> io::Result<()> {
+    let state_path = StateStore::path_for_config(config_path);
+    let mut state = match StateStore::load(&state_path) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    for index in select_games(roms_dir, &roms, selection) {
+        let game = &roms[index];
+        report_hidden(game, state.set_hidden(&game.path, hide), hide);
+    }
+    state.save(&state_path)
+}
+
+/// Moves the selected games to the trash after confirmation.
+fn delete_games(config_path: &Path, roms_dir: &str, selection: &[GameRef]) -> io::Result<()> {
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let mut command_input = CommandInput::default();
+    let mut journal = open_journal(config_path, "delete");
+    for index in select_games(roms_dir, &roms, selection) {
+        let game = &roms[index];
+        if confirm_delete(game, &mut command_input)? {
+            delete_game(config_path, game, journal.as_mut());
+        }
+    }
+    Ok(())
+}
+
+/// Finds the games named on the command line by number or ID, skipping (and reporting) the
+/// ones that don't exist.
+///
+/// # Returns
+/// The indices of the games in `roms`.
+fn select_games(roms_dir: &str, roms: &[Game], selection: &[GameRef]) -> Vec<usize> {
+    selection
+        .iter()
+        .filter_map(|rom| {
+            rom.resolve(Path::new(roms_dir), roms)
+                .inspect_err(|e| println!("🚫 Skipping {}: {}", rom, e))
+                .ok()
+        })
+        .collect()
+}
+
+/// Asks whether a game should really be deleted; only "y" or "yes" confirm.
+///
+/// `--yes` confirms without asking and `--no-input` keeps the game.
+fn confirm_delete(game: &Game, command_input: &mut CommandInput) -> io::Result<bool> {
+    print!("🗑️ Move {} to the trash? [y/N]: ", game.path.display());
+    io::stdout().flush()?;
+    let confirmed = match prompts::automatic_answer(false) {
+        Some(answer) => {
+            println!("{}", if answer { "yes" } else { "no" });
+            answer
+        }
+        None => {
+            let answer = command_input.read_line()?;
+            answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes")
+        }
+    };
+    if !confirmed {
+        println!("Kept {}.", game.path.file_name().unwrap_or_default().to_string_lossy());
+    }
+    Ok(confirmed)
+}
+
+/// Starts a batch in the undo journal, warning instead of failing if the journal is unusable.
+fn open_journal(config_path: &Path, command: &str) -> Option<BatchRecorder> {
+    BatchRecorder::begin(config_path, command)
+        .inspect_err(|e| eprintln!("⚠️ Changes won't be undoable: {}", e))
+        .ok()
+}
+
+/// Moves a game's file (or directory) and its companion files to the system trash and drops
+/// what was stored about it.
+///
+/// The move is recorded in `journal`, so `undo` can bring the game back from the trash.
+///
+/// # Returns
+/// Whether the game was deleted.
+fn delete_game(config_path: &Path, game: &Game, journal: Option<&mut BatchRecorder>) -> bool {
+    // MSU-1 packs and soft patches are useless without their ROM.
+    let companions = Companions::find(game).map(|c| c.files()).unwrap_or_default();
+    if let Err(e) = trash::delete(&game.path) {
+        eprintln!("❌ Could not move {} to the trash: {}", game.path.display(), e);
+        return false;
+    }
+    println!("🗑️ Moved to the trash: {} ('undo' restores it)", game.path.display());
+    let mut trashed = vec![game.path.clone()];
+    for companion in companions {
+        match trash::delete(&companion) {
+            Ok(()) => trashed.push(companion),
+            Err(e) => eprintln!("⚠️ Could not move {} to the trash: {}", companion.display(), e),
+        }
+    }
+    if let Some(journal) = journal {
+        for path in trashed {
+            if let Err(e) = journal.record(Operation::Trashed { path }) {
+                eprintln!("⚠️ Could not record the deletion in the undo journal: {}", e);
+            }
+        }
+    }
+
+    // Otherwise `verify` would report the file as missing from then on.
+    let state_path = StateStore::path_for_config(config_path);
+    let hashes_path = HashStore::path_for_config(config_path);
+    let forgotten = StateStore::load(&state_path)
+        .and_then(|mut state| {
+            state.forget(&game.path);
+            state.save(&state_path)
+        })
+        .and_then(|()| HashStore::load(&hashes_path))
+        .and_then(|mut hashes| {
+            hashes.forget(&game.path);
+            hashes.save(&hashes_path)
+        });
+    if let Err(e) = forgotten {
+        eprintln!("⚠️ Could not update the library data for {}: {}", game.path.display(), e);
+    }
+    true
+}
+
+/// Tells the user whether hiding or unhiding a game changed anything.
+fn report_hidden(game: &Game, changed: bool, hide: bool) {
+    let file_name = game.path.file_name().unwrap_or_default().to_string_lossy();
+    match (changed, hide) {
+        (true, true) => println!("🙈 Hidden: {} ('l --hidden' lists hidden games)", file_name),
+        (true, false) => println!("👀 Shown again: {}", file_name),
+        (false, true) => println!("🙈 Already hidden: {}", file_name),
+        (false, false) => println!("👀 Not hidden: {}", file_name),
+    }
+}
+
+/// Creates or restores a backup of the setup stored next to the configuration file.
+fn run_backup_command(config_path: &Path, action: BackupAction) -> io::Result<()> {
+    let (result, archive, verb) = match action {
+        BackupAction::Create { archive } => {
+            let archive = archive.unwrap_or_else(|| {
+                PathBuf::from(format!("rom_loader_backup_{}.zip", chrono::Local::now().format("%Y-%m-%d")))
+            });
+            (backup::create(config_path, &archive), archive, "Backed up")
+        }
+        BackupAction::Restore { archive, force } => (backup::restore(config_path, &archive, force), archive, "Restored"),
+    };
+    match result {
+        Ok(files) => {
+            for file in &files {
+                println!("  {}", file.display());
+            }
+            println!("💾 {} {} files ({}).", verb, files.len(), archive.display());
+        }
+        Err(e) => eprintln!("❌ {}", e),
+    }
+    Ok(())
+}
+
+/// Lists or prunes the items in the cache directory.
+fn run_cache_command(cache: &Cache, action: CacheAction) -> io::Result<()> {
+    let limit = |max_bytes: Option<u64>| max_bytes.map_or_else(|| "no limit".to_string(), |max| format!("limit {}", preload::format_size(max)));
+    match action {
+        CacheAction::Show => {
+            let items = cache.items()?;
+            let total: u64 = items.iter().map(|item| item.size).sum();
+            println!("🗄️ {} items, {} in {} ({}):", items.len(), preload::format_size(total), cache.dir().display(), limit(cache.max_bytes()));
+            for item in &items {
+                let name = item.path.strip_prefix(cache.dir()).unwrap_or(&item.path);
+                println!("  {:>8}  {}  {}", preload::format_size(item.size), history::format_timestamp(item.last_used), name.display());
+            }
+        }
+        CacheAction::Prune { max_mb, dry_run } => {
+            let Some(max_bytes) = max_mb.map(|mb| mb * 1024 * 1024).or(cache.max_bytes()) else {
+                eprintln!("❌ The cache has no size limit; set cache_max_mb in the settings or give --max-mb.");
+                return Ok(());
+            };
+            let removed = match cache.prune(max_bytes, dry_run) {
+                Ok(removed) => removed,
+                Err(e) => {
+                    eprintln!("❌ Could not prune the cache in {}: {}", cache.dir().display(), e);
+                    return Ok(());
+                }
+            };
+            for item in &removed {
+                println!("  🗑️ {} ({})", item.path.display(), preload::format_size(item.size));
+            }
+            let freed = preload::format_size(removed.iter().map(|item| item.size).sum());
+            match (removed.len(), dry_run) {
+                (0, _) => println!("✅ The cache already fits in {}.", preload::format_size(max_bytes)),
+                (count, true) => println!("🔍 Would remove {} items, freeing {}.", count, freed),
+                (count, false) => println!("🧹 Removed {} items, freeing {}.", count, freed),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Imports offline metadata database files into the metadata store next to the configuration.
+fn import_metadata(config_path: &Path, files: &[PathBuf]) -> io::Result<()> {
+    let metadata_path = MetadataDb::path_for_config(config_path);
+    let mut metadata = match MetadataDb::load(&metadata_path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(());
+        }
+    };
+
+    let mut imported_any = false;
+    for file in files {
+        match metadata.import_file(file) {
+            Ok(count) => {
+                println!("📚 Imported {} games from {}", count, file.display());
+                imported_any = true;
+            }
+            Err(e) => eprintln!("❌ {}", e),
+        }
+    }
+
+    if imported_any {
+        metadata.save(&metadata_path)?;
+        println!("💾 Saved {} metadata entries to: {}", metadata.entries.len(), metadata_path.display());
+    }
+    Ok(())
+}
+
+/// Lists the games of the homebrew feeds and downloads the picked ones into the library,
+/// recording them in the metadata store as homebrew.
+#[cfg(feature = "install")]
+fn discover_homebrew(
+    config_path: &Path,
+    roms_dir: &str,
+    settings: &Settings,
+    sources: &[String],
+    system: Option<&str>,
+    picks: &[usize],
+) -> io::Result<()> {
+    let sources = if sources.is_empty() { &settings.homebrew_sources } else { sources };
+    if sources.is_empty() {
+        eprintln!("❌ No homebrew feeds configured; add them to homebrew_sources in the settings, or pass --source.");
+        return Ok(());
+    }
+    let mut entries = Vec::new();
+    for source in sources {
+        match homebrew::load_feed(source) {
+            Ok(games) => entries.extend(games),
+            Err(e) => eprintln!("⚠️ Skipping feed {}: {}", source, e),
+        }
+    }
+    if let Some(system) = system {
+        entries.retain(|entry| entry.system.eq_ignore_ascii_case(system));
+    }
+    if entries.is_empty() {
+        println!("📭 The feeds list no games{}.", system.map(|s| format!(" for {}", s)).unwrap_or_default());
+        return Ok(());
+    }
+
+    let metadata_path = MetadataDb::path_for_config(config_path);
+    let mut metadata = MetadataDb::load(&metadata_path).unwrap_or_default();
+    println!("🎁 Homebrew games:");
+    for (i, entry) in entries.iter().enumerate() {
+        let mut details: Vec<&str> = [&entry.author, &entry.year, &entry.license].into_iter().filter_map(|d| d.as_deref()).collect();
+        details.insert(0, &entry.system);
+        let downloaded = metadata.entries.iter().any(|m| m.homebrew && m.title == entry.title);
+        println!("  {:>3}. {} ({}){}", i + 1, entry.title, details.join(", "), if downloaded { " ✅ in library" } else { "" });
+    }
+
+    let picks = if !picks.is_empty() {
+        picks.to_vec()
+    } else if prompts::interactive() {
+        print!("Download which games? (numbers, Enter for none): ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        let mut picks = Vec::new();
+        for word in answer.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty()) {
+            match word.parse() {
+                Ok(number) => picks.push(number),
+                Err(_) => {
+                    eprintln!("❌ '{}' is not a number from the list.", word);
+                    return Ok(());
+                }
+            }
+        }
+        picks
+    } else {
+        return Ok(());
+    };
+
+    let cache = Cache::for_config(config_path, settings);
+    let mut downloaded_any = false;
+    for number in picks {
+        let Some(entry) = number.checked_sub(1).and_then(|i| entries.get(i)) else {
+            eprintln!("❌ There is no game {} in the list.", number);
+            continue;
+        };
+        println!("⬇️ Downloading {}...", entry.title);
+        match homebrew::download(entry, Path::new(roms_dir), &cache) {
+            Ok(roms) => {
+                for rom in roms {
+                    metadata.insert(homebrew::metadata_for(entry, &rom));
+                    println!("✅ {}", rom.display());
+                }
+                downloaded_any = true;
+            }
+            Err(e) => eprintln!("❌ {}: {}", entry.title, e),
+        }
+    }
+    if downloaded_any {
+        metadata.save(&metadata_path)?;
+        println!("💡 List them with 'list --search is:homebrew'.");
+    }
+    Ok(())
+}
+
+/// Lists, adds or removes macros.
+fn run_macro_command(config_path: &Path, action: MacroAction) -> io::Result<()> {
+    let macros_path = MacroStore::path_for_config(config_path);
+    let mut store = match MacroStore::load(&macros_path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    match action {
+        MacroAction::List => {
+            if store.macros.is_empty() {
+                println!("📭 No macros saved yet. Record one with 'record <name>' in the interactive browser.");
+            }
+            for (name, commands) in &store.macros {
+                println!("  {}: {}", name, commands.join("; "));
+            }
+            return Ok(());
+        }
+        MacroAction::Add { name, commands } => {
+            let commands = macros::parse_commands(&commands);
+            if commands.is_empty() {
+                eprintln!("❌ A macro needs at least one command.");
+                return Ok(());
+            }
+            store.macros.insert(name.clone(), commands);
+            println!("💾 Macro '{}' saved.", name);
+        }
+        MacroAction::Remove { name } => {
+            if store.macros.remove(&name).is_none() {
+                eprintln!("❌ No macro named '{}'.", name);
+                return Ok(());
+            }
+            println!("🗑️ Macro '{}' removed.", name);
+        }
+        MacroAction::Run { .. } => unreachable!("macro run starts the interactive browser"),
+    }
+    if let Err(e) = store.save(&macros_path) {
+        eprintln!("❌ Could not save macros: {}", e);
+    }
+    Ok(())
+}
+
+/// Lists or edits playlists.
+fn run_playlist_command(config_path: &Path, roms_dir: &str, action: PlaylistAction) -> io::Result<()> {
+    let playlists_path = PlaylistStore::path_for_config(config_path);
+    let mut store = match PlaylistStore::load(&playlists_path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let id_of = |index: usize| game_ids::game_id(Path::new(roms_dir), &roms[index]);
+    match action {
+        PlaylistAction::List => {
+            if store.playlists.is_empty() {
+                println!("📭 No playlists yet. Make one with 'playlist add <name> <ROM>...'.");
+            }
+            let by_id: HashMap<String, &Game> = (0..roms.len()).map(|index| (id_of(index), &roms[index])).collect();
+            for (name, ids) in &store.playlists {
+                println!("🎶 {} ({} games)", name, ids.len());
+                for id in ids {
+                    match by_id.get(id) {
+                        Some(game) => println!("  {} {}", id, game.path.file_name().unwrap_or_default().to_string_lossy()),
+                        None => println!("  {} (not in this library)", id),
+                    }
+                }
+            }
+            return Ok(());
+        }
+        PlaylistAction::Add { name, roms: selection } => {
+            let playlist = store.playlists.entry(name.clone()).or_default();
+            let before = playlist.len();
+            for id in select_games(roms_dir, &roms, &selection).into_iter().map(id_of) {
+                if !playlist.contains(&id) {
+                    playlist.push(id);
+                }
+            }
+            let (added, total) = (playlist.len() - before, playlist.len());
+            if total == 0 {
+                store.playlists.remove(&name);
+                return Ok(());
+            }
+            println!("💾 Added {} games to '{}', which now has {}.", added, name, total);
+        }
+        PlaylistAction::Remove { name, roms: selection } => {
+            let Some(playlist) = store.playlists.get_mut(&name) else {
+                eprintln!("❌ No playlist named '{}'.", name);
+                return Ok(());
+            };
+            if selection.is_empty() {
+                store.playlists.remove(&name);
+                println!("🗑️ Playlist '{}' removed.", name);
+            } else {
+                let ids: Vec<String> = select_games(roms_dir, &roms, &selection).into_iter().map(id_of).collect();
+                let before = playlist.len();
+                playlist.retain(|id| !ids.contains(id));
+                println!("🗑️ Removed {} games from '{}'.", before - playlist.len(), name);
+            }
+        }
+    }
+    if let Err(e) = store.save(&playlists_path) {
+        eprintln!("❌ Could not save playlists: {}", e);
+    }
+    Ok(())
+}
+
+/// Launches the games of a playlist one after another, in order or shuffled.
+fn play_playlist(config_path: &Path, roms_dir: &str, name: &str, shuffle: bool) -> io::Result<()> {
+    let store = match PlaylistStore::load(&PlaylistStore::path_for_config(config_path)) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return Ok(());
+        }
+    };
+    let Some(ids) = store.playlists.get(name) else {
+        eprintln!("❌ No playlist named '{}'; 'playlist list' shows them.", name);
+        return Ok(());
+    };
+    let Some(emulator_config) = load_emulator_config(config_path) else { return Ok(()) };
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let by_id: HashMap<String, &Game> = roms.iter().map(|game| (game_ids::game_id(Path::new(roms_dir), game), game)).collect();
+    let mut games: Vec<&Game> = Vec::new();
+    for id in ids {
+        match by_id.get(id) {
+            Some(game) => games.push(game),
+            None => println!("🚫 Skipping {}: not in this library.", id),
+        }
+    }
+    if games.is_empty() {
+        println!("📭 None of the games of '{}' are in this library.", name);
+        return Ok(());
+    }
+    if shuffle {
+        playlists::shuffle(&mut games);
+    }
+    let state = StateStore::load(&StateStore::path_for_config(config_path)).unwrap_or_default();
+    let metadata = MetadataDb::load(&MetadataDb::path_for_config(config_path)).unwrap_or_default();
+    println!("🎶 Playing '{}': {} games{}.", name, games.len(), if shuffle { ", shuffled" } else { "" });
+    let played = play_in_turn(&games, config_path, &emulator_config, &state, &metadata);
+    if played < games.len() {
+        println!("⏹️ Playlist stopped after {} of {} games.", played, games.len());
+    } else {
+        println!("🏁 Playlist finished.");
+    }
+    Ok(())
+}
+
+/// Launches games one after another, each as soon as the previous emulator exits.
+///
+/// Ctrl-C during a game stops after it, instead of starting the next one.
+///
+/// # Returns
+/// How many games were launched.
+fn play_in_turn(games: &[&Game], config_path: &Path, emulator_config: &EmulatorConfig, state: &StateStore, metadata: &MetadataDb) -> usize {
+    let extension_to_emulator = emulator_config.extension_map();
+    for (played, game) in games.iter().enumerate() {
+        if played > 0 && interrupt::requested() {
+            return played;
+        }
+        println!("\n▶️ Up now: {} ({} more queued)",
+            game.path.file_name().unwrap_or_default().to_string_lossy(),
+            games.len() - played - 1
+        );
+        match suggested_emulator(game, emulator_config, &extension_to_emulator, state) {
+            Some(emulator) => launch_game(emulator, game, config_path, metadata, &LaunchOverrides::default()),
+            None => report_missing_emulator(game),
+        }
+    }
+    games.len()
+}
+
+/// Launches a single game from the command line, optionally with an emulator other than the suggested one.
+///
+/// Choosing an emulator here is a one-off and is not learned as a preference.
+fn launch_single(config_path: &Path, roms_dir: &str, rom: &GameRef, with: Option<&str>, overrides: &LaunchOverrides) -> io::Result<()> {
+    let Some(emulator_config) = load_emulator_config(config_path) else { return Ok(()) };
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let game = match rom.resolve(Path::new(roms_dir), &roms) {
+        Ok(index) => &roms[index],
+        Err(e) => {
+            eprintln!("🚫 {}", e);
+            return Ok(());
+        }
+    };
+    let state = StateStore::load(&StateStore::path_for_config(config_path)).unwrap_or_default();
+    let metadata = MetadataDb::load(&MetadataDb::path_for_config(config_path)).unwrap_or_default();
+
+    let emulator = match with {
+        Some(name) => match emulator_config.find_emulator(name) {
+            Some(emulator) => emulator,
+            None => {
+                eprintln!("❌ No emulator named '{}' in the configuration.", name);
+                return Ok(());
+            }
+        },
+        None => match suggested_emulator(game, &emulator_config, &emulator_config.extension_map(), &state) {
+            Some(emulator) => emulator,
+            None => {
+                report_missing_emulator(game);
+                return Ok(());
+            }
+        },
+    };
+    launch_game(emulator, game, config_path, &metadata, overrides);
+    Ok(())
+}
+
+/// Launches each selected ROM for a few seconds and reports the ones that failed to start.
+fn run_test_launch(
+    config_path: &Path,
+    roms_dir: &str,
+    selection: &[GameRef],
+    all: bool,
+    seconds: u64,
+    report_path: Option<&Path>,
+) -> io::Result<()> {
+    if !all && selection.is_empty() {
+        eprintln!("❌ Pass ROM numbers or IDs to test, or --all to test the whole library.");
+        return Ok(());
+    }
+    let Some(emulator_config) = load_emulator_config(config_path) else { return Ok(()) };
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let state = StateStore::load(&StateStore::path_for_config(config_path)).unwrap_or_default();
+
+    let indices: Vec<usize> = if all { (0..roms.len()).collect() } else { select_games(roms_dir, &roms, selection) };
+    let games: Vec<&Game> = indices.iter().map(|&index| &roms[index]).collect();
+
+    println!("🧪 Test-launching {} ROMs for {} seconds each...", games.len(), seconds);
+    let results = test_games(&emulator_config, &state, &games, seconds);
+    print_test_report("Test-Launch Report", &results, report_path)?;
+    Ok(())
+}
+
+/// Boots a test ROM for each system with its default emulator for a few seconds and reports
+/// the systems whose emulator failed to start or exited early.
+fn run_core_smoke_test(config_path: &Path, seconds: u64, report_path: Option<&Path>) -> io::Result<()> {
+    let Some(emulator_config) = load_emulator_config(config_path) else { return Ok(()) };
+    let dir = test_launch::smoke_test_dir(config_path);
+    if let Err(e) = test_launch::write_builtin_roms(&dir) {
+        eprintln!("⚠️ Could not write the built-in test ROMs to {}: {}", dir.display(), e);
+    }
+    let Some(roms) = scan_library(&dir) else { return Ok(()) };
+
+    // The first ROM of each system is its test ROM.
+    let mut by_system: BTreeMap<&str, &Game> = BTreeMap::new();
+    for game in &roms {
+        if let Some(system) = game.system() {
+            by_system.entry(system.id).or_insert(game);
+        }
+    }
+    let extension_to_emulator = emulator_config.extension_map();
+    let untested: Vec<&str> = systems::SYSTEMS
+        .iter()
+        .filter(|system| !by_system.contains_key(system.id))
+        .filter(|system| {
+            emulator_config.systems.contains_key(system.id) || system.extensions.iter().any(|ext| extension_to_emulator.contains_key(*ext))
+        })
+        .map(|system| system.name)
+        .collect();
 
-// Supported ROM extensions that the scanner will look for.
-const SUPPORTED_ROM_EXTENSIONS: &[&str] = &["nes", "snes", "smc", "sfc", "gb", "gba", "n64", "ps1", "md", "gen", "bin", "zip", "7z"];
+    let games: Vec<&Game> = by_system.into_values().collect();
+    println!("🧪 Booting a test ROM for {} systems for {} seconds each...", games.len(), seconds);
+    let results = test_games(&emulator_config, &StateStore::default(), &games, seconds);
+    if !untested.is_empty() {
+        println!("💡 Not tested, no test ROM: {}. Put a homebrew ROM for each in {}", untested.join(", "), dir.display());
+    }
+    let ok = print_test_report("Core Smoke Test", &results, report_path)?;
+    exit_on_failure(ok)
+}
 
-/// Command-line arguments for the ROM Loader.
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    /// Path to the directory containing ROMs.
-    #[arg(short, long, value_name = "DIR")]
-    roms_dir: String,
+/// Launches each game with its suggested emulator for `seconds`, printing how each went.
+///
+/// Without a display the emulators get a virtual one, when `xvfb-run` is installed.
+fn test_games(emulator_config: &EmulatorConfig, state: &StateStore, games: &[&Game], seconds: u64) -> Vec<TestResult> {
+    let extension_to_emulator = emulator_config.extension_map();
+    let mut results = Vec::new();
+    let _busy = interrupt::Busy::start();
+    for (position, game) in games.iter().enumerate() {
+        if interrupt::requested() {
+            println!("⏹️ Test launches stopped after {} of {} ROMs.", position, games.len());
+            break;
+        }
+        println!("\n[{}/{}] {}", position + 1, games.len(), game.path.file_name().unwrap_or_default().to_string_lossy());
 
-    /// Path to the JSON configuration file for emulators.
-    #[arg(short, long, value_name = "FILE", default_value = "emulators.json")]
-    config_file: String,
+        let Some(emulator) = suggested_emulator(game, emulator_config, &extension_to_emulator, state) else {
+            println!("  ⏭️ No emulator configured, skipped.");
+            results.push(TestResult { rom: game.path.clone(), emulator: None, outcome: TestOutcome::NoEmulator });
+            continue;
+        };
+        let hooks = LaunchHooks::before_launch(emulator, game);
+        let system_name = effective_system_name(emulator, game);
+        let outcome = match rom_launcher::build_command(
+            &emulator.executable(),
+            game.launch_path(),
+            &emulator.name,
+            emulator.core().as_ref(),
+            system_name.as_ref(),
+            hooks.options(),
+        ) {
+            Ok(command) => match test_launch::headless_wrapper() {
+                Some(wrapper) => test_launch::run_test(rom_launcher::wrap_command(wrapper, command), seconds),
+                None => test_launch::run_test(command, seconds),
+            },
+            Err(e) => TestOutcome::FailedToStart { error: e.to_string() },
+        };
+        hooks.after_exit();
+
+        match &outcome {
+            TestOutcome::Running => println!("  ✅ Still running after {}s.", seconds),
+            TestOutcome::ExitedEarly { exit_code, .. } => match exit_code {
+                Some(code) => println!("  ❌ Exited early with code {}.", code),
+                None => println!("  ❌ Exited early (terminated by a signal)."),
+            },
+            TestOutcome::FailedToStart { error } => println!("  ❌ Failed to start: {}", error),
+            TestOutcome::NoEmulator => {}
+        }
+        results.push(TestResult { rom: game.path.clone(), emulator: Some(emulator.name.clone()), outcome });
+    }
+    results
 }
 
-fn main() -> io::Result<()> {
-    // Parse command-line arguments.
-    let args = Args::parse();
+/// Prints the summary of test launches and writes the JSON report, if asked for.
+///
+/// # Returns
+/// Whether every tested ROM worked.
+fn print_test_report(title: &str, results: &[TestResult], report_path: Option<&Path>) -> io::Result<bool> {
+    let broken: Vec<&TestResult> = results.iter().filter(|r| r.outcome.is_broken()).collect();
+    let skipped = results.iter().filter(|r| matches!(r.outcome, TestOutcome::NoEmulator)).count();
+    println!("\n--- {} ---", title);
+    println!("  Tested: {}, working: {}, broken: {}, skipped: {}",
+        results.len() - skipped,
+        results.len() - skipped - broken.len(),
+        broken.len(),
+        skipped
+    );
+    for result in &broken {
+        println!("  ❌ {} [{}]", result.rom.display(), result.emulator.as_deref().unwrap_or("?"));
+        if let TestOutcome::ExitedEarly { stderr_tail, .. } = &result.outcome {
+            for line in stderr_tail.lines() {
+                println!("       {}", line);
+            }
+        }
+    }
+    println!("{}\n", "-".repeat(title.len() + 8));
 
-    println!("🚀 Starting ROM Loader...");
+    if let Some(path) = report_path {
+        test_launch::write_report(results, path)?;
+        println!("📝 Report written to: {}", path.display());
+    }
+    Ok(broken.is_empty())
+}
 
-    // 1. Load Emulator Configuration
-    let config_path = PathBuf::from(&args.config_file);
-    let emulator_config = match EmulatorConfig::load(&config_path) {
-        Ok(config) => {
-            println!("✅ Loaded emulator configuration from: {}", config_path.display());
-            config
+/// Prints the launch history, applying the `history` subcommand's filters.
+fn show_history(
+    config_path: &Path,
+    rom_filter: Option<&str>,
+    emulator_filter: Option<&str>,
+    failed_only: bool,
+    unique: bool,
+    limit: usize,
+) -> io::Result<()> {
+    let history = LaunchHistory::for_config(config_path);
+    let rom_filter = rom_filter.map(|f| f.to_lowercase());
+    let emulator_filter = emulator_filter.map(|f| f.to_lowercase());
+
+    // Newest first, so `--unique` keeps the latest launch of each ROM.
+    let mut entries: Vec<HistoryEntry> = history.load()?
+        .into_iter()
+        .rev()
+        .filter(|e| !failed_only || !e.success)
+        .filter(|e| rom_filter.as_ref().is_none_or(|f| {
+            e.rom.file_name().unwrap_or_default().to_string_lossy().to_lowercase().contains(f)
+        }))
+        .filter(|e| emulator_filter.as_ref().is_none_or(|f| e.emulator.to_lowercase().contains(f)))
+        .collect();
+
+    let mut launch_counts: HashMap<PathBuf, usize> = HashMap::new();
+    if unique {
+        for entry in &entries {
+            *launch_counts.entry(entry.rom.clone()).or_insert(0) += 1;
         }
+        let mut seen = Vec::new();
+        entries.retain(|e| {
+            if seen.contains(&e.rom) {
+                false
+            } else {
+                seen.push(e.rom.clone());
+                true
+            }
+        });
+    }
+
+    if entries.is_empty() {
+        println!("📭 No matching launches in the history.");
+        return Ok(());
+    }
+
+    println!("\n--- Launch History ---");
+    for entry in entries.iter().take(limit) {
+        let status = if entry.success {
+            "✅".to_string()
+        } else if let Some(error) = &entry.error {
+            format!("❌ {}", error)
+        } else {
+            match entry.exit_code {
+                Some(code) => format!("❌ exit code {}", code),
+                None => "❌ terminated".to_string(),
+            }
+        };
+        let count = match launch_counts.get(&entry.rom) {
+            Some(1) => " (1 launch)".to_string(),
+            Some(count) => format!(" ({} launches)", count),
+            None => String::new(),
+        };
+        println!(
+            "  {}  {}  [{}]  {}  {}{}",
+            history::format_timestamp(entry.timestamp),
+            entry.rom.file_name().unwrap_or_default().to_string_lossy(),
+            entry.emulator,
+            history::format_duration(entry.duration_secs),
+            status,
+            count
+        );
+    }
+    println!("----------------------\n");
+    Ok(())
+}
+
+/// Opens manual number `which` of a game in the document viewer, listing the others.
+fn open_manual(config_path: &Path, roms_dir: &str, rom: &GameRef, which: usize) -> io::Result<()> {
+    let Some(roms) = scan_library(Path::new(roms_dir)) else { return Ok(()) };
+    let game = match rom.resolve(Path::new(roms_dir), &roms) {
+        Ok(index) => &roms[index],
         Err(e) => {
-            eprintln!("❌ Error loading emulator configuration from {}: {}", config_path.display(), e);
-            eprintln!("Please ensure 'emulators.json' exists and is correctly formatted.");
+            eprintln!("🚫 {}", e);
             return Ok(());
         }
     };
-
-    // Create a mapping from file extension to the preferred emulator.
-    // This allows quick lookup of which emulator to use for a given ROM extension.
-    let mut extension_to_emulator: HashMap<String, &Emulator> = HashMap::new();
-    for emulator in &emulator_config.emulators {
-        for ext in &emulator.extensions {
-            // Prioritize the first emulator found for an extension.
-            // In a more advanced setup, you might allow users to set preferences.
-            extension_to_emulator.entry(ext.to_lowercase()).or_insert(emulator);
+    let found = manuals::find(config_path, game);
+    if found.is_empty() {
+        println!("📭 No manual for {}; put a PDF named like the ROM next to it or into {}.",
+            game.path.file_name().unwrap_or_default().to_string_lossy(),
+            manuals::manuals_dir(config_path, game).display()
+        );
+        return Ok(());
+    }
+    let Some(manual) = which.checked_sub(1).and_then(|i| found.get(i)) else {
+        eprintln!("🚫 There is no manual number {} (found {}).", which, found.len());
+        return Ok(());
+    };
+    if found.len() > 1 {
+        for (i, path) in found.iter().enumerate() {
+            println!("  {}. {}", i + 1, path.display());
         }
     }
+    match manuals::open_in_viewer(manual) {
+        Ok(()) => println!("📖 Opened {}", manual.display()),
+        Err(e) => eprintln!("❌ {}", e),
+    }
+    Ok(())
+}
 
-    // 2. Scan for ROMs
-    let roms_dir_path = PathBuf::from(&args.roms_dir);
-    let rom_scanner = RomScanner::new(&roms_dir_path, SUPPORTED_ROM_EXTENSIONS);
+/// Prints the full details of a game: database metadata, play statistics, hashes and file details.
+///
+/// Games whose file name is not in the metadata database are looked up by CRC32 instead; for a
+/// zip or 7z holding a single ROM, that is the CRC32 of the ROM inside, as dats list it.
+fn print_game_info(config_path: &Path, game: &Game, id: &str, metadata: &MetadataDb, history: &LaunchHistory) {
+    let file_name = game.path.file_name().unwrap_or_default().to_string_lossy();
+    println!("\n--- {} ---", file_name);
 
-    let roms = match rom_scanner.scan_roms() {
-        Ok(r) => {
-            if r.is_empty() {
-                println!("⚠️ No supported ROMs found in {}.", roms_dir_path.display());
-                return Ok(());
+    // Directory-based games have no single image to hash.
+    let crc = match game.kind {
+        GameKind::File => match hashing::crc32_file(&game.path) {
+            Ok(crc) => Some(crc),
+            Err(e) => {
+                eprintln!("⚠️ Could not hash {}: {}", game.path.display(), e);
+                None
+            }
+        },
+        GameKind::Directory { .. } => None,
+    };
+    let members = match game.kind {
+        GameKind::File if hashing::is_archive(&game.path) => match hashing::archive_crc32s(&game.path) {
+            Ok(members) => members,
+            Err(e) => {
+                eprintln!("⚠️ Could not read archive {}: {}", game.path.display(), e);
+                Vec::new()
+            }
+        },
+        _ => Vec::new(),
+    };
+    let lookup_crc = match members.as_slice() {
+        [single] => Some(single.crc32.as_str()),
+        _ => crc.as_deref(),
+    };
+    let game_metadata = metadata.lookup(game).or_else(|| lookup_crc.and_then(|crc| metadata.lookup_crc(crc)));
+
+    match game_metadata {
+        Some(m) => {
+            println!("  Title:       {}", m.title);
+            let fields = [
+                ("Description", &m.description),
+                ("Year", &m.year),
+                ("Genre", &m.genre),
+                ("Developer", &m.developer),
+                ("Publisher", &m.publisher),
+                ("Players", &m.players),
+                ("Region", &m.region),
+                ("Rating", &m.rating),
+                ("Set", &m.set_name),
+                ("Clone of", &m.clone_of),
+            ];
+            for (label, value) in fields {
+                // Many databases repeat the title as the description.
+                if let Some(value) = value.as_ref().filter(|v| **v != m.title) {
+                    println!("  {:<12} {}", format!("{}:", label), value);
+                }
+            }
+            if let Some(parent) = arcade::missing_parent(game, m) {
+                println!("  ⚠️ Parent set '{}' is missing; this clone needs it to run.", parent);
             }
-            println!("📚 Found {} ROMs in {}:", r.len(), roms_dir_path.display());
-            r
         }
-        Err(e) => {
-            eprintln!("❌ Error scanning ROMs in {}: {}", roms_dir_path.display(), e);
-            return Ok(());
+        None => println!("  (No metadata found; import a database with 'metadata import <file>')"),
+    }
+
+    println!("  ID:          {}", id);
+    println!("  System:      {}", game.system().map_or("unknown", |s| s.name));
+    if let Some(source) = game.get_extension().and_then(systems::music_source).and_then(systems::find_by_id) {
+        println!("  Music from:  {}", source.name);
+    }
+    if game.kind == GameKind::File {
+        match rom_format::read_header(&game.path) {
+            Ok(Some(header)) => {
+                if let Some(label) = header.label() {
+                    println!("  Header:      {}", label);
+                }
+                if let Some(code) = &header.code {
+                    println!("  Game code:   {}", code);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("⚠️ Could not read the header of {}: {}", game.path.display(), e),
         }
-    };
+    }
+    if game.system().is_some_and(|s| s.id == "snes") {
+        if let Ok(Some(chip)) = rom_format::snes_coprocessor(&game.path) {
+            println!("  Chip:        {}", chip);
+        }
+    }
+    println!("  Path:        {}", game.path.display());
+    if let Some(companions) = Companions::find(game) {
+        if companions.has_msu1() {
+            println!("  MSU-1:       {} audio tracks{}", companions.msu_tracks.len(),
+                if companions.msu_data.is_some() { " and a data file" } else { "" });
+        }
+        if let Some(manifest) = &companions.manifest {
+            println!("  Manifest:    {}", manifest.display());
+        }
+        for patch in &companions.patches {
+            println!("  Patch:       {}", patch.display());
+        }
+    }
+    for manual in manuals::find(config_path, game) {
+        println!("  Manual:      {}", manual.display());
+    }
+    for art in screenshots::images_in(&screenshots::game_assets_dir(config_path, game)) {
+        println!("  Art:         {}", art.display());
+    }
+    let screenshots_dir = screenshots::screenshots_dir(config_path, game);
+    let screenshot_count = screenshots::images_in(&screenshots_dir).len();
+    if screenshot_count > 0 {
+        println!("  Screenshots: {} in {}", screenshot_count, screenshots_dir.display());
+    }
+    if let GameKind::Directory { marker, .. } = &game.kind {
+        println!("  Launches:    {}", marker.display());
+    }
+    if let Ok(file_metadata) = std::fs::metadata(&game.path) {
+        if file_metadata.is_file() {
+            println!("  Size:        {} bytes", file_metadata.len());
+        }
+        if let Ok(modified) = file_metadata.modified() {
+            let timestamp = modified.duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            println!("  Modified:    {}", history::format_timestamp(timestamp));
+        }
+    }
+    if let Some(crc) = &crc {
+        println!("  CRC32:       {}", crc);
+    }
+    for member in &members {
+        println!("    {}  {}", member.crc32, member.name);
+    }
 
-    // Function to display the ROM list. This is now callable from multiple places.
-    let display_rom_list = |roms: &[Rom], ext_to_emu: &HashMap<String, &Emulator>| {
-        println!("\n--- Current ROMs List ---");
-        for (i, rom) in roms.iter().enumerate() {
-            let suggested_emulator_name = rom.path.extension()
-                .and_then(|ext_os| ext_os.to_str())
-                .and_then(|ext_str| ext_to_emu.get(&ext_str.to_lowercase()))
-                .map_or("Unknown".to_string(), |e| e.name.clone());
+    // Play time and launch count come from the launch history.
+    match history.load() {
+        Ok(entries) => {
+            let launches: Vec<&HistoryEntry> = entries.iter().filter(|e| e.rom == game.path).collect();
+            if let Some(last) = launches.last() {
+                let total: u64 = launches.iter().map(|e| e.duration_secs).sum();
+                println!("  Play time:   {} over {} launches", history::format_duration(total), launches.len());
+                println!("  Last played: {} with {}", history::format_timestamp(last.timestamp), last.emulator);
+            } else {
+                println!("  Play time:   never launched");
+            }
+        }
+        Err(e) => eprintln!("⚠️ Could not read launch history: {}", e),
+    }
+    println!("{}\n", "-".repeat(file_name.chars().count() + 8));
+}
 
-            println!(
-                "  {}. {} (Type: {}, Suggested Emulator: {})",
-                i + 1,
-                rom.path.file_name().unwrap_or_default().to_string_lossy(),
-                rom.get_extension().unwrap_or("unknown"),
-                suggested_emulator_name
-            );
+/// Runs the interactive ROM browser: scan, list, and launch on selection.
+///
+/// `startup_commands` (a macro) are run as if typed before reading commands from stdin.
+fn run_interactive(
+    config_path: &Path,
+    roms_dir: &str,
+    settings: &Settings,
+    mut after_session: PostSessionAction,
+    startup_commands: &[String],
+) -> io::Result<()> {
+    println!("🚀 Starting ROM Loader...");
+
+    // 1. Load Emulator Configuration
+    let Some(mut emulator_config) = load_emulator_config(config_path) else { return Ok(()) };
+
+    // Watch the configuration so edits are picked up without restarting, unless memory is short.
+    let config_watcher = if settings::low_memory() {
+        None
+    } else {
+        match ConfigWatcher::new(config_path) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                eprintln!("⚠️ Configuration changes won't be picked up automatically: {}", e);
+                None
+            }
         }
-        println!("-------------------------\n");
     };
 
+    // 2. Scan for ROMs
+    let roms_dir_path = PathBuf::from(roms_dir);
+    let history = LaunchHistory::for_config(config_path);
+    let state_path = StateStore::path_for_config(config_path);
+    let mut state = StateStore::load(&state_path).unwrap_or_else(|e| {
+        eprintln!("⚠️ Could not load saved state, starting fresh: {}", e);
+        StateStore::default()
+    });
+    let mut metadata = MetadataDb::load(&MetadataDb::path_for_config(config_path)).unwrap_or_else(|e| {
+        eprintln!("⚠️ Could not load game metadata, showing file names only: {}", e);
+        MetadataDb::default()
+    });
+    let Some(mut roms) = scan_library(&roms_dir_path) else { return Ok(()) };
+    if settings::low_memory() {
+        metadata.retain_games(&roms);
+    }
+    let languages = variants::preferred_languages(&settings.language_priority);
+    let mut variants = Variants::group(&roms, &languages);
+
     // Initial display of ROMs
-    display_rom_list(&roms, &extension_to_emulator);
+    let default_filter = ListFilter { hide_clones: settings.ui.hide_clones, ..ListFilter::default() };
+    let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, BTreeSet::new());
+    display_rom_list(&roms, &emulator_config, &state, &metadata, &variants, &default_filter, &appearance);
+    // The filter of the last listing, which 'select a' selects from.
+    let mut last_filter = default_filter;
+
+    // Games waiting to be launched one after another (indices into `roms`).
+    let mut queue: Vec<usize> = Vec::new();
+    // Games selected for a batch action (indices into `roms`).
+    let mut selection: BTreeSet<usize> = BTreeSet::new();
+
+    let macros_path = MacroStore::path_for_config(config_path);
+    let mut command_input = CommandInput::default();
+    command_input.replay(startup_commands);
+    // Cabinet buttons type the same commands as the keyboard; they're watched until the browser quits.
+    let _buttons = settings.gpio.as_ref().and_then(|pins| watch_buttons(pins, &mut command_input));
+    // The game picked with 'n' and 'p' (an index into `roms`), which Enter launches.
+    let mut highlighted: Option<usize> = None;
 
     // 3. User Selection and Launch
     loop {
-        print!("🔢 Enter the number of the ROM to launch, 'l' to list games, or 'q' to quit: ");
+        print!("🔢 Enter the number of the ROM to launch, 'l' to list games, 'h' for help, or 'q' to quit: ");
         io::stdout().flush()?; // Ensure the prompt is displayed.
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        let input = input.trim();
+        let input = command_input.read_line()?;
+        let input = input.as_str();
+
+        // Apply configuration edits made since the last command before acting on this one.
+        if config_watcher.as_ref().is_some_and(|w| w.has_changed()) {
+            match EmulatorConfig::load(config_path) {
+                Ok(new_config) => {
+                    report_config_changes(&emulator_config, &new_config);
+                    emulator_config = new_config;
+                }
+                Err(e) => eprintln!("⚠️ Configuration changed but could not be reloaded, keeping the previous one: {}", e),
+            }
+        }
+        let extension_to_emulator = emulator_config.extension_map();
 
         if input.eq_ignore_ascii_case("q") {
             println!("👋 Exiting ROM Loader. Goodbye!");
             break;
-        } else if input.eq_ignore_ascii_case("l") {
-            display_rom_list(&roms, &extension_to_emulator);
+        } else if input.eq_ignore_ascii_case("l") || input.starts_with("l ") {
+            match InteractiveListArgs::try_parse_from(input[1..].split_whitespace()) {
+                Ok(mut args) => {
+                    args.filter.hide_clones |= settings.ui.hide_clones;
+                    let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, selection.clone());
+                    display_rom_list(&roms, &emulator_config, &state, &metadata, &variants, &args.filter, &appearance);
+                    last_filter = args.filter;
+                }
+                Err(e) => println!("🚫 {}", e.to_string().lines().next().unwrap_or_default()),
+            }
+        } else if let Some(query) = input.strip_prefix("search").filter(|rest| rest.is_empty() || rest.starts_with(' ')) {
+            // The rest of the line is the query, so it needs no shell-style quoting as with 'l --search'.
+            match query.trim().parse::<Query>() {
+                Ok(query) => {
+                    let filter = ListFilter { search: Some(query), hide_clones: settings.ui.hide_clones, ..ListFilter::default() };
+                    let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, selection.clone());
+                    display_rom_list(&roms, &emulator_config, &state, &metadata, &variants, &filter, &appearance);
+                    last_filter = filter;
+                }
+                Err(e) => println!("🚫 Invalid search: {} (e.g. 'search genre:rpg year:<1995 mario')", e),
+            }
+        } else if let Some(letter) = input.strip_prefix('g').filter(|rest| rest.is_empty() || rest.starts_with(' ')) {
+            // Jumps keep the filters of the last listing, so 'l --genre rpg' then 'g m' narrows it down.
+            let filter = ListFilter { letter: None, ..last_filter.clone() };
+            let mut chars = letter.trim().chars();
+            match (chars.next(), chars.next()) {
+                (None, _) => {
+                    let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, BTreeSet::new());
+                    let listed = listed_games(&roms, &state, &metadata, &variants, &filter, &appearance);
+                    print_letter_index(&roms, &listed, &metadata, &appearance);
+                }
+                (Some(letter), None) if letter.is_ascii_alphabetic() || letter == '#' => {
+                    let filter = ListFilter { letter: Some(letter.to_ascii_uppercase()), ..filter };
+                    let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, selection.clone());
+                    display_rom_list(&roms, &emulator_config, &state, &metadata, &variants, &filter, &appearance);
+                    last_filter = filter;
+                }
+                _ => println!("🚫 Usage: g <letter> jumps to the games starting with a letter ('#' for the rest); g alone shows the index."),
+            }
+        } else if input.eq_ignore_ascii_case("h") {
+            print_interactive_help();
+        } else if input == "n" || input == "p" {
+            let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, BTreeSet::new());
+            let listed = listed_games(&roms, &state, &metadata, &variants, &last_filter, &appearance);
+            if listed.is_empty() {
+                println!("📭 The last listing has no games.");
+                continue;
+            }
+            let position = highlighted.and_then(|index| listed.iter().position(|&i| i == index));
+            let position = match (position, input) {
+                (None, "n") => 0,
+                (None, _) => listed.len() - 1,
+                (Some(position), "n") => (position + 1) % listed.len(),
+                (Some(position), _) => (position + listed.len() - 1) % listed.len(),
+            };
+            let index = listed[position];
+            highlighted = Some(index);
+            println!("▶️ {}. {} (Enter to launch)", index + 1, roms[index].path.file_name().unwrap_or_default().to_string_lossy());
+        } else if let Some(index) = highlighted.filter(|_| input.is_empty()) {
+            command_input.replay(&[(index + 1).to_string()]);
+        } else if input == "macros" {
+            match MacroStore::load(&macros_path) {
+                Ok(store) if store.macros.is_empty() => println!("📭 No macros saved yet."),
+                Ok(store) => {
+                    for (name, commands) in &store.macros {
+                        println!("  {}: {}", name, commands.join("; "));
+                    }
+                }
+                Err(e) => println!("🚫 {}", e),
+            }
+        } else if let Some(name) = input.strip_prefix("macro ") {
+            match MacroStore::load(&macros_path).map(|mut store| store.macros.remove(name.trim())) {
+                Ok(Some(commands)) => command_input.replay(&commands),
+                Ok(None) => println!("🚫 No macro named '{}'.", name.trim()),
+                Err(e) => println!("🚫 {}", e),
+            }
+        } else if let Some(record_args) = input.strip_prefix("record") {
+            match record_args.trim() {
+                "" => println!("🚫 Usage: record <name> to start recording a macro, record stop to save it."),
+                "stop" => match command_input.stop_recording() {
+                    Some((_, commands)) if commands.is_empty() => println!("🚫 Nothing recorded, macro not saved."),
+                    Some((name, commands)) => {
+                        let saved = MacroStore::load(&macros_path).and_then(|mut store| {
+                            store.macros.insert(name.clone(), commands);
+                            store.save(&macros_path)
+                        });
+                        match saved {
+                            Ok(()) => println!("💾 Macro '{}' saved. Replay it with 'macro {}' or 'rom_loader macro run {}'.", name, name, name),
+                            Err(e) => eprintln!("❌ Could not save macro '{}': {}", name, e),
+                        }
+                    }
+                    None => println!("🚫 Not recording. Start with 'record <name>'."),
+                },
+                _ if command_input.is_recording() => println!("🚫 Already recording; finish with 'record stop' first."),
+                name => {
+                    command_input.start_recording(name);
+                    println!("⏺️ Recording macro '{}'. Commands run as usual; 'record stop' saves it.", name);
+                }
+            }
+        } else if let Some(action) = input.strip_prefix("after") {
+            let action = action.trim();
+            if !action.is_empty() {
+                match action.parse() {
+                    Ok(action) => after_session = action,
+                    Err(e) => println!("🚫 {}", e),
+                }
+            }
+            println!("⏭️ After each session: {}", after_session);
+        } else if let Some(pick_args) = input.strip_prefix("e ") {
+            // One-off emulator override, e.g. to compare cores; the choice is not learned.
+            match pick_args.trim().parse::<usize>() {
+                Ok(num) if num > 0 && num <= roms.len() => {
+                    let game = &roms[num - 1];
+                    let suggested = suggested_emulator(game, &emulator_config, &extension_to_emulator, &state);
+                    let candidates = emulator_candidates(game, &emulator_config, suggested, true);
+                    if candidates.is_empty() {
+                        println!("🚫 No emulators are configured.");
+                        continue;
+                    }
+                    let emulator = prompt_emulator_choice(&candidates, suggested, &mut command_input)?;
+                    launch_game(emulator, game, config_path, &metadata, &LaunchOverrides::default());
+                    if !finish_session(&after_session, game) {
+                        break;
+                    }
+                }
+                _ => println!("🚫 Usage: e <n>, where <n> is a ROM number from the list."),
+            }
+        } else if let Some((command, hide_args)) = input.split_once(' ').filter(|(c, _)| *c == "hide" || *c == "unhide") {
+            let hide = command == "hide";
+            match hide_args.trim().parse::<usize>() {
+                Ok(num) if num > 0 && num <= roms.len() => {
+                    let game = &roms[num - 1];
+                    report_hidden(game, state.set_hidden(&game.path, hide), hide);
+                    if let Err(e) = state.save(&state_path) {
+                        eprintln!("⚠️ Could not save state: {}", e);
+                    }
+                }
+                _ => println!("🚫 Usage: {} <n>, where <n> is a ROM number from the list.", command),
+            }
+        } else if let Some(delete_args) = input.strip_prefix("delete ") {
+            match delete_args.trim().parse::<usize>() {
+                Ok(num) if num > 0 && num <= roms.len() => {
+                    let index = num - 1;
+                    if confirm_delete(&roms[index], &mut command_input)?
+                        && delete_game(config_path, &roms[index], open_journal(config_path, "delete").as_mut()) {
+                        roms.remove(index);
+                        variants = Variants::group(&roms, &languages);
+                        // Keep the queue, selection and highlight pointing at the same games now that later numbers moved up.
+                        queue.retain(|&queued| queued != index);
+                        for queued in &mut queue {
+                            if *queued > index {
+                                *queued -= 1;
+                            }
+                        }
+                        selection = selection.iter().filter(|&&i| i != index).map(|&i| if i > index { i - 1 } else { i }).collect();
+                        highlighted = highlighted.filter(|&i| i != index).map(|i| if i > index { i - 1 } else { i });
+                        // The in-memory state still knew the game; drop it so a later save doesn't bring it back.
+                        state = StateStore::load(&state_path).unwrap_or(state);
+                        println!("🔢 Games after #{} moved up one number.", num);
+                    }
+                }
+                _ => println!("🚫 Usage: delete <n>, where <n> is a ROM number from the list."),
+            }
+        } else if let Some(info_args) = input.strip_prefix("info") {
+            match info_args.trim().parse::<GameRef>().map(|rom| rom.resolve(Path::new(roms_dir), &roms)) {
+                Ok(Ok(index)) => {
+                    let ids = GameIds::new(Path::new(roms_dir), &roms);
+                    print_game_info(config_path, &roms[index], ids.short(index), &metadata, &history);
+                }
+                Ok(Err(e)) => println!("🚫 {}", e),
+                Err(_) => println!("🚫 Usage: info <n>, where <n> is a ROM number from the list or a game ID."),
+            }
+        } else if let Some(select_args) = input.strip_prefix("select").filter(|rest| rest.is_empty() || rest.starts_with(' ')) {
+            match select_args.trim() {
+                "" => print_selection(&selection, &roms),
+                "a" | "all" => {
+                    let appearance = ListAppearance::new(config_path, roms_dir, &settings.ui.list, BTreeSet::new());
+                    selection.extend(listed_games(&roms, &state, &metadata, &variants, &last_filter, &appearance));
+                    println!("☑️ {} games selected.", selection.len());
+                }
+                "clear" => {
+                    selection.clear();
+                    println!("🧹 Selection cleared.");
+                }
+                numbers => {
+                    for arg in numbers.split_whitespace() {
+                        match parse_rom_range(arg, roms.len()) {
+                            Some(range) => {
+                                for index in range {
+                                    if !selection.remove(&index) {
+                                        selection.insert(index);
+                                    }
+                                }
+                            }
+                            None => println!("🚫 Skipping '{}': not a valid ROM number or range.", arg),
+                        }
+                    }
+                    println!("☑️ {} games selected.", selection.len());
+                }
+            }
+        } else if let Some(action) = input.strip_prefix("batch").filter(|rest| rest.is_empty() || rest.starts_with(' ')) {
+            if selection.is_empty() {
+                println!("📭 Nothing is selected. Select games with 'select <n>...' or 'select a'.");
+                continue;
+            }
+            match action.trim() {
+                command @ ("hide" | "unhide") => {
+                    let hide = command == "hide";
+                    for &index in &selection {
+                        report_hidden(&roms[index], state.set_hidden(&roms[index].path, hide), hide);
+                    }
+                    if let Err(e) = state.save(&state_path) {
+                        eprintln!("⚠️ Could not save state: {}", e);
+                    }
+                }
+                "queue" => {
+                    queue.extend(selection.iter().copied());
+                    print_queue(&queue, &roms);
+                }
+                "verify" => {
+                    let games: Vec<Game> = selection.iter().map(|&index| roms[index].clone()).collect();
+                    verify_games(config_path, &games);
+                }
+                _ => println!("🚫 Usage: batch <action>, where <action> is hide, unhide, queue or verify."),
+            }
+        } else if let Some(queue_args) = input.strip_prefix("queue") {
+            let mut parts = queue_args.split_whitespace();
+            match parts.next() {
+                None | Some("show") => print_queue(&queue, &roms),
+                Some("add") => {
+                    for arg in parts {
+                        match arg.parse::<usize>() {
+                            Ok(num) if num > 0 && num <= roms.len() => queue.push(num - 1),
+                            _ => println!("🚫 Skipping '{}': not a valid ROM number.", arg),
+                        }
+                    }
+                    print_queue(&queue, &roms);
+                }
+                Some("clear") => {
+                    queue.clear();
+                    println!("🧹 Launch queue cleared.");
+                }
+                Some("start") => {
+                    if queue.is_empty() {
+                        println!("📭 The launch queue is empty. Add games with 'queue add <n> <n> ...'.");
+                    }
+                    // Marathon mode: each game starts as soon as the previous emulator exits.
+                    let games: Vec<&Game> = queue.iter().map(|&index| &roms[index]).collect();
+                    let played = play_in_turn(&games, config_path, &emulator_config, &state, &metadata);
+                    queue.drain(..played);
+                    if !queue.is_empty() {
+                        println!("⏹️ Launch queue stopped; {} games are still queued.", queue.len());
+                    }
+                    if let Some(game) = played.checked_sub(1).map(|last| games[last]) {
+                        println!("🏁 Launch queue finished.");
+                        if !finish_session(&after_session, game) {
+                            break;
+                        }
+                    }
+                }
+                Some(other) => println!("🚫 Unknown queue command '{}'. Use 'queue add <n>...', 'queue', 'queue clear' or 'queue start'.", other),
+            }
         } else {
-            match input.parse::<usize>() {
+            let selection = match input.strip_prefix("v ") {
+                // The variant submenu picks which ROM number gets launched.
+                Some(variant_args) => match pick_variant(variant_args, &roms, &variants, &mut command_input)? {
+                    Some(num) => Ok(num),
+                    None => continue,
+                },
+                None => input.parse::<usize>(),
+            };
+            match selection {
                 Ok(num) if num > 0 && num <= roms.len() => {
                     let selected_rom = &roms[num - 1];
                     println!("You selected: {}", selected_rom.path.file_name().unwrap_or_default().to_string_lossy());
 
                     // Find the appropriate emulator for the selected ROM.
-                    let rom_extension = selected_rom.get_extension().unwrap_or("").to_lowercase();
-                    if let Some(emulator) = extension_to_emulator.get(&rom_extension) {
-                        println!("Launching {} with {}...",
-                            selected_rom.path.file_name().unwrap_or_default().to_string_lossy(),
-                            emulator.name
-                        );
-                        // Pass emulator name, core path, AND system name for specific handling
-                        if let Err(e) = rom_launcher::launch_rom(
-                            &emulator.path,
-                            &selected_rom.path,
-                            &emulator.name,
-                            emulator.core_path.as_ref(),
-                            emulator.system_name.as_ref()
-                        ) {
-                            eprintln!("❌ Failed to launch emulator: {}", e);
-                        } else {
-                            println!("✅ Launch command sent.");
+                    let suggested = suggested_emulator(selected_rom, &emulator_config, &extension_to_emulator, &state);
+                    let candidates = emulator_candidates(selected_rom, &emulator_config, suggested, false);
+                    let chosen = if candidates.len() > 1 {
+                        Some(prompt_emulator_choice(&candidates, suggested, &mut command_input)?)
+                    } else {
+                        candidates.first().copied()
+                    };
+
+                    if let Some(emulator) = chosen {
+                        // Remember overrides so repeated choices become the default.
+                        let mut learned = false;
+                        if suggested.is_some_and(|s| s.name != emulator.name) {
+                            state.record_override(&selected_rom.path, selected_rom.system().map(|s| s.id), &emulator.name);
+                            learned = state.preferred_emulator(&selected_rom.path, selected_rom.system().map(|s| s.id)) == Some(emulator.name.as_str());
+                            if learned {
+                                println!("🧠 {} will be suggested for this game from now on.", emulator.name);
+                            }
+                            if let Err(e) = state.save(&state_path) {
+                                eprintln!("⚠️ Could not save state: {}", e);
+                            }
+                        } else if suggested.is_some() && state.record_accepted(&selected_rom.path, selected_rom.system().map(|s| s.id)) {
+                            if let Err(e) = state.save(&state_path) {
+                                eprintln!("⚠️ Could not save state: {}", e);
+                            }
+                        }
+                        // A conflict the user resolved by hand; offer to keep the answer.
+                        if candidates.len() > 1 && !learned && suggested.is_none_or(|s| s.name != emulator.name) {
+                            remember_choice(config_path, &mut state, &state_path, emulator, selected_rom, &mut command_input)?;
+                        }
+                        launch_game(emulator, selected_rom, config_path, &metadata, &LaunchOverrides::default());
+                        if !finish_session(&after_session, selected_rom) {
+                            break;
                         }
                     } else {
-                        eprintln!("❌ No configured emulator found for '{}' files.", rom_extension);
-                        eprintln!("Please add an entry to your 'emulators.json' for this ROM type.");
+                        report_missing_emulator(selected_rom);
                     }
                 }
                 _ => {
-                    println!("🚫 Invalid selection. Please enter a valid number, 'l', or 'q'.");
+                    println!("🚫 Invalid selection. Please enter a valid number, or 'h' for the list of commands.");
                 }
             }
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Starts watching the cabinet buttons of the `gpio` setting, which send the browser's
+/// commands: next and previous are 'n' and 'p', launch is Enter and exit is 'q'.
+#[cfg(feature = "gpio")]
+fn watch_buttons(pins: &GpioPins, command_input: &mut CommandInput) -> Option<gpio::Buttons> {
+    let commands = command_input.listen();
+    let on_press = move |action| {
+        let command = match action {
+            ButtonAction::Next => "n",
+            ButtonAction::Previous => "p",
+            ButtonAction::Launch => "",
+            ButtonAction::Exit => "q",
+        };
+        let _ = commands.send(command.to_string());
+    };
+    match gpio::Buttons::watch(pins, on_press) {
+        Ok(buttons) => {
+            println!("🕹️ Cabinet buttons ready.");
+            Some(buttons)
+        }
+        Err(e) => {
+            eprintln!("⚠️ Cabinet buttons won't work: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "gpio"))]
+fn watch_buttons(_pins: &GpioPins, _command_input: &mut CommandInput) -> Option<()> {
+    eprintln!("⚠️ Cabinet buttons won't work: the gpio setting needs rom-loader built with the gpio feature.");
+    None
+}
+
+/// Shows the language variants of a game and asks which one to launch.
+///
+/// # Returns
+/// The ROM number of the chosen variant, or `None` if there is nothing to launch.
+fn pick_variant(args: &str, roms: &[Game], variants: &Variants, command_input: &mut CommandInput) -> io::Result<Option<usize>> {
+    let index = match args.trim().parse::<usize>() {
+        Ok(num) if num > 0 && num <= roms.len() => num - 1,
+        _ => {
+            println!("🚫 Usage: v <n>, where <n> is a ROM number from the list.");
+            return Ok(None);
+        }
+    };
+    let group = variants.of(index);
+    if group.is_empty() {
+        println!("🚫 {} has no other language variants.", roms[index].path.file_name().unwrap_or_default().to_string_lossy());
+        return Ok(None);
+    }
+    println!("\n--- Variants ---");
+    for (position, &i) in group.iter().enumerate() {
+        let name = roms[i].path.file_name().unwrap_or_default().to_string_lossy();
+        let tags = variants::language_tags(&name);
+        let languages = if tags.languages.is_empty() { "unknown language".to_string() } else { tags.languages.join(", ") };
+        println!("  {}. {} [{}{}]{}",
+            position + 1,
+            name,
+            languages,
+            if tags.translation { ", fan translation" } else { "" },
+            if position == 0 { " (default)" } else { "" }
+        );
+    }
+    print!("🔢 Variant to launch (Enter for the default): ");
+    io::stdout().flush()?;
+    let answer = command_input.read_line()?;
+    if answer.is_empty() {
+        return Ok(Some(group[0] + 1));
+    }
+    match answer.parse::<usize>() {
+        Ok(num) if num > 0 && num <= group.len() => Ok(Some(group[num - 1] + 1)),
+        _ => {
+            println!("🚫 Not a variant number.");
+            Ok(None)
+        }
+    }
+}
+
+/// Runs the post-session action once a game or the launch queue has exited.
+///
+/// # Returns
+/// `false` if the interactive browser should exit.
+fn finish_session(action: &PostSessionAction, last_game: &Game) -> bool {
+    match action.run(&last_game.path) {
+        Ok(true) => true,
+        Ok(false) => {
+            println!("👋 Exiting ROM Loader. Goodbye!");
+            false
+        }
+        Err(e) => {
+            eprintln!("⚠️ Post-session action '{}' failed: {}", action, e);
+            true
+        }
+    }
+}
+
+/// Lists the commands understood by the interactive prompt.
+fn print_interactive_help() {
+    println!("\n--- Commands ---");
+    println!("  <n>              Launch ROM number <n>");
+    println!("  l                List games");
+    println!("  l --genre <g> --players <n> --year <a..b> --hide-clones --hidden");
+    println!("                   List games matching imported metadata");
+    println!("  l --all-variants List every language variant, not just the preferred one");
+    println!("  search <query>   List the games matching a search, e.g. genre:rpg year:<1995 mario");
+    println!("                   (fields: title, genre, developer, publisher, region, rating, description,");
+    println!("                   system, file, year, players; -term excludes, \"quotes\" group words)");
+    println!("  g <letter>       List the games starting with a letter ('#' for digits and symbols)");
+    println!("  g                Show how many games start with each letter");
+    println!("  v <n>            Pick a language or translation variant of ROM number <n> to launch");
+    println!("  e <n>            Launch ROM number <n> with an emulator of your choice, just this once");
+    println!("  n / p            Highlight the next / previous game of the last listing");
+    println!("  (Enter)          Launch the highlighted game");
+    println!("  info <n>         Show details about ROM number (or ID) <n>");
+    println!("  hide <n>         Hide ROM number <n> from the list (l --hidden shows hidden ROMs)");
+    println!("  unhide <n>       Show ROM number <n> in the list again");
+    println!("  delete <n>       Move ROM number <n> to the trash (asks first)");
+    println!("  select <n>...    Select or deselect ROMs for a batch action (ranges like 3-7 work too)");
+    println!("  select a         Select every game of the last listing");
+    println!("  select           Show the selection; 'select clear' empties it");
+    println!("  batch <action>   Apply an action to the selected ROMs: hide, unhide, queue or verify");
+    println!("  queue add <n>... Add ROMs to the launch queue");
+    println!("  queue            Show the launch queue");
+    println!("  queue clear      Empty the launch queue");
+    println!("  queue start      Launch the queued ROMs one after another");
+    println!("  after [action]   Show or set what happens after a session:");
+    println!("                   return, quit, shutdown, suspend or script:<path>");
+    println!("  record <name>    Record the following commands into a macro");
+    println!("  record stop      Stop recording and save the macro");
+    println!("  macro <name>     Replay a macro");
+    println!("  macros           List the saved macros");
+    println!("  h                Show this help");
+    println!("  q                Quit");
+    println!("----------------\n");
+}
+
+/// Prints the games selected for a batch action.
+fn print_selection(selection: &BTreeSet<usize>, roms: &[Game]) {
+    if selection.is_empty() {
+        println!("📭 Nothing is selected.");
+        return;
+    }
+    println!("\n--- Selection ---");
+    for &index in selection {
+        println!("  {}. {}", index + 1, roms[index].path.file_name().unwrap_or_default().to_string_lossy());
+    }
+    println!("-----------------\n");
+}
+
+/// Parses a ROM number or a range of them (e.g. `3-7`) into indices into the game list.
+fn parse_rom_range(arg: &str, count: usize) -> Option<std::ops::RangeInclusive<usize>> {
+    let (first, last) = arg.split_once('-').unwrap_or((arg, arg));
+    let first = first.trim().parse::<usize>().ok()?;
+    let last = last.trim().parse::<usize>().ok()?;
+    (first > 0 && first <= last && last <= count).then(|| first - 1..=last - 1)
+}
+
+/// Re-hashes the files of the given games and compares them with their stored hashes, like
+/// `verify --deep` for just these games.
+fn verify_games(config_path: &Path, games: &[Game]) {
+    let store_path = HashStore::path_for_config(config_path);
+    let mut store = match HashStore::load(&store_path) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return;
+        }
+    };
+    let files = library_files(games);
+    let threads = Settings::load(config_path).unwrap_or_default().hash_threads;
+    let options = VerifyOptions { deep: true, min_age_secs: None, inner: false, now: history::now_timestamp(), threads };
+    let mut problems = 0;
+    for (file, result) in files.iter().zip(verify::verify_files(&files, &mut store, &options)) {
+        match result {
+            Ok(VerifyStatus::Corrupted { expected, actual }) => {
+                println!("  💥 CORRUPTED: {} (CRC32 {} expected, found {})", file.display(), expected, actual);
+                problems += 1;
+            }
+            Ok(VerifyStatus::Modified) => println!("  ✏️ Modified since last check, hash updated: {}", file.display()),
+            Ok(_) => {}
+            Err(e) => {
+                println!("  ⚠️ Could not read {}: {}", file.display(), e);
+                problems += 1;
+            }
+        }
+    }
+    if let Err(e) = store.save(&store_path) {
+        eprintln!("⚠️ Could not save the hash store: {}", e);
+    }
+    match problems {
+        0 => println!("✅ Checked {} files, no corruption found.", files.len()),
+        n => println!("💥 Checked {} files, {} have problems.", files.len(), n),
+    }
+}
+
+/// Returns the games a listing with `filter` shows (indices into `roms`), as [`display_rom_list`] picks them.
+fn listed_games(
+    roms: &[Game],
+    state: &StateStore,
+    metadata: &MetadataDb,
+    variants: &Variants,
+    filter: &ListFilter,
+    appearance: &ListAppearance,
+) -> Vec<usize> {
+    (0..roms.len())
+        .filter(|&i| state.is_hidden(&roms[i].path) == filter.hidden && filter.matches(metadata.lookup(&roms[i])))
+        .filter(|&i| filter.all_variants || !variants.is_alternate(i))
+        .filter(|&i| filter.matches_title(&roms[i], metadata.lookup(&roms[i]), &appearance.title(&roms[i], metadata.lookup(&roms[i]))))
+        .collect()
+}
+
+/// Prints the games waiting in the launch queue.
+fn print_queue(queue: &[usize], roms: &[Game]) {
+    if queue.is_empty() {
+        println!("📭 The launch queue is empty.");
+        return;
+    }
+    println!("\n--- Launch Queue ---");
+    for (position, &index) in queue.iter().enumerate() {
+        println!("  {}. {} (#{})", position + 1, roms[index].path.file_name().unwrap_or_default().to_string_lossy(), index + 1);
+    }
+    println!("--------------------\n");
+}
+
+/// Prints the numbered ROM list with each game's system and suggested emulator.
+///
+/// Games rejected by the filter are left out, but the others keep their numbers so they can still be launched by them.
+/// Hidden games are only listed with `--hidden`, which lists nothing else.
+fn display_rom_list(
+    roms: &[Game],
+    emulator_config: &EmulatorConfig,
+    state: &StateStore,
+    metadata: &MetadataDb,
+    variants: &Variants,
+    filter: &ListFilter,
+    appearance: &ListAppearance,
+) {
+    let display = &appearance.display;
+    let extension_to_emulator = emulator_config.extension_map();
+    let ids = display.columns().contains(&ListColumn::Id).then(|| GameIds::new(&appearance.roms_dir, roms));
+    println!("\n--- Current ROMs List ---");
+    let mut shown = 0;
+    let mut hidden = 0;
+    let mut alternates = 0;
+    for (i, rom) in roms.iter().enumerate() {
+        let game_metadata = metadata.lookup(rom);
+        if state.is_hidden(&rom.path) {
+            hidden += 1;
+        }
+        if state.is_hidden(&rom.path) != filter.hidden || !filter.matches(game_metadata) {
+            continue;
+        }
+        if !filter.all_variants && variants.is_alternate(i) {
+            alternates += 1;
+            continue;
+        }
+        let mut title = appearance.title(rom, game_metadata);
+        if !filter.matches_title(rom, game_metadata, &title) {
+            continue;
+        }
+        shown += 1;
+        let suggested_emulator_name = suggested_emulator(rom, emulator_config, &extension_to_emulator, state)
+            .map_or_else(
+                || {
+                    // Fall back to the registry's default core so the user knows what to configure.
+                    match rom.system() {
+                        Some(system) => match system.default_core {
+                            Some(core) => format!("Unknown, try RetroArch core '{}'", core),
+                            None => "Unknown".to_string(),
+                        },
+                        None => "Unknown".to_string(),
+                    }
+                },
+                |e| e.name.clone(),
+            );
+
+        // Year and genre follow the title unless they have columns of their own.
+        let columns = display.columns();
+        if !columns.contains(&ListColumn::Year) && !columns.contains(&ListColumn::Genre) {
+            if let Some(summary) = game_metadata.and_then(|m| m.summary()) {
+                title = format!("{} ({})", title, summary);
+            }
+        }
+        if let Some(width) = display.max_width.filter(|&width| title.chars().count() > width) {
+            title = format!("{}…", title.chars().take(width.saturating_sub(1)).collect::<String>());
+        }
+
+        let other_variants = match variants.of(i).len() {
+            0 => String::new(),
+            _ if filter.all_variants => String::new(),
+            n => format!(" [{} variants, 'v {}']", n, i + 1),
+        };
+
+        let details: Vec<String> = columns
+            .iter()
+            .filter_map(|column| match column {
+                ListColumn::Type => Some(format!("Type: {}", rom.get_extension().unwrap_or("unknown"))),
+                ListColumn::System => Some(format!("System: {}", rom.system().map_or("unknown", |s| s.id))),
+                ListColumn::Emulator => Some(format!("Suggested Emulator: {}", suggested_emulator_name)),
+                ListColumn::Year => game_metadata.and_then(|m| m.year.as_ref()).map(|year| format!("Year: {}", year)),
+                ListColumn::Genre => game_metadata.and_then(|m| m.genre.as_ref()).map(|genre| format!("Genre: {}", genre)),
+                ListColumn::Players => game_metadata.and_then(|m| m.players.as_ref()).map(|players| format!("Players: {}", players)),
+                ListColumn::LastPlayed => appearance.last_played.get(&rom.path).map(|&timestamp| {
+                    let date = match &display.date_format {
+                        Some(format) => history::format_timestamp_with(timestamp, format),
+                        None => history::format_timestamp(timestamp),
+                    };
+                    format!("Last played: {}", date)
+                }),
+                ListColumn::Id => ids.as_ref().map(|ids| format!("ID: {}", ids.short(i))),
+            })
+            .collect();
+        println!(
+            "  {}{}. {}{}{}",
+            if appearance.selection.contains(&i) { "☑️ " } else { "" },
+            i + 1,
+            title,
+            other_variants,
+            if details.is_empty() { String::new() } else { format!(" ({})", details.join(", ")) }
+        );
+    }
+    if alternates > 0 {
+        println!("  ({} language variants not shown; 'l --all-variants' lists them)", alternates);
+    }
+    if filter.is_active() {
+        println!("  ({} of {} games match the filter)", shown, roms.len());
+    } else if hidden > 0 {
+        println!("  ({} hidden games not shown; 'l --hidden' lists them)", hidden);
+    }
+    println!("-------------------------\n");
+}
+
+/// How the game list is shown: the `ui.list` settings, the games selected for batch actions,
+/// and what the chosen columns need from the launch history.
+struct ListAppearance {
+    display: ListDisplay,
+    /// The library's directory, which game IDs are relative to.
+    roms_dir: PathBuf,
+    titles: TitleCleaner,
+    selection: BTreeSet<usize>,
+    /// Time of the most recent launch of each ROM; only loaded for the `last_played` column.
+    last_played: HashMap<PathBuf, u64>,
+}
+
+impl ListAppearance {
+    fn new(config_path: &Path, roms_dir: &str, display: &ListDisplay, selection: BTreeSet<usize>) -> Self {
+        let mut last_played = HashMap::new();
+        if display.columns().contains(&ListColumn::LastPlayed) {
+            for entry in LaunchHistory::for_config(config_path).load().unwrap_or_default() {
+                let latest = last_played.entry(entry.rom).or_insert(entry.timestamp);
+                *latest = (*latest).max(entry.timestamp);
+            }
+        }
+        let titles = TitleCleaner::new(display.title_rules.as_deref());
+        ListAppearance { display: display.clone(), roms_dir: PathBuf::from(roms_dir), titles, selection, last_played }
+    }
+
+    /// The title a game is listed under, as chosen by `ui.list.title`.
+    fn title(&self, rom: &Game, metadata: Option<&GameMetadata>) -> String {
+        // The database title, or for names like "rom1.gba" that say nothing, the cartridge header's.
+        let known_title = match metadata {
+            Some(m) => Some(m.title.clone()),
+            None if rom.kind == GameKind::File && rom_format::is_uninformative_name(&rom.path) => {
+                rom_format::read_header(&rom.path).ok().flatten().and_then(|header| header.label())
+            }
+            None => None,
+        };
+        let file_name = rom.path.file_name().unwrap_or_default().to_string_lossy();
+        match (self.display.title, known_title) {
+            (TitleSource::Title, Some(known)) => known,
+            (TitleSource::Title, None) => self.titles.clean(&file_name),
+            (TitleSource::Filename, Some(known)) => format!("{} — {}", file_name, known),
+            (TitleSource::Filename, None) => file_name.to_string(),
+        }
+    }
+}
+
+/// Returns the letter a title is indexed under for `g`: its first letter or digit upper-cased,
+/// or '#' if that isn't a letter from A to Z.
+fn index_letter(title: &str) -> char {
+    match title.chars().find(|c| c.is_alphanumeric()).map(|c| c.to_ascii_uppercase()) {
+        Some(c) if c.is_ascii_uppercase() => c,
+        _ => '#',
+    }
+}
+
+/// Prints how many games of a listing start with each letter, for jumping there with `g <letter>`.
+fn print_letter_index(roms: &[Game], listed: &[usize], metadata: &MetadataDb, appearance: &ListAppearance) {
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for &i in listed {
+        *counts.entry(index_letter(&appearance.title(&roms[i], metadata.lookup(&roms[i])))).or_default() += 1;
+    }
+    if counts.is_empty() {
+        println!("📭 No games to index.");
+        return;
+    }
+    let index: Vec<String> = counts.iter().map(|(letter, count)| format!("{} ({})", letter, count)).collect();
+    println!("🔤 {}", index.join("  "));
+    println!("   Jump to a letter with 'g <letter>'.");
+}
+
+/// Returns the names of the systems an emulator configuration can launch.
+fn covered_systems(config: &EmulatorConfig) -> BTreeSet<&'static str> {
+    config.emulators
+        .iter()
+        .flat_map(|e| e.extensions.iter())
+        .filter_map(|ext| systems::find_by_extension(ext).or_else(|| systems::find_by_id(ext)))
+        .map(|system| system.name)
+        .collect()
+}
+
+/// Tells the user what a configuration reload changed.
+fn report_config_changes(old: &EmulatorConfig, new: &EmulatorConfig) {
+    println!("🔄 Emulator configuration changed, reloaded {} emulators.", new.emulators.len());
+
+    let old_names: BTreeSet<&str> = old.emulators.iter().map(|e| e.name.as_str()).collect();
+    let new_names: BTreeSet<&str> = new.emulators.iter().map(|e| e.name.as_str()).collect();
+    for name in new_names.difference(&old_names) {
+        println!("  ➕ Emulator added: {}", name);
+    }
+    for name in old_names.difference(&new_names) {
+        println!("  ➖ Emulator removed: {}", name);
+    }
+
+    let old_systems = covered_systems(old);
+    let new_systems = covered_systems(new);
+    for system in new_systems.difference(&old_systems) {
+        println!("  🎮 Now available: {}", system);
+    }
+    for system in old_systems.difference(&new_systems) {
+        println!("  🚫 No longer available: {}", system);
+    }
+}